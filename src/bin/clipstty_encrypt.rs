@@ -0,0 +1,84 @@
+//! Manage encryption at rest for the ClipSTTy data directory
+
+use std::path::PathBuf;
+
+use stt_clippy::services::encryption::{self, KeySource, StorageCipher, KEY_CONFIG_FILE};
+
+fn usage() {
+    println!("USAGE:");
+    println!("  clipstty_encrypt generate-key <path>   Write a new random key file");
+    println!("  clipstty_encrypt migrate               Encrypt recordings and transcripts with the current key");
+    println!("  clipstty_encrypt rotate                Re-encrypt recordings and transcripts with a new key");
+    println!("  clipstty_encrypt status                Show whether the data directory is encrypted");
+    println!();
+    println!("ENVIRONMENT VARIABLES:");
+    println!("  CLIPSTTY_DATA_DIR        Data directory (default: ~/.clipstty)");
+    println!("  CLIPSTTY_PASSPHRASE      Current passphrase");
+    println!("  CLIPSTTY_KEY_FILE        Current key file (instead of a passphrase)");
+    println!("  CLIPSTTY_NEW_PASSPHRASE  New passphrase for rotate");
+    println!("  CLIPSTTY_NEW_KEY_FILE    New key file for rotate");
+}
+
+fn data_directory() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let home = || std::env::var("HOME").map_err(|_| "HOME environment variable not set");
+    Ok(match std::env::var("CLIPSTTY_DATA_DIR") {
+        Ok(dir) if dir.starts_with("~/") => PathBuf::from(home()?).join(&dir[2..]),
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => PathBuf::from(home()?).join(".clipstty"),
+    })
+}
+
+fn current_key() -> Result<KeySource, Box<dyn std::error::Error>> {
+    KeySource::from_env().ok_or_else(|| "set CLIPSTTY_PASSPHRASE or CLIPSTTY_KEY_FILE".into())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let data_dir = data_directory()?;
+
+    match args.first().map(String::as_str) {
+        Some("generate-key") => {
+            let path = args.get(1).ok_or("generate-key needs a path")?;
+            let key = encryption::generate_key_file(&PathBuf::from(path))?;
+            println!("🔑 Wrote key {} to {}", key.id_hex(), path);
+            println!("   Keep it outside the data directory and back it up: data cannot be recovered without it.");
+        }
+        Some("migrate") => {
+            let cipher = StorageCipher::unlock(&data_dir, &current_key()?)?;
+            println!("🔒 Encrypting {} with key {}...", data_dir.display(), cipher.key_id());
+            let report = encryption::encrypt_directory(&data_dir, &cipher)?;
+            println!(
+                "✅ {} files encrypted, {} re-keyed, {} already encrypted ({:.1} MB)",
+                report.files_encrypted,
+                report.files_reencrypted,
+                report.files_skipped,
+                report.bytes_processed as f64 / 1024.0 / 1024.0
+            );
+        }
+        Some("rotate") => {
+            let new = KeySource::from_env_vars("CLIPSTTY_NEW_KEY_FILE", "CLIPSTTY_NEW_PASSPHRASE")
+                .ok_or("set CLIPSTTY_NEW_PASSPHRASE or CLIPSTTY_NEW_KEY_FILE")?;
+            println!("🔄 Rotating key for {}...", data_dir.display());
+            let (cipher, report) = encryption::rotate_key(&data_dir, &current_key()?, &new)?;
+            println!(
+                "✅ Now using key {}: {} files re-keyed, {} newly encrypted",
+                cipher.key_id(),
+                report.files_reencrypted,
+                report.files_encrypted
+            );
+        }
+        Some("status") => {
+            if !data_dir.join(KEY_CONFIG_FILE).exists() {
+                println!("🔓 {} is not encrypted", data_dir.display());
+            } else {
+                match KeySource::from_env().map(|source| StorageCipher::unlock(&data_dir, &source)) {
+                    Some(Ok(cipher)) => println!("🔒 {} is encrypted with key {}", data_dir.display(), cipher.key_id()),
+                    Some(Err(e)) => println!("🔒 {} is encrypted: {}", data_dir.display(), e),
+                    None => println!("🔒 {} is encrypted (no key given)", data_dir.display()),
+                }
+            }
+        }
+        _ => usage(),
+    }
+    Ok(())
+}
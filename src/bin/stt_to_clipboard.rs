@@ -9,9 +9,16 @@ use stt_clippy::services::{
     paste::PasteService, 
     stt::STTService,
     audio_session_manager::{AudioSessionManager, SessionConfig},
-    voice_commands::{comprehensive_registry::create_comprehensive_command_engine, ServiceContext},
+    audio_playback::AudioPlaybackService,
+    echo_cancel::EchoReference,
+    encryption::{KeySource, StorageCipher},
+    retention::{parse_duration, RetentionEngine},
+    tts::{estimate_speech_duration, synthesize_to_samples},
+    voice_commands::{comprehensive_registry::create_comprehensive_command_engine, ServiceContext, VoiceCommandError},
+    wake_word::{WakeDecision, WakeWordConfig, WakeWordGate},
 };
-use tracing::{info, debug, error};
+use stt_clippy::core::config::Config;
+use tracing::{info, debug, error, warn};
 use tracing_subscriber::prelude::*;
 use std::path::PathBuf;
 
@@ -46,6 +53,22 @@ fn get_data_directory() -> Result<PathBuf, Box<dyn std::error::Error>> {
     Ok(data_dir)
 }
 
+/// Settings file: `CLIPSTTY_CONFIG`, or `config.toml` in the data directory
+fn config_path(data_dir: &std::path::Path) -> PathBuf {
+    std::env::var("CLIPSTTY_CONFIG")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| data_dir.join("config.toml"))
+}
+
+/// Load settings from the settings file, defaults when there is none
+fn load_config(path: &PathBuf) -> Result<Config, Box<dyn std::error::Error>> {
+    if !path.exists() {
+        return Ok(Config::new());
+    }
+    info!("Loading configuration from: {}", path.display());
+    Ok(Config::from_file(path)?)
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // setup tracing to stdout with colors
@@ -70,6 +93,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  ENERGY_LOG_COOLDOWN_MS  Energy log cooldown in ms (default: 100)");
     println!("  CLIPSTTY_DATA_DIR       Data directory for transcripts (default: ~/.clipstty)");
     println!("  CLIPSTTY_LOG_LEVEL      Logging level: debug, info, warn, error (default: info)");
+    println!("  CLIPSTTY_CONFIG         Settings file (default: config.toml in the data directory)");
+    println!("  CLIPSTTY_PASSPHRASE     Encrypt recordings and transcripts with this passphrase");
+    println!("  CLIPSTTY_KEY_FILE       Encrypt with a key file instead (see clipstty_encrypt)");
+    println!("  CLIPSTTY_RETENTION      Delete recordings and transcripts older than this, e.g. 30d, 1y, never (default: keep everything)");
+    println!("  CLIPSTTY_RETENTION_DRY_RUN  Set to 1 to only log what retention would delete");
+    println!("  CLIPSTTY_SESSION_ROLLOVER   Set to 1 to continue in a new session at the 4h limit instead of stopping");
     println!();
     println!("EXAMPLES:");
     println!("  # Basic usage with default model");
@@ -251,7 +280,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!(target: "runner", "│");
     info!(target: "runner", "│ COMMAND HANDLING:");
     info!(target: "runner", "│   Command cooldown: 1500ms (duplicate prevention)");
-    info!(target: "runner", "│   TTS quiet period: 3000ms (feedback prevention)");
+    info!(target: "runner", "│   TTS feedback:     echo-cancelled (input stays live)");
+    info!(target: "runner", "│   Wake word:        off (say \"enable wake word\")");
     info!(target: "runner", "│");
     info!(target: "runner", "╰─────────────────────────────────────────────────────");
     println!();
@@ -260,22 +290,83 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let data_dir = get_data_directory()?;
     info!(target: "runner", "[stt_to_clipboard].main data directory: {}", data_dir.display());
 
+    let settings_path = config_path(&data_dir);
+    let config = load_config(&settings_path)?;
+    let mut privacy = config.privacy.clone();
+
+    // Unlock storage encryption when a passphrase or key file is configured
+    let cipher = match KeySource::from_env().or_else(|| KeySource::from_config(&privacy)) {
+        Some(source) => {
+            let cipher = StorageCipher::unlock(&data_dir, &source)?;
+            info!(target: "runner", "[stt_to_clipboard].main storage encryption enabled (key {})", cipher.key_id());
+            Some(Arc::new(cipher))
+        }
+        None => None,
+    };
+
     // Capture audio continuously with simple segment window
     info!(target: "runner", "[stt_to_clipboard].main initializing audio service");
     let mut audio_service = AudioService::new()?;
+    // Stream format, gain, echo cancellation, pre-roll and the input device
+    // last chosen by voice command all come from the settings file
+    audio_service.apply_config(&config.audio)?;
+    audio_service.persist_device_to(Some(settings_path.clone()));
     info!(target: "runner", "[stt_to_clipboard].main audio service initialized");
     
     // Create AudioSessionManager for recording functionality
     let audio_service_arc = Arc::new(Mutex::new(audio_service));
-    let session_config = SessionConfig::default();
+    let mut session_config = SessionConfig::default();
+    session_config.supervisor.rollover = std::env::var("CLIPSTTY_SESSION_ROLLOVER").is_ok_and(|v| v == "1");
     let audio_session_manager = Arc::new(Mutex::new(
-        AudioSessionManager::new(
+        AudioSessionManager::new_with_cipher(
             audio_service_arc.clone(),
             data_dir.clone(),
             session_config,
+            cipher.clone(),
         )?
     ));
     info!(target: "runner", "[stt_to_clipboard].main audio session manager initialized");
+
+    // Finish any sessions a previous run left behind mid-recording
+    match audio_session_manager.lock().unwrap().recover_unfinished_sessions() {
+        Ok(recovered) if !recovered.is_empty() => {
+            info!(target: "runner", "[stt_to_clipboard].main recovered {} unfinished session(s)", recovered.len());
+            for session in &recovered {
+                println!("🩹 Recovered unfinished session '{}' ({:.1}s)", session.name, session.duration.as_secs_f64());
+            }
+        }
+        Ok(_) => {}
+        Err(e) => warn!(target: "runner", "[stt_to_clipboard].main session recovery failed: {}", e),
+    }
+
+    // Expire old recordings and transcripts now and periodically while running;
+    // only when the user opts in by setting a retention period. Runs after
+    // recovery so crashed sessions are finished before anything is swept.
+    if let Ok(spec) = std::env::var("CLIPSTTY_RETENTION") {
+        privacy.data_retention = spec;
+        privacy.auto_expiry = true;
+    }
+    let _retention = match RetentionEngine::from_privacy(data_dir.clone(), &privacy, cipher.clone())? {
+        Some(mut engine) => {
+            // Report what the first sweep will remove before anything is deleted
+            engine.set_dry_run(true);
+            match engine.run() {
+                Ok(preview) => {
+                    info!(target: "runner", "[stt_to_clipboard].main retention would expire {} file(s), {} session(s), {} transcript(s), {} bytes",
+                        preview.files_deleted, preview.sessions_removed, preview.transcripts_removed, preview.space_freed);
+                    for path in &preview.deleted_paths {
+                        info!(target: "runner", "  - {}", path.display());
+                    }
+                }
+                Err(e) => warn!(target: "runner", "[stt_to_clipboard].main retention preview failed: {}", e),
+            }
+            engine.set_dry_run(std::env::var("CLIPSTTY_RETENTION_DRY_RUN").is_ok_and(|v| v == "1"));
+            let interval = parse_duration(&privacy.retention_check_interval)?.to_std()?;
+            info!(target: "runner", "[stt_to_clipboard].main retention {:?}, checked every {}", engine.rules(), privacy.retention_check_interval);
+            Some(engine.spawn(interval))
+        }
+        None => None,
+    };
     
     // Get and log available audio devices
     if let Ok(devices) = audio_service_arc.lock().unwrap().get_devices() {
@@ -327,9 +418,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize comprehensive voice command engine
     let mut voice_command_engine = create_comprehensive_command_engine();
     
+    // Wake word gate; enrolled samples persist in the data directory
+    let wake_word_templates = data_dir.join("wake_word_templates.json");
+    let mut wake_word_gate = WakeWordGate::new(WakeWordConfig::from(&config.stt));
+    if config.stt.wake_word_enabled {
+        info!(target: "runner", "[stt_to_clipboard].main wake word \"{}\" enabled (sensitivity {:.2})", config.stt.wake_word_phrase, config.stt.wake_word_sensitivity);
+    }
+    if wake_word_templates.exists() {
+        match wake_word_gate.load_templates(&wake_word_templates) {
+            Ok(count) => info!(target: "runner", "[stt_to_clipboard].main loaded {} wake word samples", count),
+            Err(e) => warn!(target: "runner", "[stt_to_clipboard].main failed to load wake word samples: {}", e),
+        }
+    }
+    let wake_word = Arc::new(Mutex::new(wake_word_gate));
+
     // Create service context and connect AudioSessionManager
     let service_context = ServiceContext {
         audio_session_manager: Some(audio_session_manager.clone()),
+        audio_service: Some(audio_service_arc.clone()),
+        wake_word: Some(wake_word.clone()),
     };
     voice_command_engine.set_service_context(service_context);
     
@@ -343,7 +450,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let command_cooldown = Duration::from_millis(1500);
     // Quiet period after command: skip STT to avoid feedback/retrigger
     let mut command_quiet_until: Option<Instant> = None;
-    // Extended quiet period after TTS to avoid microphone feedback
+    // Quiet period after TTS, only used when feedback cannot be echo-cancelled
     let mut tts_quiet_until: Option<Instant> = None;
 
     // simple continuous loop: every window_ms collect & transcribe if there is speech
@@ -371,7 +478,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!(target: "runner", "│ VAD response time:  {}ms (silence detection)", hangover_ms);
     info!(target: "runner", "│ Min utterance:      {}ms (shortest speech)", min_speech_ms);
     info!(target: "runner", "│ Command cooldown:   {}ms (duplicate prevention)", command_cooldown.as_millis());
-    info!(target: "runner", "│ TTS quiet period:   {}ms (feedback prevention)", 3000);
+    info!(target: "runner", "│ TTS feedback:       echo-cancelled (quiet period only as fallback)");
     info!(target: "runner", "│ Expected RTF:       0.1-0.3x (real-time factor)");
     info!(target: "runner", "│");
     info!(target: "runner", "│ ADVANCED FEATURES:");
@@ -394,6 +501,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  • 'start recording [session_name]' - Begin audio recording");
     println!("  • 'stop recording' - End current recording");
     println!("  • 'pause recording' / 'resume recording' - Control recording state");
+    println!("  • 'mark that' / 'new chapter [title]' - Drop a marker or chapter in the recording");
     println!("  • 'list audio sessions' - Show all recorded sessions");
     println!("  • 'compress audio files' - Optimize storage space");
     println!("  • 'show storage statistics' - Display usage metrics");
@@ -447,6 +555,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     loop {
         std::thread::sleep(Duration::from_millis(poll_ms));
 
+        // Recover from unplugged/failed input devices and announce the change
+        let device_events = audio_service_arc.lock().map(|mut a| a.poll_device_health()).unwrap_or_default();
+        for event in device_events {
+            info!(target: "runner", "[stt_to_clipboard].main device event: {:?}", event);
+            if event.affects_capture() {
+                tts_quiet_until = speak_feedback(&event.announcement(), feedback_reference(&audio_service_arc));
+                // The new stream may run at a different rate; drop audio from the old device
+                if let Ok(mut buf) = captured.lock() { buf.clear(); }
+                voice_active = false;
+                last_voice_instant = None;
+                segment_first_instant = None;
+            }
+        }
+
+        // Auto-pause silent recording sessions and stop or roll over at the duration limit
+        let supervisor_event = audio_session_manager.lock().unwrap().supervise();
+        match supervisor_event {
+            Ok(Some(event)) => {
+                info!(target: "runner", "[stt_to_clipboard].main session supervisor: {}", event.announcement());
+                tts_quiet_until = speak_feedback(&event.announcement(), feedback_reference(&audio_service_arc));
+            }
+            Ok(None) => {}
+            Err(e) => error!(target: "runner", "[stt_to_clipboard].main session supervisor failed: {}", e),
+        }
+
         // pull buffer snapshot
         let (audio_raw, input_sr) = {
             let buf = captured.lock().unwrap().clone();
@@ -574,6 +707,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         
         if energy >= energy_threshold {
             last_voice_instant = Some(now);
+            audio_session_manager.lock().unwrap().note_voice_activity();
             if !voice_active {
                 voice_active = true;
                 segment_first_instant = Some(now);
@@ -631,8 +765,43 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     rtf,
                                     log_prob_str
                                 );
+                                // Wake word gate decides whether this utterance may be a command
+                                let decision = wake_word
+                                    .lock()
+                                    .map(|mut gate| gate.route(&result.text, &seg_audio, 16000))
+                                    .unwrap_or_else(|_| WakeDecision::Ungated(result.text.clone()));
+                                let (command_text, wake_triggered) = match decision {
+                                    WakeDecision::Ungated(text) => (Some(text), false),
+                                    WakeDecision::Command(text) => (Some(text), true),
+                                    WakeDecision::Dictation(_) => (None, false),
+                                    WakeDecision::WakeOnly => {
+                                        info!(target: "runner", "[stt_to_clipboard].main wake word heard, awaiting command");
+                                        tts_quiet_until = speak_feedback("Listening", feedback_reference(&audio_service_arc));
+                                        voice_active = false;
+                                        last_voice_instant = None;
+                                        segment_first_instant = None;
+                                        continue;
+                                    }
+                                    WakeDecision::Enrolled { templates } => {
+                                        info!(target: "runner", "[stt_to_clipboard].main wake word sample {} recorded", templates);
+                                        if let Ok(gate) = wake_word.lock() {
+                                            if let Err(e) = gate.save_templates(&wake_word_templates) {
+                                                warn!(target: "runner", "[stt_to_clipboard].main failed to save wake word samples: {}", e);
+                                            }
+                                        }
+                                        tts_quiet_until = speak_feedback(&format!("Wake word sample {} recorded", templates), feedback_reference(&audio_service_arc));
+                                        voice_active = false;
+                                        last_voice_instant = None;
+                                        segment_first_instant = None;
+                                        continue;
+                                    }
+                                };
                                 // Command recognition: intercept voice commands using comprehensive engine
-                                match voice_command_engine.process_voice_input(&result.text, result.confidence).await {
+                                let command_outcome = match &command_text {
+                                    Some(text) => voice_command_engine.process_voice_input(text, result.confidence).await,
+                                    None => Err(VoiceCommandError::CommandNotFound(result.text.clone())),
+                                };
+                                match command_outcome {
                                     Ok(command_result) => {
                                         let now = Instant::now();
                                         let suppress = match (last_command_text.as_ref(), last_command_instant) {
@@ -668,8 +837,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                             }
                                             
                                             // Speak the result message for feedback
-                                            speak(&command_result.message);
-                                            tts_quiet_until = Some(Instant::now() + Duration::from_millis(3000));
+                                            tts_quiet_until = speak_feedback(&command_result.message, feedback_reference(&audio_service_arc));
                                             
                                             last_command_text = Some(result.text.clone());
                                             last_command_instant = Some(now);
@@ -683,6 +851,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                         }
                                         continue;
                                     }
+                                    Err(_) if wake_triggered => {
+                                        // Addressed to us, so never dictate it
+                                        info!(target: "runner", "[stt_to_clipboard].main wake word command not recognized: {:?}", command_text);
+                                        tts_quiet_until = speak_feedback("Command not recognized", feedback_reference(&audio_service_arc));
+                                        voice_active = false;
+                                        last_voice_instant = None;
+                                        segment_first_instant = None;
+                                        continue;
+                                    }
                                     Err(_) => {
                                         // Not a recognized command, continue with normal transcription
                                     }
@@ -1163,6 +1340,39 @@ impl NarrationState {
 
 
 
+/// Echo canceller reference tap, if spoken feedback can be cancelled from the mic
+fn feedback_reference(audio_service: &Arc<Mutex<AudioService>>) -> Option<EchoReference> {
+    let audio = audio_service.lock().ok()?;
+    audio.is_echo_cancellation_enabled().then(|| audio.get_echo_reference())
+}
+
+/// Speak feedback without going deaf. With echo cancellation the speech is
+/// rendered and played through the canceller's reference tap so the user can
+/// talk over it. Otherwise fall back to muting input for about as long as the
+/// message takes to say; the returned instant is when input may resume.
+fn speak_feedback(text: &str, echo_reference: Option<EchoReference>) -> Option<Instant> {
+    static PLAYBACK: Mutex<()> = Mutex::new(());
+    if let Some(reference) = echo_reference {
+        match synthesize_to_samples(text) {
+            Ok((samples, sample_rate)) => {
+                std::thread::spawn(move || {
+                    // One message at a time, in order
+                    let _guard = PLAYBACK.lock();
+                    let mut playback = AudioPlaybackService::default();
+                    playback.set_echo_reference(Some(reference));
+                    if let Err(e) = playback.play_samples(samples, sample_rate) {
+                        warn!(target: "runner", "[stt_to_clipboard].speak_feedback playback failed: {}", e);
+                    }
+                });
+                return None;
+            }
+            Err(e) => debug!(target: "runner", "[stt_to_clipboard].speak_feedback cannot render speech ({}), muting input instead", e),
+        }
+    }
+    speak(text);
+    cfg!(target_os = "macos").then(|| Instant::now() + estimate_speech_duration(text))
+}
+
 #[cfg(target_os = "macos")]
 fn speak(text: &str) {
     let _ = std::process::Command::new("say").arg(text).spawn();
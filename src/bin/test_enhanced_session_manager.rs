@@ -105,6 +105,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         quality_monitoring: true,
         backup_enabled: false,
         default_audio_source: AudioSource::Microphone,
+        ..SessionConfig::default()
     };
     
     let mut session_manager = AudioSessionManager::new(
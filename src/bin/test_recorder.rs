@@ -301,7 +301,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                                     
                                                     // Play back the recording
                                                     let playback_path = std::path::Path::new(&full_path);
-                                                    match playback_service.play_wav_file(playback_path) {
+                                                    match playback_service.play_file(playback_path) {
                                                         Ok(_) => {
                                                             println!("🔊 Playback completed. Review the recording quality.");
                                                             
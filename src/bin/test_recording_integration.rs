@@ -40,6 +40,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create ServiceContext
     let service_context = ServiceContext {
         audio_session_manager: Some(audio_session_manager.clone()),
+        audio_service: Some(audio_service.clone()),
+        wake_word: None,
     };
     println!("🔗 Created ServiceContext");
     
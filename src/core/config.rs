@@ -71,6 +71,10 @@ pub struct AudioConfig {
     #[serde(default)]
     pub device_name: String,
 
+    /// Preferred input device by stable ID, e.g. "CoreAudio:USB Headset" (takes precedence over `device_name`)
+    #[serde(default)]
+    pub device_id: String,
+
     /// Activation mode for capture start/stop behavior
     #[serde(default = "default_activation_mode")]
     pub activation_mode: ActivationMode,
@@ -90,6 +94,50 @@ pub struct AudioConfig {
     /// Energy monitoring log cooldown in milliseconds
     #[serde(default = "default_energy_log_cooldown")]
     pub energy_log_cooldown: u64,
+
+    /// Enable automatic gain control
+    #[serde(default = "default_auto_gain_control")]
+    pub auto_gain_control: bool,
+
+    /// AGC target RMS level (0.0 to 1.0)
+    #[serde(default = "default_agc_target_rms")]
+    pub agc_target_rms: f32,
+
+    /// AGC attack time in milliseconds
+    #[serde(default = "default_agc_attack_ms")]
+    pub agc_attack_ms: f32,
+
+    /// AGC release time in milliseconds
+    #[serde(default = "default_agc_release_ms")]
+    pub agc_release_ms: f32,
+
+    /// Maximum gain the AGC may apply in dB
+    #[serde(default = "default_agc_max_gain_db")]
+    pub agc_max_gain_db: f32,
+
+    /// Manual input gain in dB (used when AGC is disabled)
+    #[serde(default)]
+    pub manual_gain_db: f32,
+
+    /// Capture buffer size in frames (0 for the driver default)
+    #[serde(default)]
+    pub buffer_size: u32,
+
+    /// Capture sample format: "f32", "i16", "i32" or "u16" (empty for the device default)
+    #[serde(default)]
+    pub sample_format: String,
+
+    /// Length of the pre-roll buffer kept across stream restarts, in milliseconds
+    #[serde(default = "default_preroll_ms")]
+    pub preroll_ms: u32,
+
+    /// Cancel the echo of spoken feedback from the microphone instead of muting input
+    #[serde(default = "default_echo_cancellation")]
+    pub echo_cancellation: bool,
+
+    /// Echo canceller filter length in milliseconds (speaker-to-mic delay plus room tail)
+    #[serde(default = "default_echo_filter_length_ms")]
+    pub echo_filter_length_ms: u32,
 }
 
 /// STT configuration
@@ -107,6 +155,14 @@ pub struct STTConfig {
     #[serde(default = "default_language")]
     pub language: String,
 
+    /// Whisper model file; empty uses WHISPER_MODEL_PATH
+    #[serde(default)]
+    pub model_path: String,
+
+    /// Beam search width; 0 or 1 decodes greedily
+    #[serde(default)]
+    pub beam_size: u32,
+
     /// Enable punctuation
     #[serde(default = "default_enable_punctuation")]
     pub enable_punctuation: bool,
@@ -122,6 +178,18 @@ pub struct STTConfig {
     /// API endpoint for cloud STT services
     #[serde(default)]
     pub api_endpoint: String,
+
+    /// Only treat speech as a command after the wake phrase
+    #[serde(default)]
+    pub wake_word_enabled: bool,
+
+    /// Wake phrase that switches the next utterance into command mode
+    #[serde(default = "default_wake_word_phrase")]
+    pub wake_word_phrase: String,
+
+    /// Wake phrase detection sensitivity (0.0 strict to 1.0 lenient)
+    #[serde(default = "default_wake_word_sensitivity")]
+    pub wake_word_sensitivity: f32,
 }
 
 /// Clipboard configuration
@@ -208,18 +276,43 @@ pub struct PrivacyConfig {
     #[serde(default = "default_data_retention")]
     pub data_retention: String,
 
-    /// Automatically expire old data
+    /// Automatically expire old data; off unless the user opts in
     #[serde(default = "default_auto_expiry")]
     pub auto_expiry: bool,
 
+    /// Retention for raw session audio; falls back to `data_retention`
+    #[serde(default)]
+    pub raw_audio_retention: Option<String>,
+
+    /// Retention for silence-trimmed session audio; falls back to `data_retention`
+    #[serde(default)]
+    pub cleaned_audio_retention: Option<String>,
+
+    /// Retention for per-utterance segment clips; falls back to `data_retention`
+    #[serde(default)]
+    pub segment_retention: Option<String>,
+
+    /// Retention for transcripts and session metadata; falls back to `data_retention`
+    #[serde(default)]
+    pub transcript_retention: Option<String>,
+
+    /// How often expired data is swept while running (e.g., "6h")
+    #[serde(default = "default_retention_check_interval")]
+    pub retention_check_interval: String,
+
     /// List of sensitive applications to exclude
     #[serde(default)]
     pub sensitive_apps: Vec<String>,
 
-    /// Encrypt clipboard history at rest
+    /// Encrypt recordings and transcripts at rest
     #[serde(default = "default_encrypt_storage")]
     pub encrypt_storage: bool,
 
+    /// Key file for storage encryption; a passphrase from
+    /// `CLIPSTTY_PASSPHRASE` is used when unset
+    #[serde(default)]
+    pub encryption_key_file: Option<PathBuf>,
+
     /// Enable usage analytics (opt-in)
     #[serde(default = "default_enable_analytics")]
     pub enable_analytics: bool,
@@ -342,6 +435,14 @@ impl Config {
             .into());
         }
 
+        if !(0.0..=1.0).contains(&self.stt.wake_word_sensitivity) {
+            return Err(crate::core::error::ConfigError::InvalidValue(format!(
+                "Wake word sensitivity must be between 0.0 and 1.0: {}",
+                self.stt.wake_word_sensitivity
+            ))
+            .into());
+        }
+
         // Validate clipboard capacity
         if self.clipboard.max_history > MAX_CLIPBOARD_HISTORY {
             return Err(crate::core::error::ConfigError::InvalidValue(format!(
@@ -395,6 +496,12 @@ fn default_enable_capitalization() -> bool {
 fn default_language() -> String {
     "en".to_string()
 }
+fn default_wake_word_phrase() -> String {
+    "clippy".to_string()
+}
+fn default_wake_word_sensitivity() -> f32 {
+    0.5
+}
 fn default_clipboard_capacity() -> usize {
     DEFAULT_CLIPBOARD_CAPACITY
 }
@@ -458,7 +565,10 @@ fn default_data_retention() -> String {
     "30d".to_string()
 }
 fn default_auto_expiry() -> bool {
-    true
+    false
+}
+fn default_retention_check_interval() -> String {
+    "6h".to_string()
 }
 fn default_encrypt_storage() -> bool {
     true
@@ -502,6 +612,38 @@ fn default_energy_log_cooldown() -> u64 {
     100
 }
 
+fn default_auto_gain_control() -> bool {
+    true
+}
+
+fn default_agc_target_rms() -> f32 {
+    0.05
+}
+
+fn default_agc_attack_ms() -> f32 {
+    10.0
+}
+
+fn default_agc_release_ms() -> f32 {
+    500.0
+}
+
+fn default_agc_max_gain_db() -> f32 {
+    20.0
+}
+
+fn default_preroll_ms() -> u32 {
+    2000
+}
+
+fn default_echo_cancellation() -> bool {
+    true
+}
+
+fn default_echo_filter_length_ms() -> u32 {
+    80
+}
+
 impl AudioConfig {
     pub fn new() -> Self {
         Self {
@@ -512,11 +654,23 @@ impl AudioConfig {
             enable_vad: true,
             noise_reduction: true,
             device_name: String::new(),
+            device_id: String::new(),
             activation_mode: default_activation_mode(),
             enable_energy_monitoring: default_enable_energy_monitoring(),
             energy_threshold_high: default_energy_threshold_high(),
             energy_threshold_low: default_energy_threshold_low(),
             energy_log_cooldown: default_energy_log_cooldown(),
+            auto_gain_control: default_auto_gain_control(),
+            agc_target_rms: default_agc_target_rms(),
+            agc_attack_ms: default_agc_attack_ms(),
+            agc_release_ms: default_agc_release_ms(),
+            agc_max_gain_db: default_agc_max_gain_db(),
+            manual_gain_db: 0.0,
+            buffer_size: 0,
+            sample_format: String::new(),
+            preroll_ms: default_preroll_ms(),
+            echo_cancellation: default_echo_cancellation(),
+            echo_filter_length_ms: default_echo_filter_length_ms(),
         }
     }
 }
@@ -527,10 +681,15 @@ impl STTConfig {
             backend: "local".to_string(),
             model_size: DEFAULT_STT_MODEL.to_string(),
             language: "en".to_string(),
+            model_path: String::new(),
+            beam_size: 0,
             enable_punctuation: true,
             enable_capitalization: true,
             api_key: String::new(),
             api_endpoint: String::new(),
+            wake_word_enabled: false,
+            wake_word_phrase: default_wake_word_phrase(),
+            wake_word_sensitivity: default_wake_word_sensitivity(),
         }
     }
 }
@@ -581,9 +740,15 @@ impl PrivacyConfig {
     pub fn new() -> Self {
         Self {
             data_retention: "30d".to_string(),
-            auto_expiry: true,
+            auto_expiry: default_auto_expiry(),
+            raw_audio_retention: None,
+            cleaned_audio_retention: None,
+            segment_retention: None,
+            transcript_retention: None,
+            retention_check_interval: default_retention_check_interval(),
             sensitive_apps: Vec::new(),
             encrypt_storage: true,
+            encryption_key_file: None,
             enable_analytics: false,
         }
     }
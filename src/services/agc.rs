@@ -0,0 +1,324 @@
+//! Automatic gain control (AGC) and soft limiter for the capture path.
+//!
+//! The AGC measures the RMS level of each incoming frame and smoothly steers
+//! the applied gain towards a target level, using separate attack (gain
+//! reduction) and release (gain increase) time constants. A soft limiter runs
+//! after the gain stage so loud headsets never hard-clip the signal fed to
+//! VAD and STT. When AGC is disabled a fixed manual gain is applied instead.
+
+use serde::{Deserialize, Serialize};
+
+/// AGC configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AGCConfig {
+    /// Enable automatic gain adjustment (manual gain is used when disabled)
+    pub enabled: bool,
+    /// Target RMS level (linear, 0.0 to 1.0)
+    pub target_rms: f32,
+    /// Attack time in milliseconds (how fast gain is reduced)
+    pub attack_ms: f32,
+    /// Release time in milliseconds (how fast gain is increased)
+    pub release_ms: f32,
+    /// Maximum gain in dB
+    pub max_gain_db: f32,
+    /// Minimum gain in dB
+    pub min_gain_db: f32,
+    /// Frames below this RMS are treated as silence and relax gain towards 0 dB
+    pub noise_gate_rms: f32,
+    /// Limiter threshold (linear); samples above it are soft-limited
+    pub limiter_threshold: f32,
+    /// Fixed gain in dB applied when AGC is disabled
+    pub manual_gain_db: f32,
+}
+
+/// Gain statistics gathered since the last reset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GainStats {
+    /// Gain currently applied in dB
+    pub current_gain_db: f32,
+    /// Lowest gain applied in dB
+    pub min_gain_db: f32,
+    /// Highest gain applied in dB
+    pub max_gain_db: f32,
+    /// Average gain applied in dB (per frame)
+    pub average_gain_db: f32,
+    /// Number of frames in which the limiter engaged
+    pub limiter_activations: u32,
+    /// Number of frames processed
+    pub frames_processed: u64,
+}
+
+/// Automatic gain control with a soft limiter
+#[derive(Debug)]
+pub struct AutomaticGainControl {
+    config: AGCConfig,
+    /// Current linear gain
+    gain: f32,
+    stats: GainStats,
+    gain_db_sum: f64,
+}
+
+impl Default for AGCConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            target_rms: 0.05,
+            attack_ms: 10.0,
+            release_ms: 500.0,
+            max_gain_db: 20.0,
+            min_gain_db: -20.0,
+            noise_gate_rms: 0.01,
+            limiter_threshold: 0.8,
+            manual_gain_db: 0.0,
+        }
+    }
+}
+
+impl From<&crate::core::config::AudioConfig> for AGCConfig {
+    fn from(config: &crate::core::config::AudioConfig) -> Self {
+        Self {
+            enabled: config.auto_gain_control,
+            target_rms: config.agc_target_rms,
+            attack_ms: config.agc_attack_ms,
+            release_ms: config.agc_release_ms,
+            max_gain_db: config.agc_max_gain_db,
+            manual_gain_db: config.manual_gain_db,
+            ..Self::default()
+        }
+    }
+}
+
+impl From<&crate::core::types::AudioConfig> for AGCConfig {
+    fn from(config: &crate::core::types::AudioConfig) -> Self {
+        Self {
+            enabled: config.auto_gain_control,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for GainStats {
+    fn default() -> Self {
+        Self {
+            current_gain_db: 0.0,
+            min_gain_db: 0.0,
+            max_gain_db: 0.0,
+            average_gain_db: 0.0,
+            limiter_activations: 0,
+            frames_processed: 0,
+        }
+    }
+}
+
+impl AutomaticGainControl {
+    /// Create a new AGC stage
+    pub fn new(config: AGCConfig) -> Self {
+        let gain = if config.enabled { 1.0 } else { db_to_linear(config.manual_gain_db) };
+        let mut agc = Self {
+            config,
+            gain,
+            stats: GainStats::default(),
+            gain_db_sum: 0.0,
+        };
+        agc.reset_stats();
+        agc
+    }
+
+    /// Get current configuration
+    pub fn config(&self) -> &AGCConfig {
+        &self.config
+    }
+
+    /// Replace configuration
+    pub fn set_config(&mut self, config: AGCConfig) {
+        self.config = config;
+    }
+
+    /// Enable or disable automatic gain adjustment
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.config.enabled = enabled;
+    }
+
+    /// Check if automatic gain adjustment is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Set the manual gain in dB (used while AGC is disabled)
+    pub fn set_manual_gain_db(&mut self, gain_db: f32) {
+        self.config.manual_gain_db = gain_db.clamp(self.config.min_gain_db, self.config.max_gain_db);
+    }
+
+    /// Get the manual gain in dB
+    pub fn manual_gain_db(&self) -> f32 {
+        self.config.manual_gain_db
+    }
+
+    /// Gain currently applied in dB
+    pub fn current_gain_db(&self) -> f32 {
+        linear_to_db(self.gain)
+    }
+
+    /// Get gain statistics since the last reset
+    pub fn stats(&self) -> GainStats {
+        self.stats.clone()
+    }
+
+    /// Reset gain statistics (e.g. at the start of a recording session)
+    pub fn reset_stats(&mut self) {
+        let current = self.current_gain_db();
+        self.stats = GainStats {
+            current_gain_db: current,
+            min_gain_db: current,
+            max_gain_db: current,
+            average_gain_db: current,
+            limiter_activations: 0,
+            frames_processed: 0,
+        };
+        self.gain_db_sum = 0.0;
+    }
+
+    /// Apply gain and limiting to a frame of mono samples in place
+    pub fn process(&mut self, samples: &mut [f32], sample_rate: u32) {
+        if samples.is_empty() || sample_rate == 0 {
+            return;
+        }
+
+        let target_gain = self.target_gain(samples);
+        let attack = time_constant_coefficient(self.config.attack_ms, sample_rate);
+        let release = time_constant_coefficient(self.config.release_ms, sample_rate);
+        let coefficient = if target_gain < self.gain { attack } else { release };
+
+        let mut limited = false;
+        for sample in samples.iter_mut() {
+            self.gain = target_gain + (self.gain - target_gain) * coefficient;
+            let amplified = *sample * self.gain;
+            let (output, engaged) = soft_limit(amplified, self.config.limiter_threshold);
+            limited |= engaged;
+            *sample = output;
+        }
+
+        self.record_frame(limited);
+    }
+
+    /// Compute the gain this frame should converge to
+    fn target_gain(&self, samples: &[f32]) -> f32 {
+        if !self.config.enabled {
+            return db_to_linear(self.config.manual_gain_db);
+        }
+
+        let rms = compute_rms(samples);
+        let desired = if rms < self.config.noise_gate_rms {
+            // Do not pump up background noise; drift back towards unity instead
+            1.0
+        } else {
+            self.config.target_rms / rms
+        };
+        desired.clamp(
+            db_to_linear(self.config.min_gain_db),
+            db_to_linear(self.config.max_gain_db),
+        )
+    }
+
+    fn record_frame(&mut self, limited: bool) {
+        let gain_db = self.current_gain_db();
+        self.stats.frames_processed += 1;
+        self.gain_db_sum += gain_db as f64;
+        self.stats.current_gain_db = gain_db;
+        self.stats.min_gain_db = self.stats.min_gain_db.min(gain_db);
+        self.stats.max_gain_db = self.stats.max_gain_db.max(gain_db);
+        self.stats.average_gain_db = (self.gain_db_sum / self.stats.frames_processed as f64) as f32;
+        if limited {
+            self.stats.limiter_activations += 1;
+        }
+    }
+}
+
+/// Convert decibels to a linear gain factor
+pub fn db_to_linear(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Convert a linear gain factor to decibels
+pub fn linear_to_db(gain: f32) -> f32 {
+    20.0 * gain.max(1e-6).log10()
+}
+
+/// Soft limiter: samples below `threshold` pass through unchanged, samples
+/// above it are compressed with a tanh knee that approaches (but never
+/// exceeds) full scale. Returns the output sample and whether limiting engaged.
+fn soft_limit(sample: f32, threshold: f32) -> (f32, bool) {
+    let threshold = threshold.clamp(0.1, 0.99);
+    let magnitude = sample.abs();
+    if magnitude <= threshold {
+        return (sample, false);
+    }
+    let headroom = 1.0 - threshold;
+    let limited = threshold + headroom * ((magnitude - threshold) / headroom).tanh();
+    (limited.copysign(sample), true)
+}
+
+/// Per-sample smoothing coefficient for a time constant in milliseconds
+fn time_constant_coefficient(time_ms: f32, sample_rate: u32) -> f32 {
+    if time_ms <= 0.0 {
+        return 0.0;
+    }
+    (-1.0 / (time_ms / 1000.0 * sample_rate as f32)).exp()
+}
+
+fn compute_rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum_sq: f32 = samples.iter().map(|s| s * s).sum();
+    (sum_sq / samples.len() as f32).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(amplitude: f32, len: usize, sample_rate: u32) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_quiet_input_is_boosted_towards_target() {
+        let mut agc = AutomaticGainControl::new(AGCConfig::default());
+        let sample_rate = 16000;
+        let mut last = Vec::new();
+        for _ in 0..50 {
+            let mut frame = sine(0.02, 1600, sample_rate);
+            agc.process(&mut frame, sample_rate);
+            last = frame;
+        }
+        let rms = compute_rms(&last);
+        assert!(rms > 0.04 && rms < 0.06, "rms after AGC was {rms}");
+        assert!(agc.stats().current_gain_db > 6.0);
+    }
+
+    #[test]
+    fn test_limiter_prevents_clipping() {
+        let mut agc = AutomaticGainControl::new(AGCConfig {
+            enabled: false,
+            manual_gain_db: 12.0,
+            ..AGCConfig::default()
+        });
+        let mut frame = sine(0.9, 1600, 16000);
+        agc.process(&mut frame, 16000);
+        assert!(frame.iter().all(|s| s.abs() <= 1.0));
+        assert!(agc.stats().limiter_activations > 0);
+    }
+
+    #[test]
+    fn test_silence_does_not_raise_gain() {
+        let mut agc = AutomaticGainControl::new(AGCConfig::default());
+        for _ in 0..50 {
+            let mut frame = sine(0.001, 1600, 16000);
+            agc.process(&mut frame, 16000);
+        }
+        assert!(agc.current_gain_db().abs() < 0.5);
+    }
+}
@@ -0,0 +1,401 @@
+//! Integrity checking and repair of the session archive.
+//!
+//! The audio index (`session_index.json`) and the transcript index
+//! (`transcripts/index.json`) can drift from the files on disk after manual
+//! deletes or crashes, and sessions then silently drop out of listings.
+//! [`ArchiveChecker`] verifies every index entry against its file, checks
+//! audio headers and checksums, finds orphaned audio and session directories
+//! missing from the index, and with repair enabled rebuilds both indexes from
+//! `session_metadata.json` and the transcript files. Damaged or orphaned files
+//! are never deleted: unreadable transcript files are renamed aside and
+//! orphaned audio is moved to `lost+found/`.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tracing::{info, warn};
+
+use super::audio_archive::AudioError;
+use super::audio_session_manager::{collect_session_metadata, read_saved_metadata};
+use super::audio_storage::{FileAudioStorage, StorageConfig};
+use super::encryption::{self, StorageCipher};
+use super::flac;
+use super::session_journal::JOURNAL_DIR;
+use super::transcript_storage::FileTranscriptStorage;
+
+/// Directory under the data directory that orphaned audio is moved to
+pub const LOST_AND_FOUND_DIR: &str = "lost+found";
+
+const AUDIO_EXTENSIONS: &[&str] = &["wav", "flac", "opus", "ogg", "mp3"];
+
+/// Kind of problem found in the archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    /// An index file could not be read
+    CorruptIndex,
+    /// An index entry or session refers to a file that is gone
+    MissingFile,
+    /// A metadata or transcript file could not be parsed
+    CorruptFile,
+    /// An audio file's header is not valid for its format
+    InvalidHeader,
+    /// An audio file no longer matches the checksum in the index
+    ChecksumMismatch,
+    /// An index entry disagrees with the file it points to
+    StaleIndexEntry,
+    /// A session or transcript on disk is missing from its index
+    Unindexed,
+    /// An audio file nothing refers to
+    Orphaned,
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::CorruptIndex => "corrupt index",
+            Self::MissingFile => "missing file",
+            Self::CorruptFile => "corrupt file",
+            Self::InvalidHeader => "invalid header",
+            Self::ChecksumMismatch => "checksum mismatch",
+            Self::StaleIndexEntry => "stale index entry",
+            Self::Unindexed => "not indexed",
+            Self::Orphaned => "orphaned file",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One problem found by a check
+#[derive(Debug, Clone)]
+pub struct ArchiveIssue {
+    pub kind: IssueKind,
+    pub path: PathBuf,
+    pub detail: String,
+    /// Whether the repair pass fixed it
+    pub repaired: bool,
+}
+
+impl ArchiveIssue {
+    pub fn new(kind: IssueKind, path: impl Into<PathBuf>, detail: impl Into<String>) -> Self {
+        Self { kind, path: path.into(), detail: detail.into(), repaired: false }
+    }
+}
+
+impl fmt::Display for ArchiveIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.kind, self.path.display(), self.detail)?;
+        if self.repaired {
+            write!(f, " [repaired]")?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of an archive check
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveCheckReport {
+    pub sessions_checked: usize,
+    pub audio_files_checked: usize,
+    pub transcripts_checked: usize,
+    pub issues: Vec<ArchiveIssue>,
+}
+
+impl ArchiveCheckReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Issues still present after the check
+    pub fn unrepaired(&self) -> impl Iterator<Item = &ArchiveIssue> {
+        self.issues.iter().filter(|issue| !issue.repaired)
+    }
+}
+
+/// Checks, and optionally repairs, an archive data directory
+pub struct ArchiveChecker {
+    data_dir: PathBuf,
+    cipher: Option<Arc<StorageCipher>>,
+    repair: bool,
+}
+
+impl ArchiveChecker {
+    pub fn new(data_dir: PathBuf) -> Self {
+        Self::new_with_cipher(data_dir, None)
+    }
+
+    pub fn new_with_cipher(data_dir: PathBuf, cipher: Option<Arc<StorageCipher>>) -> Self {
+        Self { data_dir, cipher, repair: false }
+    }
+
+    /// Fix what can be fixed instead of only reporting it
+    pub fn set_repair(&mut self, repair: bool) {
+        self.repair = repair;
+    }
+
+    /// Check the archive, opening its audio index
+    pub fn run(&self) -> Result<ArchiveCheckReport, AudioError> {
+        let mut report = ArchiveCheckReport::default();
+        let index_path = self.data_dir.join("session_index.json");
+        let open = || FileAudioStorage::new_with_cipher(self.data_dir.clone(), StorageConfig::default(), self.cipher.clone());
+        let mut storage = match open() {
+            Ok(storage) => storage,
+            Err(e) => {
+                let mut issue = ArchiveIssue::new(IssueKind::CorruptIndex, &index_path, e.to_string());
+                if !self.repair {
+                    report.issues.push(issue);
+                    return Ok(report);
+                }
+                // Keep the unreadable index for inspection and rebuild from scratch
+                fs::rename(&index_path, index_path.with_extension("json.corrupt"))?;
+                issue.repaired = true;
+                report.issues.push(issue);
+                open()?
+            }
+        };
+        self.run_with_storage(&mut storage, report)
+    }
+
+    /// Check the archive against an audio index that is already open
+    pub fn run_with_storage(&self, storage: &mut FileAudioStorage, mut report: ArchiveCheckReport) -> Result<ArchiveCheckReport, AudioError> {
+        let (checked, issues) = storage.check_index(self.repair)?;
+        report.audio_files_checked += checked;
+        report.issues.extend(issues);
+
+        self.check_sessions(storage, &mut report)?;
+        self.check_orphans(storage, &mut report)?;
+
+        let (checked, issues) = FileTranscriptStorage::check(&self.data_dir.join("transcripts"), self.cipher.as_deref(), self.repair)
+            .map_err(|e| AudioError::StorageError(e.to_string()))?;
+        report.transcripts_checked = checked;
+        report.issues.extend(issues);
+
+        for issue in report.unrepaired() {
+            warn!(issue = %issue, "Archive check found a problem");
+        }
+        info!(
+            sessions = report.sessions_checked,
+            audio_files = report.audio_files_checked,
+            transcripts = report.transcripts_checked,
+            issues = report.issues.len(),
+            repaired = report.issues.iter().filter(|issue| issue.repaired).count(),
+            "🩺 Archive check finished"
+        );
+        Ok(report)
+    }
+
+    /// Saved sessions: readable metadata, valid raw audio (WAV, or FLAC once
+    /// compressed), and an index entry
+    fn check_sessions(&self, storage: &mut FileAudioStorage, report: &mut ArchiveCheckReport) -> Result<(), AudioError> {
+        let mut paths = Vec::new();
+        collect_session_metadata(&self.data_dir.join("sessions"), &mut paths)?;
+        for path in paths {
+            report.sessions_checked += 1;
+            let session = match read_saved_metadata(&path, self.cipher.as_deref()) {
+                Ok(session) => session,
+                Err(e) => {
+                    report.issues.push(ArchiveIssue::new(IssueKind::CorruptFile, &path, e.to_string()));
+                    continue;
+                }
+            };
+            match encryption::read_file(&session.file_path, self.cipher.as_deref()) {
+                Ok(audio) => {
+                    let header = if session.file_path.extension().is_some_and(|ext| ext == "flac") {
+                        flac::read_stream_info(&audio).map(|_| ()).map_err(|e| e.to_string())
+                    } else {
+                        hound::WavReader::new(Cursor::new(audio)).map(|_| ()).map_err(|e| e.to_string())
+                    };
+                    if let Err(e) = header {
+                        report.issues.push(ArchiveIssue::new(IssueKind::InvalidHeader, &session.file_path, e));
+                        continue;
+                    }
+                }
+                Err(e) => {
+                    report.issues.push(ArchiveIssue::new(IssueKind::MissingFile, &session.file_path, e.to_string()));
+                    continue;
+                }
+            }
+            if !storage.is_indexed(session.id) {
+                let mut issue = ArchiveIssue::new(IssueKind::Unindexed, &path, format!("session {} is missing from the audio index", session.id));
+                if self.repair {
+                    storage.index_saved_session(&session)?;
+                    issue.repaired = true;
+                }
+                report.issues.push(issue);
+            }
+        }
+        Ok(())
+    }
+
+    /// Audio files outside session directories that the index doesn't know
+    fn check_orphans(&self, storage: &FileAudioStorage, report: &mut ArchiveCheckReport) -> Result<(), AudioError> {
+        let indexed = storage.indexed_paths();
+        let mut orphans = Vec::new();
+        find_orphans(&self.data_dir, &self.data_dir, &indexed, &mut orphans)?;
+        for orphan in orphans {
+            let mut issue = ArchiveIssue::new(IssueKind::Orphaned, &orphan, "not referenced by any session or index entry");
+            if self.repair {
+                let relative = orphan.strip_prefix(&self.data_dir).unwrap_or(&orphan);
+                let target = self.data_dir.join(LOST_AND_FOUND_DIR).join(relative);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(&orphan, &target)?;
+                issue.detail = format!("moved to {}", target.display());
+                issue.repaired = true;
+            }
+            report.issues.push(issue);
+        }
+        Ok(())
+    }
+}
+
+fn find_orphans(root: &Path, dir: &Path, indexed: &HashSet<PathBuf>, orphans: &mut Vec<PathBuf>) -> Result<(), AudioError> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    // Everything in a session directory, finished or still journalling, belongs to it
+    if dir.join("session_metadata.json").exists() || dir.join(JOURNAL_DIR).is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if dir == root && path.file_name().is_some_and(|name| name == "transcripts" || name == LOST_AND_FOUND_DIR) {
+                continue;
+            }
+            find_orphans(root, &path, indexed, orphans)?;
+        } else if is_audio_file(&path) && !indexed.contains(&path) && !path.with_extension("json").exists() {
+            // Older recordings keep their metadata in a JSON file of the same name
+            orphans.push(path);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| AUDIO_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::audio_archive::{AudioFormat, AudioFormatInfo, AudioStorage, RecordingSession, SearchCriteria};
+    use crate::services::audio_session_manager::AudioRecordingSession;
+    use crate::services::transcript_storage::FileStorageConfig;
+    use crate::services::transcription_log::{TranscriptStorage, TranscriptionLogConfig, TranscriptionLogService};
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn format_info() -> AudioFormatInfo {
+        AudioFormatInfo { sample_rate: 8000, channels: 1, bit_depth: 16, format: AudioFormat::WAV }
+    }
+
+    fn write_wav(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let spec = hound::WavSpec { channels: 1, sample_rate: 8000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        (0..800).for_each(|i| writer.write_sample(i as i16).unwrap());
+        writer.finalize().unwrap();
+    }
+
+    fn save_session(data_dir: &Path, name: &str) -> AudioRecordingSession {
+        let dir = data_dir.join("sessions").join(name);
+        let session = AudioRecordingSession::for_test(name)
+            .with_duration(Duration::from_millis(100))
+            .with_file_path(dir.join("raw_audio.wav"));
+        write_wav(&session.file_path);
+        let metadata = serde_json::json!({ "session": session });
+        fs::write(dir.join("session_metadata.json"), serde_json::to_vec(&metadata).unwrap()).unwrap();
+        session
+    }
+
+    #[test]
+    fn test_repair_rebuilds_audio_index_and_moves_orphans() {
+        let dir = TempDir::new().unwrap();
+        let data_dir = dir.path().to_path_buf();
+        let saved = save_session(&data_dir, "standup");
+        write_wav(&data_dir.join("stray").join("old_take.wav"));
+        // Recordings saved by voice commands keep a metadata file next to the audio
+        let legacy = data_dir.join("sessions/2024/05/07").join(format!("memo_{}.wav", Uuid::new_v4()));
+        write_wav(&legacy);
+        fs::write(legacy.with_extension("json"), b"{}").unwrap();
+
+        // An index entry whose file was deleted by hand
+        let mut storage = FileAudioStorage::new(data_dir.clone(), StorageConfig::default()).unwrap();
+        let recorded = RecordingSession {
+            id: Uuid::new_v4(),
+            name: "deleted".to_string(),
+            description: None,
+            start_time: Utc::now(),
+            end_time: None,
+            duration: Duration::from_secs(1),
+            file_path: PathBuf::new(),
+            file_size: 0,
+            format_info: format_info(),
+            tags: Vec::new(),
+            transcript_count: 0,
+            metadata: HashMap::new(),
+        };
+        storage.store_audio(&recorded, &[0.1; 800]).unwrap();
+        let stored = storage.list_sessions(SearchCriteria::default()).unwrap().remove(0);
+        fs::remove_file(&stored.file_path).unwrap();
+        drop(storage);
+
+        let report = ArchiveChecker::new(data_dir.clone()).run().unwrap();
+        let kinds: Vec<IssueKind> = report.issues.iter().map(|issue| issue.kind).collect();
+        assert!(kinds.contains(&IssueKind::MissingFile));
+        assert!(kinds.contains(&IssueKind::Unindexed));
+        assert!(kinds.contains(&IssueKind::Orphaned));
+        assert_eq!(report.unrepaired().count(), report.issues.len());
+
+        let mut checker = ArchiveChecker::new(data_dir.clone());
+        checker.set_repair(true);
+        let report = checker.run().unwrap();
+        assert_eq!(report.unrepaired().count(), 0, "{:?}", report.issues);
+        assert!(data_dir.join(LOST_AND_FOUND_DIR).join("stray/old_take.wav").exists());
+        assert!(legacy.exists());
+
+        let storage = FileAudioStorage::new(data_dir.clone(), StorageConfig::default()).unwrap();
+        let listed = storage.list_sessions(SearchCriteria::default()).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, saved.id);
+        assert!(ArchiveChecker::new(data_dir).run().unwrap().is_clean());
+    }
+
+    #[test]
+    fn test_repair_rebuilds_transcript_index_from_files() {
+        let dir = TempDir::new().unwrap();
+        let transcripts_dir = dir.path().join("transcripts");
+        let config = TranscriptionLogConfig { storage_path: transcripts_dir.clone(), ..Default::default() };
+        let mut log = TranscriptionLogService::new(config).unwrap();
+        let first = log.log_transcription("first note", 0.9, "base", 1000).unwrap();
+        let second = log.log_transcription("second note entirely different", 0.9, "base", 1000).unwrap();
+        drop(log);
+
+        fs::remove_file(transcripts_dir.join("index.json")).unwrap();
+        fs::write(transcripts_dir.join("transcripts_broken.json"), b"{ not json").unwrap();
+
+        let report = ArchiveChecker::new(dir.path().to_path_buf()).run().unwrap();
+        assert_eq!(report.transcripts_checked, 2);
+        assert_eq!(report.issues.iter().filter(|issue| issue.kind == IssueKind::Unindexed).count(), 2);
+        assert!(report.issues.iter().any(|issue| issue.kind == IssueKind::CorruptFile));
+
+        let mut checker = ArchiveChecker::new(dir.path().to_path_buf());
+        checker.set_repair(true);
+        assert_eq!(checker.run().unwrap().unrepaired().count(), 0);
+        assert!(transcripts_dir.join("transcripts_broken.json.corrupt").exists());
+
+        let storage = FileTranscriptStorage::new(transcripts_dir, FileStorageConfig::default()).unwrap();
+        assert!(storage.get_transcript(first.id).unwrap().is_some());
+        assert!(storage.get_transcript(second.id).unwrap().is_some());
+    }
+}
@@ -1,29 +1,70 @@
 //! Audio service for capturing and processing audio input.
 
 use crate::{core::types::*, Result};
+use crate::core::config::Config;
 use crate::services::vad::VADService;
+use crate::services::agc::{AGCConfig, AutomaticGainControl, GainStats};
+use crate::services::capture_config::{self, CaptureConfig, NegotiatedConfig, PreRollBuffer};
+use crate::services::device_monitor::{self, DeviceEvent, DeviceWatcher};
+use crate::services::source_mixer::{MixerStats, SecondarySourceConfig, SourceMixer};
+use crate::services::echo_cancel::{EchoCancellerConfig, EchoReference, EchoStats, EchoSuppressor};
 use crate::core::types::VADResult;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Sample, SampleFormat, Stream};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicU8, Ordering};
 use std::fmt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Callback receiving mono f32 frames and their sample rate
+type AudioFrameCallback = Arc<dyn Fn(&[f32], u32) + Send + Sync>;
+/// Callback receiving interleaved frames, channel count and sample rate
+type ChannelFrameCallback = Arc<dyn Fn(&[f32], u16, u32) + Send + Sync>;
+/// Callback receiving device hot-plug events
+type DeviceCallback = Arc<dyn Fn(&DeviceEvent) + Send + Sync>;
 
 /// Audio service for managing audio capture and processing
 pub struct AudioService {
     input_device: Option<cpal::Device>,
     input_stream: Option<Stream>,
     capturing: bool,
-    selected_device_name: Option<String>,
+    preferred_device: Option<String>,
+    active_device_id: Option<String>,
+    failed_over: bool,
+    capture_wanted: bool,
+    capture_lost: bool,
+    stream_error: Arc<Mutex<Option<String>>>,
+    device_watcher: DeviceWatcher,
+    last_device_scan: Option<Instant>,
+    device_callbacks: Vec<DeviceCallback>,
+    pending_events: Vec<DeviceEvent>,
     vad: Option<Arc<Mutex<VADService>>>,
     vad_callback: Option<Arc<dyn Fn(VADResult) + Send + Sync>>, 
-    audio_callbacks: Vec<Arc<dyn Fn(&[f32], u32) + Send + Sync>>,
+    audio_callbacks: Vec<AudioFrameCallback>,
+    channel_callbacks: Vec<ChannelFrameCallback>,
     ptt_active: bool,
     vad_enabled: bool,
     vad_control: Arc<AtomicU8>,
+    agc: Arc<Mutex<AutomaticGainControl>>,
+    capture_config: CaptureConfig,
+    active_config: Option<NegotiatedConfig>,
+    preroll: Arc<Mutex<PreRollBuffer>>,
+    secondary_source: Option<SecondarySourceConfig>,
+    secondary_stream: Option<Stream>,
+    mixer: Option<Arc<Mutex<SourceMixer>>>,
+    echo: Arc<Mutex<EchoSuppressor>>,
+    /// Config file the chosen input device is saved to on switch
+    settings_path: Option<PathBuf>,
 }
 
+/// Default pre-roll length in milliseconds
+const DEFAULT_PREROLL_MS: u32 = 2000;
+
+/// How often the input device list is rescanned for hot-plug changes
+const DEVICE_SCAN_INTERVAL: Duration = Duration::from_secs(2);
+
 impl AudioService {
     /// Create a new audio service
     pub fn new() -> Result<Self> {
@@ -31,20 +72,101 @@ impl AudioService {
             input_device: None,
             input_stream: None,
             capturing: false,
-            selected_device_name: None,
+            preferred_device: None,
+            active_device_id: None,
+            failed_over: false,
+            capture_wanted: false,
+            capture_lost: false,
+            stream_error: Arc::new(Mutex::new(None)),
+            device_watcher: DeviceWatcher::new(),
+            last_device_scan: None,
+            device_callbacks: Vec::new(),
+            pending_events: Vec::new(),
             vad: None,
             vad_callback: None,
             audio_callbacks: Vec::new(),
+            channel_callbacks: Vec::new(),
             ptt_active: false,
             vad_enabled: true,
             vad_control: Arc::new(AtomicU8::new(0)),
+            agc: Arc::new(Mutex::new(AutomaticGainControl::new(AGCConfig::default()))),
+            capture_config: CaptureConfig::default(),
+            active_config: None,
+            preroll: Arc::new(Mutex::new(PreRollBuffer::new(DEFAULT_PREROLL_MS))),
+            secondary_source: None,
+            secondary_stream: None,
+            mixer: None,
+            echo: Arc::new(Mutex::new(EchoSuppressor::new(EchoCancellerConfig::default(), EchoReference::new()))),
+            settings_path: None,
         })
     }
 
-	/// Select input device by name (None to use system default)
-	pub fn select_input_device_by_name(&mut self, name: Option<String>) {
-		self.selected_device_name = name;
-	}
+    /// Select input device by name (None to use system default).
+    /// The name is resolved to a stable device ID when the device is present.
+    pub fn select_input_device_by_name(&mut self, name: Option<String>) {
+        let resolved = name.map(|n| {
+            list_input_devices(&cpal::default_host())
+                .into_iter()
+                .find(|(id, dname, _)| device_monitor::matches_preference(&n, id, dname))
+                .map(|(id, _, _)| id)
+                .unwrap_or(n)
+        });
+        self.select_input_device_by_id(resolved);
+    }
+
+    /// Select input device by stable ID (None to use system default)
+    pub fn select_input_device_by_id(&mut self, device_id: Option<String>) {
+        self.preferred_device = device_id;
+        self.failed_over = false;
+    }
+
+    /// Preferred input device (stable ID, or a legacy display name)
+    pub fn preferred_device(&self) -> Option<&str> {
+        self.preferred_device.as_deref()
+    }
+
+    /// Save the device chosen by `switch_input_device` to this config file as `audio.device_id`
+    pub fn persist_device_to(&mut self, settings_path: Option<PathBuf>) {
+        self.settings_path = settings_path;
+    }
+
+    /// Switch capture to another input device, restarting a running stream,
+    /// and remember it in the settings file
+    pub fn switch_input_device(&mut self, device_id: Option<String>) -> Result<()> {
+        self.select_input_device_by_id(device_id);
+        self.restart_capture()?;
+        self.save_preferred_device()
+    }
+
+    fn save_preferred_device(&self) -> Result<()> {
+        let Some(path) = &self.settings_path else {
+            return Ok(());
+        };
+        let mut config = if path.exists() { Config::from_file(path)? } else { Config::new() };
+        config.audio.device_id = self.preferred_device.clone().unwrap_or_default();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        config.save_to_file(path)?;
+        info!(device = %config.audio.device_id, path = %path.display(), "Saved preferred input device");
+        Ok(())
+    }
+
+    /// Apply audio settings from the application configuration
+    pub fn apply_config(&mut self, config: &crate::core::config::AudioConfig) -> Result<()> {
+        self.configure_agc(AGCConfig::from(config));
+        self.configure_echo_cancellation(EchoCancellerConfig::from(config));
+        self.set_preroll_duration_ms(config.preroll_ms);
+        let preferred = [&config.device_id, &config.device_name]
+            .into_iter()
+            .find(|d| !d.is_empty())
+            .cloned();
+        if preferred.is_some() {
+            self.select_input_device_by_name(preferred);
+        }
+        self.set_capture_config(CaptureConfig::from(config))?;
+        Ok(())
+    }
 
     /// Attach a VAD service used to gate/monitor voice activity
     pub fn attach_vad(&mut self, vad: Arc<Mutex<VADService>>) {
@@ -84,6 +206,236 @@ impl AudioService {
         }
     }
 
+    /// Replace the AGC configuration
+    pub fn configure_agc(&mut self, config: AGCConfig) {
+        if let Ok(mut g) = self.agc.lock() {
+            g.set_config(config);
+        }
+    }
+
+    /// Enable or disable automatic gain control at runtime
+    pub fn set_agc_enabled(&mut self, enabled: bool) {
+        if let Ok(mut g) = self.agc.lock() {
+            g.set_enabled(enabled);
+            info!(agc_enabled = enabled, "Updated automatic gain control");
+        }
+    }
+
+    /// Query AGC enabled flag
+    pub fn is_agc_enabled(&self) -> bool {
+        self.agc.lock().map(|g| g.is_enabled()).unwrap_or(false)
+    }
+
+    /// Set manual input gain in dB (applied while AGC is disabled)
+    pub fn set_manual_gain_db(&mut self, gain_db: f32) {
+        if let Ok(mut g) = self.agc.lock() {
+            g.set_manual_gain_db(gain_db);
+            info!(manual_gain_db = g.manual_gain_db(), "Set manual input gain");
+        }
+    }
+
+    /// Get gain statistics gathered since the last reset
+    pub fn get_gain_stats(&self) -> GainStats {
+        self.agc.lock().map(|g| g.stats()).unwrap_or_default()
+    }
+
+    /// Reset gain statistics (e.g. at the start of a recording session)
+    pub fn reset_gain_stats(&self) {
+        if let Ok(mut g) = self.agc.lock() {
+            g.reset_stats();
+        }
+    }
+
+    /// Provide a thread-safe handle to the AGC stage
+    pub fn get_agc_handle(&self) -> Arc<Mutex<AutomaticGainControl>> { self.agc.clone() }
+
+    /// Update the echo canceller configuration (the echo path is re-learned)
+    pub fn configure_echo_cancellation(&mut self, config: EchoCancellerConfig) {
+        if let Ok(mut e) = self.echo.lock() {
+            e.set_config(config);
+        }
+    }
+
+    /// Enable or disable echo cancellation at runtime
+    pub fn set_echo_cancellation_enabled(&mut self, enabled: bool) {
+        if let Ok(mut e) = self.echo.lock() {
+            let config = EchoCancellerConfig { enabled, ..e.config().clone() };
+            e.set_config(config);
+        }
+    }
+
+    /// Whether echo cancellation is enabled
+    pub fn is_echo_cancellation_enabled(&self) -> bool {
+        self.echo.lock().map(|e| e.config().enabled).unwrap_or(false)
+    }
+
+    /// Current echo canceller settings
+    pub fn echo_cancellation_config(&self) -> EchoCancellerConfig {
+        self.echo.lock().map(|e| e.config().clone()).unwrap_or_default()
+    }
+
+    /// Reference tap that playback of spoken feedback must write into so
+    /// its echo can be removed from the microphone
+    pub fn get_echo_reference(&self) -> EchoReference {
+        self.echo.lock().map(|e| e.reference()).unwrap_or_default()
+    }
+
+    /// Whether feedback playback (or its echo tail) is currently being cancelled
+    pub fn is_echo_active(&self) -> bool {
+        self.echo.lock().map(|e| e.is_active()).unwrap_or(false)
+    }
+
+    /// Echo canceller statistics
+    pub fn get_echo_stats(&self) -> EchoStats {
+        self.echo.lock().map(|e| e.stats()).unwrap_or_default()
+    }
+
+    /// Get the requested capture stream parameters
+    pub fn capture_config(&self) -> &CaptureConfig {
+        &self.capture_config
+    }
+
+    /// Set requested capture stream parameters. If capture is running the
+    /// stream is restarted with the new parameters; the pre-roll buffer is kept.
+    /// Returns the parameters actually in use when capturing.
+    pub fn set_capture_config(&mut self, config: CaptureConfig) -> Result<Option<NegotiatedConfig>> {
+        if config == self.capture_config {
+            return Ok(self.active_config.clone());
+        }
+        info!(?config, "Updating capture stream parameters");
+        self.capture_config = config;
+        self.restart_capture()?;
+        Ok(self.active_config.clone())
+    }
+
+    /// Request a capture sample rate (restarts a running stream)
+    pub fn set_sample_rate(&mut self, sample_rate: u32) -> Result<Option<NegotiatedConfig>> {
+        let config = CaptureConfig { sample_rate: Some(sample_rate), ..self.capture_config.clone() };
+        self.set_capture_config(config)
+    }
+
+    /// Request a capture buffer size in frames (restarts a running stream)
+    pub fn set_buffer_size(&mut self, buffer_size: u32) -> Result<Option<NegotiatedConfig>> {
+        let config = CaptureConfig { buffer_size: Some(buffer_size), ..self.capture_config.clone() };
+        self.set_capture_config(config)
+    }
+
+    /// Request a capture channel count (restarts a running stream)
+    pub fn set_channels(&mut self, channels: u16) -> Result<Option<NegotiatedConfig>> {
+        let config = CaptureConfig { channels: Some(channels), ..self.capture_config.clone() };
+        self.set_capture_config(config)
+    }
+
+    /// Stream parameters negotiated for the running stream, if capturing
+    pub fn get_active_config(&self) -> Option<NegotiatedConfig> {
+        self.active_config.clone()
+    }
+
+    /// Most recent processed mono audio and its sample rate
+    pub fn get_preroll_audio(&self) -> (Vec<f32>, u32) {
+        self.preroll.lock().map(|p| p.snapshot()).unwrap_or_default()
+    }
+
+    /// Change the pre-roll buffer length in milliseconds
+    pub fn set_preroll_duration_ms(&mut self, duration_ms: u32) {
+        if let Ok(mut p) = self.preroll.lock() {
+            p.set_duration_ms(duration_ms);
+        }
+    }
+
+    /// Provide a thread-safe handle to the pre-roll buffer
+    pub fn get_preroll_handle(&self) -> Arc<Mutex<PreRollBuffer>> { self.preroll.clone() }
+
+    /// Stable ID of the device currently being captured from
+    pub fn get_active_device_id(&self) -> Option<String> {
+        if self.capturing { self.active_device_id.clone() } else { None }
+    }
+
+    /// Whether capture is running on a fallback device because the preferred one is missing
+    pub fn is_failed_over(&self) -> bool {
+        self.failed_over
+    }
+
+    /// Register a callback to be invoked on device hot-plug and failover events
+    pub fn on_device_event<F>(&mut self, callback: F)
+    where
+        F: Fn(&DeviceEvent) + Send + Sync + 'static,
+    {
+        self.device_callbacks.push(Arc::new(callback));
+    }
+
+    /// Check stream health and the input device list, recovering capture as needed.
+    ///
+    /// Call this regularly from the thread that owns the service (cpal streams
+    /// cannot move between threads). A failed stream is torn down and restarted
+    /// on the preferred device or, failing that, the default input; when the
+    /// preferred device reappears capture moves back to it. Returns the events
+    /// raised since the last call; registered device callbacks see them too.
+    pub fn poll_device_health(&mut self) -> Vec<DeviceEvent> {
+        let failure = self.stream_error.lock().ok().and_then(|mut e| e.take());
+        if let Some(reason) = failure {
+            if self.capturing {
+                warn!(device = ?self.active_device_id, "Audio input stream failed: {reason}");
+                self.emit(DeviceEvent::Disconnected { device_id: self.active_device_id.clone(), reason });
+                self.teardown_stream();
+                self.resume_capture();
+            }
+        }
+
+        let scan_due = self.last_device_scan.map(|t| t.elapsed() >= DEVICE_SCAN_INTERVAL).unwrap_or(true);
+        if scan_due {
+            self.last_device_scan = Some(Instant::now());
+            let ids: Vec<String> = list_input_devices(&cpal::default_host()).into_iter().map(|(id, _, _)| id).collect();
+            for event in self.device_watcher.update(&ids) {
+                info!(?event, "Input device list changed");
+                self.emit(event);
+            }
+
+            if self.capture_wanted {
+                let preferred_back = self.failed_over
+                    && self.preferred_device.as_ref().map(|p| self.device_watcher.contains(p)).unwrap_or(false);
+                let active_gone = self.active_device_id.as_ref().map(|id| !self.device_watcher.contains(id)).unwrap_or(false);
+                if !self.capturing {
+                    self.resume_capture();
+                } else if active_gone {
+                    // Some backends never report an error for a vanished device
+                    self.emit(DeviceEvent::Disconnected {
+                        device_id: self.active_device_id.clone(),
+                        reason: "device no longer listed".to_string(),
+                    });
+                    self.teardown_stream();
+                    self.resume_capture();
+                } else if preferred_back {
+                    self.teardown_stream();
+                    self.resume_capture();
+                }
+            }
+        }
+
+        std::mem::take(&mut self.pending_events)
+    }
+
+    fn emit(&mut self, event: DeviceEvent) {
+        for cb in &self.device_callbacks {
+            (cb)(&event);
+        }
+        self.pending_events.push(event);
+    }
+
+    /// Try to bring capture back after a failure, reporting if no device is usable
+    fn resume_capture(&mut self) {
+        match self.start_capture() {
+            Ok(()) => self.capture_lost = false,
+            Err(e) => {
+                if !self.capture_lost {
+                    warn!("Audio capture unavailable: {e}");
+                    self.capture_lost = true;
+                    self.emit(DeviceEvent::CaptureLost { reason: e.to_string() });
+                }
+            }
+        }
+    }
+
     /// Register a callback to be invoked when VAD detects voice
     pub fn on_vad_event<F>(&mut self, callback: F)
     where
@@ -107,10 +459,66 @@ impl AudioService {
         }
     }
     
+    /// Register a callback to receive interleaved multi-channel frames.
+    /// Only emitted while a secondary source is captured in stereo mode, with
+    /// the primary source on channel 0 and the secondary on channel 1.
+    pub fn on_channel_frame<F>(&mut self, callback: F)
+    where
+        F: Fn(&[f32], u16, u32) + Send + Sync + 'static,
+    {
+        self.channel_callbacks.push(Arc::new(callback));
+
+        if self.capturing {
+            if let Err(e) = self.restart_capture() {
+                error!("Failed to restart audio capture with new callback: {}", e);
+            }
+        }
+    }
+
+    /// Capture a second input alongside the primary device (None to capture
+    /// only the primary). A running stream is restarted.
+    pub fn set_secondary_source(&mut self, source: Option<SecondarySourceConfig>) -> Result<()> {
+        info!(?source, "Updating secondary capture source");
+        self.secondary_source = source;
+        self.restart_capture()
+    }
+
+    /// Current secondary source configuration
+    pub fn secondary_source(&self) -> Option<&SecondarySourceConfig> {
+        self.secondary_source.as_ref()
+    }
+
+    /// Set per-source gains in dB for two-source capture
+    pub fn set_source_gains_db(&mut self, primary_gain_db: f32, secondary_gain_db: f32) {
+        if let Some(source) = self.secondary_source.as_mut() {
+            source.mixer.primary_gain_db = primary_gain_db;
+            source.mixer.secondary_gain_db = secondary_gain_db;
+        }
+        if let Some(mixer) = &self.mixer {
+            if let Ok(mut m) = mixer.lock() {
+                m.set_gains_db(primary_gain_db, secondary_gain_db);
+            }
+        }
+    }
+
+    /// Mixer health counters while two-source capture is running
+    pub fn get_mixer_stats(&self) -> Option<MixerStats> {
+        self.mixer.as_ref().and_then(|m| m.lock().ok().map(|m| m.stats()))
+    }
+
+    /// Find an input that captures system playback (PulseAudio/PipeWire
+    /// monitor, loopback drivers); returns its stable ID
+    pub fn find_system_audio_device(&self) -> Option<String> {
+        list_input_devices(&cpal::default_host())
+            .into_iter()
+            .find(|(_, name, _)| is_system_audio_device(name))
+            .map(|(id, _, _)| id)
+    }
+
     /// Restart audio capture (used when callbacks are added during capture)
     fn restart_capture(&mut self) -> Result<()> {
         if self.capturing {
-            self.stop_capture()?;
+            self.teardown_stream();
             self.start_capture()?;
         }
         Ok(())
@@ -148,41 +556,73 @@ impl AudioService {
 
         let host = cpal::default_host();
 
-        let device = match &self.selected_device_name {
-            Some(name) => {
-                let mut found: Option<cpal::Device> = None;
-                if let Ok(mut devices) = host.input_devices() {
-                    for d in devices.by_ref() {
-                        if let Ok(dname) = d.name() {
-                            if &dname == name {
-                                found = Some(d);
-                                break;
-                            }
-                        }
-                    }
+        let mut devices = list_input_devices(&host);
+        let preferred_index = self.preferred_device.as_ref().and_then(|p| {
+            devices.iter().position(|(id, name, _)| device_monitor::matches_preference(p, id, name))
+        });
+        let (device_id, device) = match preferred_index {
+            Some(index) => {
+                let (id, _, device) = devices.swap_remove(index);
+                (id, device)
+            }
+            None => {
+                // Preferred device missing (or none chosen): use the default input
+                let device = host
+                    .default_input_device()
+                    .ok_or_else(|| crate::core::error::AudioError::DeviceNotFound("default".into()))?;
+                let name = device.name().unwrap_or_else(|_| "<unknown>".into());
+                let id = devices
+                    .iter()
+                    .find(|(_, dname, _)| *dname == name)
+                    .map(|(id, _, _)| id.clone())
+                    .unwrap_or_else(|| format!("{}:{}", host.id().name(), name));
+                if let Some(preferred) = &self.preferred_device {
+                    warn!(preferred = %preferred, fallback = %id, "Preferred input device unavailable, using default");
                 }
-                found.ok_or_else(|| crate::core::error::AudioError::DeviceNotFound(name.clone()))?
+                (id, device)
+            }
+        };
+        let device_event = match (preferred_index.is_some(), &self.preferred_device) {
+            (true, _) if self.failed_over => Some(DeviceEvent::Reconnected { device_id: device_id.clone() }),
+            (false, Some(preferred)) if !self.failed_over => {
+                Some(DeviceEvent::FailedOver { from: Some(preferred.clone()), to: device_id.clone() })
             }
-            None => host
-                .default_input_device()
-                .ok_or_else(|| crate::core::error::AudioError::DeviceNotFound("default".into()))?,
+            (false, None) if self.active_device_id.as_ref().is_some_and(|prev| *prev != device_id) => {
+                Some(DeviceEvent::FailedOver { from: self.active_device_id.clone(), to: device_id.clone() })
+            }
+            _ => None,
         };
 
-        let supported_config = device
+        let default_config = device
             .default_input_config()
             .map_err(|e| crate::core::error::AudioError::DeviceInit(e.to_string()))?;
+        let unsupported = || crate::core::error::AudioError::UnsupportedFormat(format!("{:?}", default_config.sample_format()));
 
-        let config: cpal::StreamConfig = supported_config.clone().config();
-        let sample_format = supported_config.sample_format();
-
-        let err_fn = |err| {
-            error!("Audio input stream error: {err}");
+        let negotiated = if self.capture_config.is_default() {
+            NegotiatedConfig::from_default(&default_config)
+        } else {
+            match device.supported_input_configs() {
+                Ok(ranges) => {
+                    let ranges: Vec<_> = ranges.collect();
+                    capture_config::negotiate(&ranges, &default_config, &self.capture_config)
+                }
+                Err(e) => {
+                    warn!("Could not query supported input configs ({e}); using device default");
+                    NegotiatedConfig::from_default(&default_config)
+                }
+            }
         };
+        let mut negotiated = negotiated.ok_or_else(unsupported)?;
+        for note in &negotiated.fallbacks {
+            warn!("Capture config fallback: {note}");
+        }
 
         info!(
             device = %device.name().unwrap_or_else(|_| "<unknown>".into()),
-            sample_rate = config.sample_rate.0,
-            channels = config.channels,
+            sample_rate = negotiated.sample_rate,
+            channels = negotiated.channels,
+            buffer_size = ?negotiated.buffer_size,
+            sample_format = ?negotiated.sample_format,
             "Starting audio capture"
         );
 
@@ -195,12 +635,25 @@ impl AudioService {
             }
         }
 
-        let control = self.vad_control.clone();
-        let stream = match sample_format {
-            SampleFormat::F32 => build_stream::<f32>(&device, &config, err_fn, self.vad.clone(), self.vad_callback.clone(), self.audio_callbacks.clone(), control)?,
-            SampleFormat::I16 => build_stream::<i16>(&device, &config, err_fn, self.vad.clone(), self.vad_callback.clone(), self.audio_callbacks.clone(), control)?,
-            SampleFormat::U16 => build_stream::<u16>(&device, &config, err_fn, self.vad.clone(), self.vad_callback.clone(), self.audio_callbacks.clone(), control)?,
-            _ => return Err(crate::core::error::AudioError::UnsupportedFormat(format!("{sample_format:?}")).into()),
+        if let Ok(mut e) = self.stream_error.lock() {
+            *e = None;
+        }
+        self.mixer = self
+            .secondary_source
+            .as_ref()
+            .map(|source| Arc::new(Mutex::new(SourceMixer::new(source.mixer.clone()))));
+        let stream = match self.build_capture_stream(&device, &negotiated) {
+            Ok(stream) => stream,
+            Err(e) if !self.capture_config.is_default() => {
+                // The driver rejected the negotiated parameters; fall back to the device default
+                warn!("Requested capture config rejected ({e}); falling back to device default");
+                let mut fallback = NegotiatedConfig::from_default(&default_config).ok_or_else(unsupported)?;
+                fallback.fallbacks = negotiated.fallbacks.clone();
+                fallback.fallbacks.push(format!("requested configuration rejected by device: {e}"));
+                negotiated = fallback;
+                self.build_capture_stream(&device, &negotiated)?
+            }
+            Err(e) => return Err(e),
         };
 
         stream
@@ -208,21 +661,121 @@ impl AudioService {
             .map_err(|e| crate::core::error::AudioError::CaptureStart(e.to_string()))?;
 
         self.input_stream = Some(stream);
+        if self.secondary_source.is_some() {
+            self.secondary_stream = self.start_secondary_stream(&host, &device_id);
+        }
         self.input_device = Some(device);
+        self.active_config = Some(negotiated);
+        self.active_device_id = Some(device_id);
+        self.failed_over = preferred_index.is_none() && self.preferred_device.is_some();
         self.capturing = true;
+        self.capture_wanted = true;
+        if let Some(event) = device_event {
+            info!(?event, "Capture device changed");
+            self.emit(event);
+        }
         Ok(())
     }
 
+    /// Build an input stream for the negotiated parameters
+    fn build_capture_stream(&self, device: &cpal::Device, negotiated: &NegotiatedConfig) -> Result<Stream> {
+        let config = negotiated.stream_config();
+        let pipeline = CapturePipeline {
+            vad: self.vad.clone(),
+            vad_callback: self.vad_callback.clone(),
+            audio_callbacks: self.audio_callbacks.clone(),
+            channel_callbacks: self.channel_callbacks.clone(),
+            vad_control: self.vad_control.clone(),
+            agc: self.agc.clone(),
+            preroll: self.preroll.clone(),
+            mixer: self.mixer.clone(),
+            echo: self.echo.clone(),
+        };
+        let on_frame = move |mono: Vec<f32>, sample_rate: u32| pipeline.process(mono, sample_rate);
+        build_stream_for_format(device, &config, negotiated.sample_format.to_cpal(), self.stream_error_fn(), on_frame)
+    }
+
+    /// Open the secondary input and feed it into the mixer. Failure is not
+    /// fatal: the primary keeps running and the secondary channel stays silent.
+    fn start_secondary_stream(&self, host: &cpal::Host, primary_id: &str) -> Option<Stream> {
+        let source = self.secondary_source.as_ref()?;
+        let mixer = self.mixer.clone()?;
+        let devices = list_input_devices(host);
+        let found = match &source.device {
+            Some(preference) => devices
+                .into_iter()
+                .find(|(id, name, _)| device_monitor::matches_preference(preference, id, name)),
+            None => devices.into_iter().find(|(_, name, _)| is_system_audio_device(name)),
+        };
+        let Some((id, _, device)) = found.filter(|(id, _, _)| id != primary_id) else {
+            warn!(requested = ?source.device, "Secondary input device unavailable; recording primary only");
+            return None;
+        };
+        let supported = match device.default_input_config() {
+            Ok(config) => config,
+            Err(e) => {
+                warn!(device = %id, "Secondary input device unusable: {e}");
+                return None;
+            }
+        };
+        let config = supported.config();
+        let on_frame = move |mono: Vec<f32>, sample_rate: u32| {
+            if let Ok(mut m) = mixer.lock() {
+                m.push_secondary(&mono, sample_rate);
+            }
+        };
+        let stream = build_stream_for_format(&device, &config, supported.sample_format(), self.stream_error_fn(), on_frame)
+            .and_then(|stream| {
+                stream
+                    .play()
+                    .map_err(|e| crate::core::error::AudioError::CaptureStart(e.to_string()))?;
+                Ok(stream)
+            });
+        match stream {
+            Ok(stream) => {
+                info!(device = %id, sample_rate = config.sample_rate.0, channels = config.channels, "Started secondary audio capture");
+                Some(stream)
+            }
+            Err(e) => {
+                warn!(device = %id, "Failed to start secondary audio capture: {e}");
+                None
+            }
+        }
+    }
+
+    /// Stream error handler that flags the failure for poll_device_health
+    fn stream_error_fn(&self) -> impl Fn(cpal::StreamError) + Send + 'static {
+        let stream_error = self.stream_error.clone();
+        move |err: cpal::StreamError| {
+            error!("Audio input stream error: {err}");
+            // Picked up by poll_device_health, which restarts or fails over the stream
+            if let Ok(mut e) = stream_error.lock() {
+                *e = Some(err.to_string());
+            }
+        }
+    }
+
     /// Stop audio capture
     pub fn stop_capture(&mut self) -> Result<()> {
+        self.capture_wanted = false;
+        self.capture_lost = false;
         if !self.capturing {
             return Ok(());
         }
 
         info!("Stopping audio capture");
+        self.teardown_stream();
+        Ok(())
+    }
+
+    /// Drop the running stream without changing whether capture is wanted
+    fn teardown_stream(&mut self) {
         // Dropping the stream stops capture
         self.input_stream = None;
+        self.secondary_stream = None;
+        self.mixer = None;
         self.input_device = None;
+        self.active_config = None;
         self.capturing = false;
         // Stop VAD if attached
         if let Some(vad) = &self.vad {
@@ -230,7 +783,6 @@ impl AudioService {
                 let _ = g.stop();
             }
         }
-        Ok(())
     }
 
     /// Check if currently capturing
@@ -254,39 +806,36 @@ impl AudioService {
         let mut devices_info: Vec<AudioDevice> = Vec::new();
 
         // Input devices
-        if let Ok(devices) = host.input_devices() {
-            for device in devices {
-                let name = device.name().unwrap_or_else(|_| "<unknown>".into());
-                let mut sample_rates: Vec<u32> = Vec::new();
-                let mut channels: Vec<u16> = Vec::new();
+        for (id, name, device) in list_input_devices(&host) {
+            let mut sample_rates: Vec<u32> = Vec::new();
+            let mut channels: Vec<u16> = Vec::new();
 
-                if let Ok(configs) = device.supported_input_configs() {
-                    for cfg in configs {
-                        let sr_min = cfg.min_sample_rate().0;
-                        let sr_max = cfg.max_sample_rate().0;
-                        // Store range endpoints to indicate capability without enumerating all
-                        if !sample_rates.contains(&sr_min) {
-                            sample_rates.push(sr_min);
-                        }
-                        if !sample_rates.contains(&sr_max) {
-                            sample_rates.push(sr_max);
-                        }
-                        let ch = cfg.channels();
-                        if !channels.contains(&ch) {
-                            channels.push(ch);
-                        }
+            if let Ok(configs) = device.supported_input_configs() {
+                for cfg in configs {
+                    let sr_min = cfg.min_sample_rate().0;
+                    let sr_max = cfg.max_sample_rate().0;
+                    // Store range endpoints to indicate capability without enumerating all
+                    if !sample_rates.contains(&sr_min) {
+                        sample_rates.push(sr_min);
+                    }
+                    if !sample_rates.contains(&sr_max) {
+                        sample_rates.push(sr_max);
+                    }
+                    let ch = cfg.channels();
+                    if !channels.contains(&ch) {
+                        channels.push(ch);
                     }
                 }
-
-                devices_info.push(AudioDevice {
-                    name: name.clone(),
-                    id: name.clone(),
-                    sample_rates,
-                    channels,
-                    is_default: name == default_input_name,
-                    device_type: AudioDeviceType::Input,
-                });
             }
+
+            devices_info.push(AudioDevice {
+                name: name.clone(),
+                id,
+                sample_rates,
+                channels,
+                is_default: name == default_input_name,
+                device_type: AudioDeviceType::Input,
+            });
         }
 
         // Output devices (optional, useful for duplex display)
@@ -328,21 +877,134 @@ impl AudioService {
     }
 }
 
-fn build_stream<T>(
+/// Enumerate input devices as (stable ID, display name, device)
+fn list_input_devices(host: &cpal::Host) -> Vec<(String, String, cpal::Device)> {
+    let devices: Vec<(String, cpal::Device)> = match host.input_devices() {
+        Ok(devices) => devices
+            .map(|d| (d.name().unwrap_or_else(|_| "<unknown>".into()), d))
+            .collect(),
+        Err(e) => {
+            warn!("Failed to enumerate input devices: {e}");
+            Vec::new()
+        }
+    };
+    let names: Vec<String> = devices.iter().map(|(name, _)| name.clone()).collect();
+    let ids = device_monitor::stable_device_ids(host.id().name(), &names);
+    ids.into_iter()
+        .zip(devices)
+        .map(|(id, (name, device))| (id, name, device))
+        .collect()
+}
+
+/// Whether a device name looks like a system playback capture device
+fn is_system_audio_device(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["monitor", "loopback", "blackhole", "stereo mix", "what u hear", "soundflower"]
+        .iter()
+        .any(|hint| name.contains(hint))
+}
+
+/// Processing shared by every frame of the primary capture stream
+#[derive(Clone)]
+struct CapturePipeline {
+    vad: Option<Arc<Mutex<VADService>>>,
+    vad_callback: Option<Arc<dyn Fn(VADResult) + Send + Sync>>,
+    audio_callbacks: Vec<AudioFrameCallback>,
+    channel_callbacks: Vec<ChannelFrameCallback>,
+    vad_control: Arc<AtomicU8>,
+    agc: Arc<Mutex<AutomaticGainControl>>,
+    preroll: Arc<Mutex<PreRollBuffer>>,
+    mixer: Option<Arc<Mutex<SourceMixer>>>,
+    echo: Arc<Mutex<EchoSuppressor>>,
+}
+
+impl CapturePipeline {
+    fn process(&self, mut mono: Vec<f32>, sample_rate: u32) {
+        // Remove the echo of our own spoken feedback from the microphone
+        if let Ok(mut e) = self.echo.lock() {
+            e.process(&mut mono, sample_rate);
+        }
+
+        // Combine with the secondary source (the primary stream is the clock master)
+        if let Some(mixer) = &self.mixer {
+            if let Ok(mut m) = mixer.lock() {
+                m.push_primary(&mono, sample_rate);
+                let frame = m.pull();
+                mono = frame.mono;
+                if let Some(stereo) = frame.stereo {
+                    for cb in &self.channel_callbacks {
+                        (cb)(&stereo, 2, sample_rate);
+                    }
+                }
+            }
+        }
+
+        // Apply automatic/manual gain and soft limiting before any analysis
+        if let Ok(mut g) = self.agc.lock() {
+            g.process(&mut mono, sample_rate);
+        }
+
+        // Keep recent audio outside the stream so it survives restarts
+        if let Ok(mut p) = self.preroll.lock() {
+            p.push(&mono, sample_rate);
+        }
+
+        // Check VAD control flag (1=start, 2=stop) to reconcile state lazily
+        match self.vad_control.swap(0, Ordering::SeqCst) {
+            1 => { if let Some(v) = self.vad.as_ref() { if let Ok(mut g) = v.lock() { let _ = g.start(); } } },
+            2 => { if let Some(v) = self.vad.as_ref() { if let Ok(mut g) = v.lock() { let _ = g.stop(); } } },
+            _ => {}
+        }
+
+        if let Some(vad_ref) = self.vad.as_ref() {
+            if let Ok(mut g) = vad_ref.lock() {
+                if let Ok(vr) = g.process_frame(&mono, sample_rate) {
+                    if vr.voice_detected {
+                        if let Some(cb) = self.vad_callback.as_ref() { (cb)(vr.clone()); }
+                    }
+                }
+            }
+        }
+
+        // Emit raw audio frame to all registered listeners
+        for cb in &self.audio_callbacks {
+            (cb)(&mono, sample_rate);
+        }
+    }
+}
+
+fn build_stream_for_format<F>(
     device: &cpal::Device,
     config: &cpal::StreamConfig,
+    sample_format: SampleFormat,
     err_fn: impl Fn(cpal::StreamError) + Send + 'static,
-    vad: Option<Arc<Mutex<VADService>>>,
-    vad_callback: Option<Arc<dyn Fn(VADResult) + Send + Sync>>, 
-    audio_callbacks: Vec<Arc<dyn Fn(&[f32], u32) + Send + Sync>>,
-    vad_control: Arc<AtomicU8>,
+    on_frame: F,
+) -> Result<Stream>
+where
+    F: FnMut(Vec<f32>, u32) + Send + 'static,
+{
+    match sample_format {
+        SampleFormat::F32 => build_stream::<f32, _>(device, config, err_fn, on_frame),
+        SampleFormat::I16 => build_stream::<i16, _>(device, config, err_fn, on_frame),
+        SampleFormat::I32 => build_stream::<i32, _>(device, config, err_fn, on_frame),
+        SampleFormat::U16 => build_stream::<u16, _>(device, config, err_fn, on_frame),
+        other => Err(crate::core::error::AudioError::UnsupportedFormat(format!("{other:?}")).into()),
+    }
+}
+
+fn build_stream<T, F>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    err_fn: impl Fn(cpal::StreamError) + Send + 'static,
+    mut on_frame: F,
 ) -> Result<Stream>
 where
     T: Sample + Send + 'static + cpal::SizedSample,
     T: cpal::FromSample<f32>,
     f32: cpal::FromSample<T>,
+    F: FnMut(Vec<f32>, u32) + Send + 'static,
 {
-    // Basic skeleton: receive data, convert to mono f32, and feed into VAD (if attached)
+    // Receive data, convert to mono f32, and hand it to the frame handler
     let channels = config.channels as usize;
     let sample_rate = config.sample_rate.0;
     let stream = device
@@ -361,28 +1023,7 @@ where
                         })
                         .collect()
                 };
-
-                // Check VAD control flag (1=start, 2=stop) to reconcile state lazily
-                match vad_control.swap(0, Ordering::SeqCst) {
-                    1 => { if let Some(v) = vad.as_ref() { if let Ok(mut g) = v.lock() { let _ = g.start(); } } },
-                    2 => { if let Some(v) = vad.as_ref() { if let Ok(mut g) = v.lock() { let _ = g.stop(); } } },
-                    _ => {}
-                }
-
-                if let Some(vad_ref) = vad.as_ref() {
-                    if let Ok(mut g) = vad_ref.lock() {
-                        if let Ok(vr) = g.process_frame(&mono, sample_rate) {
-                            if vr.voice_detected {
-                                if let Some(cb) = vad_callback.as_ref() { (cb)(vr.clone()); }
-                            }
-                        }
-                    }
-                }
-
-                // Emit raw audio frame to all registered listeners
-                for cb in &audio_callbacks {
-                    (cb)(&mono, sample_rate);
-                }
+                on_frame(mono, sample_rate);
             },
             err_fn,
             None,
@@ -395,7 +1036,10 @@ impl fmt::Debug for AudioService {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("AudioService")
             .field("capturing", &self.capturing)
-            .field("selected_device_name", &self.selected_device_name)
+            .field("preferred_device", &self.preferred_device)
+            .field("active_device_id", &self.active_device_id)
+            .field("failed_over", &self.failed_over)
+            .field("device_callbacks", &format!("{} callbacks", self.device_callbacks.len()))
             .field("ptt_active", &self.ptt_active)
             .field("vad_enabled", &self.vad_enabled)
             .field("input_device", &self.input_device.as_ref().map(|_| "Device"))
@@ -403,7 +1047,50 @@ impl fmt::Debug for AudioService {
             .field("vad", &self.vad)
             .field("vad_callback", &self.vad_callback.as_ref().map(|_| "Callback"))
             .field("audio_callbacks", &format!("{} callbacks", self.audio_callbacks.len()))
+            .field("channel_callbacks", &format!("{} callbacks", self.channel_callbacks.len()))
+            .field("secondary_source", &self.secondary_source)
+            .field("secondary_stream", &self.secondary_stream.as_ref().map(|_| "Stream"))
             .field("vad_control", &self.vad_control)
+            .field("agc", &self.agc)
+            .field("capture_config", &self.capture_config)
+            .field("active_config", &self.active_config)
+            .field("preroll", &self.preroll.lock().map(|p| p.duration_ms()).ok())
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_file_gain_settings_reach_capture_pipeline() {
+        let mut config = Config::new();
+        config.audio.auto_gain_control = false;
+        config.audio.manual_gain_db = 6.0;
+        let mut service = AudioService::new().unwrap();
+        service.apply_config(&config.audio).unwrap();
+        assert!(!service.is_agc_enabled());
+
+        // The capture callback runs every frame through this shared gain stage
+        let agc = service.get_agc_handle();
+        let mut agc = agc.lock().unwrap();
+        for _ in 0..5 {
+            agc.process(&mut vec![0.01f32; 16000], 16000);
+        }
+        assert!((agc.current_gain_db() - 6.0).abs() < 0.1, "{}", agc.current_gain_db());
+    }
+
+    #[test]
+    fn test_config_file_echo_settings_reach_canceller() {
+        let mut config = Config::new();
+        config.audio.echo_filter_length_ms = 250;
+        let mut service = AudioService::new().unwrap();
+        service.apply_config(&config.audio).unwrap();
+        assert_eq!(service.echo_cancellation_config().filter_length_ms, 250);
+
+        config.audio.echo_cancellation = false;
+        service.apply_config(&config.audio).unwrap();
+        assert!(!service.is_echo_cancellation_enabled());
+    }
+}
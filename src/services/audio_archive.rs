@@ -231,14 +231,22 @@ pub struct CompressionResult {
     pub compressed_size: u64,
     pub compression_ratio: f64,
     pub time_taken: Duration,
+    /// Files left as they were because they were missing or unreadable, with the reason
+    pub skipped: Vec<(PathBuf, String)>,
 }
 
 /// Cleanup result
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct CleanupResult {
     pub files_deleted: usize,
     pub space_freed: u64,
     pub sessions_removed: usize,
+    /// Transcript entries expired
+    pub transcripts_removed: usize,
+    /// Nothing was deleted; the counts describe what would have been
+    pub dry_run: bool,
+    /// Files and directories deleted (or that would be)
+    pub deleted_paths: Vec<PathBuf>,
 }
 
 /// Retention policy
@@ -466,6 +474,9 @@ impl AudioArchiveService {
         println!("   Original size: {:.2} MB", result.original_size as f64 / 1024.0 / 1024.0);
         println!("   Compressed size: {:.2} MB", result.compressed_size as f64 / 1024.0 / 1024.0);
         println!("   Compression ratio: {:.1}%", result.compression_ratio * 100.0);
+        for (path, reason) in &result.skipped {
+            println!("   Skipped {}: {}", path.display(), reason);
+        }
         
         Ok(result)
     }
@@ -720,6 +731,7 @@ mod tests {
                 compressed_size: 512 * 1024,
                 compression_ratio: 0.5,
                 time_taken: Duration::from_secs(1),
+                skipped: Vec::new(),
             })
         }
         
@@ -728,6 +740,7 @@ mod tests {
                 files_deleted: 0,
                 space_freed: 0,
                 sessions_removed: 0,
+                ..Default::default()
             })
         }
     }
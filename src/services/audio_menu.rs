@@ -5,6 +5,8 @@
 
 use std::collections::HashMap;
 use std::io::{self, Write};
+use std::path::Path;
+use std::sync::mpsc;
 use std::time::Duration;
 use chrono::{DateTime, Utc};
 use crossterm::{
@@ -20,6 +22,14 @@ use super::audio_archive::{
     AudioArchiveService, AudioError, RecordingSession, SearchCriteria, 
     StorageStats, SessionId, RecordingStatus
 };
+use super::audio_playback::{self, AudioPlaybackService, PlaybackController};
+use super::audio_session_manager::{AudioRecordingSession, TranscriptSegment};
+use super::session_markers;
+
+/// How far the arrow keys seek during playback
+const PLAYBACK_SEEK_SECONDS: f64 = 10.0;
+/// Speed change per +/- key press during playback
+const PLAYBACK_SPEED_STEP: f32 = 0.25;
 
 /// Interactive audio menu system
 pub struct AudioRecordingMenu {
@@ -667,6 +677,13 @@ impl AudioRecordingMenu {
                     }
                 }
             }
+            MenuState::SessionManagement => {
+                let sessions = self.audio_service.list_sessions(None).unwrap_or_default();
+                match sessions.get(self.selected_index).filter(|_| self.selected_index < 10) {
+                    Some(session) => self.navigate_to(MenuState::SessionDetails(session.id)),
+                    None => Ok(MenuResult::Continue),
+                }
+            }
             _ => Ok(MenuResult::Continue),
         }
     }
@@ -709,6 +726,13 @@ impl AudioRecordingMenu {
                     _ => Ok(MenuResult::Continue),
                 }
             }
+            MenuState::SessionDetails(session_id) => {
+                let session_id = *session_id;
+                match c.to_digit(10) {
+                    Some(n) if n > 0 => self.play_from_marker(session_id, n as usize - 1),
+                    _ => Ok(MenuResult::Continue),
+                }
+            }
             _ => Ok(MenuResult::Continue),
         }
     }
@@ -736,9 +760,167 @@ impl AudioRecordingMenu {
         Ok(())
     }
     
-    /// Render session details (placeholder)
-    fn render_session_details(&self, stdout: &mut io::Stdout, _session_id: SessionId) -> Result<(), AudioError> {
-        execute!(stdout, Print("Session details...\n"))?;
+    /// Find a session by ID
+    fn find_session(&self, session_id: SessionId) -> Option<RecordingSession> {
+        self.audio_service.list_sessions(None).ok()?
+            .into_iter()
+            .find(|session| session.id == session_id)
+    }
+
+    /// Play a session starting at one of its markers
+    fn play_from_marker(&mut self, session_id: SessionId, index: usize) -> Result<MenuResult, AudioError> {
+        let Some(session) = self.find_session(session_id) else {
+            self.show_error("Session not found")?;
+            return Ok(MenuResult::Continue);
+        };
+        let Some(marker) = session_markers::markers(&session.metadata).into_iter().nth(index) else {
+            return Ok(MenuResult::Continue);
+        };
+        self.play_interactive(&session.name, &session.file_path, marker.at, &[])?;
+        Ok(MenuResult::Continue)
+    }
+
+    /// Play a recorded session, highlighting the transcript line being spoken
+    pub fn play_session(&mut self, session: &AudioRecordingSession) -> Result<(), AudioError> {
+        self.play_interactive(&session.name, &session.file_path, Duration::ZERO, &session.transcript_segments)
+    }
+
+    /// Play a file with keyboard controls until it ends or the user stops it
+    fn play_interactive(
+        &self,
+        title: &str,
+        path: &Path,
+        start: Duration,
+        segments: &[TranscriptSegment],
+    ) -> Result<(), AudioError> {
+        let (position_tx, position_rx) = mpsc::channel();
+        let mut playback = AudioPlaybackService::default();
+        let controller = match playback.start_file(path, Some(Box::new(move |position| {
+            let _ = position_tx.send(position);
+        }))) {
+            Ok(controller) => controller,
+            Err(e) => return self.show_error(&format!("Playback failed: {}", e)),
+        };
+        controller.seek(start);
+
+        let mut stdout = io::stdout();
+        let mut position = start;
+        self.render_playback(&mut stdout, title, &controller, position, segments)?;
+        while !controller.is_finished() {
+            let mut redraw = false;
+            if let Some(latest) = position_rx.try_iter().last() {
+                // Only redraw once a second or when the spoken line changes
+                redraw = latest.as_secs() != position.as_secs()
+                    || audio_playback::segment_at(segments, latest) != audio_playback::segment_at(segments, position);
+                position = latest;
+            }
+            if event::poll(Duration::from_millis(100))? {
+                if let Event::Key(key) = event::read()? {
+                    match key.code {
+                        KeyCode::Char(' ') => {
+                            controller.toggle_pause();
+                        }
+                        KeyCode::Left => controller.seek_by(-PLAYBACK_SEEK_SECONDS),
+                        KeyCode::Right => controller.seek_by(PLAYBACK_SEEK_SECONDS),
+                        KeyCode::Char('+') | KeyCode::Char('=') => {
+                            let _ = controller.set_speed(controller.speed() + PLAYBACK_SPEED_STEP);
+                        }
+                        KeyCode::Char('-') => {
+                            let _ = controller.set_speed(controller.speed() - PLAYBACK_SPEED_STEP);
+                        }
+                        KeyCode::Esc | KeyCode::Char('q') => break,
+                        _ => continue,
+                    }
+                    position = controller.position();
+                    redraw = true;
+                }
+            }
+            if redraw {
+                self.render_playback(&mut stdout, title, &controller, position, segments)?;
+            }
+        }
+        controller.stop();
+        Ok(())
+    }
+
+    /// Render the playback status and the transcript around the current line
+    fn render_playback(
+        &self,
+        stdout: &mut io::Stdout,
+        title: &str,
+        controller: &PlaybackController,
+        position: Duration,
+        segments: &[TranscriptSegment],
+    ) -> Result<(), AudioError> {
+        let theme = DisplayTheme::default();
+        execute!(stdout, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        execute!(stdout, SetForegroundColor(theme.accent_color))?;
+        execute!(stdout, Print(&format!("▶ {}\n\n", title)))?;
+        execute!(stdout, ResetColor)?;
+        let (now, total) = (position.as_secs(), controller.duration().as_secs());
+        execute!(stdout, Print(&format!(
+            "{}:{:02} / {}:{:02}  {:.2}x{}\n\n",
+            now / 60, now % 60, total / 60, total % 60,
+            controller.speed(),
+            if controller.is_paused() { "  (paused)" } else { "" }
+        )))?;
+
+        let current = audio_playback::segment_at(segments, position);
+        // Keep the current line near the top of a ten-line window
+        let first = current
+            .or_else(|| segments.iter().position(|segment| segment.start_time > position))
+            .unwrap_or(0)
+            .saturating_sub(2);
+        for (i, segment) in segments.iter().enumerate().skip(first).take(10) {
+            let secs = segment.start_time.as_secs();
+            if Some(i) == current {
+                execute!(stdout, SetForegroundColor(theme.accent_color))?;
+                execute!(stdout, Print(&format!("► {:>3}:{:02}  {}\n", secs / 60, secs % 60, segment.text)))?;
+                execute!(stdout, ResetColor)?;
+            } else {
+                execute!(stdout, Print(&format!("  {:>3}:{:02}  {}\n", secs / 60, secs % 60, segment.text)))?;
+            }
+        }
+
+        execute!(stdout, SetForegroundColor(theme.secondary_color))?;
+        execute!(stdout, Print("\nSpace pause/resume, ←/→ seek 10s, +/- speed, Esc stop\n"))?;
+        execute!(stdout, ResetColor)?;
+        stdout.flush().map_err(|e| AudioError::RecordingError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Render session details with its markers
+    fn render_session_details(&self, stdout: &mut io::Stdout, session_id: SessionId) -> Result<(), AudioError> {
+        let theme = DisplayTheme::default();
+        let Some(session) = self.find_session(session_id) else {
+            execute!(stdout, Print("Session not found\n"))?;
+            return Ok(());
+        };
+
+        execute!(stdout, SetForegroundColor(theme.accent_color))?;
+        execute!(stdout, Print(&format!("📄 {}\n\n", session.name)))?;
+        execute!(stdout, ResetColor)?;
+        execute!(stdout, Print(&format!("Started:  {}\n", session.start_time.format("%Y-%m-%d %H:%M"))))?;
+        execute!(stdout, Print(&format!("Duration: {:.1}s\n\n", session.duration.as_secs_f64())))?;
+
+        let markers = session_markers::markers(&session.metadata);
+        let chapters = session_markers::chapters(&markers, session.duration);
+        if markers.is_empty() {
+            execute!(stdout, Print("No markers\n"))?;
+            return Ok(());
+        }
+        execute!(stdout, Print("Markers:\n"))?;
+        for (i, marker) in markers.iter().enumerate().take(9) {
+            let title = chapters.iter()
+                .find(|chapter| chapter.start == marker.at)
+                .map(|chapter| chapter.title.clone())
+                .unwrap_or_default();
+            let secs = marker.at.as_secs();
+            execute!(stdout, Print(&format!("  {}. {:>3}:{:02}  {}\n", i + 1, secs / 60, secs % 60, title)))?;
+        }
+        execute!(stdout, SetForegroundColor(theme.secondary_color))?;
+        execute!(stdout, Print("\nPress 1-9 to play from a marker\n"))?;
+        execute!(stdout, ResetColor)?;
         Ok(())
     }
     
@@ -855,6 +1037,7 @@ mod tests {
                 compressed_size: 0,
                 compression_ratio: 0.0,
                 time_taken: Duration::from_secs(0),
+                skipped: Vec::new(),
             })
         }
         fn cleanup_old_files(&mut self, _retention_policy: &super::RetentionPolicy) -> Result<super::CleanupResult, AudioError> {
@@ -862,6 +1045,7 @@ mod tests {
                 files_deleted: 0,
                 space_freed: 0,
                 sessions_removed: 0,
+                ..Default::default()
             })
         }
     }
@@ -1,20 +1,35 @@
 //! Audio playback service for playing back recorded audio files.
 
 use crate::Result;
+use crate::services::echo_cancel::EchoReference;
+use crate::services::audio_session_manager::{AudioRecordingSession, TranscriptSegment};
+use crate::services::encryption::{self, StorageCipher};
+use crate::services::time_stretch::{TimeStretcher, MAX_SPEED, MIN_SPEED};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 
 use hound::WavReader;
+use std::collections::VecDeque;
+use std::ops::Range;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::{mpsc, Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use std::time::Duration;
 use tracing::{error, info};
+use uuid::Uuid;
+
+/// Receives the playback position while a [`PlaybackController`] is playing
+pub type PositionCallback = Box<dyn FnMut(Duration) + Send>;
+
+/// How often the position callback fires
+const POSITION_INTERVAL: Duration = Duration::from_millis(50);
 
 /// Audio playback service for playing WAV files
 pub struct AudioPlaybackService {
     output_device: Option<cpal::Device>,
     is_playing: Arc<AtomicBool>,
+    echo_reference: Option<EchoReference>,
+    cipher: Option<Arc<StorageCipher>>,
 }
 
 impl AudioPlaybackService {
@@ -23,89 +38,194 @@ impl AudioPlaybackService {
         Ok(Self {
             output_device: None,
             is_playing: Arc::new(AtomicBool::new(false)),
+            echo_reference: None,
+            cipher: None,
         })
     }
 
+    /// Feed everything played into an echo canceller reference tap
+    pub fn set_echo_reference(&mut self, reference: Option<EchoReference>) {
+        self.echo_reference = reference;
+    }
+
+    /// Decrypt encrypted session files before playing them
+    pub fn set_cipher(&mut self, cipher: Option<Arc<StorageCipher>>) {
+        self.cipher = cipher;
+    }
+
+    /// Read a file, decrypting it if needed
+    fn read_audio_file(&self, file_path: &Path) -> Result<Vec<u8>> {
+        encryption::read_file(file_path, self.cipher.as_deref())
+            .map_err(|e| crate::core::error::AudioError::PlaybackError(format!("Failed to read {}: {}", file_path.display(), e)).into())
+    }
+
     /// Play a WAV file and block until playback is complete
     pub fn play_wav_file(&mut self, file_path: &Path) -> Result<()> {
         info!("Playing audio file: {}", file_path.display());
-        
-        // Read the WAV file
-        let mut reader = WavReader::open(file_path)
+        let (samples, sample_rate) = self.decode_wav_file(file_path)?;
+        self.play_samples(samples, sample_rate)
+    }
+
+    /// Play a FLAC file (e.g. an archived session) and block until playback is complete
+    pub fn play_flac_file(&mut self, file_path: &Path) -> Result<()> {
+        info!("Playing FLAC file: {}", file_path.display());
+        let (samples, sample_rate) = self.decode_flac_file(file_path)?;
+        self.play_samples(samples, sample_rate)
+    }
+
+    /// Play an Ogg/Opus file and block until playback is complete
+    pub fn play_opus_file(&mut self, file_path: &Path) -> Result<()> {
+        info!("Playing Opus file: {}", file_path.display());
+        let (samples, sample_rate) = self.decode_opus_file(file_path)?;
+        self.play_samples(samples, sample_rate)
+    }
+
+    /// Play a WAV, FLAC or Opus file, chosen by extension
+    pub fn play_file(&mut self, file_path: &Path) -> Result<()> {
+        self.play_file_from(file_path, Duration::ZERO)
+    }
+
+    /// Play a file starting `start` into it, e.g. at a session marker
+    pub fn play_file_from(&mut self, file_path: &Path, start: Duration) -> Result<()> {
+        info!("Playing {} from {:.1}s", file_path.display(), start.as_secs_f64());
+        let controller = self.start_file(file_path, None)?;
+        if start >= controller.duration() {
+            return Err(crate::core::error::AudioError::PlaybackError(format!(
+                "Start position {:.1}s is past the end of the file",
+                start.as_secs_f64()
+            )).into());
+        }
+        controller.seek(start);
+        self.wait_for(controller);
+        Ok(())
+    }
+
+    /// Start playing a file without blocking; the controller seeks, pauses and changes speed
+    pub fn start_file(&mut self, file_path: &Path, on_position: Option<PositionCallback>) -> Result<PlaybackController> {
+        let (samples, sample_rate) = self.decode_file(file_path)?;
+        self.start_samples(samples, sample_rate, on_position)
+    }
+
+    /// Start playing one transcript segment of a session without blocking
+    pub fn start_segment(
+        &mut self,
+        session: &AudioRecordingSession,
+        segment_id: Uuid,
+        on_position: Option<PositionCallback>,
+    ) -> Result<PlaybackController> {
+        let segment = session.transcript_segments.iter()
+            .find(|segment| segment.id == segment_id)
+            .ok_or_else(|| crate::core::error::AudioError::PlaybackError(format!("No transcript segment {}", segment_id)))?;
+        let range = segment.start_time..segment.end_time;
+        let controller = self.start_file(&session.file_path, on_position)?;
+        controller.set_range(range);
+        Ok(controller)
+    }
+
+    /// Play one transcript segment of a session and block until it is done
+    pub fn play_segment(&mut self, session: &AudioRecordingSession, segment_id: Uuid) -> Result<()> {
+        let controller = self.start_segment(session, segment_id, None)?;
+        self.wait_for(controller);
+        Ok(())
+    }
+
+    /// Decode a WAV, FLAC or Opus file to mono samples, chosen by extension
+    pub fn decode_file(&self, file_path: &Path) -> Result<(Vec<f32>, u32)> {
+        let extension = file_path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "flac" => self.decode_flac_file(file_path),
+            "opus" | "ogg" => self.decode_opus_file(file_path),
+            _ => self.decode_wav_file(file_path),
+        }
+    }
+
+    fn decode_wav_file(&self, file_path: &Path) -> Result<(Vec<f32>, u32)> {
+        let data = self.read_audio_file(file_path)?;
+        let mut reader = WavReader::new(std::io::Cursor::new(data))
             .map_err(|e| crate::core::error::AudioError::PlaybackError(format!("Failed to open WAV file: {}", e)))?;
         
         let spec = reader.spec();
         let samples: std::result::Result<Vec<f32>, hound::Error> = reader.samples::<i16>()
             .map(|s| s.map(|sample| sample as f32 / 32768.0))
             .collect();
-        
-        let audio_samples = samples
-            .map_err(|e| crate::core::error::AudioError::PlaybackError(format!("Failed to read audio samples: {}", e)))?;
+        let channels = spec.channels.max(1) as usize;
+        // Downmix to mono for playback; two-source sessions are stereo
+        let audio_samples: Vec<f32> = samples
+            .map_err(|e| crate::core::error::AudioError::PlaybackError(format!("Failed to read audio samples: {}", e)))?
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
         
         if audio_samples.is_empty() {
             return Err(crate::core::error::AudioError::PlaybackError("Audio file is empty".to_string()).into());
         }
-        
-        // Get the default output device
-        let host = cpal::default_host();
-        let device = host.default_output_device()
-            .ok_or_else(|| crate::core::error::AudioError::PlaybackError("No output device available".to_string()))?;
-        
-        // Configure the output stream to match the file's sample rate
-        let config = cpal::StreamConfig {
-            channels: 1, // We convert to mono
-            sample_rate: cpal::SampleRate(spec.sample_rate),
-            buffer_size: cpal::BufferSize::Default,
-        };
-        
-        // Prepare audio data for playback
-        let audio_data = Arc::new(Mutex::new(audio_samples));
-        let sample_index = Arc::new(Mutex::new(0usize));
-        let is_playing_clone = self.is_playing.clone();
-        let data_ref = audio_data.clone();
-        let index_ref = sample_index.clone();
-        
+
+        Ok((audio_samples, spec.sample_rate))
+    }
+
+    fn decode_flac_file(&self, file_path: &Path) -> Result<(Vec<f32>, u32)> {
+        let audio = crate::services::flac::decode(&self.read_audio_file(file_path)?)
+            .map_err(|e| crate::core::error::AudioError::PlaybackError(format!("Failed to read FLAC file: {}", e)))?;
+        let channels = audio.info.channels.max(1) as usize;
+        // Downmix to mono for playback
+        let audio_samples: Vec<f32> = audio.to_f32()
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+
+        if audio_samples.is_empty() {
+            return Err(crate::core::error::AudioError::PlaybackError("Audio file is empty".to_string()).into());
+        }
+
+        Ok((audio_samples, audio.info.sample_rate))
+    }
+
+    fn decode_opus_file(&self, file_path: &Path) -> Result<(Vec<f32>, u32)> {
+        let data = self.read_audio_file(file_path)?;
+        let audio = crate::services::ogg_opus::decode(&data)
+            .map_err(|e| crate::core::error::AudioError::PlaybackError(format!("Failed to decode Opus file: {}", e)))?;
+        let channels = audio.channels.max(1) as usize;
+        let audio_samples: Vec<f32> = audio.samples
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+
+        if audio_samples.is_empty() {
+            return Err(crate::core::error::AudioError::PlaybackError("Audio file is empty".to_string()).into());
+        }
+
+        Ok((audio_samples, audio.sample_rate))
+    }
+
+    /// Play mono samples and block until playback is complete
+    pub fn play_samples(&mut self, audio_samples: Vec<f32>, sample_rate: u32) -> Result<()> {
+        let controller = self.start_samples(audio_samples, sample_rate, None)?;
+        self.wait_for(controller);
+        info!("Audio playback completed");
+        Ok(())
+    }
+
+    /// Start playing mono samples without blocking
+    pub fn start_samples(
+        &mut self,
+        audio_samples: Vec<f32>,
+        sample_rate: u32,
+        on_position: Option<PositionCallback>,
+    ) -> Result<PlaybackController> {
+        PlaybackController::start(audio_samples, sample_rate, self.echo_reference.clone(), on_position)
+    }
+
+    /// Block until `controller` finishes or [`stop`](Self::stop) is called
+    fn wait_for(&self, controller: PlaybackController) {
         self.is_playing.store(true, Ordering::SeqCst);
-        
-        // Build the output stream
-        let stream = device.build_output_stream(
-            &config,
-            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                let mut index = index_ref.lock().unwrap();
-                let audio = data_ref.lock().unwrap();
-                
-                for sample in data.iter_mut() {
-                    if *index < audio.len() {
-                        *sample = audio[*index];
-                        *index += 1;
-                    } else {
-                        *sample = 0.0; // Silence after audio ends
-                    }
-                }
-                
-                // Check if we've reached the end
-                if *index >= audio.len() {
-                    is_playing_clone.store(false, Ordering::SeqCst);
-                }
-            },
-            |err| error!("Audio playback error: {}", err),
-            None,
-        ).map_err(|e| crate::core::error::AudioError::PlaybackError(format!("Failed to build output stream: {}", e)))?;
-        
-        // Start playback
-        stream.play()
-            .map_err(|e| crate::core::error::AudioError::PlaybackError(format!("Failed to start playback: {}", e)))?;
-        
-        // Wait for playback to complete
-        while self.is_playing.load(Ordering::SeqCst) {
+        while self.is_playing.load(Ordering::SeqCst) && !controller.is_finished() {
             thread::sleep(Duration::from_millis(10));
         }
-        
         // Give a small buffer for the audio to finish playing
         thread::sleep(Duration::from_millis(100));
-        
-        info!("Audio playback completed");
-        Ok(())
+        self.is_playing.store(false, Ordering::SeqCst);
     }
 
     /// Check if currently playing audio
@@ -124,6 +244,357 @@ impl Default for AudioPlaybackService {
         Self::new().unwrap_or_else(|_| Self {
             output_device: None,
             is_playing: Arc::new(AtomicBool::new(false)),
+            echo_reference: None,
+            cipher: None,
+        })
+    }
+}
+
+/// Index of the transcript segment being spoken at `position`
+pub fn segment_at(segments: &[TranscriptSegment], position: Duration) -> Option<usize> {
+    segments.iter().position(|segment| segment.start_time <= position && position < segment.end_time)
+}
+
+/// Shared between the audio callback and the controller
+struct PlaybackState {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    /// Read position in `samples`
+    position: f64,
+    /// Playback stops here
+    end: usize,
+    speed: f32,
+    paused: bool,
+    finished: bool,
+    stretcher: TimeStretcher,
+    /// Stretched output not yet handed to the device
+    pending: VecDeque<f32>,
+}
+
+impl PlaybackState {
+    fn next_sample(&mut self) -> f32 {
+        if self.paused || self.finished {
+            return 0.0;
+        }
+        if let Some(sample) = self.pending.pop_front() {
+            return sample;
+        }
+        let index = self.position as usize;
+        if index >= self.end {
+            self.finished = true;
+            return 0.0;
+        }
+        if self.speed == 1.0 {
+            self.position += 1.0;
+            return self.samples[index];
+        }
+        let mut output = Vec::with_capacity(self.stretcher.hop());
+        self.stretcher.render(&self.samples[..self.end], &mut self.position, self.speed, &mut output);
+        self.pending.extend(output);
+        self.pending.pop_front().unwrap_or(0.0)
+    }
+
+    /// Position of the sample being heard, accounting for stretched output still queued
+    fn heard_position(&self) -> f64 {
+        (self.position - self.pending.len() as f64 * self.speed as f64).max(0.0)
+    }
+
+    fn sample_at(&self, t: Duration) -> usize {
+        ((t.as_secs_f64() * self.sample_rate as f64) as usize).min(self.samples.len())
+    }
+
+    fn seek_to(&mut self, position: f64) {
+        self.position = position.min(self.end as f64);
+        self.pending.clear();
+        self.stretcher.reset();
+        self.finished = false;
+    }
+}
+
+/// Handle to audio playing on a background stream; dropping it stops playback
+pub struct PlaybackController {
+    state: Arc<Mutex<PlaybackState>>,
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl PlaybackController {
+    fn start(
+        samples: Vec<f32>,
+        sample_rate: u32,
+        echo_reference: Option<EchoReference>,
+        on_position: Option<PositionCallback>,
+    ) -> Result<Self> {
+        let state = Arc::new(Mutex::new(PlaybackState {
+            end: samples.len(),
+            samples,
+            sample_rate,
+            position: 0.0,
+            speed: 1.0,
+            paused: false,
+            finished: false,
+            stretcher: TimeStretcher::new(sample_rate),
+            pending: VecDeque::new(),
+        }));
+
+        // cpal streams are not Send, so the stream lives on its own thread,
+        // which also reports the position off the audio thread
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let (ready_tx, ready_rx) = mpsc::channel::<std::result::Result<(), String>>();
+        let thread_state = state.clone();
+        let thread = thread::spawn(move || {
+            let stream = match build_stream(thread_state.clone(), sample_rate, echo_reference) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+
+            let mut on_position = on_position;
+            let mut last_reported = None;
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stop_rx.recv_timeout(POSITION_INTERVAL) {
+                let Some(callback) = on_position.as_mut() else { continue };
+                let position = match thread_state.lock() {
+                    Ok(state) => Duration::from_secs_f64(state.heard_position() / state.sample_rate.max(1) as f64),
+                    Err(_) => break,
+                };
+                if last_reported != Some(position) {
+                    last_reported = Some(position);
+                    callback(position);
+                }
+            }
+            drop(stream);
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok(())) => Ok(Self { state, stop: Some(stop_tx), thread: Some(thread) }),
+            Ok(Err(e)) => Err(crate::core::error::AudioError::PlaybackError(e).into()),
+            Err(_) => Err(crate::core::error::AudioError::PlaybackError("Playback thread exited".to_string()).into()),
+        }
+    }
+
+    fn with_state<T>(&self, f: impl FnOnce(&mut PlaybackState) -> T) -> T {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut state)
+    }
+
+    /// Jump to `position`, clamped to the end of the audio
+    pub fn seek(&self, position: Duration) {
+        self.with_state(|state| {
+            let sample = state.sample_at(position);
+            state.seek_to(sample as f64);
+        });
+    }
+
+    /// Jump forwards (positive) or backwards (negative) by `seconds`
+    pub fn seek_by(&self, seconds: f64) {
+        let target = (self.position().as_secs_f64() + seconds).max(0.0);
+        self.seek(Duration::from_secs_f64(target));
+    }
+
+    /// Play only `range` of the audio, starting at its beginning
+    pub fn set_range(&self, range: Range<Duration>) {
+        self.with_state(|state| {
+            let start = state.sample_at(range.start);
+            state.end = state.sample_at(range.end).max(start);
+            state.seek_to(start as f64);
+        });
+    }
+
+    pub fn pause(&self) {
+        self.with_state(|state| state.paused = true);
+    }
+
+    pub fn resume(&self) {
+        self.with_state(|state| state.paused = false);
+    }
+
+    /// Toggle pause; returns whether playback is now paused
+    pub fn toggle_pause(&self) -> bool {
+        self.with_state(|state| {
+            state.paused = !state.paused;
+            state.paused
         })
     }
+
+    pub fn is_paused(&self) -> bool {
+        self.with_state(|state| state.paused)
+    }
+
+    /// Change the playback speed without changing pitch
+    pub fn set_speed(&self, speed: f32) -> Result<()> {
+        if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+            return Err(crate::core::error::AudioError::PlaybackError(format!(
+                "Playback speed must be between {}x and {}x, got {}x",
+                MIN_SPEED, MAX_SPEED, speed
+            )).into());
+        }
+        self.with_state(|state| {
+            let position = state.heard_position();
+            state.speed = speed;
+            state.seek_to(position);
+        });
+        Ok(())
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.with_state(|state| state.speed)
+    }
+
+    /// Position in the audio currently being heard
+    pub fn position(&self) -> Duration {
+        self.with_state(|state| Duration::from_secs_f64(state.heard_position() / state.sample_rate.max(1) as f64))
+    }
+
+    /// Length of the whole audio
+    pub fn duration(&self) -> Duration {
+        self.with_state(|state| Duration::from_secs_f64(state.samples.len() as f64 / state.sample_rate.max(1) as f64))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.with_state(|state| state.finished)
+    }
+
+    /// Block until playback reaches the end
+    pub fn wait(&self) {
+        while !self.is_finished() {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Stop playback and release the output device
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for PlaybackController {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn build_stream(
+    state: Arc<Mutex<PlaybackState>>,
+    sample_rate: u32,
+    echo_reference: Option<EchoReference>,
+) -> std::result::Result<cpal::Stream, String> {
+    let host = cpal::default_host();
+    let device = host.default_output_device().ok_or_else(|| "No output device available".to_string())?;
+
+    // Configure the output stream to match the file's sample rate
+    let config = cpal::StreamConfig {
+        channels: 1, // We convert to mono
+        sample_rate: cpal::SampleRate(sample_rate),
+        buffer_size: cpal::BufferSize::Default,
+    };
+
+    let stream = device.build_output_stream(
+        &config,
+        move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            match state.lock() {
+                Ok(mut state) => data.iter_mut().for_each(|sample| *sample = state.next_sample()),
+                Err(_) => data.fill(0.0),
+            }
+            // Let the capture side cancel what is about to come out of the speaker
+            if let Some(reference) = &echo_reference {
+                reference.push(data, sample_rate);
+            }
+        },
+        |err| error!("Audio playback error: {}", err),
+        None,
+    ).map_err(|e| format!("Failed to build output stream: {}", e))?;
+
+    stream.play().map_err(|e| format!("Failed to start playback: {}", e))?;
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(samples: Vec<f32>) -> PlaybackState {
+        PlaybackState {
+            end: samples.len(),
+            samples,
+            sample_rate: 16000,
+            position: 0.0,
+            speed: 1.0,
+            paused: false,
+            finished: false,
+            stretcher: TimeStretcher::new(16000),
+            pending: VecDeque::new(),
+        }
+    }
+
+    #[test]
+    fn test_playback_state_speed_pause_and_range() {
+        let mut playback = state((0..16000).map(|i| (i as f32 * 0.05).sin()).collect());
+        playback.paused = true;
+        assert_eq!(playback.next_sample(), 0.0);
+        assert_eq!(playback.position, 0.0);
+        playback.paused = false;
+
+        playback.speed = 2.0;
+        let mut played = 0;
+        while !playback.finished {
+            playback.next_sample();
+            played += 1;
+        }
+        assert!((7800..8300).contains(&played), "{} samples", played);
+
+        playback.end = 8000;
+        playback.speed = 1.0;
+        playback.seek_to(4000.0);
+        assert_eq!(playback.next_sample(), playback.samples[4000]);
+        let remaining = std::iter::from_fn(|| (!playback.finished).then(|| playback.next_sample())).count();
+        assert_eq!(remaining, 4000);
+    }
+
+    #[test]
+    fn test_decode_stereo_wav_downmixes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("raw_audio.wav");
+        let spec = hound::WavSpec { channels: 2, sample_rate: 16000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..1600 {
+            writer.write_sample(8192i16).unwrap();
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let (samples, sample_rate) = AudioPlaybackService::new().unwrap().decode_wav_file(&path).unwrap();
+        assert_eq!(sample_rate, 16000);
+        assert_eq!(samples.len(), 1600);
+        assert!((samples[0] - 0.125).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_segment_at() {
+        let segment = |start: u64, end: u64| TranscriptSegment {
+            id: Uuid::new_v4(),
+            start_time: Duration::from_secs(start),
+            end_time: Duration::from_secs(end),
+            text: String::new(),
+            confidence: 1.0,
+            speaker_id: None,
+            language: None,
+            word_count: 0,
+            is_final: true,
+            prosody: None,
+        };
+        let segments = vec![segment(0, 2), segment(3, 5)];
+        assert_eq!(segment_at(&segments, Duration::from_secs(1)), Some(0));
+        assert_eq!(segment_at(&segments, Duration::from_millis(2500)), None);
+        assert_eq!(segment_at(&segments, Duration::from_secs(3)), Some(1));
+    }
 }
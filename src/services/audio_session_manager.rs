@@ -4,10 +4,10 @@
 //! transcript logging, and comprehensive session tracking.
 
 use std::collections::HashMap;
-use std::fs::{self, File, OpenOptions};
-use std::io::{BufWriter, Write};
+use std::fs::{self, OpenOptions};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::borrow::Cow;
 use std::time::{Duration, Instant, SystemTime};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -19,7 +19,30 @@ use crate::services::audio::AudioService;
 use crate::services::stt::STTService;
 use crate::services::vad::VADService;
 use crate::services::audio_storage::FileAudioStorage;
-use crate::services::audio_archive::AudioFormatInfo;
+use crate::services::audio_archive::{AudioError as ArchiveError, AudioFormatInfo, AudioStorage, CompressionResult};
+use crate::services::flac;
+use crate::services::source_mixer::{MixMode, SecondarySourceConfig, SourceMixerConfig};
+use crate::services::signal_analysis::{labels_from_segments, SignalAnalyzer};
+use crate::services::diarization::{DiarizationConfig, SpeakerCluster, SpeakerDiarizer};
+use crate::services::prosody::{ProsodyAnalyzer, ProsodyFeatures};
+use crate::services::transcription_log::SignalMetrics;
+use crate::services::transcription_manager::{TranscriptionManager, TranscriptionManagerConfig};
+use crate::services::encryption::{self, StorageCipher};
+use crate::services::path_template::{self, PathFields, PathTemplate};
+use crate::services::session_journal::{JournalStream, JournalWriter, SessionJournal};
+use crate::services::session_edit::{self, SessionAudio, SplitPoint};
+use crate::services::session_markers::{self, MarkerKind, SessionMarker};
+use crate::services::session_supervisor::{SessionSupervisor, SupervisorAction, SupervisorConfig, SupervisorEvent};
+use crate::services::transcript_revisions::{self, DecodingConfig, RetranscriptionJob, RetranscriptionSelection, TranscriptRevision};
+use crate::services::session_import::{self, ImportOutcome, CONTENT_HASH_KEY, IMPORTED_FROM_KEY, TIMESTAMP_SOURCE_KEY};
+use crate::services::session_bundle::{self, BundleManifest, BundleOptions, IdPolicy};
+use crate::services::dataset_export::{DatasetOptions, DatasetSummary, DatasetWriter};
+use crate::services::archive_check::{ArchiveCheckReport, ArchiveChecker};
+use crate::services::waveform::{self, LoudnessSummary, SessionOverview, WaveformLevel};
+
+/// Seconds of recent audio kept in memory for speaker and prosody analysis;
+/// anything older is read back from the journal
+const RECENT_AUDIO_SECONDS: usize = 120;
 
 /// Audio source type for recording
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -32,6 +55,10 @@ pub enum AudioSource {
     Mixed,
     /// Specific device by name
     Device(String),
+    /// Two named inputs captured together (primary `None` = default microphone)
+    Devices { primary: Option<String>, secondary: String },
+    /// An existing audio file brought into the archive
+    Imported(PathBuf),
 }
 
 impl std::fmt::Display for AudioSource {
@@ -41,6 +68,10 @@ impl std::fmt::Display for AudioSource {
             AudioSource::SystemAudio => write!(f, "System Audio"),
             AudioSource::Mixed => write!(f, "Mixed (Microphone + System Audio)"),
             AudioSource::Device(name) => write!(f, "Device: {}", name),
+            AudioSource::Devices { primary, secondary } => {
+                write!(f, "Devices: {} + {}", primary.as_deref().unwrap_or("Microphone"), secondary)
+            }
+            AudioSource::Imported(path) => write!(f, "Imported: {}", path.display()),
         }
     }
 }
@@ -87,6 +118,87 @@ pub struct TranscriptSegment {
     pub language: Option<String>,
     pub word_count: usize,
     pub is_final: bool,
+    /// Pitch, loudness, pauses and speaking rate of the segment audio
+    #[serde(default)]
+    pub prosody: Option<ProsodyFeatures>,
+}
+
+#[cfg(test)]
+impl AudioRecordingSession {
+    /// A stopped 8 kHz mono WAV session with no transcript, for tests
+    pub(crate) fn for_test(name: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: None,
+            start_time: Utc::now(),
+            end_time: None,
+            duration: Duration::ZERO,
+            audio_source: AudioSource::Microphone,
+            file_path: PathBuf::from("raw_audio.wav"),
+            file_size: 0,
+            format_info: AudioFormatInfo {
+                sample_rate: 8000,
+                channels: 1,
+                bit_depth: 16,
+                format: crate::services::audio_archive::AudioFormat::WAV,
+            },
+            transcript_segments: Vec::new(),
+            tags: Vec::new(),
+            metadata: HashMap::new(),
+            state: SessionState::Stopped,
+            quality_metrics: QualityMetrics::default(),
+        }
+    }
+
+    pub(crate) fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub(crate) fn with_file_path(mut self, file_path: impl Into<PathBuf>) -> Self {
+        self.file_path = file_path.into();
+        self
+    }
+
+    pub(crate) fn with_segments(mut self, segments: Vec<TranscriptSegment>) -> Self {
+        self.transcript_segments = segments;
+        self
+    }
+}
+
+#[cfg(test)]
+impl TranscriptSegment {
+    /// A final segment between two offsets in seconds, with 0.9 confidence
+    pub(crate) fn for_test(start: f64, end: f64, text: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            start_time: Duration::from_secs_f64(start),
+            end_time: Duration::from_secs_f64(end),
+            text: text.to_string(),
+            confidence: 0.9,
+            speaker_id: None,
+            language: None,
+            word_count: text.split_whitespace().count(),
+            is_final: true,
+            prosody: None,
+        }
+    }
+
+    pub(crate) fn with_confidence(mut self, confidence: f32) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    pub(crate) fn with_speaker(mut self, speaker: &str) -> Self {
+        self.speaker_id = Some(speaker.to_string());
+        self
+    }
+
+    pub(crate) fn with_language(mut self, language: &str) -> Self {
+        self.language = Some(language.to_string());
+        self
+    }
 }
 
 /// Audio quality metrics
@@ -98,6 +210,21 @@ pub struct QualityMetrics {
     pub silence_percentage: f32,
     pub clipping_events: u32,
     pub vad_accuracy: f32,
+    /// Average input gain applied by the AGC/manual gain stage (dB)
+    #[serde(default)]
+    pub average_gain_db: f32,
+    /// Lowest input gain applied during the session (dB)
+    #[serde(default)]
+    pub min_gain_db: f32,
+    /// Highest input gain applied during the session (dB)
+    #[serde(default)]
+    pub max_gain_db: f32,
+    /// Number of capture frames in which the soft limiter engaged
+    #[serde(default)]
+    pub limiter_activations: u32,
+    /// Full signal analysis of the session audio
+    #[serde(default)]
+    pub signal_metrics: Option<SignalMetrics>,
 }
 
 /// Session manager for handling audio recording sessions
@@ -111,12 +238,14 @@ pub struct AudioSessionManager {
     stt_service: Option<Arc<Mutex<STTService>>>,
     /// VAD service for voice detection
     vad_service: Option<Arc<Mutex<VADService>>>,
+    /// Transcript log that imported recordings are added to
+    transcription_manager: Option<Arc<Mutex<TranscriptionManager>>>,
     /// Audio storage backend
     storage: Arc<Mutex<FileAudioStorage>>,
     /// Session configuration
     config: SessionConfig,
-    /// Audio buffer for recording
-    audio_buffer: Arc<Mutex<Vec<f32>>>,
+    /// The last few minutes of the recording; the full audio streams to the journal
+    audio_buffer: Arc<Mutex<RecentAudio>>,
     /// Recording state
     recording_state: Arc<Mutex<SessionState>>,
     /// Session history
@@ -125,6 +254,275 @@ pub struct AudioSessionManager {
     storage_dir: PathBuf,
     /// Actual sample rate from audio capture
     actual_sample_rate: Arc<Mutex<Option<u32>>>,
+    /// Labels transcript segments with speakers
+    diarizer: SpeakerDiarizer,
+    /// Pitch and prosody of transcript segments
+    prosody: ProsodyAnalyzer,
+    /// Encrypts session audio and metadata when set
+    cipher: Option<Arc<StorageCipher>>,
+    /// On-disk journal of the session being recorded
+    journal: Option<(SessionJournal, JournalWriter)>,
+    /// Enforces the silence timeout and duration limit
+    supervisor: SessionSupervisor,
+}
+
+/// Recent mono audio, addressed by sample position from the session start
+#[derive(Debug, Default)]
+struct RecentAudio {
+    samples: Vec<f32>,
+    /// Position of `samples[0]` in the session
+    offset: usize,
+}
+
+impl RecentAudio {
+    fn push(&mut self, samples: &[f32], window: usize) {
+        self.samples.extend_from_slice(samples);
+        // Trim in bulk so appends stay cheap
+        if self.samples.len() > window * 2 {
+            let excess = self.samples.len() - window;
+            self.samples.drain(..excess);
+            self.offset += excess;
+        }
+    }
+
+    /// Samples recorded so far
+    fn len(&self) -> usize {
+        self.offset + self.samples.len()
+    }
+
+    /// `start..end` if it is still in memory
+    fn range(&self, start: usize, end: usize) -> Option<&[f32]> {
+        if start < self.offset {
+            return None;
+        }
+        let end = end.min(self.len());
+        self.samples.get(start - self.offset..end.max(start) - self.offset)
+    }
+}
+
+/// Audio that session outputs are written from, a block at a time
+enum OutputAudio<'a> {
+    Samples { mono: &'a [f32], stereo: &'a [f32] },
+    /// Read chunk by chunk, so a long recording is never held in memory
+    Journal(&'a SessionJournal),
+}
+
+impl OutputAudio<'_> {
+    fn is_empty(&self, stream: JournalStream) -> Result<bool, ArchiveError> {
+        match self {
+            Self::Samples { mono, stereo } => Ok(match stream {
+                JournalStream::Mono => mono.is_empty(),
+                JournalStream::Stereo => stereo.is_empty(),
+            }),
+            Self::Journal(journal) => journal.is_empty(stream),
+        }
+    }
+
+    fn for_each_block(
+        &self,
+        stream: JournalStream,
+        mut f: impl FnMut(&[f32]) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::Samples { mono, stereo } => {
+                let samples = match stream {
+                    JournalStream::Mono => mono,
+                    JournalStream::Stereo => stereo,
+                };
+                if !samples.is_empty() {
+                    f(samples)?;
+                }
+            }
+            Self::Journal(journal) => {
+                for chunk in journal.read_chunks(stream)? {
+                    f(&chunk?.1)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A 16-bit WAV file written a block at a time
+enum WavOutput {
+    /// Streamed to a temporary file that replaces the destination when finished
+    Plain {
+        writer: hound::WavWriter<std::io::BufWriter<fs::File>>,
+        file: fs::File,
+        temp_path: PathBuf,
+        path: PathBuf,
+    },
+    /// The cipher seals whole files, so encrypted audio is kept as samples and
+    /// encoded in memory; plaintext audio never reaches the disk
+    Sealed {
+        samples: Vec<i16>,
+        spec: hound::WavSpec,
+        path: PathBuf,
+        cipher: Arc<StorageCipher>,
+    },
+}
+
+impl WavOutput {
+    fn create(path: &Path, format_info: &AudioFormatInfo, cipher: Option<Arc<StorageCipher>>) -> Result<Self, Box<dyn std::error::Error>> {
+        let spec = hound::WavSpec {
+            channels: format_info.channels,
+            sample_rate: format_info.sample_rate,
+            bits_per_sample: format_info.bit_depth as u16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        if let Some(cipher) = cipher {
+            return Ok(Self::Sealed { samples: Vec::new(), spec, path: path.to_path_buf(), cipher });
+        }
+        let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+        let file = fs::File::create(&temp_path)?;
+        let writer = hound::WavWriter::new(std::io::BufWriter::new(file.try_clone()?), spec)?;
+        Ok(Self::Plain { writer, file, temp_path, path: path.to_path_buf() })
+    }
+
+    fn write(&mut self, samples: &[f32]) -> Result<(), hound::Error> {
+        // Convert f32 samples to i16
+        let to_i16 = |sample: f32| (sample * i16::MAX as f32) as i16;
+        match self {
+            Self::Plain { writer, .. } => {
+                for &sample in samples {
+                    writer.write_sample(to_i16(sample))?;
+                }
+            }
+            Self::Sealed { samples: encoded, .. } => encoded.extend(samples.iter().map(|&sample| to_i16(sample))),
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::Plain { writer, file, temp_path, path } => {
+                writer.finalize()?;
+                file.sync_all()?;
+                fs::rename(&temp_path, &path)?;
+            }
+            Self::Sealed { samples, spec, path, cipher } => {
+                encryption::write_file(&path, &encode_wav(samples, spec)?, Some(&cipher))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Encode 16-bit samples as a WAV file in memory
+fn encode_wav(samples: impl IntoIterator<Item = i16>, spec: hound::WavSpec) -> Result<Vec<u8>, hound::Error> {
+    let mut wav = std::io::Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut wav, spec)?;
+    for sample in samples {
+        writer.write_sample(sample)?;
+    }
+    writer.finalize()?;
+    Ok(wav.into_inner())
+}
+
+/// Energy-based speech detection over audio fed a block at a time; only the
+/// samples still needed by the next frame are kept between blocks
+struct SpeechDetector {
+    frame_size: usize,
+    hop_size: usize,
+    energy_threshold: f32,
+    min_speech_duration: usize,
+    max_silence_gap: usize,
+    buffer: Vec<f32>,
+    /// Position of `buffer[0]` in the audio
+    buffer_start: usize,
+    next_frame: usize,
+    total_samples: usize,
+    current_segment_start: Option<usize>,
+    last_speech_frame: usize,
+    segments: Vec<(usize, usize)>,
+}
+
+impl SpeechDetector {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            frame_size: (sample_rate as f32 * 0.025) as usize, // 25ms frames
+            hop_size: ((sample_rate as f32 * 0.010) as usize).max(1), // 10ms hop
+            energy_threshold: 0.001, // Configurable threshold
+            min_speech_duration: (sample_rate as f32 * 0.5) as usize, // 500ms minimum
+            max_silence_gap: (sample_rate as f32 * 0.3) as usize, // 300ms max gap
+            buffer: Vec::new(),
+            buffer_start: 0,
+            next_frame: 0,
+            total_samples: 0,
+            current_segment_start: None,
+            last_speech_frame: 0,
+            segments: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, samples: &[f32]) {
+        self.buffer.extend_from_slice(samples);
+        self.total_samples += samples.len();
+        while self.next_frame + self.frame_size <= self.total_samples {
+            self.process_frame(self.next_frame + self.frame_size);
+        }
+        let consumed = self.next_frame.min(self.total_samples) - self.buffer_start;
+        self.buffer.drain(..consumed);
+        self.buffer_start += consumed;
+    }
+
+    fn process_frame(&mut self, frame_end: usize) {
+        let frame_start = self.next_frame;
+        self.next_frame += self.hop_size;
+
+        // Calculate frame energy
+        let frame = &self.buffer[frame_start - self.buffer_start..frame_end - self.buffer_start];
+        let is_speech = frame_energy(frame) > self.energy_threshold;
+
+        if is_speech {
+            if self.current_segment_start.is_none() {
+                self.current_segment_start = Some(frame_start);
+            }
+            self.last_speech_frame = frame_end;
+        } else if let Some(segment_start) = self.current_segment_start {
+            // Check if silence gap is too long
+            if frame_start > self.last_speech_frame && frame_start - self.last_speech_frame > self.max_silence_gap {
+                // End current segment if it's long enough
+                if self.last_speech_frame > segment_start && self.last_speech_frame - segment_start >= self.min_speech_duration {
+                    self.segments.push((segment_start, self.last_speech_frame));
+                }
+                self.current_segment_start = None;
+            }
+        }
+    }
+
+    /// Speech segments as sample ranges
+    fn finish(mut self) -> Vec<(usize, usize)> {
+        // The last frames are cut short at the end of the audio
+        while self.next_frame < self.total_samples {
+            self.process_frame(self.total_samples.min(self.next_frame + self.frame_size));
+        }
+
+        // Handle final segment
+        if let Some(segment_start) = self.current_segment_start {
+            if self.total_samples > segment_start && self.total_samples - segment_start >= self.min_speech_duration {
+                self.segments.push((segment_start, self.total_samples));
+            }
+        }
+
+        debug!(
+            segments_count = self.segments.len(),
+            total_samples = self.total_samples,
+            "🎯 Detected speech segments"
+        );
+
+        self.segments
+    }
+}
+
+/// Calculate energy of an audio frame
+fn frame_energy(frame: &[f32]) -> f32 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+    let sum_squares: f32 = frame.iter().map(|&s| s * s).sum();
+    sum_squares / frame.len() as f32
 }
 
 /// Session configuration
@@ -139,8 +537,21 @@ pub struct SessionConfig {
     pub quality_monitoring: bool,
     pub backup_enabled: bool,
     pub default_audio_source: AudioSource,
+    /// Mix mode and per-source gain for two-source recordings (Mixed / Devices)
+    pub source_mix: SourceMixerConfig,
+    /// Speaker labelling of transcript segments
+    pub diarization: DiarizationConfig,
+    /// Session directory layout under `sessions/`, e.g. `{date:%Y/%m}/{tag}/{name}-{id8}`
+    pub layout: PathTemplate,
+    /// Audio per journal chunk; at most this much is lost if the process dies
+    pub journal_chunk_duration: Duration,
+    /// Auto-pause, limit warning and rollover behaviour
+    pub supervisor: SupervisorConfig,
 }
 
+/// Default session directory layout
+pub const DEFAULT_SESSION_LAYOUT: &str = "{date:%Y/%m/%d}/{name}_{id}";
+
 impl Default for SessionConfig {
     fn default() -> Self {
         Self {
@@ -153,6 +564,11 @@ impl Default for SessionConfig {
             quality_monitoring: true,
             backup_enabled: true,
             default_audio_source: AudioSource::Microphone,
+            source_mix: SourceMixerConfig::default(),
+            diarization: DiarizationConfig::default(),
+            layout: PathTemplate::parse(DEFAULT_SESSION_LAYOUT).expect("default session layout is valid"),
+            journal_chunk_duration: Duration::from_secs(10),
+            supervisor: SupervisorConfig::default(),
         }
     }
 }
@@ -163,6 +579,16 @@ impl AudioSessionManager {
         audio_service: Arc<Mutex<AudioService>>,
         storage_dir: PathBuf,
         config: SessionConfig,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::new_with_cipher(audio_service, storage_dir, config, None)
+    }
+
+    /// Create a session manager that encrypts recordings and metadata at rest
+    pub fn new_with_cipher(
+        audio_service: Arc<Mutex<AudioService>>,
+        storage_dir: PathBuf,
+        config: SessionConfig,
+        cipher: Option<Arc<StorageCipher>>,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         // Ensure storage directory exists
         if !storage_dir.exists() {
@@ -172,24 +598,37 @@ impl AudioSessionManager {
         // Initialize storage backend
         let storage_config = crate::services::audio_storage::StorageConfig::default();
         let storage = Arc::new(Mutex::new(
-            FileAudioStorage::new(storage_dir.clone(), storage_config)?
+            FileAudioStorage::new_with_cipher(storage_dir.clone(), storage_config, cipher.clone())?
         ));
 
+        let diarizer = SpeakerDiarizer::new(config.diarization.clone());
+
         Ok(Self {
             current_session: None,
             audio_service,
             stt_service: None,
             vad_service: None,
+            transcription_manager: None,
             storage,
             config,
-            audio_buffer: Arc::new(Mutex::new(Vec::new())),
+            audio_buffer: Arc::new(Mutex::new(RecentAudio::default())),
             recording_state: Arc::new(Mutex::new(SessionState::Idle)),
             session_history: Vec::new(),
             storage_dir,
             actual_sample_rate: Arc::new(Mutex::new(None)),
+            diarizer,
+            prosody: ProsodyAnalyzer::default(),
+            cipher,
+            journal: None,
+            supervisor: SessionSupervisor::default(),
         })
     }
 
+    /// Cipher used for stored sessions, if encryption is enabled
+    pub fn cipher(&self) -> Option<&Arc<StorageCipher>> {
+        self.cipher.as_ref()
+    }
+
     /// Attach STT service for transcription
     pub fn attach_stt_service(&mut self, stt_service: Arc<Mutex<STTService>>) {
         self.stt_service = Some(stt_service);
@@ -200,6 +639,17 @@ impl AudioSessionManager {
         self.vad_service = Some(vad_service);
     }
 
+    /// Open a transcript log, encrypted like the sessions, so imported recordings can be
+    /// searched like dictation. Returns the manager to share for search and analytics.
+    pub fn attach_transcription_manager(
+        &mut self,
+        config: TranscriptionManagerConfig,
+    ) -> Result<Arc<Mutex<TranscriptionManager>>, Box<dyn std::error::Error>> {
+        let manager = Arc::new(Mutex::new(TranscriptionManager::new_with_cipher(config, self.cipher.clone())?));
+        self.transcription_manager = Some(manager.clone());
+        Ok(manager)
+    }
+
     /// Start a new recording session
     pub fn start_recording_session(
         &mut self,
@@ -218,7 +668,7 @@ impl AudioSessionManager {
         let audio_source = audio_source.unwrap_or_else(|| self.config.default_audio_source.clone());
 
         // Generate session directory (new approach - no single file)
-        let session_dir = self.create_session_directory_for_new_session(&session_id, &name, &start_time)?;
+        let session_dir = self.create_session_directory_for_new_session(&session_id, &name, &start_time, &tags)?;
         
         // Temporary file path - will be updated when comprehensive outputs are generated
         let temp_file_path = session_dir.join("session.wav");
@@ -250,36 +700,67 @@ impl AudioSessionManager {
         // Configure audio source
         self.configure_audio_source(&audio_source)?;
 
-        // Reset sample rate for new session
+        // Stream the recording to a journal so a crash loses at most one chunk
+        let journal = SessionJournal::create(&session_dir, self.cipher.clone())?;
+        journal.write_header(&session)?;
+        let writer = journal.start_writer(self.config.journal_chunk_duration);
+        let journal_sender = writer.sender();
+        self.journal = Some((journal, writer));
+
+        // Reset sample rate for new session; callbacks left from earlier sessions
+        // hold the old buffer and journal and go quiet
         *self.actual_sample_rate.lock().unwrap() = None;
+        self.audio_buffer = Arc::new(Mutex::new(RecentAudio::default()));
+        self.diarizer.reset();
+
+        // Gain statistics are reported per session
+        if let Ok(audio_service) = self.audio_service.lock() {
+            audio_service.reset_gain_stats();
+        }
 
         // Update state FIRST to ensure callback can capture audio
         *self.recording_state.lock().unwrap() = SessionState::Recording;
         self.current_session = Some(session);
 
         // Set up audio callback to capture data
-        let buffer = self.audio_buffer.clone();
+        let buffer = Arc::downgrade(&self.audio_buffer);
+        let sender = journal_sender.clone();
         let state = self.recording_state.clone();
         let sample_rate_ref = self.actual_sample_rate.clone();
         
         if let Ok(mut audio_service) = self.audio_service.lock() {
             audio_service.on_audio_frame(move |samples, sample_rate| {
                 // Store the actual sample rate on first callback
+                let mut session_rate = sample_rate;
                 if let Ok(mut sr) = sample_rate_ref.lock() {
-                    if sr.is_none() {
-                        *sr = Some(sample_rate);
-                        debug!(
-                            actual_sample_rate = sample_rate,
-                            "🎵 Captured actual audio sample rate"
-                        );
+                    match *sr {
+                        None => {
+                            *sr = Some(sample_rate);
+                            debug!(
+                                actual_sample_rate = sample_rate,
+                                "🎵 Captured actual audio sample rate"
+                            );
+                        }
+                        Some(rate) => session_rate = rate,
                     }
                 }
                 
                 if let Ok(current_state) = state.lock() {
                     if *current_state == SessionState::Recording {
-                        if let Ok(mut buf) = buffer.lock() {
+                        let Some(recent) = buffer.upgrade() else {
+                            return;
+                        };
+                        let guard = recent.lock();
+                        if let Ok(mut buf) = guard {
                             let before_len = buf.len();
-                            buf.extend_from_slice(samples);
+                            let samples: Cow<[f32]> = if session_rate == sample_rate {
+                                Cow::Borrowed(samples)
+                            } else {
+                                // Stream was restarted at a new rate mid-session; keep the session at its original rate
+                                Cow::Owned(crate::services::capture_config::resample_linear(samples, sample_rate, session_rate))
+                            };
+                            buf.push(&samples, RECENT_AUDIO_SECONDS * session_rate as usize);
+                            sender.append(JournalStream::Mono, &samples, session_rate);
                             debug!(
                                 samples_received = samples.len(),
                                 sample_rate = sample_rate,
@@ -298,6 +779,18 @@ impl AudioSessionManager {
                 }
             });
 
+            // Two-source stereo recordings keep each source on its own channel
+            if self.records_stereo(&audio_source) {
+                let sender = journal_sender;
+                let state = self.recording_state.clone();
+                audio_service.on_channel_frame(move |samples, channels, sample_rate| {
+                    let recording = state.lock().map(|s| *s == SessionState::Recording).unwrap_or(false);
+                    if recording && channels == 2 {
+                        sender.append(JournalStream::Stereo, samples, sample_rate);
+                    }
+                });
+            }
+
             // Start audio capture
             audio_service.start_capture()?;
         }
@@ -320,9 +813,19 @@ impl AudioSessionManager {
                 .unwrap_or_default();
             session.state = SessionState::Stopped;
 
-            // Stop audio capture
+            // Stop audio capture and collect gain statistics for the session
             if let Ok(mut audio_service) = self.audio_service.lock() {
                 audio_service.stop_capture()?;
+                let gain_stats = audio_service.get_gain_stats();
+                session.quality_metrics.average_gain_db = gain_stats.average_gain_db;
+                session.quality_metrics.min_gain_db = gain_stats.min_gain_db;
+                session.quality_metrics.max_gain_db = gain_stats.max_gain_db;
+                session.quality_metrics.limiter_activations = gain_stats.limiter_activations;
+                if let Some(mixer_stats) = audio_service.get_mixer_stats() {
+                    session.metadata.insert("mixer_underruns".to_string(), mixer_stats.underruns.to_string());
+                    session.metadata.insert("mixer_dropped_samples".to_string(), mixer_stats.dropped_samples.to_string());
+                    session.metadata.insert("drift_correction_ppm".to_string(), format!("{:.1}", mixer_stats.drift_correction_ppm));
+                }
             }
 
             // Update session format info with actual sample rate
@@ -337,33 +840,43 @@ impl AudioSessionManager {
                 }
             }
 
+            // Flush the journal; the outputs are written from it chunk by chunk
+            let journal = self.journal.take().map(|(journal, writer)| {
+                if let Err(e) = writer.finish() {
+                    warn!(session_id = %session.id, error = %e, "⚠️  Journal writer reported an error; saving what reached disk");
+                }
+                journal
+            });
+            let recorded = match &journal {
+                Some(journal) => !journal.is_empty(JournalStream::Mono)?,
+                None => false,
+            };
+
             // Save all audio outputs
-            if let Ok(buffer) = self.audio_buffer.lock() {
-                info!(
-                    buffer_size = buffer.len(),
-                    buffer_empty = buffer.is_empty(),
-                    actual_sample_rate = session.format_info.sample_rate,
-                    "📊 Checking audio buffer for session outputs"
+            info!(
+                recorded,
+                actual_sample_rate = session.format_info.sample_rate,
+                "📊 Checking journaled audio for session outputs"
+            );
+
+            if let Some(journal) = journal.as_ref().filter(|_| recorded) {
+                // Save multiple audio outputs
+                self.write_session_outputs(&mut session, &OutputAudio::Journal(journal))?;
+            } else {
+                warn!(
+                    session_id = %session.id,
+                    "⚠️  Audio buffer is empty, no comprehensive outputs will be generated"
                 );
-                
-                if !buffer.is_empty() {
-                    // Save multiple audio outputs
-                    self.save_session_outputs(&mut session, &buffer)?;
-                } else {
-                    warn!(
-                        session_id = %session.id,
-                        "⚠️  Audio buffer is empty, no comprehensive outputs will be generated"
-                    );
-                    
-                    // Still save basic session metadata even if no audio was captured
-                    self.save_session_metadata(&session)?;
-                }
+
+                // Still save basic session metadata even if no audio was captured
+                self.save_session_metadata(&session)?;
             }
 
-            // Clear audio buffer
-            if let Ok(mut buffer) = self.audio_buffer.lock() {
-                buffer.clear();
+            // The outputs are written, so the journal is no longer needed
+            if let Some(journal) = journal {
+                journal.remove()?;
             }
+            self.audio_buffer = Arc::new(Mutex::new(RecentAudio::default()));
 
             // Update state
             *self.recording_state.lock().unwrap() = SessionState::Idle;
@@ -424,6 +937,111 @@ impl AudioSessionManager {
         Ok(false)
     }
 
+    /// Drop a marker at the current position of the recording
+    pub fn add_marker(&mut self, kind: MarkerKind, label: Option<String>) -> Result<SessionMarker, Box<dyn std::error::Error>> {
+        let sample_rate = self.actual_sample_rate.lock().ok().and_then(|sr| *sr)
+            .or_else(|| self.current_session.as_ref().map(|s| s.format_info.sample_rate))
+            .unwrap_or(44100);
+        let recorded = self.get_audio_buffer_size();
+        let session = self.current_session.as_mut()
+            .ok_or_else(|| ArchiveError::RecordingError("No active recording session to mark".to_string()))?;
+
+        let marker = SessionMarker::new(
+            Duration::from_secs_f64(recorded as f64 / sample_rate.max(1) as f64),
+            kind,
+            label,
+        );
+        let mut markers = session_markers::markers(&session.metadata);
+        markers.push(marker.clone());
+        session_markers::set_markers(&mut session.metadata, &markers);
+        if let Some((journal, _)) = &self.journal {
+            if let Err(e) = journal.write_header(session) {
+                warn!(session_id = %session.id, error = %e, "Failed to update session journal");
+            }
+        }
+
+        info!(
+            session_id = %session.id,
+            kind = ?marker.kind,
+            label = marker.label.as_deref().unwrap_or(""),
+            at = ?marker.at,
+            "📍 Added session marker"
+        );
+        Ok(marker)
+    }
+
+    /// Record that the VAD heard speech, for the silence timeout
+    pub fn note_voice_activity(&mut self) {
+        self.supervisor.observe_voice(Instant::now());
+    }
+
+    /// Apply the silence timeout and duration limit to the current session;
+    /// call regularly from the main loop
+    pub fn supervise(&mut self) -> Result<Option<SupervisorEvent>, Box<dyn std::error::Error>> {
+        let now = Instant::now();
+        let status = self.current_session.as_ref().map(|session| {
+            let elapsed = (Utc::now() - session.start_time).to_std().unwrap_or_default();
+            (session.id, &session.state, elapsed)
+        });
+        let Some(action) = self.supervisor.tick(&self.config, now, status) else {
+            return Ok(None);
+        };
+
+        Ok(match action {
+            SupervisorAction::Pause => self.pause_recording_session()?
+                .then(|| SupervisorEvent::AutoPaused { silence: self.supervisor.silence(now) }),
+            SupervisorAction::Resume => self.resume_recording_session()?.then_some(SupervisorEvent::AutoResumed),
+            SupervisorAction::Warn { remaining } => {
+                info!(remaining = ?remaining, "⏳ Recording session approaching its duration limit");
+                Some(SupervisorEvent::LimitApproaching { remaining, rollover: self.config.supervisor.rollover })
+            }
+            SupervisorAction::Stop => {
+                self.set_session_metadata("auto_stopped", "max_duration");
+                self.stop_recording_session()?.map(|session| SupervisorEvent::Stopped { session })
+            }
+            SupervisorAction::Rollover => self.roll_over_session()?,
+        })
+    }
+
+    /// Stop the current session at its limit and continue in a new one with the same settings
+    fn roll_over_session(&mut self) -> Result<Option<SupervisorEvent>, Box<dyn std::error::Error>> {
+        self.set_session_metadata("auto_stopped", "rollover");
+        let Some(previous) = self.stop_recording_session()? else {
+            return Ok(None);
+        };
+
+        let base_name = previous.metadata.get("rollover_base").cloned().unwrap_or_else(|| previous.name.clone());
+        let part = previous.metadata.get("rollover_part").and_then(|p| p.parse::<u32>().ok()).unwrap_or(1) + 1;
+        let next = self.start_recording_session(
+            format!("{} (part {})", base_name, part),
+            previous.description.clone(),
+            Some(previous.audio_source.clone()),
+            previous.tags.clone(),
+        )?;
+        self.set_session_metadata("rollover_base", &base_name);
+        self.set_session_metadata("rollover_part", &part.to_string());
+        self.set_session_metadata("rollover_previous", &previous.id.to_string());
+
+        info!(
+            previous_session = %previous.id,
+            next_session = %next,
+            part = part,
+            "🔁 Rolled recording over to a new session"
+        );
+        Ok(Some(SupervisorEvent::RolledOver { previous, next }))
+    }
+
+    fn set_session_metadata(&mut self, key: &str, value: &str) {
+        if let Some(session) = &mut self.current_session {
+            session.metadata.insert(key.to_string(), value.to_string());
+            if let Some((journal, _)) = &self.journal {
+                if let Err(e) = journal.write_header(session) {
+                    warn!(session_id = %session.id, error = %e, "Failed to update session journal");
+                }
+            }
+        }
+    }
+
     /// Check if currently recording
     pub fn is_recording(&self) -> bool {
         if let Ok(state) = self.recording_state.lock() {
@@ -451,6 +1069,28 @@ impl AudioSessionManager {
         start_time: Duration,
         end_time: Duration,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        // Identify the speaker and prosody from the recorded audio for this span
+        let sample_rate = self.actual_sample_rate.lock().ok().and_then(|sr| *sr).unwrap_or(0);
+        let segment_audio = if self.current_session.is_some() && sample_rate > 0 {
+            let at = |t: Duration| (t.as_secs_f64() * sample_rate as f64) as usize;
+            let recent = self.audio_buffer.lock().ok()
+                .and_then(|buffer| buffer.range(at(start_time), at(end_time)).map(<[f32]>::to_vec));
+            // Older audio has already been flushed to the journal
+            recent.or_else(|| {
+                self.journal.as_ref()
+                    .and_then(|(journal, _)| journal.read_range(JournalStream::Mono, at(start_time), at(end_time)).ok())
+            }).filter(|audio| !audio.is_empty())
+        } else {
+            None
+        };
+        let speaker_id = segment_audio
+            .as_ref()
+            .and_then(|audio| self.diarizer.identify(audio, sample_rate))
+            .map(|speaker| speaker.speaker_id);
+        let prosody = segment_audio
+            .as_ref()
+            .map(|audio| self.prosody.analyze(audio, sample_rate, text.split_whitespace().count()));
+
         if let Some(session) = &mut self.current_session {
             let segment = TranscriptSegment {
                 id: Uuid::new_v4(),
@@ -458,117 +1098,266 @@ impl AudioSessionManager {
                 end_time,
                 text: text.clone(),
                 confidence,
-                speaker_id: None,
+                speaker_id: speaker_id.clone(),
                 language: None,
                 word_count: text.split_whitespace().count(),
                 is_final: true,
+                prosody,
             };
 
             session.transcript_segments.push(segment);
 
+            // Keep the journal's snapshot current so a recovered session keeps its transcript
+            if let Some((journal, _)) = &self.journal {
+                if let Err(e) = journal.write_header(session) {
+                    warn!(session_id = %session.id, error = %e, "Failed to update session journal");
+                }
+            }
+
             debug!(
                 session_id = %session.id,
                 text = %text,
                 confidence = confidence,
                 start_time = ?start_time,
                 end_time = ?end_time,
+                speaker_id = ?speaker_id,
                 "📝 Added transcript segment to session"
             );
         }
         Ok(())
     }
 
+    /// Enroll a named speaker so their segments are labelled with the name
+    pub fn enroll_speaker(&mut self, name: &str, samples: &[f32], sample_rate: u32) -> bool {
+        self.diarizer.enroll(name, samples, sample_rate)
+    }
+
+    /// Speakers identified in the current session, plus enrolled speakers
+    pub fn speakers(&self) -> &[SpeakerCluster] {
+        self.diarizer.speakers()
+    }
+
+    /// Speaker diarizer, for loading and saving enrolled profiles
+    pub fn diarizer_mut(&mut self) -> &mut SpeakerDiarizer {
+        &mut self.diarizer
+    }
+
     /// Configure audio source for recording
     fn configure_audio_source(&self, source: &AudioSource) -> Result<(), Box<dyn std::error::Error>> {
         if let Ok(mut audio_service) = self.audio_service.lock() {
+            let secondary = |device: Option<String>| SecondarySourceConfig {
+                device,
+                mixer: self.config.source_mix.clone(),
+            };
             match source {
                 AudioSource::Microphone => {
                     audio_service.select_input_device_by_name(None);
+                    audio_service.set_secondary_source(None)?;
                 }
                 AudioSource::Device(name) => {
                     audio_service.select_input_device_by_name(Some(name.clone()));
+                    audio_service.set_secondary_source(None)?;
                 }
                 AudioSource::SystemAudio => {
-                    // For system audio, we'd need to configure a loopback device
-                    // This is platform-specific and would require additional implementation
-                    warn!("System audio recording not yet implemented, falling back to microphone");
-                    audio_service.select_input_device_by_name(None);
+                    let monitor = audio_service.find_system_audio_device();
+                    if monitor.is_none() {
+                        warn!("No system audio monitor/loopback input found, falling back to microphone");
+                    }
+                    audio_service.select_input_device_by_id(monitor);
+                    audio_service.set_secondary_source(None)?;
                 }
                 AudioSource::Mixed => {
-                    // Mixed audio would require multiple input streams
-                    warn!("Mixed audio recording not yet implemented, falling back to microphone");
+                    if audio_service.find_system_audio_device().is_none() {
+                        warn!("No system audio monitor/loopback input found, recording microphone only");
+                    }
                     audio_service.select_input_device_by_name(None);
+                    audio_service.set_secondary_source(Some(secondary(None)))?;
+                }
+                AudioSource::Devices { primary, secondary: second } => {
+                    audio_service.select_input_device_by_name(primary.clone());
+                    audio_service.set_secondary_source(Some(secondary(Some(second.clone()))))?;
+                }
+                AudioSource::Imported(path) => {
+                    return Err(Box::new(ArchiveError::InvalidConfiguration(format!(
+                        "Cannot record from imported file {}",
+                        path.display()
+                    ))));
                 }
             }
         }
         Ok(())
     }
 
+    /// Whether a source is recorded as two separate stereo channels
+    fn records_stereo(&self, source: &AudioSource) -> bool {
+        matches!(source, AudioSource::Mixed | AudioSource::Devices { .. })
+            && self.config.source_mix.mode == MixMode::Stereo
+    }
+
     /// Generate file path for session
     fn generate_session_file_path(&self, session_id: &Uuid, name: &str, start_time: &DateTime<Utc>) -> PathBuf {
-        let date_dir = start_time.format("%Y/%m/%d").to_string();
-        let sanitized_name = self.sanitize_filename(name);
-        let filename = format!("{}_{}.wav", sanitized_name, session_id);
-        
-        self.storage_dir
-            .join("sessions")
-            .join(date_dir)
-            .join(filename)
+        self.session_path(session_id, name, start_time, &[]).with_extension("wav")
     }
 
-    /// Sanitize filename for safe storage
-    fn sanitize_filename(&self, name: &str) -> String {
-        name.chars()
-            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
-            .collect::<String>()
-            .chars()
-            .take(50) // Limit length
-            .collect()
+    /// Where the session layout puts a session, under `sessions/`
+    fn session_path(&self, session_id: &Uuid, name: &str, start_time: &DateTime<Utc>, tags: &[String]) -> PathBuf {
+        let fields = PathFields {
+            id: *session_id,
+            name,
+            start_time: *start_time,
+            tags,
+        };
+        let mut relative = self.config.layout.render(&fields);
+        if relative.as_os_str().is_empty() {
+            relative = PathBuf::from(session_id.to_string());
+        }
+        self.storage_dir.join("sessions").join(relative)
     }
 
     /// Save comprehensive session outputs: raw audio, cleaned audio, segments, and metadata
-    fn save_session_outputs(&self, session: &mut AudioRecordingSession, samples: &[f32]) -> Result<(), Box<dyn std::error::Error>> {
+    fn save_session_outputs(&self, session: &mut AudioRecordingSession, samples: &[f32], stereo: &[f32]) -> Result<(), Box<dyn std::error::Error>> {
+        self.write_session_outputs(session, &OutputAudio::Samples { mono: samples, stereo })
+    }
+
+    /// Write the session outputs in two passes over the audio, a block at a time
+    fn write_session_outputs(&self, session: &mut AudioRecordingSession, audio: &OutputAudio) -> Result<(), Box<dyn std::error::Error>> {
         let sample_rate = session.format_info.sample_rate;
         
         // Create session output directory structure
         let session_dir = self.create_session_directory(session)?;
         
-        // 1. Save raw audio (complete recording)
+        // 1. Save raw audio (complete recording); two-source stereo sessions keep both channels.
+        //    The same pass feeds the waveform and, on the mono mix, speech detection
         let raw_audio_path = session_dir.join("raw_audio.wav");
-        self.save_audio_to_file_path(&raw_audio_path, samples, &session.format_info)?;
+        let stereo = !audio.is_empty(JournalStream::Stereo)?;
+        let (raw_stream, channels) = if stereo {
+            session.format_info.channels = 2;
+            (JournalStream::Stereo, 2)
+        } else {
+            (JournalStream::Mono, 1)
+        };
+        let mono_format = AudioFormatInfo { channels: 1, ..session.format_info.clone() };
+        let mut raw_audio = WavOutput::create(&raw_audio_path, &session.format_info, self.cipher.clone())?;
+        let mut waveform = waveform::WaveformBuilder::new(channels, sample_rate);
+        let mut detector = SpeechDetector::new(sample_rate);
+        let mut total_samples = 0;
+        audio.for_each_block(raw_stream, |block| {
+            raw_audio.write(block)?;
+            waveform.push(block);
+            if !stereo {
+                detector.push(block);
+                total_samples += block.len();
+            }
+            Ok(())
+        })?;
+        if stereo {
+            audio.for_each_block(JournalStream::Mono, |block| {
+                detector.push(block);
+                total_samples += block.len();
+                Ok(())
+            })?;
+        }
+        raw_audio.finish()?;
+        // Rewritten audio replaces any FLAC the archive compressed it to
+        let compressed_path = raw_audio_path.with_extension("flac");
+        if compressed_path.exists() {
+            fs::remove_file(&compressed_path)?;
+        }
         session.file_path = raw_audio_path.clone();
         session.file_size = self.get_file_size(&raw_audio_path)?;
         
         // 2. Detect speech segments and silence
-        let speech_segments = self.detect_speech_segments(samples, sample_rate)?;
-        
-        // 3. Create cleaned audio (silence removed)
+        let speech_segments = detector.finish();
+
+        // 3. Second pass over the mono mix: signal quality, cleaned audio (silence
+        //    removed) and individual segment files
+        let analyzer = SignalAnalyzer::default();
+        let labels = labels_from_segments(&speech_segments, analyzer.frame_len(sample_rate), total_samples);
+        let mut signal = analyzer.accumulator(sample_rate, labels);
         let cleaned_audio_path = session_dir.join("cleaned_audio.wav");
-        let cleaned_samples = self.remove_silence_from_audio(samples, &speech_segments);
-        self.save_audio_to_file_path(&cleaned_audio_path, &cleaned_samples, &session.format_info)?;
-        
-        // 4. Create individual segment files
+        let mut cleaned_audio = WavOutput::create(&cleaned_audio_path, &mono_format, self.cipher.clone())?;
+        let mut cleaned_samples = 0;
         let segments_dir = session_dir.join("segments");
         fs::create_dir_all(&segments_dir)?;
-        let audio_segments = self.extract_individual_segments(samples, &speech_segments, &segments_dir, session, sample_rate)?;
+        let mut audio_segments = Vec::new();
+        let mut pending = speech_segments.iter().copied().peekable();
+        let mut segment_samples = Vec::new();
+        let mut position = 0;
+        let saved: &AudioRecordingSession = session;
+        audio.for_each_block(JournalStream::Mono, |block| {
+            signal.push(block);
+            let block_end = position + block.len();
+            while let Some(&(start, end)) = pending.peek() {
+                if start >= block_end {
+                    break;
+                }
+                segment_samples.extend_from_slice(&block[start.max(position) - position..end.min(block_end) - position]);
+                if end > block_end {
+                    break;
+                }
+                cleaned_audio.write(&segment_samples)?;
+                cleaned_samples += segment_samples.len();
+                let segment = self.extract_segment(audio_segments.len(), (start, end), &segment_samples, &segments_dir, saved, sample_rate)?;
+                audio_segments.push(segment);
+                segment_samples.clear();
+                pending.next();
+            }
+            position = block_end;
+            Ok(())
+        })?;
+        cleaned_audio.finish()?;
+
+        info!(
+            segments_extracted = audio_segments.len(),
+            segments_dir = %segments_dir.display(),
+            "📁 Extracted individual audio segments"
+        );
+
+        let signal = signal.finish();
+        session.quality_metrics.average_volume = signal.audio_levels.rms;
+        session.quality_metrics.peak_volume = signal.audio_levels.peak;
+        session.quality_metrics.signal_to_noise_ratio = signal.snr_db;
+        session.quality_metrics.silence_percentage = signal.silence_percentage;
+        session.quality_metrics.clipping_events = signal.clipping_events;
+        session.quality_metrics.signal_metrics = Some(signal);
         
-        // 5. Create comprehensive metadata file
+        // 4. Export chapters for sessions with markers
+        let markers = session_markers::markers(&session.metadata);
+        if !markers.is_empty() {
+            let chapters = session_markers::chapters(&markers, Duration::from_secs_f64(total_samples as f64 / sample_rate as f64));
+            encryption::write_file(&session_dir.join("chapters.vtt"), session_markers::to_webvtt(&chapters).as_bytes(), self.cipher.as_deref())?;
+            encryption::write_file(
+                &session_dir.join("chapters.ffmetadata"),
+                session_markers::to_ffmetadata(&chapters, &session.name).as_bytes(),
+                self.cipher.as_deref(),
+            )?;
+        }
+
+        // 5. Waveform envelopes, speech map and loudness for quick overviews
+        waveform::save(&session_dir, &waveform.finish(&speech_segments), self.cipher.as_deref())?;
+
+        // 6. Create comprehensive metadata file
         let outputs = SessionOutputs {
             raw_audio_path: raw_audio_path.clone(),
             cleaned_audio_path: cleaned_audio_path.clone(),
             segments_directory: segments_dir.clone(),
             metadata_path: session_dir.join("session_metadata.json"),
             segments: audio_segments.clone(),
-            total_raw_duration: Duration::from_secs_f32(samples.len() as f32 / sample_rate as f32),
-            total_cleaned_duration: Duration::from_secs_f32(cleaned_samples.len() as f32 / sample_rate as f32),
-            silence_removed_duration: if samples.len() >= cleaned_samples.len() {
-                Duration::from_secs_f32((samples.len() - cleaned_samples.len()) as f32 / sample_rate as f32)
+            total_raw_duration: Duration::from_secs_f32(total_samples as f32 / sample_rate as f32),
+            total_cleaned_duration: Duration::from_secs_f32(cleaned_samples as f32 / sample_rate as f32),
+            silence_removed_duration: if total_samples >= cleaned_samples {
+                Duration::from_secs_f32((total_samples - cleaned_samples) as f32 / sample_rate as f32)
             } else {
                 Duration::from_secs(0)
             },
         };
         
         self.save_comprehensive_metadata(session, &outputs)?;
+
+        // 7. Keep the audio index in step so the session shows up in listings
+        if let Err(e) = self.storage.lock().map_err(|e| e.to_string()).and_then(|mut storage| storage.index_saved_session(session).map_err(|e| e.to_string())) {
+            warn!(session_id = %session.id, error = %e, "Failed to index saved session");
+        }
         
         info!(
             session_id = %session.id,
@@ -587,23 +1376,9 @@ impl AudioSessionManager {
 
     /// Save audio data to a specific file path
     fn save_audio_to_file_path(&self, file_path: &Path, samples: &[f32], format_info: &AudioFormatInfo) -> Result<(), Box<dyn std::error::Error>> {
-        // Create WAV file
-        let spec = hound::WavSpec {
-            channels: format_info.channels,
-            sample_rate: format_info.sample_rate,
-            bits_per_sample: format_info.bit_depth as u16,
-            sample_format: hound::SampleFormat::Int,
-        };
-
-        let mut writer = hound::WavWriter::create(file_path, spec)?;
-        
-        // Convert f32 samples to i16 and write
-        for &sample in samples {
-            let sample_i16 = (sample * i16::MAX as f32) as i16;
-            writer.write_sample(sample_i16)?;
-        }
-        
-        writer.finalize()?;
+        let mut wav = WavOutput::create(file_path, format_info, self.cipher.clone())?;
+        wav.write(samples)?;
+        wav.finish()?;
 
         debug!(
             file_path = %file_path.display(),
@@ -619,189 +1394,89 @@ impl AudioSessionManager {
         Ok(fs::metadata(path)?.len())
     }
 
-    /// Create session directory structure for new session
-    fn create_session_directory_for_new_session(&self, session_id: &Uuid, name: &str, start_time: &DateTime<Utc>) -> Result<PathBuf, Box<dyn std::error::Error>> {
-        let date_dir = start_time.format("%Y/%m/%d").to_string();
-        let sanitized_name = self.sanitize_filename(name);
-        let session_dir_name = format!("{}_{}", sanitized_name, session_id);
-        
-        let session_dir = self.storage_dir
-            .join("sessions")
-            .join(date_dir)
-            .join(session_dir_name);
-            
+    /// Create session directory structure for new session; a layout that
+    /// leaves out `{id}` gets a numeric suffix when the directory is taken
+    fn create_session_directory_for_new_session(&self, session_id: &Uuid, name: &str, start_time: &DateTime<Utc>, tags: &[String]) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let session_dir = path_template::unique_path(
+            self.session_path(session_id, name, start_time, tags),
+            |candidate| candidate.exists(),
+        );
         fs::create_dir_all(&session_dir)?;
         Ok(session_dir)
     }
 
-    /// Create session directory structure
+    /// Create session directory structure; reuses the directory picked when recording started
     fn create_session_directory(&self, session: &AudioRecordingSession) -> Result<PathBuf, Box<dyn std::error::Error>> {
-        let date_dir = session.start_time.format("%Y/%m/%d").to_string();
-        let sanitized_name = self.sanitize_filename(&session.name);
-        let session_dir_name = format!("{}_{}", sanitized_name, session.id);
-        
-        let session_dir = self.storage_dir
-            .join("sessions")
-            .join(date_dir)
-            .join(session_dir_name);
-            
+        let session_dir = match session.file_path.parent() {
+            Some(dir) if dir.starts_with(self.storage_dir.join("sessions")) => dir.to_path_buf(),
+            _ => self.session_path(&session.id, &session.name, &session.start_time, &session.tags),
+        };
         fs::create_dir_all(&session_dir)?;
         Ok(session_dir)
     }
 
     /// Detect speech segments using energy-based VAD
     fn detect_speech_segments(&self, samples: &[f32], sample_rate: u32) -> Result<Vec<(usize, usize)>, Box<dyn std::error::Error>> {
-        let frame_size = (sample_rate as f32 * 0.025) as usize; // 25ms frames
-        let hop_size = (sample_rate as f32 * 0.010) as usize;   // 10ms hop
-        let energy_threshold = 0.001; // Configurable threshold
-        let min_speech_duration = (sample_rate as f32 * 0.5) as usize; // 500ms minimum
-        let max_silence_gap = (sample_rate as f32 * 0.3) as usize; // 300ms max gap
-        
-        let mut speech_segments = Vec::new();
-        let mut current_segment_start: Option<usize> = None;
-        let mut last_speech_frame = 0;
-        
-        // Process audio in frames
-        for frame_start in (0..samples.len()).step_by(hop_size) {
-            let frame_end = (frame_start + frame_size).min(samples.len());
-            let frame = &samples[frame_start..frame_end];
-            
-            // Calculate frame energy
-            let energy = self.calculate_frame_energy(frame);
-            let is_speech = energy > energy_threshold;
-            
-            if is_speech {
-                if current_segment_start.is_none() {
-                    current_segment_start = Some(frame_start);
-                }
-                last_speech_frame = frame_end;
-            } else if let Some(segment_start) = current_segment_start {
-                // Check if silence gap is too long
-                if frame_start > last_speech_frame && frame_start - last_speech_frame > max_silence_gap {
-                    // End current segment if it's long enough
-                    if last_speech_frame > segment_start && last_speech_frame - segment_start >= min_speech_duration {
-                        speech_segments.push((segment_start, last_speech_frame));
-                    }
-                    current_segment_start = None;
-                }
-            }
-        }
-        
-        // Handle final segment
-        if let Some(segment_start) = current_segment_start {
-            if samples.len() > segment_start && samples.len() - segment_start >= min_speech_duration {
-                speech_segments.push((segment_start, samples.len()));
-            }
-        }
-        
-        debug!(
-            segments_count = speech_segments.len(),
-            total_samples = samples.len(),
-            "🎯 Detected speech segments"
-        );
-        
-        Ok(speech_segments)
-    }
-
-    /// Calculate energy of an audio frame
-    fn calculate_frame_energy(&self, frame: &[f32]) -> f32 {
-        if frame.is_empty() {
-            return 0.0;
-        }
-        let sum_squares: f32 = frame.iter().map(|&s| s * s).sum();
-        sum_squares / frame.len() as f32
+        let mut detector = SpeechDetector::new(sample_rate);
+        detector.push(samples);
+        Ok(detector.finish())
     }
 
-    /// Remove silence from audio based on speech segments
-    fn remove_silence_from_audio(&self, samples: &[f32], speech_segments: &[(usize, usize)]) -> Vec<f32> {
-        let mut cleaned_samples = Vec::new();
-        
-        for &(start, end) in speech_segments {
-            if start < samples.len() && end <= samples.len() {
-                cleaned_samples.extend_from_slice(&samples[start..end]);
-            }
-        }
-        
-        debug!(
-            original_samples = samples.len(),
-            cleaned_samples = cleaned_samples.len(),
-            compression_ratio = cleaned_samples.len() as f32 / samples.len() as f32,
-            "✂️  Removed silence from audio"
-        );
-        
-        cleaned_samples
-    }
-
-    /// Extract individual audio segments to separate files
-    fn extract_individual_segments(
+    /// Save one speech segment to its own file
+    fn extract_segment(
         &self,
-        samples: &[f32],
-        speech_segments: &[(usize, usize)],
+        index: usize,
+        (start_sample, end_sample): (usize, usize),
+        segment_samples: &[f32],
         segments_dir: &Path,
         session: &AudioRecordingSession,
         sample_rate: u32,
-    ) -> Result<Vec<AudioSegment>, Box<dyn std::error::Error>> {
-        let mut audio_segments = Vec::new();
+    ) -> Result<AudioSegment, Box<dyn std::error::Error>> {
+        let segment_id = Uuid::new_v4();
+        let segment_filename = format!("segment_{:03}_{}.wav", index + 1, segment_id);
+        let segment_path = segments_dir.join(&segment_filename);
         
-        for (i, &(start_sample, end_sample)) in speech_segments.iter().enumerate() {
-            let segment_id = Uuid::new_v4();
-            let segment_filename = format!("segment_{:03}_{}.wav", i + 1, segment_id);
-            let segment_path = segments_dir.join(&segment_filename);
-            
-            // Extract segment samples
-            if start_sample < samples.len() && end_sample <= samples.len() {
-                let segment_samples = &samples[start_sample..end_sample];
-                
-                // Save segment to file
-                self.save_audio_to_file_path(&segment_path, segment_samples, &session.format_info)?;
-                
-                // Calculate timing
-                let start_time = Duration::from_secs_f32(start_sample as f32 / sample_rate as f32);
-                let end_time = Duration::from_secs_f32(end_sample as f32 / sample_rate as f32);
-                let duration = end_time - start_time;
-                
-                // Calculate average energy
-                let average_energy = self.calculate_frame_energy(segment_samples);
-                
-                // Try to match with transcript segments
-                let (text, confidence) = self.find_matching_transcript_segment(session, start_time, end_time);
-                
-                let audio_segment = AudioSegment {
-                    id: segment_id,
-                    start_sample,
-                    end_sample,
-                    start_time,
-                    end_time,
-                    duration,
-                    text,
-                    confidence,
-                    file_path: segment_path.clone(),
-                    file_size: self.get_file_size(&segment_path)?,
-                    is_speech: true,
-                    average_energy,
-                };
-                
-                audio_segments.push(audio_segment);
-                
-                debug!(
-                    segment_id = %segment_id,
-                    filename = %segment_filename,
-                    start_time = ?start_time,
-                    end_time = ?end_time,
-                    duration = ?duration,
-                    samples = segment_samples.len(),
-                    "🎵 Extracted audio segment"
-                );
-            }
-        }
+        // Save segment to file (segments are always cut from the mono mix)
+        let segment_format = AudioFormatInfo { channels: 1, ..session.format_info.clone() };
+        self.save_audio_to_file_path(&segment_path, segment_samples, &segment_format)?;
         
-        info!(
-            segments_extracted = audio_segments.len(),
-            segments_dir = %segments_dir.display(),
-            "📁 Extracted individual audio segments"
-        );
+        // Calculate timing
+        let start_time = Duration::from_secs_f32(start_sample as f32 / sample_rate as f32);
+        let end_time = Duration::from_secs_f32(end_sample as f32 / sample_rate as f32);
+        let duration = end_time - start_time;
+        
+        // Calculate average energy and signal quality
+        let average_energy = frame_energy(segment_samples);
+        let signal_metrics = SignalAnalyzer::default().analyze(segment_samples, sample_rate);
+        
+        // Try to match with transcript segments
+        let (text, confidence) = self.find_matching_transcript_segment(session, start_time, end_time);
         
-        Ok(audio_segments)
+        debug!(
+            segment_id = %segment_id,
+            filename = %segment_filename,
+            start_time = ?start_time,
+            end_time = ?end_time,
+            duration = ?duration,
+            samples = segment_samples.len(),
+            "🎵 Extracted audio segment"
+        );
+
+        Ok(AudioSegment {
+            id: segment_id,
+            start_sample,
+            end_sample,
+            start_time,
+            end_time,
+            duration,
+            text,
+            confidence,
+            file_path: segment_path.clone(),
+            file_size: self.get_file_size(&segment_path)?,
+            is_speech: true,
+            average_energy,
+            signal_metrics: Some(signal_metrics),
+        })
     }
 
     /// Find matching transcript segment for audio timing
@@ -851,9 +1526,8 @@ impl AudioSessionManager {
             version: env!("CARGO_PKG_VERSION").to_string(),
         };
         
-        let file = File::create(&outputs.metadata_path)?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, &metadata)?;
+        let data = serde_json::to_vec_pretty(&metadata)?;
+        encryption::write_file(&outputs.metadata_path, &data, self.cipher.as_deref())?;
         
         info!(
             metadata_path = %outputs.metadata_path.display(),
@@ -867,9 +1541,8 @@ impl AudioSessionManager {
     /// Save session metadata to JSON file (legacy method, kept for compatibility)
     fn save_session_metadata(&self, session: &AudioRecordingSession) -> Result<(), Box<dyn std::error::Error>> {
         let metadata_path = session.file_path.with_extension("json");
-        let file = File::create(&metadata_path)?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, session)?;
+        let data = serde_json::to_vec_pretty(session)?;
+        encryption::write_file(&metadata_path, &data, self.cipher.as_deref())?;
 
         debug!(
             metadata_path = %metadata_path.display(),
@@ -879,6 +1552,640 @@ impl AudioSessionManager {
         Ok(())
     }
 
+    /// Turn journals left by sessions that never stopped (crash, power loss)
+    /// into normal session outputs; call at startup before recording
+    pub fn recover_unfinished_sessions(&mut self) -> Result<Vec<AudioRecordingSession>, Box<dyn std::error::Error>> {
+        let active = self.journal.as_ref().map(|(journal, _)| journal.dir().to_path_buf());
+        let mut recovered = Vec::new();
+        for journal_dir in SessionJournal::find_unfinished(&self.storage_dir.join("sessions"))? {
+            if active.as_ref() == Some(&journal_dir) {
+                continue;
+            }
+            let journal = SessionJournal::open(journal_dir, self.cipher.clone());
+            match self.recover_session(&journal) {
+                Ok(session) => {
+                    info!(
+                        session_id = %session.id,
+                        name = %session.name,
+                        duration = ?session.duration,
+                        "🩹 Recovered unfinished recording session"
+                    );
+                    self.session_history.push(session.clone());
+                    recovered.push(session);
+                }
+                Err(e) => warn!(journal = %journal.dir().display(), error = %e, "⚠️  Could not recover session journal"),
+            }
+        }
+        Ok(recovered)
+    }
+
+    fn recover_session(&self, journal: &SessionJournal) -> Result<AudioRecordingSession, Box<dyn std::error::Error>> {
+        let mut session: AudioRecordingSession = journal.read_header()?;
+        // Only the length is needed up front; the outputs read the journal chunk by chunk
+        let (journal_rate, recorded) = journal.measure(JournalStream::Mono)?;
+
+        if let Some(sample_rate) = journal_rate {
+            session.format_info.sample_rate = sample_rate;
+        }
+        let sample_rate = session.format_info.sample_rate.max(1);
+        session.duration = Duration::from_secs_f64(recorded as f64 / sample_rate as f64);
+        session.end_time = Some(session.start_time + chrono::Duration::from_std(session.duration).unwrap_or_default());
+        session.state = SessionState::Stopped;
+        session.metadata.insert("recovered_from_journal".to_string(), "true".to_string());
+
+        if recorded == 0 {
+            self.save_session_metadata(&session)?;
+        } else {
+            self.write_session_outputs(&mut session, &OutputAudio::Journal(journal))?;
+        }
+        journal.remove()?;
+        Ok(session)
+    }
+
+    /// Keep only `start..end` of a saved session
+    pub fn trim_session(&mut self, session_id: Uuid, start: Duration, end: Duration) -> Result<AudioRecordingSession, Box<dyn std::error::Error>> {
+        let (mut session, audio) = self.load_saved_session(session_id)?;
+        let range = session_edit::edit_range(start, end, audio.duration())?;
+        let revisions = transcript_revisions::retime_revisions(&self.saved_revisions(&session)?, session.id, range.clone());
+
+        session.transcript_segments = session_edit::retime_segments(&session.transcript_segments, range.clone());
+        let markers = session_markers::retime_markers(&session_markers::markers(&session.metadata), range.clone());
+        session_markers::set_markers(&mut session.metadata, &markers);
+        session.start_time += chrono::Duration::from_std(range.start)?;
+        session_edit::record_edit(&mut session, &format!("trim {:.2}s-{:.2}s", range.start.as_secs_f64(), range.end.as_secs_f64()));
+        self.rewrite_session(&mut session, &audio.slice(range), &revisions)?;
+
+        info!(session_id = %session.id, duration = ?session.duration, "✂️  Trimmed session");
+        Ok(session)
+    }
+
+    /// Split a saved session in two; the second part becomes a new session
+    pub fn split_session(&mut self, session_id: Uuid, at: SplitPoint) -> Result<(AudioRecordingSession, AudioRecordingSession), Box<dyn std::error::Error>> {
+        let (mut first, audio) = self.load_saved_session(session_id)?;
+        let total = audio.duration();
+        let at = at.resolve(&first.transcript_segments)?;
+        if at.is_zero() || at >= total {
+            return Err(Box::new(ArchiveError::InvalidConfiguration(format!(
+                "Split point {:.2}s is outside the {:.2}s session",
+                at.as_secs_f64(),
+                total.as_secs_f64()
+            ))));
+        }
+
+        let mut second = first.clone();
+        second.id = Uuid::new_v4();
+        second.name = format!("{} (part 2)", first.name);
+        second.start_time = first.start_time + chrono::Duration::from_std(at)?;
+        second.transcript_segments = session_edit::retime_segments(&first.transcript_segments, at..total);
+        let markers = session_markers::markers(&first.metadata);
+        session_markers::set_markers(&mut second.metadata, &session_markers::retime_markers(&markers, at..total));
+        session_markers::set_markers(&mut first.metadata, &session_markers::retime_markers(&markers, Duration::ZERO..at));
+        second.metadata.insert("split_from".to_string(), first.id.to_string());
+        let second_dir = self.create_session_directory_for_new_session(&second.id, &second.name, &second.start_time, &second.tags)?;
+        second.file_path = second_dir.join("raw_audio.wav");
+        first.transcript_segments = session_edit::retime_segments(&first.transcript_segments, Duration::ZERO..at);
+        let revisions = self.saved_revisions(&first)?;
+        let first_revisions = transcript_revisions::retime_revisions(&revisions, first.id, Duration::ZERO..at);
+        let second_revisions = transcript_revisions::retime_revisions(&revisions, second.id, at..total);
+
+        let description = format!("split at {:.2}s", at.as_secs_f64());
+        session_edit::record_edit(&mut first, &description);
+        session_edit::record_edit(&mut second, &description);
+        self.rewrite_session(&mut first, &audio.slice(Duration::ZERO..at), &first_revisions)?;
+        self.rewrite_session(&mut second, &audio.slice(at..total), &second_revisions)?;
+
+        info!(session_id = %first.id, new_session_id = %second.id, at = ?at, "✂️  Split session");
+        Ok((first, second))
+    }
+
+    /// Append `second` to `first` and delete `second`
+    pub fn merge_sessions(&mut self, first_id: Uuid, second_id: Uuid) -> Result<AudioRecordingSession, Box<dyn std::error::Error>> {
+        if first_id == second_id {
+            return Err(Box::new(ArchiveError::InvalidConfiguration("Cannot merge a session with itself".to_string())));
+        }
+        let (mut first, mut audio) = self.load_saved_session(first_id)?;
+        let (second, second_audio) = self.load_saved_session(second_id)?;
+        if second.start_time < first.start_time {
+            return Err(Box::new(ArchiveError::InvalidConfiguration(format!(
+                "Session {} starts before {}; merge them the other way round",
+                second.id, first.id
+            ))));
+        }
+        if audio.sample_rate != second_audio.sample_rate {
+            return Err(Box::new(ArchiveError::InvalidConfiguration(format!(
+                "Cannot merge sessions recorded at {} Hz and {} Hz",
+                audio.sample_rate, second_audio.sample_rate
+            ))));
+        }
+
+        let offset = audio.duration();
+        let revisions = transcript_revisions::merge_revisions(
+            &first, &self.saved_revisions(&first)?,
+            &second, &self.saved_revisions(&second)?,
+            offset,
+        );
+        audio.append(&second_audio);
+        first.transcript_segments.extend(session_edit::offset_segments(&second.transcript_segments, offset));
+        let mut markers = session_markers::markers(&first.metadata);
+        markers.extend(session_markers::offset_markers(&session_markers::markers(&second.metadata), offset));
+        session_markers::set_markers(&mut first.metadata, &markers);
+        for tag in &second.tags {
+            if !first.tags.contains(tag) {
+                first.tags.push(tag.clone());
+            }
+        }
+        session_edit::record_edit(&mut first, &format!("merged {} at {:.2}s", second.id, offset.as_secs_f64()));
+        self.rewrite_session(&mut first, &audio, &revisions)?;
+
+        // The merged session now holds everything; remove the second one
+        if let Some(dir) = second.file_path.parent() {
+            if Some(dir) != first.file_path.parent() && dir.starts_with(self.storage_dir.join("sessions")) {
+                fs::remove_dir_all(dir)?;
+            }
+        }
+        if let Ok(mut storage) = self.storage.lock() {
+            storage.unindex_session(second_id)?;
+        }
+        self.session_history.retain(|session| session.id != second_id);
+
+        info!(session_id = %first.id, merged_session_id = %second_id, duration = ?first.duration, "🔗 Merged sessions");
+        Ok(first)
+    }
+
+    /// Replace `start..end` of a saved session with silence and blank the overlapping transcript
+    pub fn redact_session(&mut self, session_id: Uuid, start: Duration, end: Duration) -> Result<AudioRecordingSession, Box<dyn std::error::Error>> {
+        let (mut session, mut audio) = self.load_saved_session(session_id)?;
+        let range = session_edit::edit_range(start, end, audio.duration())?;
+
+        audio.silence(range.clone());
+        let redacted = session_edit::redact_segments(&mut session.transcript_segments, range.clone());
+        let mut revisions = self.saved_revisions(&session)?;
+        for revision in &mut revisions {
+            session_edit::redact_segments(&mut revision.segments, range.clone());
+        }
+        session_edit::record_edit(&mut session, &format!("redact {:.2}s-{:.2}s", range.start.as_secs_f64(), range.end.as_secs_f64()));
+        self.rewrite_session(&mut session, &audio, &revisions)?;
+
+        info!(session_id = %session.id, redacted_segments = redacted, range = ?range, "🔇 Redacted session audio");
+        Ok(session)
+    }
+
+    /// Load a saved session and its raw audio for editing
+    fn load_saved_session(&self, session_id: Uuid) -> Result<(AudioRecordingSession, SessionAudio), Box<dyn std::error::Error>> {
+        if self.current_session.as_ref().is_some_and(|session| session.id == session_id) {
+            return Err(Box::new(ArchiveError::RecordingError("Stop the session before editing it".to_string())));
+        }
+        let metadata_path = self.find_session_metadata(&self.storage_dir.join("sessions"), session_id)?
+            .ok_or(ArchiveError::SessionNotFound(session_id))?;
+        read_saved_session(&metadata_path, self.cipher.as_deref())
+    }
+
+    /// Re-transcribe saved sessions in the background, storing each result as a transcript revision
+    pub fn start_retranscription(
+        &self,
+        selection: RetranscriptionSelection,
+        decoding: DecodingConfig,
+    ) -> Result<RetranscriptionJob, Box<dyn std::error::Error>> {
+        let mut paths = Vec::new();
+        collect_session_metadata(&self.storage_dir.join("sessions"), &mut paths)?;
+
+        let mut targets = Vec::new();
+        for path in paths {
+            let Ok(session) = read_saved_metadata(&path, self.cipher.as_deref()) else {
+                continue;
+            };
+            let selected = match &selection {
+                RetranscriptionSelection::Sessions(ids) => ids.contains(&session.id),
+                RetranscriptionSelection::Since(since) => session.start_time >= *since,
+            };
+            let recording = self.current_session.as_ref().is_some_and(|current| current.id == session.id);
+            if selected && !recording {
+                targets.push(path);
+            }
+        }
+        if let RetranscriptionSelection::Sessions(ids) = &selection {
+            if targets.len() < ids.len() {
+                warn!(requested = ids.len(), found = targets.len(), "Some sessions to re-transcribe were not found");
+            }
+        }
+
+        info!(sessions = targets.len(), model = %decoding.model_label(), "📝 Starting re-transcription");
+        Ok(RetranscriptionJob::spawn(targets, decoding, self.cipher.clone()))
+    }
+
+    /// Import a WAV, FLAC or Ogg Opus file as a saved session dated from the file's
+    /// own metadata, detecting speech and transcribing it like a recording.
+    /// A file whose audio is already in the archive is not imported again.
+    /// Ogg Opus needs the `opus-archive` feature; Ogg Vorbis is refused.
+    pub fn import_audio_file(&mut self, path: &Path, tags: Vec<String>) -> Result<ImportOutcome, Box<dyn std::error::Error>> {
+        // Importing shares the diarizer with the live session
+        if self.is_recording() {
+            return Err(Box::new(crate::core::error::AudioError::AlreadyRecording));
+        }
+
+        let imported = session_import::decode(path)?;
+        if let Some(existing) = self.find_imported_session(&imported.content_hash)? {
+            info!(file = %path.display(), existing = %existing, "Audio file is already in the archive");
+            return Ok(ImportOutcome::Duplicate { existing });
+        }
+
+        let session_id = Uuid::new_v4();
+        let name = imported.name();
+        let session_dir = self.create_session_directory_for_new_session(&session_id, &name, &imported.recorded_at, &tags)?;
+        let audio = &imported.audio;
+        let duration = audio.duration();
+
+        let mut metadata = HashMap::new();
+        metadata.insert(CONTENT_HASH_KEY.to_string(), imported.content_hash.clone());
+        metadata.insert(IMPORTED_FROM_KEY.to_string(), path.display().to_string());
+        metadata.insert(TIMESTAMP_SOURCE_KEY.to_string(), imported.timestamp_source.as_str().to_string());
+
+        let mut session = AudioRecordingSession {
+            id: session_id,
+            name,
+            description: None,
+            start_time: imported.recorded_at,
+            end_time: Some(imported.recorded_at + chrono::Duration::from_std(duration).unwrap_or_default()),
+            duration,
+            audio_source: AudioSource::Imported(path.to_path_buf()),
+            file_path: session_dir.join("raw_audio.wav"),
+            file_size: 0,
+            format_info: AudioFormatInfo {
+                sample_rate: audio.sample_rate,
+                channels: 1,
+                bit_depth: 16,
+                format: crate::services::audio_archive::AudioFormat::WAV,
+            },
+            transcript_segments: Vec::new(),
+            tags,
+            metadata,
+            state: SessionState::Stopped,
+            quality_metrics: QualityMetrics::default(),
+        };
+
+        match (&self.stt_service, self.config.auto_transcribe) {
+            (Some(stt), true) => {
+                let stt = stt.clone();
+                self.transcribe_imported(&mut session, audio, &stt)?;
+            }
+            (None, true) => warn!(file = %path.display(), "No STT service attached; importing without a transcript"),
+            _ => {}
+        }
+
+        self.save_session_outputs(&mut session, &audio.mono, &audio.stereo)?;
+        self.session_history.push(session.clone());
+        self.log_imported_transcript(&session);
+
+        info!(
+            session_id = %session.id,
+            file = %path.display(),
+            start_time = %session.start_time,
+            timestamp_source = imported.timestamp_source.as_str(),
+            duration = ?session.duration,
+            transcript_segments = session.transcript_segments.len(),
+            "📥 Imported audio file into the archive"
+        );
+
+        Ok(ImportOutcome::Imported(Box::new(session)))
+    }
+
+    /// Transcribe each speech span of an imported recording into its transcript segments
+    fn transcribe_imported(
+        &mut self,
+        session: &mut AudioRecordingSession,
+        audio: &SessionAudio,
+        stt: &Mutex<STTService>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let sample_rate = audio.sample_rate;
+        let at = |frame: usize| Duration::from_secs_f64(frame as f64 / sample_rate as f64);
+
+        self.diarizer.reset();
+        let mut model = None;
+        for (start, end) in self.detect_speech_segments(&audio.mono, sample_rate)? {
+            let span = &audio.mono[start..end.min(audio.mono.len())];
+            let samples = transcript_revisions::stt_input(span, sample_rate);
+            let result = stt.lock().map_err(|_| "STT service lock poisoned")?.transcribe(&samples)?;
+            let text = result.text.trim().to_string();
+            if text.is_empty() {
+                continue;
+            }
+            let word_count = text.split_whitespace().count();
+            session.transcript_segments.push(TranscriptSegment {
+                id: Uuid::new_v4(),
+                start_time: at(start),
+                end_time: at(end),
+                confidence: result.confidence,
+                speaker_id: self.diarizer.identify(span, sample_rate).map(|speaker| speaker.speaker_id),
+                language: result.language,
+                word_count,
+                is_final: true,
+                prosody: Some(self.prosody.analyze(span, sample_rate, word_count)),
+                text,
+            });
+            model.get_or_insert(result.model);
+        }
+        if let Some(model) = model {
+            session.metadata.insert("transcription_model".to_string(), model);
+        }
+        Ok(())
+    }
+
+    /// Overview of a saved session: duration, speech map, loudness and available
+    /// waveform zoom levels. Sessions saved before overviews existed get one now.
+    pub fn session_overview(&self, session_id: Uuid) -> Result<SessionOverview, Box<dyn std::error::Error>> {
+        Ok(self.saved_overview(session_id)?.1)
+    }
+
+    /// Integrated loudness and peak of a saved session
+    pub fn session_loudness(&self, session_id: Uuid) -> Result<LoudnessSummary, Box<dyn std::error::Error>> {
+        Ok(self.saved_overview(session_id)?.1.loudness)
+    }
+
+    /// Waveform envelope of a saved session at the stored zoom level closest to `samples_per_bin`
+    pub fn session_waveform(&self, session_id: Uuid, samples_per_bin: u32) -> Result<WaveformLevel, Box<dyn std::error::Error>> {
+        let (session_dir, overview) = self.saved_overview(session_id)?;
+        Ok(waveform::load_level(&session_dir, &overview, samples_per_bin, self.cipher.as_deref())?)
+    }
+
+    /// Read a session's overview, generating and saving it from the raw audio if it's missing
+    fn saved_overview(&self, session_id: Uuid) -> Result<(PathBuf, SessionOverview), Box<dyn std::error::Error>> {
+        let metadata_path = self.find_session_metadata(&self.storage_dir.join("sessions"), session_id)?
+            .ok_or(ArchiveError::SessionNotFound(session_id))?;
+        let session_dir = metadata_path.parent().map(Path::to_path_buf).unwrap_or_default();
+        if let Some(overview) = waveform::load_overview(&session_dir, self.cipher.as_deref())? {
+            return Ok((session_dir, overview));
+        }
+
+        let (_, audio) = self.load_saved_session(session_id)?;
+        let speech_segments = self.detect_speech_segments(&audio.mono, audio.sample_rate)?;
+        let generated = if audio.stereo.is_empty() {
+            waveform::analyze(&audio.mono, 1, audio.sample_rate, &speech_segments)
+        } else {
+            waveform::analyze(&audio.stereo, 2, audio.sample_rate, &speech_segments)
+        };
+        waveform::save(&session_dir, &generated, self.cipher.as_deref())?;
+        info!(session_id = %session_id, levels = generated.levels.len(), "📈 Generated waveform overview");
+        Ok((session_dir, generated.overview))
+    }
+
+    /// Compress archived audio; saved sessions keep their directory and have
+    /// their raw WAV converted to FLAC in place
+    pub fn compress_archive(&self) -> Result<CompressionResult, Box<dyn std::error::Error>> {
+        let mut storage = self.storage.lock().map_err(|e| ArchiveError::StorageError(e.to_string()))?;
+        let result = storage.compress_audio_files()?;
+        for (path, reason) in &result.skipped {
+            warn!(file = %path.display(), reason = %reason, "Left an audio file uncompressed");
+        }
+        info!(
+            files = result.files_compressed,
+            original_size = result.original_size,
+            compressed_size = result.compressed_size,
+            skipped = result.skipped.len(),
+            "🗜️  Compressed archived audio"
+        );
+        Ok(result)
+    }
+
+    /// Verify the archive's indexes against the files on disk, and with
+    /// `repair` rebuild them and move orphaned audio to `lost+found/`
+    pub fn check_archive(&self, repair: bool) -> Result<ArchiveCheckReport, Box<dyn std::error::Error>> {
+        // The live session's journal and files are still being written
+        if repair && self.is_recording() {
+            return Err(Box::new(crate::core::error::AudioError::AlreadyRecording));
+        }
+        let mut checker = ArchiveChecker::new_with_cipher(self.storage_dir.clone(), self.cipher.clone());
+        checker.set_repair(repair);
+        let mut storage = self.storage.lock().map_err(|e| ArchiveError::StorageError(e.to_string()))?;
+        Ok(checker.run_with_storage(&mut storage, ArchiveCheckReport::default())?)
+    }
+
+    /// Correct the text of one transcript segment of a saved session; the
+    /// correction is kept in the session's manual revision
+    pub fn correct_transcript_segment(
+        &self,
+        session_id: Uuid,
+        segment_id: Uuid,
+        text: &str,
+    ) -> Result<TranscriptRevision, Box<dyn std::error::Error>> {
+        let metadata_path = self.find_session_metadata(&self.storage_dir.join("sessions"), session_id)?
+            .ok_or(ArchiveError::SessionNotFound(session_id))?;
+        let session = read_saved_metadata(&metadata_path, self.cipher.as_deref())?;
+        let session_dir = metadata_path.parent().ok_or(ArchiveError::SessionNotFound(session_id))?;
+        let revisions = transcript_revisions::load_revisions(session_dir, self.cipher.as_deref())?;
+        let revision = transcript_revisions::correct_segment(&session, &revisions, segment_id, text)?;
+        transcript_revisions::save_revision(session_dir, &revision, self.cipher.as_deref())?;
+        Ok(revision)
+    }
+
+    /// Export the transcribed segments of saved sessions as a speech training dataset
+    pub fn export_dataset(
+        &self,
+        session_ids: &[Uuid],
+        out_dir: &Path,
+        options: DatasetOptions,
+    ) -> Result<DatasetSummary, Box<dyn std::error::Error>> {
+        let mut writer = DatasetWriter::create(out_dir, options)?;
+        for &session_id in session_ids {
+            let (session, audio) = self.load_saved_session(session_id)?;
+            let revisions = match session.file_path.parent() {
+                Some(dir) => transcript_revisions::load_revisions(dir, self.cipher.as_deref())?,
+                None => Vec::new(),
+            };
+            writer.add_session(&session, &audio, &revisions)?;
+        }
+        let summary = writer.finish()?;
+
+        info!(
+            sessions = session_ids.len(),
+            dataset = %out_dir.display(),
+            train = summary.train,
+            validation = summary.validation,
+            skipped_low_confidence = summary.skipped_low_confidence,
+            skipped_redacted = summary.skipped_redacted,
+            duration = ?summary.total_duration,
+            "🗂️  Exported speech dataset"
+        );
+        Ok(summary)
+    }
+
+    /// Pack a saved session, its transcript, markers and revisions into a bundle file
+    pub fn export_session_bundle(
+        &self,
+        session_id: Uuid,
+        out: &Path,
+        options: BundleOptions,
+    ) -> Result<BundleManifest, Box<dyn std::error::Error>> {
+        let (session, audio) = self.load_saved_session(session_id)?;
+        // Bundles carry WAV; raw audio the archive compressed to FLAC is encoded back
+        let raw_wav = if session.file_path.extension().is_some_and(|ext| ext == "flac") {
+            let (samples, channels) = if audio.stereo.is_empty() { (&audio.mono, 1) } else { (&audio.stereo, 2) };
+            let spec = hound::WavSpec { channels, sample_rate: audio.sample_rate, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+            encode_wav(samples.iter().map(|&sample| (sample * i16::MAX as f32) as i16), spec)?
+        } else {
+            encryption::read_file(&session.file_path, self.cipher.as_deref())?
+        };
+        let revisions = match session.file_path.parent() {
+            Some(dir) => transcript_revisions::load_revisions(dir, self.cipher.as_deref())?,
+            None => Vec::new(),
+        };
+        let manifest = session_bundle::write_bundle(out, &session, &raw_wav, &revisions, options)?;
+
+        info!(session_id = %session_id, bundle = %out.display(), files = manifest.files.len(), "📦 Exported session bundle");
+        Ok(manifest)
+    }
+
+    /// Recreate a session from a bundle file. With a trusted public key the
+    /// bundle must be signed by it.
+    pub fn import_session_bundle(
+        &mut self,
+        path: &Path,
+        ids: IdPolicy,
+        trusted_key: Option<&[u8]>,
+    ) -> Result<AudioRecordingSession, Box<dyn std::error::Error>> {
+        let mut bundle = session_bundle::read_bundle(path, trusted_key)?;
+        match ids {
+            IdPolicy::Remap => bundle.remap_ids(),
+            IdPolicy::Preserve => {
+                if self.find_session_metadata(&self.storage_dir.join("sessions"), bundle.session.id)?.is_some() {
+                    return Err(Box::new(ArchiveError::InvalidConfiguration(format!(
+                        "Session {} already exists; import it with remapped IDs",
+                        bundle.session.id
+                    ))));
+                }
+            }
+        }
+
+        let mut session = bundle.session;
+        let session_dir = self.create_session_directory_for_new_session(&session.id, &session.name, &session.start_time, &session.tags)?;
+        session.file_path = session_dir.join("raw_audio.wav");
+        session.format_info = AudioFormatInfo {
+            sample_rate: bundle.audio.sample_rate,
+            channels: 1,
+            bit_depth: 16,
+            format: crate::services::audio_archive::AudioFormat::WAV,
+        };
+        session.state = SessionState::Stopped;
+        self.save_session_outputs(&mut session, &bundle.audio.mono, &bundle.audio.stereo)?;
+        for revision in &bundle.revisions {
+            transcript_revisions::save_revision(&session_dir, revision, self.cipher.as_deref())?;
+        }
+        self.session_history.push(session.clone());
+        self.log_imported_transcript(&session);
+
+        info!(
+            session_id = %session.id,
+            bundle = %path.display(),
+            revisions = bundle.revisions.len(),
+            verified = bundle.verified,
+            "📦 Imported session bundle"
+        );
+        Ok(session)
+    }
+
+    /// Add an imported session's transcript to the attached transcript log
+    fn log_imported_transcript(&self, session: &AudioRecordingSession) {
+        if let Some(manager) = &self.transcription_manager {
+            // The session is saved either way; a log failure only leaves it out of search
+            let logged = manager.lock().map_err(|_| "Transcription manager lock poisoned".to_string())
+                .and_then(|mut manager| manager.process_recorded_session(session).map_err(|e| e.to_string()));
+            if let Err(e) = logged {
+                warn!(session_id = %session.id, error = %e, "Failed to add the imported transcript to the transcript log");
+            }
+        }
+    }
+
+    /// ID of the imported session whose audio has this content hash
+    fn find_imported_session(&self, content_hash: &str) -> Result<Option<Uuid>, Box<dyn std::error::Error>> {
+        let mut paths = Vec::new();
+        collect_session_metadata(&self.storage_dir.join("sessions"), &mut paths)?;
+        Ok(paths.iter()
+            .filter_map(|path| read_saved_metadata(path, self.cipher.as_deref()).ok())
+            .find(|session| session.metadata.get(CONTENT_HASH_KEY).is_some_and(|hash| hash == content_hash))
+            .map(|session| session.id))
+    }
+
+    /// Path of `session_metadata.json` for a saved session
+    fn find_session_metadata(&self, dir: &Path, session_id: Uuid) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+        #[derive(Deserialize)]
+        struct SavedId {
+            session: SavedSessionId,
+        }
+        #[derive(Deserialize)]
+        struct SavedSessionId {
+            id: Uuid,
+        }
+
+        if !dir.is_dir() {
+            return Ok(None);
+        }
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if let Some(found) = self.find_session_metadata(&path, session_id)? {
+                    return Ok(Some(found));
+                }
+            } else if path.file_name().is_some_and(|name| name == "session_metadata.json") {
+                let saved = encryption::read_file(&path, self.cipher.as_deref()).ok()
+                    .and_then(|data| serde_json::from_slice::<SavedId>(&data).ok());
+                if saved.is_some_and(|saved| saved.session.id == session_id) {
+                    return Ok(Some(path));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Revisions stored with a saved session
+    fn saved_revisions(&self, session: &AudioRecordingSession) -> Result<Vec<TranscriptRevision>, Box<dyn std::error::Error>> {
+        Ok(match session.file_path.parent() {
+            Some(dir) => transcript_revisions::load_revisions(dir, self.cipher.as_deref())?,
+            None => Vec::new(),
+        })
+    }
+
+    /// Regenerate all outputs of an edited session from its new audio, replacing
+    /// its revisions with `revisions`, already retimed to that audio
+    fn rewrite_session(
+        &mut self,
+        session: &mut AudioRecordingSession,
+        audio: &SessionAudio,
+        revisions: &[TranscriptRevision],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Segment files are named by fresh IDs, and chapters may no longer apply
+        if let Some(dir) = session.file_path.parent() {
+            let segments_dir = dir.join("segments");
+            if segments_dir.exists() {
+                fs::remove_dir_all(segments_dir)?;
+            }
+            for chapters in ["chapters.vtt", "chapters.ffmetadata"] {
+                if dir.join(chapters).exists() {
+                    fs::remove_file(dir.join(chapters))?;
+                }
+            }
+            // Revisions are saved again below, retimed to the new audio
+            let revisions_dir = dir.join(transcript_revisions::REVISIONS_DIR);
+            if revisions_dir.exists() {
+                fs::remove_dir_all(revisions_dir)?;
+            }
+        }
+        session.format_info.channels = 1;
+        session.format_info.sample_rate = audio.sample_rate;
+        session.duration = audio.duration();
+        session.end_time = Some(session.start_time + chrono::Duration::from_std(session.duration)?);
+        self.save_session_outputs(session, &audio.mono, &audio.stereo)?;
+        if let Some(dir) = session.file_path.parent() {
+            for revision in revisions {
+                transcript_revisions::save_revision(dir, revision, self.cipher.as_deref())?;
+            }
+        }
+
+        match self.session_history.iter_mut().find(|s| s.id == session.id) {
+            Some(entry) => *entry = session.clone(),
+            None => self.session_history.push(session.clone()),
+        }
+        Ok(())
+    }
+
     /// Load session history from storage directory
     pub fn load_session_history(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         let sessions_dir = self.storage_dir.join("sessions");
@@ -916,8 +2223,8 @@ impl AudioSessionManager {
 
     /// Load session from metadata file
     fn load_session_from_metadata(&self, metadata_path: &Path) -> Result<AudioRecordingSession, Box<dyn std::error::Error>> {
-        let file = File::open(metadata_path)?;
-        let session: AudioRecordingSession = serde_json::from_reader(file)?;
+        let data = encryption::read_file(metadata_path, self.cipher.as_deref())?;
+        let session: AudioRecordingSession = serde_json::from_slice(&data)?;
         Ok(session)
     }
 
@@ -932,8 +2239,14 @@ impl AudioSessionManager {
 
     /// Add test audio data (for testing purposes only)
     pub fn add_test_audio_data(&self, samples: &[f32]) -> Result<(), Box<dyn std::error::Error>> {
+        let sample_rate = self.actual_sample_rate.lock().ok().and_then(|sr| *sr)
+            .or_else(|| self.current_session.as_ref().map(|s| s.format_info.sample_rate))
+            .unwrap_or(44100);
         if let Ok(mut buffer) = self.audio_buffer.lock() {
-            buffer.extend_from_slice(samples);
+            buffer.push(samples, RECENT_AUDIO_SECONDS * sample_rate as usize);
+        }
+        if let Some((_, writer)) = &self.journal {
+            writer.sender().append(JournalStream::Mono, samples, sample_rate);
         }
         Ok(())
     }
@@ -992,10 +2305,80 @@ impl Default for QualityMetrics {
             silence_percentage: 0.0,
             clipping_events: 0,
             vad_accuracy: 0.0,
+            average_gain_db: 0.0,
+            min_gain_db: 0.0,
+            max_gain_db: 0.0,
+            limiter_activations: 0,
+            signal_metrics: None,
         }
     }
 }
 
+/// The session stored in a `session_metadata.json` file
+pub(crate) fn read_saved_metadata(metadata_path: &Path, cipher: Option<&StorageCipher>) -> Result<AudioRecordingSession, Box<dyn std::error::Error>> {
+    #[derive(Deserialize)]
+    struct SavedMetadata {
+        session: AudioRecordingSession,
+    }
+    let data = encryption::read_file(metadata_path, cipher)?;
+    let mut session = serde_json::from_slice::<SavedMetadata>(&data)?.session;
+    // Resolve the audio next to the metadata in case the data directory moved
+    if let Some(session_dir) = metadata_path.parent() {
+        session.file_path = saved_raw_audio_path(session_dir);
+    }
+    Ok(session)
+}
+
+/// Raw audio of a saved session: `raw_audio.wav`, or `raw_audio.flac` once
+/// the archive has compressed it
+fn saved_raw_audio_path(session_dir: &Path) -> PathBuf {
+    let wav = session_dir.join("raw_audio.wav");
+    let flac = wav.with_extension("flac");
+    if !wav.exists() && flac.exists() {
+        flac
+    } else {
+        wav
+    }
+}
+
+/// A saved session and its raw audio, from its `session_metadata.json` file
+pub(crate) fn read_saved_session(
+    metadata_path: &Path,
+    cipher: Option<&StorageCipher>,
+) -> Result<(AudioRecordingSession, SessionAudio), Box<dyn std::error::Error>> {
+    let session = read_saved_metadata(metadata_path, cipher)?;
+    let data = encryption::read_file(&session.file_path, cipher)?;
+    if session.file_path.extension().is_some_and(|ext| ext == "flac") {
+        let flac = flac::decode(&data)?;
+        // Same scale the WAV was written and read with, so edits round-trip exactly
+        let scale = ((1i64 << (flac.info.bits_per_sample - 1)) - 1) as f32;
+        let samples = flac.samples.iter().map(|&sample| sample as f32 / scale).collect();
+        return Ok((session, SessionAudio::from_interleaved(samples, flac.info.channels, flac.info.sample_rate)));
+    }
+    let mut reader = hound::WavReader::new(std::io::Cursor::new(data))?;
+    let spec = reader.spec();
+    let samples = reader.samples::<i16>()
+        .map(|s| s.map(|sample| sample as f32 / i16::MAX as f32))
+        .collect::<Result<Vec<f32>, _>>()?;
+    Ok((session, SessionAudio::from_interleaved(samples, spec.channels, spec.sample_rate)))
+}
+
+/// Every `session_metadata.json` under `dir`
+pub(crate) fn collect_session_metadata(dir: &Path, found: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_session_metadata(&path, found)?;
+        } else if path.file_name().is_some_and(|name| name == "session_metadata.json") {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
 /// Storage statistics
 #[derive(Debug, Clone)]
 pub struct StorageStats {
@@ -1022,6 +2405,9 @@ pub struct AudioSegment {
     pub file_size: u64,
     pub is_speech: bool,
     pub average_energy: f32,
+    /// SNR, levels, spectrum and clipping of this segment
+    #[serde(default)]
+    pub signal_metrics: Option<SignalMetrics>,
 }
 
 /// Session output files structure
@@ -1040,6 +2426,7 @@ pub struct SessionOutputs {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::services::transcription_log::SearchCriteria;
     use tempfile::TempDir;
 
     #[test]
@@ -1069,7 +2456,227 @@ mod tests {
             config,
         ).unwrap();
         
-        let sanitized = manager.sanitize_filename("Test/Session:With*Invalid?Chars");
-        assert_eq!(sanitized, "Test_Session_With_Invalid_Chars");
+        let id = Uuid::new_v4();
+        let session_dir = manager.create_session_directory_for_new_session(&id, "Test/Session:With*Invalid?Chars", &Utc::now(), &[]).unwrap();
+        let sanitized = session_dir.file_name().unwrap().to_string_lossy().into_owned();
+        assert_eq!(sanitized, format!("Test_Session_With_Invalid_Chars_{}", id));
+        assert!(session_dir.starts_with(temp_dir.path().join("sessions")));
+    }
+
+    #[test]
+    fn test_recover_unfinished_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let audio_service = Arc::new(Mutex::new(AudioService::new().unwrap()));
+        let mut manager = AudioSessionManager::new(
+            audio_service,
+            temp_dir.path().to_path_buf(),
+            SessionConfig::default(),
+        ).unwrap();
+
+        // Leave a journal behind as a crashed recording would
+        let id = Uuid::new_v4();
+        let start_time = Utc::now();
+        let session_dir = manager.create_session_directory_for_new_session(&id, "Crashed", &start_time, &[]).unwrap();
+        let session = AudioRecordingSession {
+            id,
+            start_time,
+            state: SessionState::Recording,
+            ..AudioRecordingSession::for_test("Crashed").with_file_path(session_dir.join("raw_audio.wav"))
+        };
+        let journal = SessionJournal::create(&session_dir, None).unwrap();
+        journal.write_header(&session).unwrap();
+        let writer = journal.start_writer(Duration::from_millis(500));
+        // Speech, a pause and more speech, so segments cross the 500 ms chunks
+        let audio: Vec<f32> = (0..48000)
+            .map(|i| if (16000..32000).contains(&i) { 0.0 } else { (i as f32 * 0.05).sin() * 0.3 })
+            .collect();
+        for block in audio.chunks(3000) {
+            writer.sender().append(JournalStream::Mono, block, 16000);
+        }
+        writer.finish().unwrap();
+
+        let recovered = manager.recover_unfinished_sessions().unwrap();
+        assert_eq!(recovered.len(), 1);
+        let session = &recovered[0];
+        assert_eq!(session.id, id);
+        assert_eq!(session.state, SessionState::Stopped);
+        assert_eq!(session.format_info.sample_rate, 16000);
+        assert_eq!(session.duration, Duration::from_secs(3));
+        assert_eq!(session.metadata.get("recovered_from_journal").map(String::as_str), Some("true"));
+
+        // Outputs written chunk by chunk match the whole recording
+        let wav_len = |path: &Path| hound::WavReader::open(path).unwrap().len() as usize;
+        let speech = manager.detect_speech_segments(&audio, 16000).unwrap();
+        assert_eq!(speech.len(), 2);
+        assert_eq!(wav_len(&session_dir.join("raw_audio.wav")), audio.len());
+        assert_eq!(wav_len(&session_dir.join("cleaned_audio.wav")), speech.iter().map(|(start, end)| end - start).sum::<usize>());
+        let mut segments: Vec<PathBuf> = fs::read_dir(session_dir.join("segments")).unwrap().map(|e| e.unwrap().path()).collect();
+        segments.sort();
+        assert_eq!(segments.iter().map(|path| wav_len(path)).collect::<Vec<_>>(), speech.iter().map(|(start, end)| end - start).collect::<Vec<_>>());
+        assert!(!journal.dir().exists());
+        assert!(manager.recover_unfinished_sessions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_attached_transcript_log_is_encrypted_like_sessions() {
+        let temp_dir = TempDir::new().unwrap();
+        let audio_service = Arc::new(Mutex::new(AudioService::new().unwrap()));
+        let cipher = Arc::new(StorageCipher::new(encryption::EncryptionKey::generate().unwrap()));
+        let mut manager = AudioSessionManager::new_with_cipher(
+            audio_service,
+            temp_dir.path().to_path_buf(),
+            SessionConfig::default(),
+            Some(cipher),
+        ).unwrap();
+        let log = manager.attach_transcription_manager(TranscriptionManagerConfig {
+            storage_path: temp_dir.path().to_path_buf(),
+            ..TranscriptionManagerConfig::default()
+        }).unwrap();
+
+        let session = AudioRecordingSession::for_test("memo").with_segments(vec![TranscriptSegment::for_test(0.0, 1.0, "the vault code")]);
+        assert_eq!(log.lock().unwrap().process_recorded_session(&session).unwrap().len(), 1);
+
+        let mut files = Vec::new();
+        let mut dirs = vec![temp_dir.path().join("transcripts")];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else {
+                    files.push(fs::read(path).unwrap());
+                }
+            }
+        }
+        assert!(!files.is_empty());
+        assert!(files.iter().all(|data| !data.windows(10).any(|w| w == b"vault code")));
+    }
+
+    #[test]
+    fn test_imported_bundle_reaches_transcript_log() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = AudioSessionManager::new(
+            Arc::new(Mutex::new(AudioService::new().unwrap())),
+            temp_dir.path().join("source"),
+            SessionConfig::default(),
+        ).unwrap();
+        let id = Uuid::new_v4();
+        let start_time = Utc::now();
+        let session_dir = manager.create_session_directory_for_new_session(&id, "Standup", &start_time, &[]).unwrap();
+        let mut session = AudioRecordingSession {
+            id,
+            start_time,
+            ..AudioRecordingSession::for_test("Standup")
+                .with_file_path(session_dir.join("raw_audio.wav"))
+                .with_segments(vec![TranscriptSegment::for_test(0.0, 1.0, "ship the release on friday")])
+        };
+        let samples: Vec<f32> = (0..8000).map(|i| (i as f32 * 0.2).sin() * 0.5).collect();
+        manager.save_session_outputs(&mut session, &samples, &[]).unwrap();
+        let bundle = temp_dir.path().join("standup.bundle");
+        manager.export_session_bundle(id, &bundle, BundleOptions::default()).unwrap();
+
+        let mut importer = AudioSessionManager::new(
+            Arc::new(Mutex::new(AudioService::new().unwrap())),
+            temp_dir.path().join("target"),
+            SessionConfig::default(),
+        ).unwrap();
+        let log = importer.attach_transcription_manager(TranscriptionManagerConfig {
+            storage_path: temp_dir.path().join("target"),
+            ..TranscriptionManagerConfig::default()
+        }).unwrap();
+        let imported = importer.import_session_bundle(&bundle, IdPolicy::Remap, None).unwrap();
+
+        let found = log.lock().unwrap().search_transcripts(&SearchCriteria {
+            query: Some("release".to_string()),
+            ..SearchCriteria::default()
+        }).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].transcript.session_id, Some(imported.id));
+    }
+
+    #[test]
+    fn test_compressed_session_stays_editable() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut manager = AudioSessionManager::new(
+            Arc::new(Mutex::new(AudioService::new().unwrap())),
+            temp_dir.path().to_path_buf(),
+            SessionConfig::default(),
+        ).unwrap();
+        let id = Uuid::new_v4();
+        let start_time = Utc::now();
+        let session_dir = manager.create_session_directory_for_new_session(&id, "Retro", &start_time, &[]).unwrap();
+        let mut session = AudioRecordingSession {
+            id,
+            start_time,
+            ..AudioRecordingSession::for_test("Retro").with_file_path(session_dir.join("raw_audio.wav"))
+        };
+        let samples: Vec<f32> = (0..16000).map(|i| (i as f32 * 0.2).sin() * 0.5).collect();
+        manager.save_session_outputs(&mut session, &samples, &[]).unwrap();
+        let (_, original) = manager.load_saved_session(id).unwrap();
+
+        let result = manager.compress_archive().unwrap();
+        assert_eq!(result.files_compressed, 1);
+        assert!(!session_dir.join("raw_audio.wav").exists());
+        assert!(session_dir.join("raw_audio.flac").exists());
+
+        // Reads, checks and edits find the FLAC in place of the WAV
+        let (reloaded, audio) = manager.load_saved_session(id).unwrap();
+        assert_eq!(reloaded.file_path, session_dir.join("raw_audio.flac"));
+        assert_eq!(audio.mono, original.mono);
+        assert!(manager.check_archive(false).unwrap().is_clean());
+        let trimmed = manager.trim_session(id, Duration::ZERO, Duration::from_secs(1)).unwrap();
+        assert_eq!(trimmed.file_path, session_dir.join("raw_audio.wav"));
+        assert!(!session_dir.join("raw_audio.flac").exists());
+    }
+
+    #[test]
+    fn test_split_merge_and_redact_saved_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let audio_service = Arc::new(Mutex::new(AudioService::new().unwrap()));
+        let mut manager = AudioSessionManager::new(
+            audio_service,
+            temp_dir.path().to_path_buf(),
+            SessionConfig::default(),
+        ).unwrap();
+
+        // Two seconds of speech, one of silence, two of speech at 8 kHz
+        let id = Uuid::new_v4();
+        let start_time = Utc::now();
+        let session_dir = manager.create_session_directory_for_new_session(&id, "Edit me", &start_time, &[]).unwrap();
+        let mut session = AudioRecordingSession {
+            id,
+            start_time,
+            ..AudioRecordingSession::for_test("Edit me")
+                .with_file_path(session_dir.join("raw_audio.wav"))
+                .with_segments(vec![
+                    TranscriptSegment::for_test(0.0, 2.0, "first part"),
+                    TranscriptSegment::for_test(3.0, 5.0, "second part"),
+                ])
+        };
+        let tone = |n: usize| (0..n).map(|i| (i as f32 * 0.2).sin() * 0.5);
+        let samples: Vec<f32> = tone(16000).chain(std::iter::repeat_n(0.0, 8000)).chain(tone(16000)).collect();
+        let second_segment = session.transcript_segments[1].id;
+        manager.save_session_outputs(&mut session, &samples, &[]).unwrap();
+
+        let (first, second) = manager.split_session(id, SplitPoint::BeforeSegment(second_segment)).unwrap();
+        assert_eq!(first.duration, Duration::from_secs(3));
+        assert_eq!(second.duration, Duration::from_secs(2));
+        assert_eq!(second.transcript_segments[0].text, "second part");
+        assert_eq!(second.transcript_segments[0].start_time, Duration::ZERO);
+        assert!(second.file_path.exists());
+
+        let merged = manager.merge_sessions(id, second.id).unwrap();
+        assert_eq!(merged.duration, Duration::from_secs(5));
+        assert_eq!(merged.transcript_segments[1].start_time, Duration::from_secs(3));
+        assert!(!second.file_path.exists());
+
+        let redacted = manager.redact_session(id, Duration::from_secs(3), Duration::from_secs(5)).unwrap();
+        assert_eq!(redacted.transcript_segments[1].text, crate::services::session_edit::REDACTED_TEXT);
+        let (_, audio) = manager.load_saved_session(id).unwrap();
+        assert!(audio.mono[3 * 8000..].iter().all(|&s| s == 0.0));
+        assert!(audio.mono[..8000].iter().any(|&s| s != 0.0));
+        // Only the speech before the redaction is left as a segment file
+        let segment_files = fs::read_dir(session_dir.join("segments")).unwrap().count();
+        assert_eq!(segment_files, 1);
     }
 }
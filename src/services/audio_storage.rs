@@ -3,10 +3,11 @@
 //! This module provides concrete implementations of audio storage backends
 //! with compression, indexing, and efficient file management.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File, OpenOptions};
-use std::io::{self, BufReader, BufWriter, Read, Write, Seek, SeekFrom};
+use std::io::{self, BufWriter, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use std::fmt;
 use chrono::{DateTime, Utc};
@@ -16,8 +17,14 @@ use uuid::Uuid;
 use super::audio_archive::{
     AudioStorage, AudioError, RecordingSession, SearchCriteria, StorageStats,
     CompressionResult, CleanupResult, RetentionPolicy, SessionId, AudioFileId,
-    AudioFormatInfo, AudioFormat,
+    AudioFormatInfo, AudioFormat, CompressionLevel,
 };
+use super::flac::{self, FlacEncoder};
+use super::ogg_opus;
+use super::encryption::{self, StorageCipher};
+use super::path_template::{self, PathFields, PathTemplate};
+use super::archive_check::{ArchiveIssue, IssueKind};
+use super::audio_session_manager::AudioRecordingSession;
 
 /// File-based audio storage implementation
 pub struct FileAudioStorage {
@@ -31,6 +38,8 @@ pub struct FileAudioStorage {
     compression_engine: CompressionEngine,
     /// Storage configuration
     config: StorageConfig,
+    /// Encrypts audio files and the session index when set
+    cipher: Option<Arc<StorageCipher>>,
 }
 
 /// Session index for efficient session management
@@ -63,6 +72,11 @@ pub struct SessionMetadata {
     pub transcript_count: usize,
     pub checksum: Option<String>,
     pub compression_info: Option<CompressionInfo>,
+    /// Saved by the session manager, which finds the audio by name in its
+    /// session directory; reorganizing leaves these files where they are and
+    /// compression converts them to FLAC in place
+    #[serde(default)]
+    pub managed: bool,
 }
 
 /// Audio file manager for storage operations
@@ -70,10 +84,10 @@ pub struct SessionMetadata {
 pub struct AudioFileManager {
     /// Base directory
     base_path: PathBuf,
-    /// File organization strategy
-    organization: FileOrganization,
-    /// File naming strategy
-    naming: FileNaming,
+    /// Parsed organization template
+    directory_template: PathTemplate,
+    /// Parsed naming template
+    name_template: PathTemplate,
 }
 
 /// Compression engine for audio files
@@ -110,6 +124,15 @@ pub struct StorageConfig {
     pub auto_compress: bool,
     /// Compression format preference
     pub preferred_format: AudioFormat,
+    /// Compression level; sets the bitrate of lossy formats
+    #[serde(default = "default_compression_level")]
+    pub compression_level: CompressionLevel,
+    /// Directory layout of archived files
+    #[serde(default)]
+    pub organization: FileOrganization,
+    /// File names of archived files
+    #[serde(default)]
+    pub naming: FileNaming,
     /// Enable checksums for integrity
     pub enable_checksums: bool,
     /// Index update frequency
@@ -157,36 +180,77 @@ pub struct CompressionConfig {
     pub enable_parallel: bool,
     pub chunk_size: usize,
     pub preserve_metadata: bool,
+    pub level: CompressionLevel,
 }
 
 /// File organization strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub enum FileOrganization {
     /// Flat structure - all files in one directory
     Flat,
     /// Organize by date (YYYY/MM/DD)
+    #[default]
     ByDate,
     /// Organize by session name
     ByName,
     /// Organize by tags
     ByTags,
-    /// Custom organization pattern
+    /// Custom directory template, e.g. `{date:%Y/%m}/{tag}`
     Custom(String),
 }
 
 /// File naming strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub enum FileNaming {
     /// Use session ID as filename
+    #[default]
     SessionId,
     /// Use session name (sanitized)
     SessionName,
     /// Use timestamp
     Timestamp,
-    /// Custom naming pattern
+    /// Custom file name template (without extension), e.g. `{name}-{id8}`
     Custom(String),
 }
 
+impl FileOrganization {
+    /// Directory part of the path template
+    pub fn template(&self) -> &str {
+        match self {
+            Self::Flat => "",
+            Self::ByDate => "{date:%Y/%m/%d}",
+            Self::ByName => "{name}",
+            Self::ByTags => "{tag}",
+            Self::Custom(pattern) => pattern,
+        }
+    }
+}
+
+impl FileNaming {
+    /// File name part of the path template
+    pub fn template(&self) -> &str {
+        match self {
+            Self::SessionId => "{id}",
+            Self::SessionName => "{name}",
+            Self::Timestamp => "{date:%Y%m%d_%H%M%S}",
+            Self::Custom(pattern) => pattern,
+        }
+    }
+}
+
+/// Outcome of [`FileAudioStorage::reorganize`]
+#[derive(Debug, Clone, Default)]
+pub struct ReorganizeResult {
+    /// Sessions whose files were moved
+    pub sessions_moved: usize,
+    /// Sessions already in place
+    pub sessions_unchanged: usize,
+    /// Moved sessions that got a numeric suffix because the target was taken
+    pub collisions: usize,
+    /// Session manager sessions, which stay in their session directory
+    pub sessions_skipped: usize,
+}
+
 /// Backup configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackupConfig {
@@ -201,6 +265,11 @@ pub struct BackupConfig {
 impl FileAudioStorage {
     /// Create a new file-based audio storage
     pub fn new(storage_path: PathBuf, config: StorageConfig) -> Result<Self, AudioError> {
+        Self::new_with_cipher(storage_path, config, None)
+    }
+
+    /// Create file-based audio storage that encrypts everything it writes
+    pub fn new_with_cipher(storage_path: PathBuf, config: StorageConfig, cipher: Option<Arc<StorageCipher>>) -> Result<Self, AudioError> {
         // Ensure storage directory exists
         if !storage_path.exists() {
             fs::create_dir_all(&storage_path)?;
@@ -209,7 +278,7 @@ impl FileAudioStorage {
         // Initialize session index
         let index_path = storage_path.join("session_index.json");
         let session_index = if index_path.exists() {
-            Self::load_session_index(&index_path)?
+            Self::load_session_index(&index_path, cipher.as_deref())?
         } else {
             SessionIndex::new()
         };
@@ -217,9 +286,9 @@ impl FileAudioStorage {
         // Initialize file manager
         let file_manager = AudioFileManager::new(
             storage_path.clone(),
-            FileOrganization::ByDate,
-            FileNaming::SessionId,
-        );
+            config.organization.clone(),
+            config.naming.clone(),
+        )?;
         
         // Initialize compression engine
         let compression_engine = CompressionEngine::new(CompressionConfig {
@@ -227,6 +296,7 @@ impl FileAudioStorage {
             enable_parallel: true,
             chunk_size: 4096,
             preserve_metadata: true,
+            level: config.compression_level.clone(),
         });
         
         Ok(Self {
@@ -235,35 +305,222 @@ impl FileAudioStorage {
             file_manager,
             compression_engine,
             config,
+            cipher,
         })
     }
     
     /// Load session index from file
-    fn load_session_index(path: &Path) -> Result<SessionIndex, AudioError> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let index: SessionIndex = serde_json::from_reader(reader)?;
+    fn load_session_index(path: &Path, cipher: Option<&StorageCipher>) -> Result<SessionIndex, AudioError> {
+        let data = encryption::read_file(path, cipher)?;
+        let index: SessionIndex = serde_json::from_slice(&data)?;
         Ok(index)
     }
     
     /// Save session index to file
     fn save_session_index(&self) -> Result<(), AudioError> {
         let index_path = self.storage_path.join("session_index.json");
-        let file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(index_path)?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, &self.session_index)?;
+        let data = serde_json::to_vec_pretty(&self.session_index)?;
+        encryption::write_file(&index_path, &data, self.cipher.as_deref())?;
         Ok(())
     }
     
-    /// Generate file path for a session
-    fn generate_file_path(&self, session: &RecordingSession) -> PathBuf {
-        self.file_manager.generate_path(session)
+    /// Generate a file path for a session that no other file or session uses
+    fn generate_file_path(&self, session: &RecordingSession, extension: &str) -> PathBuf {
+        let path = self.file_manager.generate_path(&PathFields::from(session)).with_extension(extension);
+        path_template::unique_path(path, |candidate| {
+            candidate.exists() || self.session_index.sessions.values().any(|s| s.file_path == candidate)
+        })
+    }
+
+    /// Move every archived file to a new layout and rewrite the session index.
+    /// Progress is saved to the index even if a move fails part way.
+    pub fn reorganize(&mut self, organization: FileOrganization, naming: FileNaming) -> Result<ReorganizeResult, AudioError> {
+        let file_manager = AudioFileManager::new(self.storage_path.clone(), organization.clone(), naming.clone())?;
+        let mut result = ReorganizeResult::default();
+
+        let mut sessions: Vec<SessionMetadata> = self.session_index.sessions.values().cloned().collect();
+        sessions.sort_by(|a, b| a.start_time.cmp(&b.start_time).then(a.id.cmp(&b.id)));
+        let mut claimed: Vec<PathBuf> = Vec::new();
+
+        for session in sessions {
+            if session.managed {
+                result.sessions_skipped += 1;
+                claimed.push(session.file_path);
+                continue;
+            }
+            let extension = session.file_path.extension()
+                .map(|ext| ext.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "wav".to_string());
+            let fields = PathFields {
+                id: session.id,
+                name: &session.name,
+                start_time: session.start_time,
+                tags: &session.tags,
+            };
+            let wanted = file_manager.generate_path(&fields).with_extension(&extension);
+            if wanted == session.file_path {
+                result.sessions_unchanged += 1;
+                claimed.push(wanted);
+                continue;
+            }
+
+            let target = path_template::unique_path(wanted.clone(), |candidate| {
+                claimed.iter().any(|p| p == candidate) || (candidate.exists() && candidate != session.file_path)
+            });
+            if target == session.file_path {
+                result.sessions_unchanged += 1;
+                claimed.push(target);
+                continue;
+            }
+            if target != wanted {
+                result.collisions += 1;
+            }
+
+            if let Err(e) = Self::move_file(&session.file_path, &target, &self.storage_path) {
+                self.save_session_index()?;
+                return Err(e);
+            }
+            if let Some(metadata) = self.session_index.sessions.get_mut(&session.id) {
+                metadata.file_path = target.clone();
+            }
+            claimed.push(target);
+            result.sessions_moved += 1;
+        }
+
+        self.file_manager = file_manager;
+        self.config.organization = organization;
+        self.config.naming = naming;
+        self.save_session_index()?;
+        Ok(result)
+    }
+
+    /// Rename a file, creating its new directory and pruning directories it leaves empty
+    fn move_file(from: &Path, to: &Path, root: &Path) -> Result<(), AudioError> {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(from, to)?;
+        let mut dir = from.parent();
+        while let Some(current) = dir {
+            if current == root || !current.starts_with(root) || fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+        Ok(())
     }
     
+    /// Format info for audio written in the preferred compressed format
+    fn compressed_format_info(&self, format_info: &AudioFormatInfo) -> AudioFormatInfo {
+        AudioFormatInfo {
+            format: self.config.preferred_format.clone(),
+            ..format_info.clone()
+        }
+    }
+
+    /// Read an uncompressed archive file: a RIFF WAV or raw f32 samples
+    fn read_uncompressed_samples(path: &Path, cipher: Option<&StorageCipher>) -> Result<Vec<f32>, AudioError> {
+        let buffer = encryption::read_file(path, cipher)?;
+        if buffer.starts_with(b"RIFF") {
+            let mut reader = hound::WavReader::new(io::Cursor::new(buffer))
+                .map_err(|e| AudioError::StorageError(format!("Failed to read WAV {}: {}", path.display(), e)))?;
+            let spec = reader.spec();
+            let samples: Result<Vec<f32>, hound::Error> = match spec.sample_format {
+                hound::SampleFormat::Float => reader.samples::<f32>().collect(),
+                hound::SampleFormat::Int => {
+                    let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                    reader.samples::<i32>().map(|s| s.map(|v| v as f32 / scale)).collect()
+                }
+            };
+            return samples.map_err(|e| AudioError::StorageError(format!("Failed to read WAV {}: {}", path.display(), e)));
+        }
+        Ok(buffer
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect())
+    }
+
+    /// Stream samples to a FLAC file next to `source`, verify it, and return its path and bytes.
+    /// Encrypted archives are encoded in memory so no plaintext reaches the disk.
+    fn write_verified_flac(source: &Path, samples: &[f32], format_info: &AudioFormatInfo, cipher: Option<&StorageCipher>) -> Result<(PathBuf, Vec<u8>), AudioError> {
+        let flac_path = source.with_extension("flac");
+        let bits = FlacCompressor::bits_per_sample(format_info);
+        let channels = format_info.channels.max(1);
+        let quantized = flac::quantize(samples, bits);
+        let usable = quantized.len() / channels as usize * channels as usize;
+
+        let chunk_len = flac::DEFAULT_BLOCK_SIZE * channels as usize * 16;
+        if let Some(cipher) = cipher {
+            let mut encoder = FlacEncoder::new(io::Cursor::new(Vec::new()), format_info.sample_rate, channels, bits)?;
+            for chunk in quantized[..usable].chunks(chunk_len) {
+                encoder.write_samples(chunk)?;
+            }
+            encryption::write_file(&flac_path, &encoder.finish()?.into_inner(), Some(cipher))?;
+        } else {
+            let file = BufWriter::new(File::create(&flac_path)?);
+            let mut encoder = FlacEncoder::new(file, format_info.sample_rate, channels, bits)?;
+            for chunk in quantized[..usable].chunks(chunk_len) {
+                encoder.write_samples(chunk)?;
+            }
+            let file = encoder.finish()?;
+            file.into_inner().map_err(|e| AudioError::IoError(e.into_error()))?.sync_all()?;
+        }
+
+        // Verify after write: the file on disk must decode to exactly what we encoded
+        let written = encryption::read_file(&flac_path, cipher)?;
+        if let Err(e) = flac::verify(&written, &quantized[..usable]) {
+            let _ = fs::remove_file(&flac_path);
+            return Err(e);
+        }
+        Ok((flac_path, written))
+    }
+
+    /// Write one session's audio in the preferred format and return its updated
+    /// metadata. The source file stays until the index points at the new one.
+    fn compress_session_file(&mut self, session_metadata: &SessionMetadata) -> Result<SessionMetadata, AudioError> {
+        let file_start = std::time::Instant::now();
+        let source_path = session_metadata.file_path.clone();
+        let audio_data = Self::read_uncompressed_samples(&source_path, self.cipher.as_deref())?;
+        let orig_size = fs::metadata(&source_path)?.len();
+        // The session manager cuts segments and edits from its raw audio, so it stays lossless
+        let format_info = if session_metadata.managed {
+            AudioFormatInfo { format: AudioFormat::FLAC, ..session_metadata.format_info.clone() }
+        } else {
+            self.compressed_format_info(&session_metadata.format_info)
+        };
+
+        // FLAC is converted in place: a verified .flac replaces the WAV
+        let (file_path, compressed_data) = if format_info.format == AudioFormat::FLAC {
+            Self::write_verified_flac(&source_path, &audio_data, &format_info, self.cipher.as_deref())?
+        } else {
+            let bytes = self.compression_engine.compress_audio(&audio_data, &format_info)?;
+            let target_path = source_path.with_extension(archive_extension(&format_info.format));
+            encryption::write_file(&target_path, &bytes, self.cipher.as_deref())?;
+            (target_path, bytes)
+        };
+        let comp_size = compressed_data.len() as u64;
+        let checksum = if self.config.enable_checksums {
+            Some(self.calculate_checksum(&compressed_data))
+        } else {
+            None
+        };
+
+        // Update session metadata
+        let mut updated_metadata = session_metadata.clone();
+        updated_metadata.file_path = file_path;
+        updated_metadata.file_size = comp_size;
+        updated_metadata.checksum = checksum.clone();
+        updated_metadata.compression_info = Some(CompressionInfo {
+            original_size: orig_size,
+            compressed_size: comp_size,
+            compression_ratio: comp_size as f64 / orig_size.max(1) as f64,
+            format: format_info.format.clone(),
+            compression_time: file_start.elapsed(),
+            checksum,
+        });
+        Ok(updated_metadata)
+    }
+
     /// Calculate file checksum
     fn calculate_checksum(&self, data: &[u8]) -> String {
         use std::collections::hash_map::DefaultHasher;
@@ -273,12 +530,135 @@ impl FileAudioStorage {
         data.hash(&mut hasher);
         format!("{:x}", hasher.finish())
     }
+
+    /// Whether a session has an entry in the index
+    pub fn is_indexed(&self, session_id: SessionId) -> bool {
+        self.session_index.sessions.contains_key(&session_id)
+    }
+
+    /// Every file the index refers to
+    pub fn indexed_paths(&self) -> HashSet<PathBuf> {
+        self.session_index.sessions.values().map(|s| s.file_path.clone()).collect()
+    }
+
+    /// Add or refresh the index entry for a session saved by the session manager
+    pub fn index_saved_session(&mut self, session: &AudioRecordingSession) -> Result<(), AudioError> {
+        if let Some(existing) = self.session_index.sessions.remove(&session.id) {
+            self.session_index.remove_from_indexes(&existing);
+        }
+        self.session_index.add_session(SessionMetadata {
+            id: session.id,
+            name: session.name.clone(),
+            start_time: session.start_time,
+            end_time: session.end_time,
+            duration: session.duration,
+            file_path: session.file_path.clone(),
+            file_size: session.file_size,
+            format_info: session.format_info.clone(),
+            tags: session.tags.clone(),
+            transcript_count: session.transcript_segments.len(),
+            // The manager rewrites raw audio on edits, so there is no stable checksum to keep
+            checksum: None,
+            compression_info: None,
+            managed: true,
+        })?;
+        self.save_session_index()
+    }
+
+    /// Drop a session's index entry without touching its files
+    pub fn unindex_session(&mut self, session_id: SessionId) -> Result<(), AudioError> {
+        if let Some(existing) = self.session_index.sessions.remove(&session_id) {
+            self.session_index.remove_from_indexes(&existing);
+            self.save_session_index()?;
+        }
+        Ok(())
+    }
+
+    /// Drop the index entries of audio files deleted outside the storage, such as
+    /// by the retention sweep. Returns how many entries were dropped.
+    pub fn unindex_files(&mut self, paths: &[PathBuf]) -> Result<usize, AudioError> {
+        let removed: Vec<SessionId> = self.session_index.sessions.values()
+            .filter(|s| paths.contains(&s.file_path))
+            .map(|s| s.id)
+            .collect();
+        for id in &removed {
+            if let Some(existing) = self.session_index.sessions.remove(id) {
+                self.session_index.remove_from_indexes(&existing);
+            }
+        }
+        if !removed.is_empty() {
+            self.save_session_index()?;
+        }
+        Ok(removed.len())
+    }
+
+    /// Verify every index entry against its file: presence, format header and checksum.
+    /// With `repair`, entries whose file is gone are dropped; damaged files are only reported.
+    /// Returns the number of files checked and the problems found.
+    pub fn check_index(&mut self, repair: bool) -> Result<(usize, Vec<ArchiveIssue>), AudioError> {
+        let mut issues = Vec::new();
+        let mut missing = Vec::new();
+        let mut checked = 0;
+        for metadata in self.session_index.sessions.values() {
+            if !metadata.file_path.exists() {
+                missing.push(metadata.id);
+                let detail = format!("indexed session {} has no audio file", metadata.id);
+                issues.push(ArchiveIssue { repaired: repair, ..ArchiveIssue::new(IssueKind::MissingFile, &metadata.file_path, detail) });
+                continue;
+            }
+            checked += 1;
+            let buffer = match encryption::read_file(&metadata.file_path, self.cipher.as_deref()) {
+                Ok(buffer) => buffer,
+                Err(e) => {
+                    issues.push(ArchiveIssue::new(IssueKind::CorruptFile, &metadata.file_path, e.to_string()));
+                    continue;
+                }
+            };
+            if let Err(detail) = check_audio_header(&metadata.file_path, &buffer) {
+                issues.push(ArchiveIssue::new(IssueKind::InvalidHeader, &metadata.file_path, detail));
+                continue;
+            }
+            if let Some(expected) = &metadata.checksum {
+                let actual = self.calculate_checksum(&buffer);
+                if actual != *expected {
+                    let detail = format!("expected {}, found {}", expected, actual);
+                    issues.push(ArchiveIssue::new(IssueKind::ChecksumMismatch, &metadata.file_path, detail));
+                }
+            }
+        }
+        if repair && !missing.is_empty() {
+            for id in missing {
+                if let Some(existing) = self.session_index.sessions.remove(&id) {
+                    self.session_index.remove_from_indexes(&existing);
+                }
+            }
+            self.save_session_index()?;
+        }
+        Ok((checked, issues))
+    }
+}
+
+/// Check that decrypted archive bytes look like the format their extension claims
+fn check_audio_header(path: &Path, buffer: &[u8]) -> Result<(), String> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("flac") => flac::read_stream_info(buffer).map(|_| ()).map_err(|e| e.to_string()),
+        Some("opus") | Some("ogg") => ogg_opus::read_packets(buffer).map(|_| ()).map_err(|e| e.to_string()),
+        _ if buffer.starts_with(b"RIFF") => hound::WavReader::new(io::Cursor::new(buffer)).map(|_| ()).map_err(|e| e.to_string()),
+        // Uncompressed archives without a RIFF header hold raw f32 samples
+        _ if buffer.len().is_multiple_of(4) => Ok(()),
+        _ => Err(format!("{} bytes is not a whole number of f32 samples", buffer.len())),
+    }
 }
 
 impl AudioStorage for FileAudioStorage {
     fn store_audio(&mut self, session: &RecordingSession, data: &[f32]) -> Result<AudioFileId, AudioError> {
         let file_id = Uuid::new_v4();
-        let file_path = self.generate_file_path(session);
+        let extension = if self.config.auto_compress {
+            archive_extension(&self.config.preferred_format)
+        } else {
+            "wav"
+        };
+        let file_path = self.generate_file_path(session, extension);
         
         // Ensure directory exists
         if let Some(parent) = file_path.parent() {
@@ -286,8 +666,10 @@ impl AudioStorage for FileAudioStorage {
         }
         
         // Convert audio data to bytes and optionally compress
+        let compress_start = std::time::Instant::now();
         let audio_bytes = if self.config.auto_compress {
-            self.compression_engine.compress_audio(data, &session.format_info)?
+            let format_info = self.compressed_format_info(&session.format_info);
+            self.compression_engine.compress_audio(data, &format_info)?
         } else {
             // Convert f32 samples to bytes (WAV format)
             let mut bytes = Vec::with_capacity(data.len() * 4);
@@ -305,9 +687,7 @@ impl AudioStorage for FileAudioStorage {
         };
         
         // Write audio data to file
-        let mut file = File::create(&file_path)?;
-        file.write_all(&audio_bytes)?;
-        file.sync_all()?;
+        encryption::write_file(&file_path, &audio_bytes, self.cipher.as_deref())?;
         
         // Create session metadata
         let metadata = SessionMetadata {
@@ -328,12 +708,13 @@ impl AudioStorage for FileAudioStorage {
                     compressed_size: audio_bytes.len() as u64,
                     compression_ratio: audio_bytes.len() as f64 / (data.len() * 4) as f64,
                     format: self.config.preferred_format.clone(),
-                    compression_time: Duration::from_millis(10), // Mock timing
+                    compression_time: compress_start.elapsed(),
                     checksum: checksum.clone(),
                 })
             } else {
                 None
             },
+            managed: false,
         };
         
         // Update session index
@@ -354,9 +735,7 @@ impl AudioStorage for FileAudioStorage {
             .ok_or_else(|| AudioError::StorageError("Session not found".to_string()))?;
         
         // Read audio data from file
-        let mut file = File::open(&session_metadata.file_path)?;
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)?;
+        let buffer = encryption::read_file(&session_metadata.file_path, self.cipher.as_deref())?;
         
         // Verify checksum if available
         if let Some(expected_checksum) = &session_metadata.checksum {
@@ -533,56 +912,37 @@ impl AudioStorage for FileAudioStorage {
         let mut files_compressed = 0;
         let mut original_size = 0u64;
         let mut compressed_size = 0u64;
+        let mut skipped = Vec::new();
         
-        // Find uncompressed sessions
+        // Find uncompressed sessions; a session re-indexed after compression names its FLAC file
         let uncompressed_sessions: Vec<_> = self.session_index.sessions.values()
-            .filter(|s| s.compression_info.is_none())
+            .filter(|s| s.compression_info.is_none() && s.file_path.extension().is_none_or(|ext| ext != "flac"))
             .cloned()
             .collect();
         
         for session_metadata in uncompressed_sessions {
-            // Read original audio data
-            let audio_data = self.retrieve_audio(session_metadata.id)?;
-            
-            // Compress the audio data
-            let compressed_data = self.compression_engine.compress_audio(&audio_data, &session_metadata.format_info)?;
-            
-            // Calculate sizes
-            let orig_size = audio_data.len() as u64 * 4;
-            let comp_size = compressed_data.len() as u64;
-            
-            // Write compressed data back to file
-            let mut file = File::create(&session_metadata.file_path)?;
-            file.write_all(&compressed_data)?;
-            file.sync_all()?;
-            
-            // Update session metadata
-            let mut updated_metadata = session_metadata.clone();
-            updated_metadata.file_size = comp_size;
-            updated_metadata.compression_info = Some(CompressionInfo {
-                original_size: orig_size,
-                compressed_size: comp_size,
-                compression_ratio: comp_size as f64 / orig_size as f64,
-                format: self.config.preferred_format.clone(),
-                compression_time: Duration::from_millis(10), // Mock timing
-                checksum: if self.config.enable_checksums {
-                    Some(self.calculate_checksum(&compressed_data))
-                } else {
-                    None
-                },
-            });
-            
-            // Update index
-            self.session_index.sessions.insert(session_metadata.id, updated_metadata);
-            
+            let updated_metadata = match self.compress_session_file(&session_metadata) {
+                Ok(updated_metadata) => updated_metadata,
+                Err(e) => {
+                    skipped.push((session_metadata.file_path, e.to_string()));
+                    continue;
+                }
+            };
+            if let Some(info) = &updated_metadata.compression_info {
+                original_size += info.original_size;
+                compressed_size += info.compressed_size;
+            }
             files_compressed += 1;
-            original_size += orig_size;
-            compressed_size += comp_size;
+
+            // Save the index before deleting the source, so it never names a missing file
+            let compressed_path = updated_metadata.file_path.clone();
+            self.session_index.sessions.insert(session_metadata.id, updated_metadata);
+            self.save_session_index()?;
+            if compressed_path != session_metadata.file_path {
+                fs::remove_file(&session_metadata.file_path)?;
+            }
         }
         
-        // Save updated index
-        self.save_session_index()?;
-        
         let time_taken = start_time.elapsed();
         let compression_ratio = if original_size > 0 {
             compressed_size as f64 / original_size as f64
@@ -596,6 +956,7 @@ impl AudioStorage for FileAudioStorage {
             compressed_size,
             compression_ratio,
             time_taken,
+            skipped,
         })
     }
     
@@ -657,6 +1018,7 @@ impl AudioStorage for FileAudioStorage {
             files_deleted,
             space_freed,
             sessions_removed,
+            ..Default::default()
         })
     }
 }
@@ -724,56 +1086,23 @@ impl SessionIndex {
 
 // Implementation of AudioFileManager
 impl AudioFileManager {
-    pub fn new(base_path: PathBuf, organization: FileOrganization, naming: FileNaming) -> Self {
-        Self {
+    pub fn new(base_path: PathBuf, organization: FileOrganization, naming: FileNaming) -> Result<Self, AudioError> {
+        let directory_template = PathTemplate::parse(organization.template())?;
+        let name_template = PathTemplate::parse(naming.template())?;
+        Ok(Self {
             base_path,
-            organization,
-            naming,
-        }
+            directory_template,
+            name_template,
+        })
     }
     
-    pub fn generate_path(&self, session: &RecordingSession) -> PathBuf {
-        let mut path = self.base_path.clone();
-        
-        // Apply organization strategy
-        match &self.organization {
-            FileOrganization::Flat => {
-                // Keep flat structure
-            }
-            FileOrganization::ByDate => {
-                let date_str = session.start_time.format("%Y/%m/%d").to_string();
-                path = path.join(date_str);
-            }
-            FileOrganization::ByName => {
-                let sanitized_name = self.sanitize_filename(&session.name);
-                path = path.join(sanitized_name);
-            }
-            FileOrganization::ByTags => {
-                if let Some(tag) = session.tags.first() {
-                    path = path.join(self.sanitize_filename(tag));
-                }
-            }
-            FileOrganization::Custom(pattern) => {
-                // Apply custom pattern (simplified)
-                path = path.join(pattern);
-            }
+    /// Path of a session's file, without extension
+    pub fn generate_path(&self, fields: &PathFields) -> PathBuf {
+        let mut file_name = self.name_template.render(fields);
+        if file_name.as_os_str().is_empty() {
+            file_name = PathBuf::from(fields.id.to_string());
         }
-        
-        // Apply naming strategy
-        let filename = match &self.naming {
-            FileNaming::SessionId => format!("{}.wav", session.id),
-            FileNaming::SessionName => format!("{}.wav", self.sanitize_filename(&session.name)),
-            FileNaming::Timestamp => format!("{}.wav", session.start_time.format("%Y%m%d_%H%M%S")),
-            FileNaming::Custom(pattern) => format!("{}.wav", pattern),
-        };
-        
-        path.join(filename)
-    }
-    
-    fn sanitize_filename(&self, name: &str) -> String {
-        name.chars()
-            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
-            .collect()
+        self.base_path.join(self.directory_template.render(fields)).join(file_name)
     }
 }
 
@@ -784,7 +1113,7 @@ impl CompressionEngine {
         
         // Add compressor implementations
         compressors.insert(AudioFormat::FLAC, Box::new(FlacCompressor::new()));
-        compressors.insert(AudioFormat::Opus, Box::new(OpusCompressor::new()));
+        compressors.insert(AudioFormat::Opus, Box::new(OpusCompressor::new(config.level.clone())));
         compressors.insert(AudioFormat::MP3, Box::new(Mp3Compressor::new()));
         
         Self {
@@ -814,51 +1143,69 @@ impl CompressionEngine {
     }
 }
 
-// Mock compressor implementations
+/// Lossless FLAC compressor
 struct FlacCompressor;
 impl FlacCompressor {
     fn new() -> Self { Self }
+
+    /// FLAC sample depth for a session; float or unset depths are stored at 16 bits
+    fn bits_per_sample(format_info: &AudioFormatInfo) -> u16 {
+        match format_info.bit_depth {
+            8 | 12 | 16 | 20 | 24 => format_info.bit_depth as u16,
+            _ => 16,
+        }
+    }
 }
 
 impl AudioCompressor for FlacCompressor {
-    fn compress(&self, data: &[f32], _format_info: &AudioFormatInfo) -> Result<Vec<u8>, AudioError> {
-        // Mock FLAC compression - in real implementation, use flac-sys
-        let compressed_size = (data.len() as f64 * 0.6) as usize;
-        Ok(vec![0u8; compressed_size.max(1)])
+    fn compress(&self, data: &[f32], format_info: &AudioFormatInfo) -> Result<Vec<u8>, AudioError> {
+        let channels = format_info.channels.max(1);
+        let usable = data.len() / channels as usize * channels as usize;
+        let bits = Self::bits_per_sample(format_info);
+        let encoded = flac::encode_f32(&data[..usable], format_info.sample_rate, channels, bits)?;
+        flac::verify(&encoded, &flac::quantize(&data[..usable], bits))?;
+        Ok(encoded)
     }
-    
+
     fn decompress(&self, data: &[u8], _format_info: &AudioFormatInfo) -> Result<Vec<f32>, AudioError> {
-        // Mock FLAC decompression
-        let sample_count = (data.len() as f64 / 0.6) as usize / 4;
-        Ok(vec![0.0f32; sample_count])
+        Ok(flac::decode(data)?.to_f32())
     }
-    
+
     fn get_compression_ratio(&self) -> f64 { 0.6 }
     fn get_name(&self) -> &str { "FLAC" }
 }
 
-struct OpusCompressor;
+/// Ogg/Opus compressor for long speech archives
+struct OpusCompressor {
+    level: CompressionLevel,
+}
 impl OpusCompressor {
-    fn new() -> Self { Self }
+    fn new(level: CompressionLevel) -> Self { Self { level } }
 }
 
 impl AudioCompressor for OpusCompressor {
-    fn compress(&self, data: &[f32], _format_info: &AudioFormatInfo) -> Result<Vec<u8>, AudioError> {
-        // Mock Opus compression - in real implementation, use opus-sys
-        let compressed_size = (data.len() as f64 * 0.3) as usize;
-        Ok(vec![0u8; compressed_size.max(1)])
+    fn compress(&self, data: &[f32], format_info: &AudioFormatInfo) -> Result<Vec<u8>, AudioError> {
+        let channels = format_info.channels.max(1);
+        let usable = data.len() / channels as usize * channels as usize;
+        ogg_opus::encode(&data[..usable], format_info.sample_rate, channels, &self.level)
     }
     
-    fn decompress(&self, data: &[u8], _format_info: &AudioFormatInfo) -> Result<Vec<f32>, AudioError> {
-        // Mock Opus decompression
-        let sample_count = (data.len() as f64 / 0.3) as usize / 4;
-        Ok(vec![0.0f32; sample_count])
+    fn decompress(&self, data: &[u8], format_info: &AudioFormatInfo) -> Result<Vec<f32>, AudioError> {
+        let audio = ogg_opus::decode(data)?;
+        if audio.sample_rate != format_info.sample_rate && format_info.sample_rate > 0 {
+            return Ok(ogg_opus::resample(&audio.samples, audio.channels, audio.sample_rate, format_info.sample_rate));
+        }
+        Ok(audio.samples)
     }
     
-    fn get_compression_ratio(&self) -> f64 { 0.3 }
+    fn get_compression_ratio(&self) -> f64 {
+        // 32-bit float PCM at 16 kHz is 512 kbps per channel
+        ogg_opus::bitrate_for_level(&self.level, 1) as f64 / 512_000.0
+    }
     fn get_name(&self) -> &str { "Opus" }
 }
 
+// Mock compressor implementations
 struct Mp3Compressor;
 impl Mp3Compressor {
     fn new() -> Self { Self }
@@ -915,6 +1262,20 @@ impl CompressionStats {
     }
 }
 
+/// File extension for audio archived in `format`
+fn archive_extension(format: &AudioFormat) -> &'static str {
+    match format {
+        AudioFormat::WAV => "wav",
+        AudioFormat::FLAC => "flac",
+        AudioFormat::Opus => "opus",
+        AudioFormat::MP3 => "mp3",
+    }
+}
+
+fn default_compression_level() -> CompressionLevel {
+    CompressionLevel::Medium
+}
+
 // Default implementations
 impl Default for StorageConfig {
     fn default() -> Self {
@@ -922,6 +1283,9 @@ impl Default for StorageConfig {
             max_file_size_mb: 100,
             auto_compress: true,
             preferred_format: AudioFormat::FLAC,
+            compression_level: default_compression_level(),
+            organization: FileOrganization::default(),
+            naming: FileNaming::default(),
             enable_checksums: true,
             index_update_interval: Duration::from_secs(60),
             backup_config: None,
@@ -985,6 +1349,7 @@ mod tests {
             transcript_count: 0,
             checksum: None,
             compression_info: None,
+            managed: false,
         };
         
         assert!(index.add_session(metadata.clone()).is_ok());
@@ -992,4 +1357,112 @@ mod tests {
         assert!(index.name_index.contains_key("test session"));
         assert!(index.tag_index.contains_key("test"));
     }
+
+    #[test]
+    fn test_reorganize_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = StorageConfig {
+            auto_compress: false,
+            ..StorageConfig::default()
+        };
+        let mut storage = FileAudioStorage::new(temp_dir.path().to_path_buf(), config).unwrap();
+        let session = |name: &str| RecordingSession {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: None,
+            start_time: "2024-05-07T09:30:00Z".parse().unwrap(),
+            end_time: None,
+            duration: Duration::from_secs(1),
+            file_path: PathBuf::new(),
+            file_size: 0,
+            format_info: AudioFormatInfo {
+                sample_rate: 16000,
+                channels: 1,
+                bit_depth: 16,
+                format: AudioFormat::WAV,
+            },
+            tags: vec!["standup".to_string()],
+            transcript_count: 0,
+            metadata: HashMap::new(),
+        };
+        let first = session("Daily sync");
+        let second = session("Daily sync");
+        storage.store_audio(&first, &[0.1; 160]).unwrap();
+        storage.store_audio(&second, &[0.2; 160]).unwrap();
+        assert!(temp_dir.path().join(format!("2024/05/07/{}.wav", first.id)).exists());
+
+        // The session manager's files stay in their session directory
+        let managed = AudioRecordingSession::for_test("Daily sync").with_file_path(temp_dir.path().join("sessions/daily/raw_audio.wav"));
+        fs::create_dir_all(managed.file_path.parent().unwrap()).unwrap();
+        let spec = hound::WavSpec { channels: 1, sample_rate: 8000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut wav = hound::WavWriter::create(&managed.file_path, spec).unwrap();
+        for i in 0..800 {
+            wav.write_sample((i * 37 % 2000) as i16).unwrap();
+        }
+        wav.finalize().unwrap();
+        storage.index_saved_session(&managed).unwrap();
+
+        let result = storage.reorganize(
+            FileOrganization::Custom("{date:%Y}/{tag}".to_string()),
+            FileNaming::Custom("{name}".to_string()),
+        ).unwrap();
+        assert_eq!(result.sessions_moved, 2);
+        assert_eq!(result.sessions_skipped, 1);
+        assert_eq!(result.collisions, 1);
+        assert!(temp_dir.path().join("2024/standup/Daily_sync.wav").exists());
+        assert!(temp_dir.path().join("2024/standup/Daily_sync-2.wav").exists());
+        assert!(!temp_dir.path().join("2024/05").exists());
+        assert!(managed.file_path.exists());
+
+        // The rewritten index survives a reload and still finds the audio
+        let reloaded = FileAudioStorage::new(temp_dir.path().to_path_buf(), StorageConfig::default()).unwrap();
+        assert_eq!(reloaded.retrieve_audio(second.id).unwrap(), vec![0.2; 160]);
+
+        // ...and are converted to FLAC where they are
+        let compressed = storage.compress_audio_files().unwrap();
+        assert_eq!(compressed.files_compressed, 3);
+        let flac_path = managed.file_path.with_extension("flac");
+        assert!(!managed.file_path.exists());
+        assert_eq!(flac::read_file(&flac_path).unwrap().samples.len(), 800);
+        assert!(storage.indexed_paths().contains(&flac_path));
+        assert_eq!(storage.compress_audio_files().unwrap().files_compressed, 0);
+    }
+
+    #[test]
+    fn test_compress_skips_missing_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = StorageConfig { auto_compress: false, ..StorageConfig::default() };
+        let mut storage = FileAudioStorage::new(temp_dir.path().to_path_buf(), config).unwrap();
+        let session = |name: &str| RecordingSession {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: None,
+            start_time: Utc::now(),
+            end_time: None,
+            duration: Duration::from_secs(1),
+            file_path: PathBuf::new(),
+            file_size: 0,
+            format_info: AudioFormatInfo { sample_rate: 16000, channels: 1, bit_depth: 16, format: AudioFormat::WAV },
+            tags: Vec::new(),
+            transcript_count: 0,
+            metadata: HashMap::new(),
+        };
+        let kept = session("kept");
+        let deleted = session("deleted");
+        storage.store_audio(&kept, &[0.25; 160]).unwrap();
+        storage.store_audio(&deleted, &[0.25; 160]).unwrap();
+        let deleted_path = storage.session_index.sessions[&deleted.id].file_path.clone();
+        fs::remove_file(&deleted_path).unwrap();
+
+        let result = storage.compress_audio_files().unwrap();
+        assert_eq!(result.files_compressed, 1);
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(result.skipped[0].0, deleted_path);
+
+        let reloaded = FileAudioStorage::new(temp_dir.path().to_path_buf(), StorageConfig::default()).unwrap();
+        let compressed = &reloaded.session_index.sessions[&kept.id];
+        assert_eq!(compressed.file_path.extension().unwrap(), "flac");
+        assert!(compressed.file_path.exists());
+        assert_eq!(reloaded.retrieve_audio(kept.id).unwrap(), vec![0.25; 160]);
+    }
 }
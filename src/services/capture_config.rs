@@ -0,0 +1,385 @@
+//! Capture stream parameters and negotiation against device capabilities.
+//!
+//! Users (config file or voice commands) ask for a sample rate, channel count,
+//! buffer size and sample format. Devices only support some combinations, so
+//! the requested parameters are matched against the device's supported
+//! configuration ranges and the closest workable configuration is chosen.
+//! Every deviation from the request is recorded so callers can report it.
+//!
+//! This module also holds the pre-roll buffer: a short ring of the most recent
+//! processed mono audio that lives outside the cpal stream, so it survives
+//! stream restarts caused by parameter changes.
+
+use cpal::{SampleFormat, SupportedBufferSize, SupportedStreamConfig, SupportedStreamConfigRange};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Sample formats the capture path can convert from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureSampleFormat {
+    F32,
+    I16,
+    I32,
+    U16,
+}
+
+impl CaptureSampleFormat {
+    /// Parse a format name such as "f32" or "i16" (case-insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "f32" | "float" | "float32" => Some(Self::F32),
+            "i16" | "s16" | "int16" => Some(Self::I16),
+            "i32" | "s32" | "int32" => Some(Self::I32),
+            "u16" | "uint16" => Some(Self::U16),
+            _ => None,
+        }
+    }
+
+    /// Convert from a cpal sample format, if the capture path supports it
+    pub fn from_cpal(format: SampleFormat) -> Option<Self> {
+        match format {
+            SampleFormat::F32 => Some(Self::F32),
+            SampleFormat::I16 => Some(Self::I16),
+            SampleFormat::I32 => Some(Self::I32),
+            SampleFormat::U16 => Some(Self::U16),
+            _ => None,
+        }
+    }
+
+    /// Convert to the cpal sample format
+    pub fn to_cpal(self) -> SampleFormat {
+        match self {
+            Self::F32 => SampleFormat::F32,
+            Self::I16 => SampleFormat::I16,
+            Self::I32 => SampleFormat::I32,
+            Self::U16 => SampleFormat::U16,
+        }
+    }
+}
+
+/// Requested capture stream parameters. `None` means "use the device default".
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CaptureConfig {
+    /// Requested sample rate in Hz
+    pub sample_rate: Option<u32>,
+    /// Requested number of input channels
+    pub channels: Option<u16>,
+    /// Requested buffer size in frames
+    pub buffer_size: Option<u32>,
+    /// Requested sample format
+    pub sample_format: Option<CaptureSampleFormat>,
+}
+
+impl CaptureConfig {
+    /// True when nothing is requested and the device default should be used as-is
+    pub fn is_default(&self) -> bool {
+        self.sample_rate.is_none()
+            && self.channels.is_none()
+            && self.buffer_size.is_none()
+            && self.sample_format.is_none()
+    }
+}
+
+impl From<&crate::core::config::AudioConfig> for CaptureConfig {
+    fn from(config: &crate::core::config::AudioConfig) -> Self {
+        Self {
+            sample_rate: Some(config.sample_rate).filter(|r| *r > 0),
+            channels: Some(config.channels).filter(|c| *c > 0),
+            buffer_size: Some(config.buffer_size).filter(|b| *b > 0),
+            sample_format: CaptureSampleFormat::parse(&config.sample_format),
+        }
+    }
+}
+
+impl From<&crate::core::types::AudioConfig> for CaptureConfig {
+    fn from(config: &crate::core::types::AudioConfig) -> Self {
+        Self {
+            sample_rate: Some(config.sample_rate).filter(|r| *r > 0),
+            channels: Some(config.channels).filter(|c| *c > 0),
+            buffer_size: Some(config.buffer_size as u32).filter(|b| *b > 0),
+            sample_format: None,
+        }
+    }
+}
+
+/// Stream parameters actually in use after negotiation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NegotiatedConfig {
+    /// Sample rate the stream runs at
+    pub sample_rate: u32,
+    /// Channel count the stream runs with
+    pub channels: u16,
+    /// Fixed buffer size in frames, or `None` for the driver default
+    pub buffer_size: Option<u32>,
+    /// Sample format delivered by the device
+    pub sample_format: CaptureSampleFormat,
+    /// Human-readable notes for every requested parameter that could not be honoured
+    pub fallbacks: Vec<String>,
+}
+
+impl NegotiatedConfig {
+    /// cpal stream configuration for this negotiation result
+    pub fn stream_config(&self) -> cpal::StreamConfig {
+        cpal::StreamConfig {
+            channels: self.channels,
+            sample_rate: cpal::SampleRate(self.sample_rate),
+            buffer_size: match self.buffer_size {
+                Some(frames) => cpal::BufferSize::Fixed(frames),
+                None => cpal::BufferSize::Default,
+            },
+        }
+    }
+
+    /// Describe the negotiated configuration from a device's default config
+    pub fn from_default(default: &SupportedStreamConfig) -> Option<Self> {
+        Some(Self {
+            sample_rate: default.sample_rate().0,
+            channels: default.channels(),
+            buffer_size: None,
+            sample_format: CaptureSampleFormat::from_cpal(default.sample_format())?,
+            fallbacks: Vec::new(),
+        })
+    }
+}
+
+/// Pick the supported configuration closest to `requested`.
+///
+/// Ranges are scored by how many requested parameters they can satisfy
+/// (sample rate weighs most, then channels, then format); ties prefer the
+/// device default's channel count and format. Returns `None` when no range
+/// uses a sample format the capture path understands.
+pub fn negotiate(
+    ranges: &[SupportedStreamConfigRange],
+    default: &SupportedStreamConfig,
+    requested: &CaptureConfig,
+) -> Option<NegotiatedConfig> {
+    let default_rate = default.sample_rate().0;
+    let wanted_rate = requested.sample_rate.unwrap_or(default_rate);
+    let wanted_channels = requested.channels.unwrap_or(default.channels());
+    let wanted_format = requested
+        .sample_format
+        .map(CaptureSampleFormat::to_cpal)
+        .unwrap_or(default.sample_format());
+
+    let best = ranges
+        .iter()
+        .filter(|r| CaptureSampleFormat::from_cpal(r.sample_format()).is_some())
+        .max_by_key(|r| {
+            let rate_ok = (r.min_sample_rate().0..=r.max_sample_rate().0).contains(&wanted_rate);
+            let mut score = 0u32;
+            if rate_ok { score += 8; }
+            if r.channels() == wanted_channels { score += 4; }
+            if r.sample_format() == wanted_format { score += 2; }
+            if r.channels() == default.channels() && r.sample_format() == default.sample_format() { score += 1; }
+            score
+        })?;
+
+    let mut fallbacks = Vec::new();
+
+    let sample_rate = if (best.min_sample_rate().0..=best.max_sample_rate().0).contains(&wanted_rate) {
+        wanted_rate
+    } else {
+        // Prefer the device default when it is usable, otherwise the nearest supported bound
+        let fallback = if (best.min_sample_rate().0..=best.max_sample_rate().0).contains(&default_rate) {
+            default_rate
+        } else {
+            wanted_rate.clamp(best.min_sample_rate().0, best.max_sample_rate().0)
+        };
+        fallbacks.push(format!("sample rate {wanted_rate}Hz unsupported, using {fallback}Hz"));
+        fallback
+    };
+
+    if best.channels() != wanted_channels {
+        fallbacks.push(format!("{wanted_channels} channel(s) unsupported, using {}", best.channels()));
+    }
+
+    let sample_format = CaptureSampleFormat::from_cpal(best.sample_format())?;
+    if let Some(format) = requested.sample_format {
+        if format != sample_format {
+            fallbacks.push(format!("sample format {format:?} unsupported, using {sample_format:?}"));
+        }
+    }
+
+    let buffer_size = match (requested.buffer_size, best.buffer_size()) {
+        (Some(frames), SupportedBufferSize::Range { min, max }) => {
+            let clamped = frames.clamp(*min, *max);
+            if clamped != frames {
+                fallbacks.push(format!("buffer size {frames} out of range, using {clamped}"));
+            }
+            Some(clamped)
+        }
+        (Some(frames), SupportedBufferSize::Unknown) => {
+            fallbacks.push(format!("buffer size {frames} not adjustable on this device, using driver default"));
+            None
+        }
+        (None, _) => None,
+    };
+
+    Some(NegotiatedConfig {
+        sample_rate,
+        channels: best.channels(),
+        buffer_size,
+        sample_format,
+        fallbacks,
+    })
+}
+
+/// Ring buffer of the most recent processed mono audio.
+///
+/// Contents are kept at a single sample rate; when frames arrive at a new rate
+/// (after a stream restart) the buffered audio is resampled instead of dropped.
+#[derive(Debug, Clone)]
+pub struct PreRollBuffer {
+    samples: VecDeque<f32>,
+    sample_rate: u32,
+    duration_ms: u32,
+}
+
+impl PreRollBuffer {
+    /// Create a pre-roll buffer holding `duration_ms` of audio
+    pub fn new(duration_ms: u32) -> Self {
+        Self { samples: VecDeque::new(), sample_rate: 0, duration_ms }
+    }
+
+    /// Configured pre-roll length in milliseconds
+    pub fn duration_ms(&self) -> u32 {
+        self.duration_ms
+    }
+
+    /// Change the pre-roll length, trimming older audio if needed
+    pub fn set_duration_ms(&mut self, duration_ms: u32) {
+        self.duration_ms = duration_ms;
+        self.trim();
+    }
+
+    /// Append a frame of mono samples captured at `sample_rate`
+    pub fn push(&mut self, frame: &[f32], sample_rate: u32) {
+        if sample_rate == 0 || self.duration_ms == 0 {
+            return;
+        }
+        if self.sample_rate != sample_rate {
+            if self.sample_rate != 0 && !self.samples.is_empty() {
+                let existing: Vec<f32> = self.samples.drain(..).collect();
+                self.samples.extend(resample_linear(&existing, self.sample_rate, sample_rate));
+            }
+            self.sample_rate = sample_rate;
+        }
+        self.samples.extend(frame.iter().copied());
+        self.trim();
+    }
+
+    /// Snapshot of the buffered audio and its sample rate
+    pub fn snapshot(&self) -> (Vec<f32>, u32) {
+        (self.samples.iter().copied().collect(), self.sample_rate)
+    }
+
+    /// Discard all buffered audio
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    fn capacity(&self) -> usize {
+        (self.sample_rate as u64 * self.duration_ms as u64 / 1000) as usize
+    }
+
+    fn trim(&mut self) {
+        let capacity = self.capacity();
+        if self.samples.len() > capacity {
+            let excess = self.samples.len() - capacity;
+            self.samples.drain(..excess);
+        }
+    }
+}
+
+/// Linear-interpolation resampler for mono audio
+pub fn resample_linear(input: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if input.is_empty() || from_rate == 0 || to_rate == 0 || from_rate == to_rate {
+        return input.to_vec();
+    }
+    let ratio = to_rate as f64 / from_rate as f64;
+    let out_len = ((input.len() as f64) * ratio).round().max(1.0) as usize;
+    let mut output = Vec::with_capacity(out_len);
+    for i in 0..out_len {
+        let src_pos = i as f64 / ratio;
+        let idx = src_pos.floor() as usize;
+        let frac = (src_pos - idx as f64) as f32;
+        let a = input[idx.min(input.len() - 1)];
+        let b = input[(idx + 1).min(input.len() - 1)];
+        output.push(a + (b - a) * frac);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cpal::SampleRate;
+
+    fn range(channels: u16, min: u32, max: u32, format: SampleFormat) -> SupportedStreamConfigRange {
+        SupportedStreamConfigRange::new(
+            channels,
+            SampleRate(min),
+            SampleRate(max),
+            SupportedBufferSize::Range { min: 64, max: 4096 },
+            format,
+        )
+    }
+
+    fn default_config() -> SupportedStreamConfig {
+        SupportedStreamConfig::new(
+            2,
+            SampleRate(48000),
+            SupportedBufferSize::Range { min: 64, max: 4096 },
+            SampleFormat::F32,
+        )
+    }
+
+    #[test]
+    fn test_negotiate_honours_supported_request() {
+        let ranges = vec![
+            range(2, 8000, 48000, SampleFormat::F32),
+            range(1, 8000, 48000, SampleFormat::I16),
+        ];
+        let requested = CaptureConfig {
+            sample_rate: Some(16000),
+            channels: Some(1),
+            buffer_size: Some(512),
+            sample_format: Some(CaptureSampleFormat::I16),
+        };
+        let negotiated = negotiate(&ranges, &default_config(), &requested).unwrap();
+        assert_eq!(negotiated.sample_rate, 16000);
+        assert_eq!(negotiated.channels, 1);
+        assert_eq!(negotiated.buffer_size, Some(512));
+        assert_eq!(negotiated.sample_format, CaptureSampleFormat::I16);
+        assert!(negotiated.fallbacks.is_empty());
+    }
+
+    #[test]
+    fn test_negotiate_falls_back_gracefully() {
+        let ranges = vec![range(2, 44100, 48000, SampleFormat::F32)];
+        let requested = CaptureConfig {
+            sample_rate: Some(16000),
+            channels: Some(1),
+            buffer_size: Some(8192),
+            sample_format: None,
+        };
+        let negotiated = negotiate(&ranges, &default_config(), &requested).unwrap();
+        assert_eq!(negotiated.sample_rate, 48000);
+        assert_eq!(negotiated.channels, 2);
+        assert_eq!(negotiated.buffer_size, Some(4096));
+        assert_eq!(negotiated.fallbacks.len(), 3);
+    }
+
+    #[test]
+    fn test_preroll_survives_rate_change() {
+        let mut preroll = PreRollBuffer::new(1000);
+        preroll.push(&vec![0.5; 16000], 16000);
+        preroll.push(&vec![0.25; 100], 48000);
+        let (samples, rate) = preroll.snapshot();
+        assert_eq!(rate, 48000);
+        assert_eq!(samples.len(), 48000);
+        assert!((samples[0] - 0.5).abs() < 1e-6);
+        assert!((samples[samples.len() - 1] - 0.25).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,395 @@
+//! Speech dataset export for model fine-tuning.
+//!
+//! Saved sessions are cut into one WAV per transcript segment and written in
+//! one of three common layouts, each with a `train` and a `validation` split:
+//!
+//! - LJSpeech: `<split>/wavs/<id>.wav` and a pipe-separated `<split>/metadata.csv`
+//! - Hugging Face audiofolder: `<split>/<id>.wav` and `<split>/metadata.jsonl`
+//! - Whisper fine-tuning: `audio/<id>.wav` and `train.jsonl` / `validation.jsonl`
+//!
+//! Splits are assigned by hashing the session or speaker, so every utterance
+//! of a session (or voice) lands on the same side and re-exports are stable.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::services::audio_archive::AudioError;
+use crate::services::audio_session_manager::{AudioRecordingSession, TranscriptSegment};
+use crate::services::capture_config::resample_linear;
+use crate::services::session_edit::{SessionAudio, REDACTED_TEXT};
+use crate::services::transcript_revisions::{self, TranscriptRevision};
+
+/// Layout of an exported dataset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetFormat {
+    LjSpeech,
+    AudioFolder,
+    WhisperJsonl,
+}
+
+/// What keeps utterances together when splitting train from validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitBy {
+    Session,
+    /// Segments without a speaker label are split by session
+    Speaker,
+}
+
+/// Which side of the split an utterance is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetSplit {
+    Train,
+    Validation,
+}
+
+impl DatasetSplit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Train => "train",
+            Self::Validation => "validation",
+        }
+    }
+}
+
+/// Options for a dataset export
+#[derive(Debug, Clone)]
+pub struct DatasetOptions {
+    pub format: DatasetFormat,
+    /// Segments below this confidence are left out
+    pub min_confidence: f32,
+    /// Use manually corrected text where a segment has been corrected
+    pub prefer_corrections: bool,
+    /// Fraction of sessions or speakers held out for validation
+    pub validation_fraction: f32,
+    pub split_by: SplitBy,
+    /// Resample utterances to this rate; `None` keeps the session rate
+    pub sample_rate: Option<u32>,
+}
+
+impl Default for DatasetOptions {
+    fn default() -> Self {
+        Self {
+            format: DatasetFormat::AudioFolder,
+            min_confidence: 0.6,
+            prefer_corrections: true,
+            validation_fraction: 0.1,
+            split_by: SplitBy::Session,
+            sample_rate: Some(16000),
+        }
+    }
+}
+
+/// What an export wrote and left out
+#[derive(Debug, Clone, Default)]
+pub struct DatasetSummary {
+    pub train: usize,
+    pub validation: usize,
+    pub corrected: usize,
+    pub skipped_low_confidence: usize,
+    pub skipped_empty: usize,
+    /// Segments blanked by redaction; their audio is silence
+    pub skipped_redacted: usize,
+    pub total_duration: Duration,
+}
+
+/// An utterance chosen for the dataset, with the text to train on
+#[derive(Debug, Clone)]
+pub struct DatasetUtterance {
+    pub id: String,
+    pub session_id: Uuid,
+    pub speaker: Option<String>,
+    pub language: Option<String>,
+    pub text: String,
+    pub start: Duration,
+    pub end: Duration,
+    pub corrected: bool,
+}
+
+/// Writes utterances of saved sessions into a dataset directory
+pub struct DatasetWriter {
+    out_dir: PathBuf,
+    options: DatasetOptions,
+    summary: DatasetSummary,
+    train: BufWriter<File>,
+    validation: BufWriter<File>,
+}
+
+impl DatasetWriter {
+    pub fn create(out_dir: &Path, options: DatasetOptions) -> Result<Self, AudioError> {
+        if !(0.0..=1.0).contains(&options.validation_fraction) {
+            return Err(AudioError::InvalidConfiguration(format!(
+                "Validation fraction must be between 0 and 1, got {}",
+                options.validation_fraction
+            )));
+        }
+        let index = |split: DatasetSplit| -> Result<BufWriter<File>, AudioError> {
+            let path = match options.format {
+                DatasetFormat::LjSpeech => out_dir.join(split.as_str()).join("metadata.csv"),
+                DatasetFormat::AudioFolder => out_dir.join(split.as_str()).join("metadata.jsonl"),
+                DatasetFormat::WhisperJsonl => out_dir.join(format!("{}.jsonl", split.as_str())),
+            };
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            Ok(BufWriter::new(File::create(path)?))
+        };
+        Ok(Self {
+            train: index(DatasetSplit::Train)?,
+            validation: index(DatasetSplit::Validation)?,
+            out_dir: out_dir.to_path_buf(),
+            options,
+            summary: DatasetSummary::default(),
+        })
+    }
+
+    /// Add the usable segments of a session; returns how many were written
+    pub fn add_session(
+        &mut self,
+        session: &AudioRecordingSession,
+        audio: &SessionAudio,
+        revisions: &[TranscriptRevision],
+    ) -> Result<usize, AudioError> {
+        let utterances = select_utterances(session, revisions, &self.options, &mut self.summary);
+        let rate = self.options.sample_rate.unwrap_or(audio.sample_rate);
+        for utterance in &utterances {
+            let samples = resample_linear(&audio.slice(utterance.start..utterance.end).mono, audio.sample_rate, rate);
+            let duration = Duration::from_secs_f64(samples.len() as f64 / rate.max(1) as f64);
+            let split = self.split_of(utterance);
+            let (wav_path, relative) = match self.options.format {
+                DatasetFormat::LjSpeech => {
+                    let relative = format!("wavs/{}.wav", utterance.id);
+                    (self.out_dir.join(split.as_str()).join(&relative), relative)
+                }
+                DatasetFormat::AudioFolder => {
+                    let relative = format!("{}.wav", utterance.id);
+                    (self.out_dir.join(split.as_str()).join(&relative), relative)
+                }
+                DatasetFormat::WhisperJsonl => {
+                    let relative = format!("audio/{}.wav", utterance.id);
+                    (self.out_dir.join(&relative), relative)
+                }
+            };
+            write_wav(&wav_path, &samples, rate)?;
+
+            let line = match self.options.format {
+                DatasetFormat::LjSpeech => {
+                    let text = utterance.text.replace(['|', '\n', '\r'], " ");
+                    format!("{}|{}|{}", utterance.id, text, text)
+                }
+                DatasetFormat::AudioFolder => json!({
+                    "file_name": relative,
+                    "transcription": utterance.text,
+                    "speaker_id": utterance.speaker,
+                    "session_id": utterance.session_id,
+                    "duration": duration.as_secs_f64(),
+                })
+                .to_string(),
+                DatasetFormat::WhisperJsonl => json!({
+                    "audio": { "path": relative },
+                    "sentence": utterance.text,
+                    "language": utterance.language,
+                    "duration": duration.as_secs_f64(),
+                })
+                .to_string(),
+            };
+            let index = match split {
+                DatasetSplit::Train => {
+                    self.summary.train += 1;
+                    &mut self.train
+                }
+                DatasetSplit::Validation => {
+                    self.summary.validation += 1;
+                    &mut self.validation
+                }
+            };
+            writeln!(index, "{}", line)?;
+            self.summary.total_duration += duration;
+        }
+        Ok(utterances.len())
+    }
+
+    pub fn finish(mut self) -> Result<DatasetSummary, AudioError> {
+        self.train.flush()?;
+        self.validation.flush()?;
+        Ok(self.summary)
+    }
+
+    fn split_of(&self, utterance: &DatasetUtterance) -> DatasetSplit {
+        let session_key = utterance.session_id.to_string();
+        let key = match (self.options.split_by, &utterance.speaker) {
+            (SplitBy::Speaker, Some(speaker)) => speaker.as_str(),
+            _ => session_key.as_str(),
+        };
+        split_for_key(key, self.options.validation_fraction)
+    }
+}
+
+/// Stable split for a session ID or speaker label
+pub fn split_for_key(key: &str, validation_fraction: f32) -> DatasetSplit {
+    let digest = ring::digest::digest(&ring::digest::SHA256, key.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest.as_ref()[..8]);
+    let position = u64::from_be_bytes(bytes) as f64 / u64::MAX as f64;
+    if position < validation_fraction as f64 {
+        DatasetSplit::Validation
+    } else {
+        DatasetSplit::Train
+    }
+}
+
+/// Segments of a session worth training on, with corrected text where wanted
+pub fn select_utterances(
+    session: &AudioRecordingSession,
+    revisions: &[TranscriptRevision],
+    options: &DatasetOptions,
+    summary: &mut DatasetSummary,
+) -> Vec<DatasetUtterance> {
+    let corrections = revisions.iter().rev().find(|revision| revision.is_manual()).filter(|_| options.prefer_corrections);
+    let model_revision = transcript_revisions::preferred_revision(
+        session,
+        &revisions.iter().filter(|revision| !revision.is_manual()).cloned().collect::<Vec<_>>(),
+    )
+    .map(|revision| revision.segments.clone());
+    let segments = model_revision.as_deref().unwrap_or(&session.transcript_segments);
+    let find = |segments: &[TranscriptSegment], id: Uuid| segments.iter().find(|segment| segment.id == id).cloned();
+
+    let prefix = session.id.simple().to_string();
+    let mut utterances = Vec::new();
+    for (i, live) in session.transcript_segments.iter().enumerate() {
+        // Corrected segments are stored with full confidence
+        let corrected = corrections
+            .and_then(|revision| find(&revision.segments, live.id))
+            .filter(|segment| segment.confidence >= 1.0);
+        let segment = corrected.clone().or_else(|| find(segments, live.id)).unwrap_or_else(|| live.clone());
+        if live.text == REDACTED_TEXT || segment.text == REDACTED_TEXT {
+            summary.skipped_redacted += 1;
+            continue;
+        }
+        let text = segment.text.trim();
+        if text.is_empty() {
+            summary.skipped_empty += 1;
+            continue;
+        }
+        if segment.confidence < options.min_confidence {
+            summary.skipped_low_confidence += 1;
+            continue;
+        }
+        if corrected.is_some() {
+            summary.corrected += 1;
+        }
+        utterances.push(DatasetUtterance {
+            id: format!("{}-{:04}", &prefix[..8], i + 1),
+            session_id: session.id,
+            speaker: live.speaker_id.clone(),
+            language: segment.language.clone().or_else(|| live.language.clone()),
+            text: text.to_string(),
+            start: live.start_time,
+            end: live.end_time,
+            corrected: corrected.is_some(),
+        });
+    }
+    utterances
+}
+
+fn write_wav(path: &Path, samples: &[f32], sample_rate: u32) -> Result<(), AudioError> {
+    let wav_error = |e: hound::Error| AudioError::StorageError(format!("Failed to write {}: {}", path.display(), e));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec).map_err(wav_error)?;
+    for &sample in samples {
+        writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).map_err(wav_error)?;
+    }
+    writer.finalize().map_err(wav_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn segment(start: u64, text: &str, confidence: f32, speaker: &str) -> TranscriptSegment {
+        TranscriptSegment::for_test(start as f64, start as f64 + 1.0, text)
+            .with_confidence(confidence)
+            .with_speaker(speaker)
+            .with_language("en")
+    }
+
+    fn session(segments: Vec<TranscriptSegment>) -> AudioRecordingSession {
+        AudioRecordingSession::for_test("Dictation").with_duration(Duration::from_secs(3)).with_segments(segments)
+    }
+
+    #[test]
+    fn test_selection_prefers_corrections_and_filters_confidence() {
+        let session = session(vec![
+            segment(0, "the quick brown fox", 0.9, "Speaker 1"),
+            segment(1, "jumps over the lazy dock", 0.7, "Speaker 1"),
+            segment(2, "mumble", 0.2, "Speaker 2"),
+            segment(3, "  ", 0.9, "Speaker 2"),
+            segment(4, REDACTED_TEXT, 0.9, "Speaker 1"),
+        ]);
+        let corrected = transcript_revisions::correct_segment(
+            &session, &[], session.transcript_segments[1].id, "jumps over the lazy dog",
+        )
+        .unwrap();
+
+        let mut summary = DatasetSummary::default();
+        let utterances = select_utterances(&session, std::slice::from_ref(&corrected), &DatasetOptions::default(), &mut summary);
+        assert_eq!(utterances.len(), 2);
+        assert_eq!(utterances[1].text, "jumps over the lazy dog");
+        assert!(utterances[1].corrected);
+        assert_eq!((summary.corrected, summary.skipped_low_confidence, summary.skipped_empty), (1, 1, 1));
+        assert_eq!(summary.skipped_redacted, 1);
+
+        let options = DatasetOptions { prefer_corrections: false, ..DatasetOptions::default() };
+        let utterances = select_utterances(&session, &[corrected], &options, &mut DatasetSummary::default());
+        assert_eq!(utterances[1].text, "jumps over the lazy dock");
+    }
+
+    #[test]
+    fn test_split_is_stable_and_respects_fraction() {
+        assert_eq!(split_for_key("Speaker 1", 0.5), split_for_key("Speaker 1", 0.5));
+        assert_eq!(split_for_key("Speaker 1", 0.0), DatasetSplit::Train);
+        assert_eq!(split_for_key("Speaker 1", 1.0), DatasetSplit::Validation);
+
+        let held_out = (0..1000).filter(|i| split_for_key(&i.to_string(), 0.2) == DatasetSplit::Validation).count();
+        assert!((150..250).contains(&held_out), "{}", held_out);
+    }
+
+    #[test]
+    fn test_ljspeech_export_writes_resampled_wavs_and_metadata() {
+        let dir = TempDir::new().unwrap();
+        let session = session(vec![segment(0, "hello | world", 0.9, "Speaker 1"), segment(1, "second line", 0.9, "Speaker 1")]);
+        let audio = SessionAudio { mono: vec![0.25; 16000], stereo: Vec::new(), sample_rate: 8000 };
+        let options = DatasetOptions {
+            format: DatasetFormat::LjSpeech,
+            validation_fraction: 0.0,
+            ..DatasetOptions::default()
+        };
+
+        let mut writer = DatasetWriter::create(dir.path(), options).unwrap();
+        assert_eq!(writer.add_session(&session, &audio, &[]).unwrap(), 2);
+        let summary = writer.finish().unwrap();
+        assert_eq!((summary.train, summary.validation), (2, 0));
+        assert_eq!(summary.total_duration, Duration::from_secs(2));
+
+        let metadata = fs::read_to_string(dir.path().join("train/metadata.csv")).unwrap();
+        let first = metadata.lines().next().unwrap();
+        let id = first.split('|').next().unwrap();
+        assert!(first.ends_with("|hello   world|hello   world"), "{}", first);
+        let reader = hound::WavReader::open(dir.path().join("train/wavs").join(format!("{}.wav", id))).unwrap();
+        assert_eq!(reader.spec().sample_rate, 16000);
+        assert_eq!(reader.len(), 16000);
+    }
+}
@@ -0,0 +1,149 @@
+//! Input device hot-plug tracking.
+//!
+//! cpal exposes devices only by display name, which is not unique (two
+//! identical USB headsets) and says nothing about the host backend. Devices
+//! are therefore identified by a stable ID built from the host name, the
+//! display name and an occurrence index for duplicates. The [`DeviceWatcher`]
+//! diffs successive device listings so the audio service can notice devices
+//! disappearing and coming back, and [`DeviceEvent`] describes what happened
+//! in a form the voice command layer and TTS can announce.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Device change notifications emitted by the audio service
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DeviceEvent {
+    /// A new input device appeared
+    DeviceAdded { device_id: String },
+    /// An input device disappeared
+    DeviceRemoved { device_id: String },
+    /// The active capture stream failed (device unplugged or driver error)
+    Disconnected { device_id: Option<String>, reason: String },
+    /// Capture moved to another device because the preferred one is unavailable
+    FailedOver { from: Option<String>, to: String },
+    /// Capture returned to the preferred device
+    Reconnected { device_id: String },
+    /// No input device is available; capture resumes when one appears
+    CaptureLost { reason: String },
+}
+
+impl DeviceEvent {
+    /// Short sentence suitable for speaking to the user
+    pub fn announcement(&self) -> String {
+        match self {
+            DeviceEvent::DeviceAdded { device_id } => format!("Audio device connected: {}", display_name(device_id)),
+            DeviceEvent::DeviceRemoved { device_id } => format!("Audio device removed: {}", display_name(device_id)),
+            DeviceEvent::Disconnected { device_id: Some(id), .. } => format!("Lost connection to {}", display_name(id)),
+            DeviceEvent::Disconnected { device_id: None, .. } => "Lost connection to the microphone".to_string(),
+            DeviceEvent::FailedOver { to, .. } => format!("Switched to {}", display_name(to)),
+            DeviceEvent::Reconnected { device_id } => format!("Reconnected to {}", display_name(device_id)),
+            DeviceEvent::CaptureLost { .. } => "No microphone available, waiting for a device".to_string(),
+        }
+    }
+
+    /// Whether the event changed which device is being captured from
+    pub fn affects_capture(&self) -> bool {
+        matches!(
+            self,
+            DeviceEvent::Disconnected { .. }
+                | DeviceEvent::FailedOver { .. }
+                | DeviceEvent::Reconnected { .. }
+                | DeviceEvent::CaptureLost { .. }
+        )
+    }
+}
+
+/// Build stable IDs for a list of device names, in enumeration order.
+///
+/// IDs look like `ALSA:USB Headset`; a second device with the same name on
+/// the same host becomes `ALSA:USB Headset#2`.
+pub fn stable_device_ids(host: &str, names: &[String]) -> Vec<String> {
+    let mut ids = Vec::with_capacity(names.len());
+    for (i, name) in names.iter().enumerate() {
+        let occurrence = names[..i].iter().filter(|n| *n == name).count();
+        if occurrence == 0 {
+            ids.push(format!("{host}:{name}"));
+        } else {
+            ids.push(format!("{host}:{name}#{}", occurrence + 1));
+        }
+    }
+    ids
+}
+
+/// Human-readable device name from a stable ID
+pub fn display_name(device_id: &str) -> &str {
+    let name = device_id.split_once(':').map(|(_, n)| n).unwrap_or(device_id);
+    match name.rsplit_once('#') {
+        Some((base, n)) if n.parse::<u32>().is_ok() => base,
+        _ => name,
+    }
+}
+
+/// Whether a stored device preference (stable ID, or a legacy display name)
+/// refers to the given device
+pub fn matches_preference(preference: &str, device_id: &str, device_name: &str) -> bool {
+    preference == device_id || preference == device_name
+}
+
+/// Tracks the set of known input devices between scans
+#[derive(Debug, Default)]
+pub struct DeviceWatcher {
+    known: Option<HashSet<String>>,
+}
+
+impl DeviceWatcher {
+    /// Create a watcher; the first scan establishes the baseline without events
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare a fresh listing with the previous one and report changes
+    pub fn update(&mut self, current: &[String]) -> Vec<DeviceEvent> {
+        let current: HashSet<String> = current.iter().cloned().collect();
+        let mut events = Vec::new();
+        if let Some(known) = &self.known {
+            let mut added: Vec<_> = current.difference(known).cloned().collect();
+            let mut removed: Vec<_> = known.difference(&current).cloned().collect();
+            added.sort();
+            removed.sort();
+            events.extend(removed.into_iter().map(|device_id| DeviceEvent::DeviceRemoved { device_id }));
+            events.extend(added.into_iter().map(|device_id| DeviceEvent::DeviceAdded { device_id }));
+        }
+        self.known = Some(current);
+        events
+    }
+
+    /// Whether a device was present in the last scan
+    pub fn contains(&self, device_id: &str) -> bool {
+        self.known.as_ref().map(|k| k.contains(device_id)).unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_ids_disambiguate_duplicates() {
+        let names = vec!["USB Headset".to_string(), "Built-in".to_string(), "USB Headset".to_string()];
+        let ids = stable_device_ids("ALSA", &names);
+        assert_eq!(ids, vec!["ALSA:USB Headset", "ALSA:Built-in", "ALSA:USB Headset#2"]);
+        assert_eq!(display_name(&ids[2]), "USB Headset");
+        assert!(matches_preference("USB Headset", &ids[0], "USB Headset"));
+    }
+
+    #[test]
+    fn test_watcher_reports_unplug_and_replug() {
+        let mut watcher = DeviceWatcher::new();
+        let both = vec!["ALSA:Built-in".to_string(), "ALSA:USB Headset".to_string()];
+        assert!(watcher.update(&both).is_empty());
+
+        let events = watcher.update(&both[..1]);
+        assert_eq!(events, vec![DeviceEvent::DeviceRemoved { device_id: "ALSA:USB Headset".into() }]);
+        assert!(!watcher.contains("ALSA:USB Headset"));
+
+        let events = watcher.update(&both);
+        assert_eq!(events, vec![DeviceEvent::DeviceAdded { device_id: "ALSA:USB Headset".into() }]);
+    }
+}
@@ -0,0 +1,420 @@
+//! Speaker diarization.
+//!
+//! Each transcribed segment is reduced to a speaker embedding: the mean and
+//! standard deviation of its MFCCs over voiced frames (the statistics pooling
+//! used by x-vector systems, without the network). Embeddings are clustered
+//! online: a segment joins the closest speaker when the cosine similarity is
+//! above the change threshold, otherwise it starts a new speaker. The current
+//! speaker is kept unless another one is clearly closer, so short noisy
+//! segments do not flip the label back and forth.
+//!
+//! Named speakers can be enrolled from a sample of their voice; their
+//! profiles persist and are matched before anonymous "Speaker_N" clusters.
+
+use crate::services::wake_word::{MfccExtractor, MFCC_COEFFS};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Diarization configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiarizationConfig {
+    /// Label segments with speakers
+    pub enabled: bool,
+    /// Cosine similarity below which a segment starts a new speaker
+    pub change_threshold: f32,
+    /// How much closer another speaker must be before the label changes
+    pub switch_margin: f32,
+    /// Upper bound on anonymous speakers per session
+    pub max_speakers: usize,
+    /// Segments shorter than this keep the current speaker, in milliseconds
+    pub min_segment_ms: u64,
+}
+
+impl Default for DiarizationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            change_threshold: 0.95,
+            switch_margin: 0.01,
+            max_speakers: 8,
+            min_segment_ms: 500,
+        }
+    }
+}
+
+/// Fixed-length voice statistics for one segment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerEmbedding {
+    /// MFCC means followed by MFCC standard deviations
+    pub vector: Vec<f32>,
+}
+
+impl SpeakerEmbedding {
+    /// Cosine similarity in [-1, 1]
+    pub fn similarity(&self, other: &SpeakerEmbedding) -> f32 {
+        let dot: f32 = self.vector.iter().zip(&other.vector).map(|(a, b)| a * b).sum();
+        let na = self.vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        let nb = other.vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if na <= f32::EPSILON || nb <= f32::EPSILON {
+            0.0
+        } else {
+            dot / (na * nb)
+        }
+    }
+
+    /// Fold another embedding into a running mean of `weight` segments
+    fn blend(&mut self, other: &SpeakerEmbedding, weight: usize) {
+        let w = weight as f32;
+        for (v, o) in self.vector.iter_mut().zip(&other.vector) {
+            *v = (*v * w + o) / (w + 1.0);
+        }
+    }
+}
+
+/// A speaker seen in this session or enrolled by name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerCluster {
+    /// Label written into transcripts ("Speaker_2", or the enrolled name)
+    pub id: String,
+    /// Name for enrolled speakers
+    pub name: Option<String>,
+    /// Running mean of the speaker's embeddings
+    pub centroid: SpeakerEmbedding,
+    /// Segments attributed to the speaker
+    pub segments: usize,
+    /// Enrolled speakers survive `reset` and are saved with `save_profiles`
+    pub enrolled: bool,
+}
+
+/// Result of labelling one segment
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeakerAssignment {
+    /// Speaker label
+    pub speaker_id: String,
+    /// Name, for enrolled speakers
+    pub name: Option<String>,
+    /// Similarity to the speaker's centroid before the update
+    pub similarity: f32,
+    /// The segment started a new speaker
+    pub new_speaker: bool,
+}
+
+/// Centroids stop moving much after this many segments
+const MAX_CENTROID_WEIGHT: usize = 20;
+
+/// Online speaker diarizer
+#[derive(Debug)]
+pub struct SpeakerDiarizer {
+    config: DiarizationConfig,
+    extractor: Option<MfccExtractor>,
+    clusters: Vec<SpeakerCluster>,
+    current: Option<usize>,
+    next_anonymous: usize,
+}
+
+impl Default for SpeakerDiarizer {
+    fn default() -> Self {
+        Self::new(DiarizationConfig::default())
+    }
+}
+
+impl SpeakerDiarizer {
+    /// Create a diarizer
+    pub fn new(config: DiarizationConfig) -> Self {
+        Self {
+            config,
+            extractor: None,
+            clusters: Vec::new(),
+            current: None,
+            next_anonymous: 1,
+        }
+    }
+
+    /// Current configuration
+    pub fn config(&self) -> &DiarizationConfig {
+        &self.config
+    }
+
+    /// Replace the configuration
+    pub fn set_config(&mut self, config: DiarizationConfig) {
+        self.config = config;
+    }
+
+    /// Whether segments should be labelled
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Known speakers, enrolled first
+    pub fn speakers(&self) -> &[SpeakerCluster] {
+        &self.clusters
+    }
+
+    /// Label of the speaker of the last segment
+    pub fn current_speaker(&self) -> Option<&str> {
+        self.current.map(|i| self.clusters[i].id.as_str())
+    }
+
+    /// Forget anonymous speakers (e.g. at the start of a session), keeping enrolled ones
+    pub fn reset(&mut self) {
+        self.clusters.retain(|c| c.enrolled);
+        self.current = None;
+        self.next_anonymous = 1;
+    }
+
+    /// Compute the embedding of a segment; `None` if it has too little voiced audio
+    pub fn embed(&mut self, samples: &[f32], sample_rate: u32) -> Option<SpeakerEmbedding> {
+        if sample_rate == 0 {
+            return None;
+        }
+        if self.extractor.as_ref().map(|e| e.sample_rate()) != Some(sample_rate) {
+            self.extractor = Some(MfccExtractor::new(sample_rate));
+        }
+        let extractor = self.extractor.as_ref()?;
+        let frames = extractor.extract_raw(samples);
+
+        // Keep frames with speech energy; silence and breath say little about the voice
+        let (hop, frame_len) = (extractor.hop(), extractor.frame_len());
+        let energies: Vec<f32> = (0..frames.len())
+            .map(|i| {
+                let frame = &samples[i * hop..(i * hop + frame_len).min(samples.len())];
+                frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32
+            })
+            .collect();
+        let peak = energies.iter().cloned().fold(0.0f32, f32::max);
+        if peak <= 1e-8 {
+            return None;
+        }
+        let voiced: Vec<&Vec<f32>> = frames
+            .iter()
+            .zip(&energies)
+            .filter(|(_, e)| **e >= peak * 0.05)
+            .map(|(f, _)| f)
+            .collect();
+        let min_frames = (self.config.min_segment_ms as usize * extractor.frames_per_second() / 1000 / 2).max(10);
+        if voiced.len() < min_frames {
+            return None;
+        }
+
+        let n = voiced.len() as f32;
+        let mut mean = vec![0.0f32; MFCC_COEFFS];
+        for f in &voiced {
+            for (m, v) in mean.iter_mut().zip(f.iter()) {
+                *m += v / n;
+            }
+        }
+        let mut std = vec![0.0f32; MFCC_COEFFS];
+        for f in &voiced {
+            for ((s, v), m) in std.iter_mut().zip(f.iter()).zip(&mean) {
+                *s += (v - m) * (v - m) / n;
+            }
+        }
+        let mut vector = mean;
+        vector.extend(std.into_iter().map(f32::sqrt));
+        Some(SpeakerEmbedding { vector })
+    }
+
+    /// Attribute an embedding to a speaker, creating one if nobody matches
+    pub fn assign(&mut self, embedding: &SpeakerEmbedding) -> SpeakerAssignment {
+        let scores: Vec<f32> = self.clusters.iter().map(|c| c.centroid.similarity(embedding)).collect();
+        let best = scores
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(i, s)| (i, *s));
+
+        // Stay with the current speaker unless someone else is clearly closer
+        let sticky = self.current.filter(|&c| {
+            let score = scores[c];
+            score >= self.config.change_threshold
+                && best.map(|(_, b)| score + self.config.switch_margin >= b).unwrap_or(true)
+        });
+        let anonymous = self.clusters.iter().filter(|c| !c.enrolled).count();
+        let chosen = match (sticky, best) {
+            (Some(c), _) => Some(c),
+            (None, Some((i, s))) if s >= self.config.change_threshold => Some(i),
+            (None, Some((i, _))) if anonymous >= self.config.max_speakers => Some(i),
+            _ => None,
+        };
+
+        match chosen {
+            Some(i) => {
+                let cluster = &mut self.clusters[i];
+                if !cluster.enrolled {
+                    cluster.centroid.blend(embedding, cluster.segments.min(MAX_CENTROID_WEIGHT));
+                }
+                cluster.segments += 1;
+                self.current = Some(i);
+                SpeakerAssignment {
+                    speaker_id: cluster.id.clone(),
+                    name: cluster.name.clone(),
+                    similarity: scores[i],
+                    new_speaker: false,
+                }
+            }
+            None => {
+                let id = format!("Speaker_{}", self.next_anonymous);
+                self.next_anonymous += 1;
+                self.clusters.push(SpeakerCluster {
+                    id: id.clone(),
+                    name: None,
+                    centroid: embedding.clone(),
+                    segments: 1,
+                    enrolled: false,
+                });
+                self.current = Some(self.clusters.len() - 1);
+                SpeakerAssignment {
+                    speaker_id: id,
+                    name: None,
+                    similarity: 1.0,
+                    new_speaker: true,
+                }
+            }
+        }
+    }
+
+    /// Label a segment. Segments too short to embed keep the current speaker;
+    /// `None` only if no speaker has been heard yet.
+    pub fn identify(&mut self, samples: &[f32], sample_rate: u32) -> Option<SpeakerAssignment> {
+        if !self.config.enabled {
+            return None;
+        }
+        match self.embed(samples, sample_rate) {
+            Some(embedding) => Some(self.assign(&embedding)),
+            None => self.current.map(|i| {
+                let cluster = &self.clusters[i];
+                SpeakerAssignment {
+                    speaker_id: cluster.id.clone(),
+                    name: cluster.name.clone(),
+                    similarity: 0.0,
+                    new_speaker: false,
+                }
+            }),
+        }
+    }
+
+    /// Enroll a named speaker from a sample of their voice (a few seconds of
+    /// speech). Re-enrolling a name replaces its profile. Returns false if the
+    /// sample has too little speech.
+    pub fn enroll(&mut self, name: &str, samples: &[f32], sample_rate: u32) -> bool {
+        let name = name.trim();
+        let Some(embedding) = self.embed(samples, sample_rate).filter(|_| !name.is_empty()) else {
+            return false;
+        };
+        let current_id = self.current_speaker().map(str::to_string);
+        self.clusters.retain(|c| !(c.enrolled && c.name.as_deref() == Some(name)));
+        let profile = SpeakerCluster {
+            id: name.to_string(),
+            name: Some(name.to_string()),
+            centroid: embedding,
+            segments: 0,
+            enrolled: true,
+        };
+        let enrolled = self.clusters.iter().filter(|c| c.enrolled).count();
+        self.clusters.insert(enrolled, profile);
+        self.current = current_id.and_then(|id| self.clusters.iter().position(|c| c.id == id));
+        true
+    }
+
+    /// Remove an enrolled speaker
+    pub fn forget(&mut self, name: &str) -> bool {
+        let current_id = self.current_speaker().map(str::to_string);
+        let before = self.clusters.len();
+        self.clusters.retain(|c| !(c.enrolled && c.name.as_deref() == Some(name)));
+        self.current = current_id.and_then(|id| self.clusters.iter().position(|c| c.id == id));
+        self.clusters.len() != before
+    }
+
+    /// Save enrolled speaker profiles
+    pub fn save_profiles(&self, path: &Path) -> std::io::Result<()> {
+        let enrolled: Vec<&SpeakerCluster> = self.clusters.iter().filter(|c| c.enrolled).collect();
+        let json = serde_json::to_string_pretty(&enrolled)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, json)
+    }
+
+    /// Load enrolled speaker profiles, replacing the current ones
+    pub fn load_profiles(&mut self, path: &Path) -> std::io::Result<usize> {
+        let json = std::fs::read_to_string(path)?;
+        let mut loaded: Vec<SpeakerCluster> = serde_json::from_str(&json)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        loaded.retain(|c| c.enrolled && c.centroid.vector.len() == MFCC_COEFFS * 2);
+        let count = loaded.len();
+        self.clusters.retain(|c| !c.enrolled);
+        loaded.append(&mut self.clusters);
+        self.clusters = loaded;
+        self.current = None;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Voiced sound with a given pitch and a resonance around `formant` Hz
+    fn voice(f0: f32, formant: f32, seconds: f32, seed: u32) -> Vec<f32> {
+        let sr = 16000.0;
+        let n = (sr * seconds) as usize;
+        (0..n)
+            .map(|i| {
+                let t = i as f32 / sr;
+                let wobble = 1.0 + 0.02 * (2.0 * std::f32::consts::PI * (3.0 + seed as f32) * t).sin();
+                let mut s = 0.0;
+                let mut h = 1;
+                while f0 * h as f32 <= 4000.0 {
+                    let f = f0 * h as f32 * wobble;
+                    let gain = 1.0 / (1.0 + ((f - formant) / 300.0).powi(2));
+                    s += gain * (2.0 * std::f32::consts::PI * f * t).sin();
+                    h += 1;
+                }
+                0.2 * s
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_online_clustering_separates_speakers() {
+        let mut diarizer = SpeakerDiarizer::default();
+        let labels: Vec<String> = [
+            voice(110.0, 500.0, 1.5, 0),
+            voice(230.0, 2000.0, 1.5, 1),
+            voice(112.0, 520.0, 1.5, 2),
+            voice(225.0, 1950.0, 1.5, 3),
+        ]
+        .iter()
+        .map(|seg| diarizer.identify(seg, 16000).unwrap().speaker_id)
+        .collect();
+
+        assert_eq!(labels[0], "Speaker_1");
+        assert_eq!(labels[1], "Speaker_2");
+        assert_eq!(labels[2], labels[0]);
+        assert_eq!(labels[3], labels[1]);
+
+        // Too short to embed: keeps the current speaker
+        let short = diarizer.identify(&voice(110.0, 500.0, 0.1, 4), 16000).unwrap();
+        assert_eq!(short.speaker_id, labels[3]);
+    }
+
+    #[test]
+    fn test_enrolled_speaker_is_named_and_persisted() {
+        let mut diarizer = SpeakerDiarizer::default();
+        assert!(diarizer.enroll("Alice", &voice(230.0, 2000.0, 2.0, 0), 16000));
+        assert!(!diarizer.enroll("Bob", &[0.0; 1600], 16000));
+
+        let a = diarizer.identify(&voice(225.0, 1980.0, 1.5, 1), 16000).unwrap();
+        assert_eq!(a.speaker_id, "Alice");
+        assert_eq!(a.name.as_deref(), Some("Alice"));
+        let b = diarizer.identify(&voice(110.0, 500.0, 1.5, 2), 16000).unwrap();
+        assert_eq!(b.speaker_id, "Speaker_1");
+
+        let path = std::env::temp_dir().join(format!("speakers_{}.json", uuid::Uuid::new_v4()));
+        diarizer.save_profiles(&path).unwrap();
+        let mut restored = SpeakerDiarizer::default();
+        assert_eq!(restored.load_profiles(&path).unwrap(), 1);
+        let _ = std::fs::remove_file(&path);
+        restored.reset();
+        assert_eq!(restored.speakers().len(), 1);
+        assert_eq!(restored.identify(&voice(232.0, 2010.0, 1.5, 3), 16000).unwrap().speaker_id, "Alice");
+    }
+}
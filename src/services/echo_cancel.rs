@@ -0,0 +1,430 @@
+//! Acoustic echo cancellation for spoken feedback.
+//!
+//! Audio we play (TTS confirmations) is pushed into an [`EchoReference`] as it
+//! is handed to the output device. The capture path pulls the same amount of
+//! reference audio for every microphone frame and runs it through an adaptive
+//! NLMS filter that learns the speaker-to-microphone path; the filter output
+//! is subtracted from the microphone signal.
+//!
+//! Frames where the residual is small compared to the microphone input are
+//! almost entirely playback and are gated to silence. Frames where the user is
+//! talking (double talk, detected with a Geigel detector) pass through with
+//! the echo estimate removed and adaptation frozen, so the user can talk over
+//! or right after feedback.
+
+use crate::services::capture_config::resample_linear;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Echo canceller configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EchoCancellerConfig {
+    /// Enable echo cancellation on the capture path
+    pub enabled: bool,
+    /// Length of the adaptive filter (echo path delay plus room tail), in milliseconds
+    pub filter_length_ms: u32,
+    /// NLMS step size (0.0 to 1.0); larger adapts faster but is noisier
+    pub step_size: f32,
+    /// Geigel double-talk threshold: the microphone peak above this fraction
+    /// of the recent reference peak is treated as the user speaking
+    pub double_talk_threshold: f32,
+    /// Frames whose residual energy is below this fraction of the microphone
+    /// energy are considered pure playback and gated
+    pub gate_residual_ratio: f32,
+    /// Keep cancelling for this long after playback ends (echo tail), in milliseconds
+    pub tail_ms: u32,
+}
+
+impl Default for EchoCancellerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            filter_length_ms: 80,
+            step_size: 0.3,
+            double_talk_threshold: 0.6,
+            gate_residual_ratio: 0.25,
+            tail_ms: 300,
+        }
+    }
+}
+
+impl From<&crate::core::config::AudioConfig> for EchoCancellerConfig {
+    fn from(config: &crate::core::config::AudioConfig) -> Self {
+        Self {
+            enabled: config.echo_cancellation,
+            filter_length_ms: config.echo_filter_length_ms,
+            ..Self::default()
+        }
+    }
+}
+
+/// Echo canceller statistics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EchoStats {
+    /// Echo return loss enhancement of the last frames with playback, in dB
+    pub erle_db: f32,
+    /// Frames processed while playback was active
+    pub frames_with_reference: u64,
+    /// Frames gated because they contained only playback
+    pub gated_frames: u64,
+    /// Frames in which the user spoke over playback
+    pub double_talk_frames: u64,
+}
+
+/// Outcome for a single capture frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EchoDecision {
+    /// No playback: the frame is untouched
+    Passthrough,
+    /// Echo estimate removed; the frame still contains near-end speech
+    Cancelled,
+    /// The frame contained only playback and was silenced
+    Gated,
+}
+
+#[derive(Debug, Default)]
+struct ReferenceQueue {
+    samples: VecDeque<f32>,
+    sample_rate: u32,
+}
+
+/// Shared tap of the audio being played, fed by playback and drained by capture
+#[derive(Debug, Clone, Default)]
+pub struct EchoReference {
+    inner: Arc<Mutex<ReferenceQueue>>,
+}
+
+/// Reference audio older than this is dropped (capture stalled or not running)
+const MAX_REFERENCE_SECONDS: u32 = 2;
+
+impl EchoReference {
+    /// Create an empty reference tap
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append audio that has just been handed to the output device
+    pub fn push(&self, samples: &[f32], sample_rate: u32) {
+        if let Ok(mut q) = self.inner.lock() {
+            if q.sample_rate != sample_rate {
+                q.samples.clear();
+                q.sample_rate = sample_rate;
+            }
+            q.samples.extend(samples.iter().copied());
+            let max = (sample_rate * MAX_REFERENCE_SECONDS) as usize;
+            let excess = q.samples.len().saturating_sub(max);
+            q.samples.drain(..excess);
+        }
+    }
+
+    /// Take `len` samples of reference at `sample_rate`, zero-filled when no playback is queued
+    pub fn take(&self, len: usize, sample_rate: u32) -> Vec<f32> {
+        let Ok(mut q) = self.inner.lock() else {
+            return vec![0.0; len];
+        };
+        if q.samples.is_empty() || q.sample_rate == 0 {
+            return vec![0.0; len];
+        }
+        let wanted = if q.sample_rate == sample_rate {
+            len
+        } else {
+            ((len as u64 * q.sample_rate as u64).div_ceil(sample_rate as u64)) as usize
+        };
+        let n = wanted.min(q.samples.len());
+        let chunk: Vec<f32> = q.samples.drain(..n).collect();
+        let mut out = if q.sample_rate == sample_rate {
+            chunk
+        } else {
+            let mut padded = chunk;
+            padded.resize(wanted, 0.0);
+            resample_linear(&padded, q.sample_rate, sample_rate)
+        };
+        out.resize(len, 0.0);
+        out
+    }
+
+    /// Whether playback audio is waiting to be consumed
+    pub fn has_pending(&self) -> bool {
+        self.inner.lock().map(|q| !q.samples.is_empty()).unwrap_or(false)
+    }
+
+    /// Drop any queued reference audio
+    pub fn clear(&self) {
+        if let Ok(mut q) = self.inner.lock() {
+            q.samples.clear();
+        }
+    }
+}
+
+/// Adaptive NLMS echo canceller
+#[derive(Debug)]
+pub struct EchoCanceller {
+    config: EchoCancellerConfig,
+    sample_rate: u32,
+    weights: Vec<f32>,
+    /// Reference history, newest sample at `pos`
+    history: Vec<f32>,
+    pos: usize,
+    history_energy: f32,
+    /// Samples since the reference was last non-silent
+    samples_since_reference: usize,
+    stats: EchoStats,
+    mic_energy_smoothed: f32,
+    residual_energy_smoothed: f32,
+    /// The filter models the echo path well enough to tell echo from speech
+    converged: bool,
+}
+
+/// Reference samples below this magnitude count as silence
+const REFERENCE_FLOOR: f32 = 1e-4;
+
+impl EchoCanceller {
+    /// Create a canceller for the given capture sample rate
+    pub fn new(config: EchoCancellerConfig, sample_rate: u32) -> Self {
+        let taps = ((sample_rate as u64 * config.filter_length_ms as u64 / 1000) as usize).max(1);
+        Self {
+            config,
+            sample_rate,
+            weights: vec![0.0; taps],
+            history: vec![0.0; taps],
+            pos: 0,
+            history_energy: 0.0,
+            samples_since_reference: usize::MAX,
+            stats: EchoStats::default(),
+            mic_energy_smoothed: 0.0,
+            residual_energy_smoothed: 0.0,
+            converged: false,
+        }
+    }
+
+    /// Sample rate the filter was sized for
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Statistics since creation
+    pub fn stats(&self) -> EchoStats {
+        self.stats.clone()
+    }
+
+    /// Whether playback (or its echo tail) is currently being cancelled
+    pub fn is_active(&self) -> bool {
+        let tail = (self.sample_rate as u64 * self.config.tail_ms as u64 / 1000) as usize;
+        self.samples_since_reference <= tail
+    }
+
+    /// Remove the echo of `reference` from `mic` in place.
+    /// `reference` must be time-aligned with `mic` and of the same length.
+    pub fn process(&mut self, mic: &mut [f32], reference: &[f32]) -> EchoDecision {
+        let reference_present = reference.iter().any(|s| s.abs() > REFERENCE_FLOOR);
+        if reference_present {
+            self.samples_since_reference = 0;
+        } else {
+            self.samples_since_reference = self.samples_since_reference.saturating_add(mic.len());
+        }
+        if !self.is_active() {
+            // Keep the learned echo path, but let idle audio through untouched
+            return EchoDecision::Passthrough;
+        }
+
+        let taps = self.weights.len();
+        let reference_peak = self.history.iter().chain(reference).fold(0.0f32, |m, s| m.max(s.abs()));
+        let mic_peak = mic.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        let double_talk = mic_peak > self.config.double_talk_threshold * reference_peak.max(REFERENCE_FLOOR)
+            && self.converged;
+
+        let mut mic_energy = 0.0f32;
+        let mut residual_energy = 0.0f32;
+        for (d, x) in mic.iter_mut().zip(reference.iter().chain(std::iter::repeat(&0.0))) {
+            // Slide the reference history (ring buffer, newest at `pos`)
+            self.pos = if self.pos == 0 { taps - 1 } else { self.pos - 1 };
+            let old = self.history[self.pos];
+            self.history[self.pos] = *x;
+            self.history_energy = if self.pos == 0 {
+                // Recompute once per pass to stop rounding errors accumulating
+                self.history.iter().map(|h| h * h).sum()
+            } else {
+                (self.history_energy + x * x - old * old).max(0.0)
+            };
+
+            let (wrapped, recent) = self.history.split_at(self.pos);
+            let estimate: f32 = recent
+                .iter()
+                .chain(wrapped)
+                .zip(&self.weights)
+                .map(|(h, w)| h * w)
+                .sum();
+            let error = *d - estimate;
+
+            if !double_talk && self.history_energy > REFERENCE_FLOOR {
+                let mu = self.config.step_size * error / (self.history_energy + 1e-6);
+                let (wrapped, recent) = self.history.split_at(self.pos);
+                for (w, h) in self.weights.iter_mut().zip(recent.iter().chain(wrapped)) {
+                    *w += mu * h;
+                }
+            }
+
+            mic_energy += *d * *d;
+            residual_energy += error * error;
+            *d = error;
+        }
+
+        self.stats.frames_with_reference += 1;
+        if !double_talk {
+            // Near-end speech says nothing about how well the echo is cancelled
+            self.mic_energy_smoothed = 0.9 * self.mic_energy_smoothed + 0.1 * mic_energy;
+            self.residual_energy_smoothed = 0.9 * self.residual_energy_smoothed + 0.1 * residual_energy;
+            self.stats.erle_db = self.erle_db();
+            if self.stats.frames_with_reference > 10 && self.stats.erle_db > 6.0 {
+                self.converged = true;
+            }
+        }
+
+        if double_talk {
+            self.stats.double_talk_frames += 1;
+            EchoDecision::Cancelled
+        } else if residual_energy <= self.config.gate_residual_ratio * mic_energy || mic_energy < 1e-9 {
+            mic.iter_mut().for_each(|s| *s = 0.0);
+            self.stats.gated_frames += 1;
+            EchoDecision::Gated
+        } else {
+            EchoDecision::Cancelled
+        }
+    }
+
+    fn erle_db(&self) -> f32 {
+        10.0 * (self.mic_energy_smoothed.max(1e-12) / self.residual_energy_smoothed.max(1e-12)).log10()
+    }
+}
+
+/// Capture-path stage: pulls reference audio and runs the canceller
+#[derive(Debug)]
+pub struct EchoSuppressor {
+    config: EchoCancellerConfig,
+    reference: EchoReference,
+    canceller: Option<EchoCanceller>,
+}
+
+impl EchoSuppressor {
+    /// Create a stage bound to a reference tap
+    pub fn new(config: EchoCancellerConfig, reference: EchoReference) -> Self {
+        Self { config, reference, canceller: None }
+    }
+
+    /// Process one microphone frame in place
+    pub fn process(&mut self, mic: &mut [f32], sample_rate: u32) -> EchoDecision {
+        if !self.config.enabled {
+            return EchoDecision::Passthrough;
+        }
+        if self.canceller.as_ref().map(|c| c.sample_rate() != sample_rate).unwrap_or(true) {
+            // The echo path is re-learned after a sample rate change
+            self.canceller = Some(EchoCanceller::new(self.config.clone(), sample_rate));
+        }
+        let reference = self.reference.take(mic.len(), sample_rate);
+        match self.canceller.as_mut() {
+            Some(canceller) => canceller.process(mic, &reference),
+            None => EchoDecision::Passthrough,
+        }
+    }
+
+    /// Replace the configuration; the filter restarts from scratch
+    pub fn set_config(&mut self, config: EchoCancellerConfig) {
+        self.config = config;
+        self.canceller = None;
+    }
+
+    /// Current configuration
+    pub fn config(&self) -> &EchoCancellerConfig {
+        &self.config
+    }
+
+    /// The reference tap playback should write into
+    pub fn reference(&self) -> EchoReference {
+        self.reference.clone()
+    }
+
+    /// Whether playback is currently being cancelled
+    pub fn is_active(&self) -> bool {
+        self.reference.has_pending() || self.canceller.as_ref().map(|c| c.is_active()).unwrap_or(false)
+    }
+
+    /// Canceller statistics
+    pub fn stats(&self) -> EchoStats {
+        self.canceller.as_ref().map(|c| c.stats()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noise(seed: &mut u32) -> f32 {
+        *seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        (*seed >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0
+    }
+
+    /// Simulated room: delayed, attenuated copy of the playback
+    fn echo_of(reference: &[f32], delay: usize, gain: f32) -> Vec<f32> {
+        (0..reference.len())
+            .map(|i| if i >= delay { gain * reference[i - delay] } else { 0.0 })
+            .collect()
+    }
+
+    #[test]
+    fn test_converges_and_gates_pure_echo() {
+        let sr = 8000;
+        let mut seed = 1;
+        let reference: Vec<f32> = (0..sr * 3).map(|_| 0.3 * noise(&mut seed)).collect();
+        let mic = echo_of(&reference, 40, 0.5);
+
+        let mut aec = EchoCanceller::new(EchoCancellerConfig { filter_length_ms: 16, ..Default::default() }, sr as u32);
+        let mut decisions = Vec::new();
+        for (m, r) in mic.chunks(160).zip(reference.chunks(160)) {
+            let mut frame = m.to_vec();
+            decisions.push(aec.process(&mut frame, r));
+        }
+        assert!(aec.stats().erle_db > 15.0, "erle {}", aec.stats().erle_db);
+        // After convergence every frame is recognised as playback only
+        assert!(decisions[decisions.len() / 2..].iter().all(|d| *d == EchoDecision::Gated));
+    }
+
+    #[test]
+    fn test_near_end_speech_passes_during_playback() {
+        let sr = 8000;
+        let mut seed = 7;
+        let reference: Vec<f32> = (0..sr * 4).map(|_| 0.3 * noise(&mut seed)).collect();
+        let mut mic = echo_of(&reference, 24, 0.4);
+        // The user starts talking after two seconds
+        for (i, s) in mic.iter_mut().enumerate().skip(sr * 2) {
+            *s += 0.5 * (2.0 * std::f32::consts::PI * 300.0 * i as f32 / sr as f32).sin();
+        }
+
+        let tap = EchoReference::new();
+        let mut stage = EchoSuppressor::new(EchoCancellerConfig { filter_length_ms: 16, ..Default::default() }, tap.clone());
+        let mut late = Vec::new();
+        for (i, (m, r)) in mic.chunks(160).zip(reference.chunks(160)).enumerate() {
+            tap.push(r, sr as u32);
+            let mut frame = m.to_vec();
+            let decision = stage.process(&mut frame, sr as u32);
+            if i * 160 >= sr * 2 {
+                late.push((decision, frame));
+            }
+        }
+        assert!(late.iter().all(|(d, _)| *d != EchoDecision::Gated));
+        assert!(stage.stats().double_talk_frames > 0);
+        // The user's speech survives cancellation
+        let energy: f32 = late.iter().flat_map(|(_, f)| f.iter()).map(|s| s * s).sum::<f32>() / (late.len() * 160) as f32;
+        assert!(energy > 0.05, "energy {energy}");
+    }
+
+    #[test]
+    fn test_reference_resamples_to_capture_rate() {
+        let tap = EchoReference::new();
+        tap.push(&vec![0.5; 441], 44100);
+        let taken = tap.take(160, 16000);
+        assert_eq!(taken.len(), 160);
+        assert!(taken.iter().all(|s| (*s - 0.5).abs() < 1e-3));
+        assert!(!tap.has_pending());
+    }
+}
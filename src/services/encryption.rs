@@ -0,0 +1,638 @@
+//! Encryption at rest for recordings and transcripts.
+//!
+//! Files are sealed with AES-256-GCM under a key derived from a passphrase
+//! (Argon2id) or read from a key file. Each encrypted file starts with a
+//! small header carrying the id of the key that sealed it, so rotation can
+//! tell old files from new ones and a wrong key is reported as such rather
+//! than as corruption. The key itself is never stored; `encryption.json` in
+//! the data directory only holds the key id and KDF parameters.
+//!
+//! Plaintext files are still readable, which lets `encrypt_directory`
+//! migrate an existing data directory in place.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zeroize::Zeroize;
+
+use crate::core::config::PrivacyConfig;
+use super::archive_check::is_audio_file;
+use super::audio_archive::AudioError;
+use super::transcription_log::TranscriptError;
+
+/// Marks the start of an encrypted file
+pub const ENCRYPTED_MAGIC: &[u8; 7] = b"CLPSENC";
+
+/// Key metadata stored in the data directory
+pub const KEY_CONFIG_FILE: &str = "encryption.json";
+
+/// Key metadata for a rotation that has not finished yet
+const PENDING_KEY_CONFIG_FILE: &str = "encryption.json.pending";
+
+const FORMAT_VERSION: u8 = 1;
+const KEY_LEN: usize = 32;
+const KEY_ID_LEN: usize = 8;
+const AAD_LEN: usize = ENCRYPTED_MAGIC.len() + 1 + KEY_ID_LEN;
+const HEADER_LEN: usize = AAD_LEN + NONCE_LEN;
+const SALT_LEN: usize = 16;
+
+/// Encryption errors
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error("Wrong passphrase or key file")]
+    WrongKey,
+
+    #[error("File is encrypted with an unknown key ({0})")]
+    UnknownKey(String),
+
+    #[error("File is encrypted but no key is loaded")]
+    Locked,
+
+    #[error("Decryption failed: data is corrupted or was tampered with")]
+    Authentication,
+
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+
+    #[error("Key derivation failed: {0}")]
+    KeyDerivation(String),
+
+    #[error("A key rotation was interrupted; rerun it with the same old and new keys")]
+    RotationInProgress,
+
+    #[error("System random number generator failed")]
+    Random,
+
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+}
+
+impl From<EncryptionError> for AudioError {
+    fn from(error: EncryptionError) -> Self {
+        AudioError::StorageError(error.to_string())
+    }
+}
+
+impl From<EncryptionError> for TranscriptError {
+    fn from(error: EncryptionError) -> Self {
+        TranscriptError::StorageError(error.to_string())
+    }
+}
+
+/// Where the storage key comes from
+#[derive(Debug, Clone)]
+pub enum KeySource {
+    /// Passphrase stretched with Argon2id
+    Passphrase(String),
+    /// File holding 32 raw bytes or 64 hex characters
+    KeyFile(PathBuf),
+}
+
+impl KeySource {
+    /// Key from `CLIPSTTY_KEY_FILE` or `CLIPSTTY_PASSPHRASE`
+    pub fn from_env() -> Option<Self> {
+        Self::from_env_vars("CLIPSTTY_KEY_FILE", "CLIPSTTY_PASSPHRASE")
+    }
+
+    /// Key from the given key-file and passphrase variables, key file first
+    pub fn from_env_vars(key_file_var: &str, passphrase_var: &str) -> Option<Self> {
+        if let Ok(path) = std::env::var(key_file_var) {
+            return Some(Self::KeyFile(PathBuf::from(path)));
+        }
+        std::env::var(passphrase_var).ok().filter(|p| !p.is_empty()).map(Self::Passphrase)
+    }
+
+    /// Key source for the privacy settings, or None when encryption is off
+    pub fn from_config(privacy: &PrivacyConfig) -> Option<Self> {
+        if !privacy.encrypt_storage {
+            return None;
+        }
+        match &privacy.encryption_key_file {
+            Some(path) => Some(Self::KeyFile(path.clone())),
+            None => Self::from_env(),
+        }
+    }
+
+    fn kind(&self) -> KeySourceKind {
+        match self {
+            Self::Passphrase(_) => KeySourceKind::Passphrase,
+            Self::KeyFile(_) => KeySourceKind::KeyFile,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum KeySourceKind {
+    Passphrase,
+    KeyFile,
+}
+
+/// Argon2id parameters for passphrase keys
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    /// Hex-encoded salt
+    pub salt: String,
+}
+
+impl KdfParams {
+    /// Recommended parameters with a fresh random salt
+    pub fn generate() -> Result<Self, EncryptionError> {
+        let mut salt = [0u8; SALT_LEN];
+        SystemRandom::new().fill(&mut salt).map_err(|_| EncryptionError::Random)?;
+        Ok(Self {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+            salt: hex::encode(salt),
+        })
+    }
+}
+
+/// Key metadata kept next to the encrypted data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeyConfig {
+    version: u32,
+    key_id: String,
+    source: KeySourceKind,
+    #[serde(default)]
+    kdf: Option<KdfParams>,
+    created_at: DateTime<Utc>,
+}
+
+impl KeyConfig {
+    fn load(path: &Path) -> Result<Option<Self>, EncryptionError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_slice(&fs::read(path)?)?))
+    }
+
+    fn save(&self, path: &Path) -> Result<(), EncryptionError> {
+        write_file(path, &serde_json::to_vec_pretty(self)?, None)
+    }
+
+    /// New metadata for a key from `source`, with the derived key
+    fn create(source: &KeySource, kdf: Option<KdfParams>) -> Result<(Self, EncryptionKey), EncryptionError> {
+        let kdf = match source {
+            KeySource::Passphrase(_) => Some(match kdf {
+                Some(kdf) => kdf,
+                None => KdfParams::generate()?,
+            }),
+            KeySource::KeyFile(_) => None,
+        };
+        let key = EncryptionKey::from_source(source, kdf.as_ref())?;
+        let config = Self {
+            version: FORMAT_VERSION as u32,
+            key_id: key.id_hex(),
+            source: source.kind(),
+            kdf,
+            created_at: Utc::now(),
+        };
+        Ok((config, key))
+    }
+
+    /// Derive the key for this metadata and check it is the right one
+    fn unlock(&self, source: &KeySource) -> Result<EncryptionKey, EncryptionError> {
+        if source.kind() != self.source {
+            return Err(EncryptionError::WrongKey);
+        }
+        let key = EncryptionKey::from_source(source, self.kdf.as_ref())?;
+        if key.id_hex() != self.key_id {
+            return Err(EncryptionError::WrongKey);
+        }
+        Ok(key)
+    }
+}
+
+/// A 256-bit storage key
+#[derive(Clone)]
+pub struct EncryptionKey {
+    bytes: [u8; KEY_LEN],
+    id: [u8; KEY_ID_LEN],
+}
+
+impl EncryptionKey {
+    pub fn from_bytes(bytes: [u8; KEY_LEN]) -> Self {
+        // The id is a MAC of a fixed label, so it reveals nothing about the key
+        let tag = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, &bytes), b"clipstty key id");
+        let mut id = [0u8; KEY_ID_LEN];
+        id.copy_from_slice(&tag.as_ref()[..KEY_ID_LEN]);
+        Self { bytes, id }
+    }
+
+    /// Fresh random key
+    pub fn generate() -> Result<Self, EncryptionError> {
+        let mut bytes = [0u8; KEY_LEN];
+        SystemRandom::new().fill(&mut bytes).map_err(|_| EncryptionError::Random)?;
+        Ok(Self::from_bytes(bytes))
+    }
+
+    /// Derive a key from a passphrase with Argon2id
+    pub fn from_passphrase(passphrase: &str, kdf: &KdfParams) -> Result<Self, EncryptionError> {
+        let salt = hex::decode(&kdf.salt).map_err(|e| EncryptionError::KeyDerivation(format!("invalid salt: {}", e)))?;
+        let params = argon2::Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(KEY_LEN))
+            .map_err(|e| EncryptionError::KeyDerivation(e.to_string()))?;
+        let argon = argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+        let mut bytes = [0u8; KEY_LEN];
+        argon
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut bytes)
+            .map_err(|e| EncryptionError::KeyDerivation(e.to_string()))?;
+        Ok(Self::from_bytes(bytes))
+    }
+
+    /// Read a key file: 32 raw bytes or 64 hex characters
+    pub fn from_key_file(path: &Path) -> Result<Self, EncryptionError> {
+        let mut contents = fs::read(path)?;
+        let trimmed = String::from_utf8_lossy(&contents).trim().to_string();
+        let decoded = if trimmed.len() == KEY_LEN * 2 {
+            hex::decode(&trimmed).ok()
+        } else {
+            None
+        };
+        let raw = decoded.as_deref().unwrap_or(&contents);
+        if raw.len() != KEY_LEN {
+            contents.zeroize();
+            return Err(EncryptionError::InvalidKey(format!(
+                "{} must hold {} bytes or {} hex characters",
+                path.display(),
+                KEY_LEN,
+                KEY_LEN * 2
+            )));
+        }
+        let mut bytes = [0u8; KEY_LEN];
+        bytes.copy_from_slice(raw);
+        contents.zeroize();
+        Ok(Self::from_bytes(bytes))
+    }
+
+    fn from_source(source: &KeySource, kdf: Option<&KdfParams>) -> Result<Self, EncryptionError> {
+        match source {
+            KeySource::Passphrase(passphrase) => {
+                let kdf = kdf.ok_or_else(|| EncryptionError::KeyDerivation("missing KDF parameters".to_string()))?;
+                Self::from_passphrase(passphrase, kdf)
+            }
+            KeySource::KeyFile(path) => Self::from_key_file(path),
+        }
+    }
+
+    /// Short public identifier of this key
+    pub fn id_hex(&self) -> String {
+        hex::encode(self.id)
+    }
+
+    fn aead_key(&self) -> Result<LessSafeKey, EncryptionError> {
+        UnboundKey::new(&AES_256_GCM, &self.bytes)
+            .map(LessSafeKey::new)
+            .map_err(|_| EncryptionError::InvalidKey("rejected by AES-256-GCM".to_string()))
+    }
+}
+
+impl Drop for EncryptionKey {
+    fn drop(&mut self) {
+        self.bytes.zeroize();
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptionKey").field("id", &self.id_hex()).finish()
+    }
+}
+
+/// Seals new files with the current key; opens files sealed by it or by
+/// any previous key
+#[derive(Debug, Clone)]
+pub struct StorageCipher {
+    current: EncryptionKey,
+    previous: Vec<EncryptionKey>,
+}
+
+impl StorageCipher {
+    pub fn new(key: EncryptionKey) -> Self {
+        Self { current: key, previous: Vec::new() }
+    }
+
+    /// Also accept files sealed with `key`
+    pub fn with_previous(mut self, key: EncryptionKey) -> Self {
+        self.previous.push(key);
+        self
+    }
+
+    /// Unlock the data directory, setting up its key metadata on first use
+    pub fn unlock(data_dir: &Path, source: &KeySource) -> Result<Self, EncryptionError> {
+        if data_dir.join(PENDING_KEY_CONFIG_FILE).exists() {
+            return Err(EncryptionError::RotationInProgress);
+        }
+        let config_path = data_dir.join(KEY_CONFIG_FILE);
+        match KeyConfig::load(&config_path)? {
+            Some(config) => Ok(Self::new(config.unlock(source)?)),
+            None => {
+                fs::create_dir_all(data_dir)?;
+                let (config, key) = KeyConfig::create(source, None)?;
+                config.save(&config_path)?;
+                Ok(Self::new(key))
+            }
+        }
+    }
+
+    /// Id of the key new files are sealed with
+    pub fn key_id(&self) -> String {
+        self.current.id_hex()
+    }
+
+    /// Seal `plaintext` with the current key
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce).map_err(|_| EncryptionError::Random)?;
+
+        let mut out = Vec::with_capacity(HEADER_LEN + plaintext.len() + AES_256_GCM.tag_len());
+        out.extend_from_slice(ENCRYPTED_MAGIC);
+        out.push(FORMAT_VERSION);
+        out.extend_from_slice(&self.current.id);
+        out.extend_from_slice(&nonce);
+
+        let mut sealed = plaintext.to_vec();
+        self.current
+            .aead_key()?
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(&out[..AAD_LEN]), &mut sealed)
+            .map_err(|_| EncryptionError::Authentication)?;
+        out.extend_from_slice(&sealed);
+        Ok(out)
+    }
+
+    /// Open data sealed by `encrypt` with the current or a previous key
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let key_id = encrypted_key_id(data).ok_or(EncryptionError::Authentication)?;
+        let key = std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|k| k.id == key_id)
+            .ok_or_else(|| EncryptionError::UnknownKey(hex::encode(key_id)))?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce.copy_from_slice(&data[AAD_LEN..HEADER_LEN]);
+        let mut sealed = data[HEADER_LEN..].to_vec();
+        let plaintext = key
+            .aead_key()?
+            .open_in_place(Nonce::assume_unique_for_key(nonce), Aad::from(&data[..AAD_LEN]), &mut sealed)
+            .map_err(|_| EncryptionError::Authentication)?;
+        Ok(plaintext.to_vec())
+    }
+
+    /// Whether `data` is already sealed with the current key
+    pub fn is_current(&self, data: &[u8]) -> bool {
+        encrypted_key_id(data) == Some(self.current.id)
+    }
+}
+
+/// Whether `data` starts with the encrypted file header
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.len() >= HEADER_LEN && data.starts_with(ENCRYPTED_MAGIC)
+}
+
+fn encrypted_key_id(data: &[u8]) -> Option<[u8; KEY_ID_LEN]> {
+    if !is_encrypted(data) || data[ENCRYPTED_MAGIC.len()] != FORMAT_VERSION {
+        return None;
+    }
+    let start = ENCRYPTED_MAGIC.len() + 1;
+    let mut id = [0u8; KEY_ID_LEN];
+    id.copy_from_slice(&data[start..start + KEY_ID_LEN]);
+    Some(id)
+}
+
+/// Read a file, decrypting it if it is encrypted
+pub fn read_file(path: &Path, cipher: Option<&StorageCipher>) -> Result<Vec<u8>, EncryptionError> {
+    let data = fs::read(path)?;
+    if !is_encrypted(&data) {
+        return Ok(data);
+    }
+    cipher.ok_or(EncryptionError::Locked)?.decrypt(&data)
+}
+
+/// Atomically write a file, encrypting it when a cipher is given
+pub fn write_file(path: &Path, data: &[u8], cipher: Option<&StorageCipher>) -> Result<(), EncryptionError> {
+    let sealed;
+    let bytes = match cipher {
+        Some(cipher) => {
+            sealed = cipher.encrypt(data)?;
+            &sealed
+        }
+        None => data,
+    };
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+    {
+        let mut file = fs::File::create(&temp_path)?;
+        io::Write::write_all(&mut file, bytes)?;
+        file.sync_all()?;
+    }
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// Write a new random key file readable only by the owner
+pub fn generate_key_file(path: &Path) -> Result<EncryptionKey, EncryptionError> {
+    if path.exists() {
+        return Err(EncryptionError::InvalidKey(format!("{} already exists", path.display())));
+    }
+    let key = EncryptionKey::generate()?;
+    let mut encoded = hex::encode(key.bytes);
+    write_file(path, encoded.as_bytes(), None)?;
+    encoded.zeroize();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(key)
+}
+
+/// Outcome of encrypting or re-keying a directory
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    /// Plaintext files that were encrypted
+    pub files_encrypted: usize,
+    /// Files moved from a previous key to the current one
+    pub files_reencrypted: usize,
+    /// Files already sealed with the current key
+    pub files_skipped: usize,
+    pub bytes_processed: u64,
+}
+
+fn is_key_material(path: &Path) -> bool {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    name.starts_with(KEY_CONFIG_FILE) || path.extension().map(|e| e == "key").unwrap_or(false)
+}
+
+/// Top-level entries of the data directory that hold archive data. Anything
+/// else there, such as settings, wake word templates and speaker profiles, is
+/// read as plaintext and left alone, apart from archived audio files.
+const ARCHIVE_ENTRIES: &[&str] = &["sessions", "transcripts", "session_index.json"];
+
+/// Encrypt the archive data under `dir` with the current key, re-keying files
+/// sealed with a previous key. Safe to rerun after an interruption.
+pub fn encrypt_directory(dir: &Path, cipher: &StorageCipher) -> Result<MigrationReport, EncryptionError> {
+    let mut report = MigrationReport::default();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let archive = path.file_name().and_then(|name| name.to_str()).is_some_and(|name| ARCHIVE_ENTRIES.contains(&name));
+        encrypt_entry(&path, cipher, archive, &mut report)?;
+    }
+    Ok(report)
+}
+
+/// Encrypt a file, or the files of a directory; outside `archive` only audio files
+fn encrypt_entry(path: &Path, cipher: &StorageCipher, archive: bool, report: &mut MigrationReport) -> Result<(), EncryptionError> {
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            encrypt_entry(&entry?.path(), cipher, archive, report)?;
+        }
+        return Ok(());
+    }
+    if !path.is_file() || is_key_material(path) || !(archive || is_audio_file(path)) {
+        return Ok(());
+    }
+    let data = fs::read(path)?;
+    if cipher.is_current(&data) {
+        report.files_skipped += 1;
+        return Ok(());
+    }
+    let plaintext = if is_encrypted(&data) {
+        report.files_reencrypted += 1;
+        cipher.decrypt(&data)?
+    } else {
+        report.files_encrypted += 1;
+        data
+    };
+    report.bytes_processed += plaintext.len() as u64;
+    write_file(path, &plaintext, Some(cipher))
+}
+
+/// Re-key the data directory from `old` to `new`. Progress is resumable:
+/// the new key metadata is only committed once every file is re-encrypted.
+pub fn rotate_key(data_dir: &Path, old: &KeySource, new: &KeySource) -> Result<(StorageCipher, MigrationReport), EncryptionError> {
+    let config_path = data_dir.join(KEY_CONFIG_FILE);
+    let pending_path = data_dir.join(PENDING_KEY_CONFIG_FILE);
+    let config = KeyConfig::load(&config_path)?
+        .ok_or_else(|| EncryptionError::InvalidKey(format!("{} has no encryption key set up", data_dir.display())))?;
+    let old_key = config.unlock(old)?;
+
+    let new_key = match KeyConfig::load(&pending_path)? {
+        Some(pending) => pending.unlock(new)?,
+        None => {
+            let (pending, key) = KeyConfig::create(new, None)?;
+            if pending.key_id == config.key_id {
+                return Err(EncryptionError::InvalidKey("the new key is the same as the current key".to_string()));
+            }
+            pending.save(&pending_path)?;
+            key
+        }
+    };
+
+    let cipher = StorageCipher::new(new_key.clone()).with_previous(old_key);
+    let report = encrypt_directory(data_dir, &cipher)?;
+    fs::rename(&pending_path, &config_path)?;
+    Ok((StorageCipher::new(new_key), report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fast_kdf() -> KdfParams {
+        KdfParams { memory_kib: 64, iterations: 1, parallelism: 1, salt: "00112233445566778899aabbccddeeff".to_string() }
+    }
+
+    #[test]
+    fn test_seal_open_and_tamper_detection() {
+        let cipher = StorageCipher::new(EncryptionKey::from_passphrase("correct horse", &fast_kdf()).unwrap());
+        let sealed = cipher.encrypt(b"meeting transcript").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.windows(7).any(|w| w == b"meeting"));
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), b"meeting transcript");
+
+        let mut tampered = sealed.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(matches!(cipher.decrypt(&tampered), Err(EncryptionError::Authentication)));
+
+        let other = StorageCipher::new(EncryptionKey::from_passphrase("wrong", &fast_kdf()).unwrap());
+        assert!(matches!(other.decrypt(&sealed), Err(EncryptionError::UnknownKey(_))));
+    }
+
+    #[test]
+    fn test_unlock_with_key_file_and_wrong_key() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let key_path = dir.path().join("storage.key");
+        generate_key_file(&key_path).unwrap();
+        let source = KeySource::KeyFile(key_path);
+
+        let cipher = StorageCipher::unlock(dir.path(), &source).unwrap();
+        let reopened = StorageCipher::unlock(dir.path(), &source).unwrap();
+        assert_eq!(cipher.key_id(), reopened.key_id());
+
+        let other_path = dir.path().join("other.key");
+        generate_key_file(&other_path).unwrap();
+        assert!(matches!(
+            StorageCipher::unlock(dir.path(), &KeySource::KeyFile(other_path)),
+            Err(EncryptionError::WrongKey)
+        ));
+        assert!(matches!(
+            StorageCipher::unlock(dir.path(), &KeySource::Passphrase("guess".to_string())),
+            Err(EncryptionError::WrongKey)
+        ));
+    }
+
+    #[test]
+    fn test_migrate_then_rotate_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let sessions = dir.path().join("sessions");
+        fs::create_dir_all(&sessions).unwrap();
+        fs::write(sessions.join("session_metadata.json"), b"{\"name\":\"standup\"}").unwrap();
+        fs::write(sessions.join("raw_audio.wav"), vec![7u8; 1000]).unwrap();
+        // Settings stay readable; the runner needs them to find the key
+        let mut config = crate::core::config::Config::new();
+        config.privacy.encryption_key_file = Some(dir.path().join("old.key"));
+        config.save_to_file(&dir.path().join("config.toml")).unwrap();
+        fs::write(dir.path().join("wake_word_templates.json"), b"{}").unwrap();
+
+        let old_path = dir.path().join("old.key");
+        generate_key_file(&old_path).unwrap();
+        let old = KeySource::KeyFile(old_path);
+        let cipher = StorageCipher::unlock(dir.path(), &old).unwrap();
+        let report = encrypt_directory(dir.path(), &cipher).unwrap();
+        assert_eq!(report.files_encrypted, 2);
+        assert!(is_encrypted(&fs::read(sessions.join("raw_audio.wav")).unwrap()));
+        assert!(!is_encrypted(&fs::read(dir.path().join(KEY_CONFIG_FILE)).unwrap()));
+        assert!(matches!(read_file(&sessions.join("raw_audio.wav"), None), Err(EncryptionError::Locked)));
+        let loaded = crate::core::config::Config::from_file(&dir.path().join("config.toml")).unwrap();
+        assert_eq!(loaded.privacy.encryption_key_file, Some(dir.path().join("old.key")));
+        assert_eq!(fs::read(dir.path().join("wake_word_templates.json")).unwrap(), b"{}");
+
+        let new_path = dir.path().join("new.key");
+        generate_key_file(&new_path).unwrap();
+        let new = KeySource::KeyFile(new_path);
+        let (rotated, report) = rotate_key(dir.path(), &old, &new).unwrap();
+        assert_eq!(report.files_reencrypted, 2);
+        assert_ne!(rotated.key_id(), cipher.key_id());
+        assert_eq!(
+            read_file(&sessions.join("session_metadata.json"), Some(&rotated)).unwrap(),
+            b"{\"name\":\"standup\"}"
+        );
+        assert!(matches!(StorageCipher::unlock(dir.path(), &old), Err(EncryptionError::WrongKey)));
+        assert_eq!(StorageCipher::unlock(dir.path(), &new).unwrap().key_id(), rotated.key_id());
+    }
+}
@@ -0,0 +1,970 @@
+//! Lossless FLAC encoding and decoding.
+//!
+//! The encoder writes standard FLAC streams: a STREAMINFO block followed by
+//! fixed-blocksize frames whose subframes use the best of the constant,
+//! verbatim and fixed (order 0-4) predictors, with Rice-coded residuals
+//! partitioned for the smallest output. Stereo input also tries left/side,
+//! right/side and mid/side decorrelation per frame. `FlacEncoder` streams to
+//! any `Write + Seek` and patches STREAMINFO when finished.
+//!
+//! The decoder reads any FLAC stream with up to 8 channels and 32-bit
+//! samples, including LPC subframes from other encoders, and checks the
+//! header and frame CRCs.
+
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use super::audio_archive::AudioError;
+
+/// Samples per channel in each encoded frame
+pub const DEFAULT_BLOCK_SIZE: usize = 4096;
+
+/// Highest Rice partition order tried by the encoder
+const MAX_PARTITION_ORDER: u32 = 8;
+
+/// 14-bit frame sync code
+const FRAME_SYNC: u64 = 0x3FFE;
+
+/// Stream parameters from STREAMINFO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlacStreamInfo {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    /// Samples per channel; 0 if unknown
+    pub total_samples: u64,
+    pub min_block_size: u16,
+    pub max_block_size: u16,
+    pub min_frame_size: u32,
+    pub max_frame_size: u32,
+}
+
+/// Decoded FLAC audio
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlacAudio {
+    pub info: FlacStreamInfo,
+    /// Interleaved integer samples at `info.bits_per_sample`
+    pub samples: Vec<i32>,
+}
+
+impl FlacAudio {
+    /// Interleaved samples scaled to [-1.0, 1.0)
+    pub fn to_f32(&self) -> Vec<f32> {
+        let scale = (1i64 << (self.info.bits_per_sample - 1)) as f32;
+        self.samples.iter().map(|s| *s as f32 / scale).collect()
+    }
+}
+
+fn codec_error(message: impl Into<String>) -> AudioError {
+    AudioError::CompressionError(message.into())
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u64, n: u32) {
+        debug_assert!(n <= 32);
+        if n == 0 {
+            return;
+        }
+        self.acc = (self.acc << n) | (value & ((1u64 << n) - 1));
+        self.bits += n;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            self.bytes.push((self.acc >> self.bits) as u8);
+        }
+    }
+
+    fn write_signed(&mut self, value: i64, n: u32) {
+        self.write(value as u64, n);
+    }
+
+    fn write_unary(&mut self, mut zeros: u64) {
+        while zeros >= 32 {
+            self.write(0, 32);
+            zeros -= 32;
+        }
+        self.write(1, zeros as u32 + 1);
+    }
+
+    fn write_rice(&mut self, value: i64, k: u32) {
+        let folded = ((value << 1) ^ (value >> 63)) as u64;
+        self.write_unary(folded >> k);
+        if k > 0 {
+            self.write(folded, k);
+        }
+    }
+
+    fn align(&mut self) {
+        if self.bits > 0 {
+            self.write(0, 8 - self.bits);
+        }
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn byte_pos(&self) -> usize {
+        self.pos / 8
+    }
+
+    fn bit(&mut self) -> Result<u32, AudioError> {
+        let byte = *self.data.get(self.pos / 8).ok_or_else(|| codec_error("Unexpected end of FLAC data"))?;
+        let bit = (byte >> (7 - (self.pos % 8))) & 1;
+        self.pos += 1;
+        Ok(bit as u32)
+    }
+
+    fn read(&mut self, n: u32) -> Result<u64, AudioError> {
+        let mut value = 0u64;
+        let mut left = n;
+        while left > 0 {
+            let offset = (self.pos % 8) as u32;
+            let byte = *self.data.get(self.pos / 8).ok_or_else(|| codec_error("Unexpected end of FLAC data"))? as u64;
+            let take = (8 - offset).min(left);
+            let chunk = (byte >> (8 - offset - take)) & ((1 << take) - 1);
+            value = (value << take) | chunk;
+            self.pos += take as usize;
+            left -= take;
+        }
+        Ok(value)
+    }
+
+    fn read_signed(&mut self, n: u32) -> Result<i64, AudioError> {
+        if n == 0 {
+            return Ok(0);
+        }
+        let raw = self.read(n)?;
+        let shift = 64 - n;
+        Ok(((raw << shift) as i64) >> shift)
+    }
+
+    fn read_unary(&mut self) -> Result<u64, AudioError> {
+        let mut zeros = 0u64;
+        loop {
+            // Skip whole zero bytes quickly
+            if self.pos.is_multiple_of(8) {
+                while self.data.get(self.pos / 8) == Some(&0) {
+                    zeros += 8;
+                    self.pos += 8;
+                }
+            }
+            if self.bit()? == 1 {
+                return Ok(zeros);
+            }
+            zeros += 1;
+        }
+    }
+
+    fn read_rice(&mut self, k: u32) -> Result<i64, AudioError> {
+        let folded = (self.read_unary()? << k) | self.read(k)?;
+        Ok(((folded >> 1) as i64) ^ -((folded & 1) as i64))
+    }
+
+    fn align(&mut self) {
+        self.pos = self.pos.div_ceil(8) * 8;
+    }
+}
+
+fn sample_rate_code(sample_rate: u32) -> u64 {
+    match sample_rate {
+        88_200 => 0b0001,
+        176_400 => 0b0010,
+        192_000 => 0b0011,
+        8_000 => 0b0100,
+        16_000 => 0b0101,
+        22_050 => 0b0110,
+        24_000 => 0b0111,
+        32_000 => 0b1000,
+        44_100 => 0b1001,
+        48_000 => 0b1010,
+        96_000 => 0b1011,
+        _ => 0b0000, // taken from STREAMINFO
+    }
+}
+
+fn sample_size_code(bits: u16) -> u64 {
+    match bits {
+        8 => 0b001,
+        12 => 0b010,
+        16 => 0b100,
+        20 => 0b101,
+        24 => 0b110,
+        32 => 0b111,
+        _ => 0b000, // taken from STREAMINFO
+    }
+}
+
+/// Frame/sample number in FLAC's extended UTF-8 coding
+fn write_utf8_number(out: &mut BitWriter, n: u64) {
+    if n < 0x80 {
+        out.write(n, 8);
+        return;
+    }
+    let continuation_bytes = match n {
+        0..=0x7FF => 1,
+        0x800..=0xFFFF => 2,
+        0x1_0000..=0x1F_FFFF => 3,
+        0x20_0000..=0x3FF_FFFF => 4,
+        0x400_0000..=0x7FFF_FFFF => 5,
+        _ => 6,
+    };
+    let lead_marker = (0xFF00u64 >> (continuation_bytes + 1)) & 0xFF;
+    out.write(lead_marker | (n >> (6 * continuation_bytes)), 8);
+    for i in (0..continuation_bytes).rev() {
+        out.write(0x80 | ((n >> (6 * i)) & 0x3F), 8);
+    }
+}
+
+fn fixed_residual(samples: &[i64], order: usize) -> Vec<i64> {
+    (order..samples.len())
+        .map(|i| {
+            let s = |k: usize| samples[i - k];
+            match order {
+                0 => s(0),
+                1 => s(0) - s(1),
+                2 => s(0) - 2 * s(1) + s(2),
+                3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+                _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+            }
+        })
+        .collect()
+}
+
+/// Best Rice parameter and its cost in bits for one partition
+fn rice_cost(residual: &[i64]) -> (u32, u64) {
+    let folded: Vec<u64> = residual.iter().map(|v| ((v << 1) ^ (v >> 63)) as u64).collect();
+    let mut best = (0u32, u64::MAX);
+    for k in 0..=30u32 {
+        let bits: u64 = folded.iter().map(|u| (u >> k) + 1 + k as u64).sum();
+        if bits < best.1 {
+            best = (k, bits);
+        }
+        if folded.iter().all(|u| u >> k == 0) {
+            break;
+        }
+    }
+    best
+}
+
+/// Rice partitioning of a residual: (partition order, parameters, total bits)
+type ResidualPlan = (u32, Vec<u32>, u64);
+
+fn plan_residual(residual: &[i64], block_size: usize, predictor_order: usize) -> ResidualPlan {
+    let mut best: Option<ResidualPlan> = None;
+    for order in 0..=MAX_PARTITION_ORDER {
+        let partitions = 1usize << order;
+        if !block_size.is_multiple_of(partitions) || block_size / partitions <= predictor_order {
+            break;
+        }
+        let per_partition = block_size / partitions;
+        let mut params = Vec::with_capacity(partitions);
+        let mut start = 0;
+        let mut bits = 0u64;
+        for p in 0..partitions {
+            let len = if p == 0 { per_partition - predictor_order } else { per_partition };
+            let (k, cost) = rice_cost(&residual[start..start + len]);
+            params.push(k);
+            bits += cost;
+            start += len;
+        }
+        let param_bits = if params.iter().any(|k| *k > 14) { 5 } else { 4 };
+        bits += 2 + 4 + partitions as u64 * param_bits;
+        if best.as_ref().map(|b| bits < b.2).unwrap_or(true) {
+            best = Some((order, params, bits));
+        }
+    }
+    best.unwrap_or((0, vec![0], u64::MAX))
+}
+
+/// Encode one subframe, choosing the cheapest representation
+fn encode_subframe(out: &mut BitWriter, samples: &[i64], bits: u32) {
+    let block_size = samples.len();
+    if samples.iter().all(|s| *s == samples[0]) {
+        out.write(0x00, 8); // SUBFRAME_CONSTANT
+        out.write_signed(samples[0], bits);
+        return;
+    }
+
+    let verbatim_bits = block_size as u64 * bits as u64;
+    let mut best: Option<(usize, Vec<i64>, ResidualPlan)> = None;
+    for order in 0..=4usize.min(block_size - 1) {
+        let residual = fixed_residual(samples, order);
+        // Residuals must fit in 32 bits signed
+        if residual.iter().any(|r| *r > i32::MAX as i64 || *r < i32::MIN as i64) {
+            continue;
+        }
+        let plan = plan_residual(&residual, block_size, order);
+        let total = plan.2 + order as u64 * bits as u64;
+        if best.as_ref().map(|b| total < b.2 .2 + (b.0 as u64) * (bits as u64)).unwrap_or(true) {
+            best = Some((order, residual, plan));
+        }
+    }
+
+    match best {
+        Some((order, residual, (partition_order, params, cost)))
+            if cost + (order as u64) * (bits as u64) < verbatim_bits =>
+        {
+            out.write(0x10 | ((order as u64) << 1), 8); // SUBFRAME_FIXED
+            for s in &samples[..order] {
+                out.write_signed(*s, bits);
+            }
+            let wide = params.iter().any(|k| *k > 14);
+            out.write(if wide { 0b01 } else { 0b00 }, 2);
+            out.write(partition_order as u64, 4);
+            let per_partition = block_size >> partition_order;
+            let mut start = 0;
+            for (p, k) in params.iter().enumerate() {
+                let len = if p == 0 { per_partition - order } else { per_partition };
+                out.write(*k as u64, if wide { 5 } else { 4 });
+                for r in &residual[start..start + len] {
+                    out.write_rice(*r, *k);
+                }
+                start += len;
+            }
+        }
+        _ => {
+            out.write(0x02, 8); // SUBFRAME_VERBATIM
+            for s in samples {
+                out.write_signed(*s, bits);
+            }
+        }
+    }
+}
+
+/// Rough cost of a channel: sum of second-order residual magnitudes
+fn channel_cost(samples: &[i64]) -> u64 {
+    samples.windows(3).map(|w| (w[2] - 2 * w[1] + w[0]).unsigned_abs()).sum()
+}
+
+/// Encode one frame of `channels` deinterleaved blocks
+fn encode_frame(info: &FlacStreamInfo, frame_number: u64, blocks: &[Vec<i64>]) -> Vec<u8> {
+    let block_size = blocks[0].len();
+    let bps = info.bits_per_sample as u32;
+
+    // Pick a stereo decorrelation mode
+    let mut assignment = blocks.len() as u64 - 1;
+    let mut channels: Vec<(Vec<i64>, u32)> = blocks.iter().map(|b| (b.clone(), bps)).collect();
+    if blocks.len() == 2 {
+        let (left, right) = (&blocks[0], &blocks[1]);
+        let side: Vec<i64> = left.iter().zip(right).map(|(l, r)| l - r).collect();
+        let mid: Vec<i64> = left.iter().zip(right).map(|(l, r)| (l + r) >> 1).collect();
+        let (cl, cr, cs, cm) = (channel_cost(left), channel_cost(right), channel_cost(&side), channel_cost(&mid));
+        let options = [(cl + cr, 1u64), (cl + cs, 8), (cr + cs, 9), (cm + cs, 10)];
+        let (_, mode) = options.iter().min_by_key(|(cost, _)| *cost).copied().unwrap_or((0, 1));
+        assignment = mode;
+        channels = match mode {
+            8 => vec![(left.clone(), bps), (side, bps + 1)],
+            9 => vec![(side, bps + 1), (right.clone(), bps)],
+            10 => vec![(mid, bps), (side, bps + 1)],
+            _ => channels,
+        };
+    }
+
+    let mut out = BitWriter::default();
+    out.write(FRAME_SYNC, 14);
+    out.write(0, 1); // reserved
+    out.write(0, 1); // fixed block size
+    let block_code = if block_size == info.max_block_size as usize && block_size == 4096 { 0b1100 } else { 0b0111 };
+    out.write(block_code, 4);
+    out.write(sample_rate_code(info.sample_rate), 4);
+    out.write(assignment, 4);
+    out.write(sample_size_code(info.bits_per_sample), 3);
+    out.write(0, 1); // reserved
+    write_utf8_number(&mut out, frame_number);
+    if block_code == 0b0111 {
+        out.write(block_size as u64 - 1, 16);
+    }
+    let header = std::mem::take(&mut out.bytes);
+    let mut out = BitWriter { bytes: header, ..BitWriter::default() };
+    let header_crc = crc8(&out.bytes);
+    out.write(header_crc as u64, 8);
+
+    for (samples, bits) in &channels {
+        encode_subframe(&mut out, samples, *bits);
+    }
+    let mut bytes = out.into_bytes();
+    let footer = crc16(&bytes);
+    bytes.extend_from_slice(&footer.to_be_bytes());
+    bytes
+}
+
+fn stream_info_block(info: &FlacStreamInfo, last: bool) -> Vec<u8> {
+    let mut out = BitWriter::default();
+    out.write(if last { 1 } else { 0 }, 1);
+    out.write(0, 7); // STREAMINFO
+    out.write(34, 24);
+    out.write(info.min_block_size as u64, 16);
+    out.write(info.max_block_size as u64, 16);
+    out.write(info.min_frame_size as u64, 24);
+    out.write(info.max_frame_size as u64, 24);
+    out.write(info.sample_rate as u64, 20);
+    out.write(info.channels as u64 - 1, 3);
+    out.write(info.bits_per_sample as u64 - 1, 5);
+    out.write(info.total_samples >> 32, 4);
+    out.write(info.total_samples & 0xFFFF_FFFF, 32);
+    for _ in 0..4 {
+        out.write(0, 32); // MD5 not computed
+    }
+    out.into_bytes()
+}
+
+/// Streaming FLAC encoder
+pub struct FlacEncoder<W: Write + Seek> {
+    writer: W,
+    info: FlacStreamInfo,
+    block_size: usize,
+    pending: Vec<i32>,
+    frame_number: u64,
+    start: u64,
+}
+
+impl<W: Write + Seek> FlacEncoder<W> {
+    /// Start a stream; STREAMINFO is rewritten with the totals by `finish`
+    pub fn new(mut writer: W, sample_rate: u32, channels: u16, bits_per_sample: u16) -> Result<Self, AudioError> {
+        if !(1..=8).contains(&channels) {
+            return Err(codec_error(format!("FLAC supports 1-8 channels, got {}", channels)));
+        }
+        if !(4..=24).contains(&bits_per_sample) {
+            return Err(codec_error(format!("FLAC encoder supports 4-24 bits per sample, got {}", bits_per_sample)));
+        }
+        if sample_rate == 0 || sample_rate >= 1 << 20 {
+            return Err(codec_error(format!("Invalid FLAC sample rate {}", sample_rate)));
+        }
+        let info = FlacStreamInfo {
+            sample_rate,
+            channels,
+            bits_per_sample,
+            total_samples: 0,
+            min_block_size: DEFAULT_BLOCK_SIZE as u16,
+            max_block_size: DEFAULT_BLOCK_SIZE as u16,
+            min_frame_size: 0,
+            max_frame_size: 0,
+        };
+        let start = writer.stream_position()?;
+        writer.write_all(b"fLaC")?;
+        writer.write_all(&stream_info_block(&info, true))?;
+        Ok(Self { writer, info, block_size: DEFAULT_BLOCK_SIZE, pending: Vec::new(), frame_number: 0, start })
+    }
+
+    /// Stream parameters so far
+    pub fn info(&self) -> &FlacStreamInfo {
+        &self.info
+    }
+
+    /// Append interleaved integer samples
+    pub fn write_samples(&mut self, interleaved: &[i32]) -> Result<(), AudioError> {
+        self.pending.extend_from_slice(interleaved);
+        let frame_len = self.block_size * self.info.channels as usize;
+        if self.pending.len() >= frame_len {
+            let whole = self.pending.len() / frame_len * frame_len;
+            let ready: Vec<i32> = self.pending.drain(..whole).collect();
+            for frame in ready.chunks(frame_len) {
+                self.write_frame(frame)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Append interleaved float samples, quantized to the stream bit depth
+    pub fn write_f32(&mut self, interleaved: &[f32]) -> Result<(), AudioError> {
+        let samples = quantize(interleaved, self.info.bits_per_sample);
+        self.write_samples(&samples)
+    }
+
+    fn write_frame(&mut self, interleaved: &[i32]) -> Result<(), AudioError> {
+        let channels = self.info.channels as usize;
+        let block_size = interleaved.len() / channels;
+        let blocks: Vec<Vec<i64>> = (0..channels)
+            .map(|c| interleaved.iter().skip(c).step_by(channels).map(|s| *s as i64).collect())
+            .collect();
+        let bytes = encode_frame(&self.info, self.frame_number, &blocks);
+        self.writer.write_all(&bytes)?;
+
+        let size = bytes.len() as u32;
+        self.info.min_frame_size = if self.frame_number == 0 { size } else { self.info.min_frame_size.min(size) };
+        self.info.max_frame_size = self.info.max_frame_size.max(size);
+        self.info.total_samples += block_size as u64;
+        if block_size < self.block_size {
+            self.info.min_block_size = self.info.min_block_size.min(block_size as u16);
+        }
+        self.frame_number += 1;
+        Ok(())
+    }
+
+    /// Flush the last partial frame, finalize STREAMINFO and return the writer
+    pub fn finish(mut self) -> Result<W, AudioError> {
+        let channels = self.info.channels as usize;
+        let remainder = std::mem::take(&mut self.pending);
+        let usable = remainder.len() / channels * channels;
+        if usable > 0 {
+            self.write_frame(&remainder[..usable])?;
+        }
+        if self.frame_number <= 1 {
+            // A single frame stream has no fixed block size to advertise
+            let only = self.info.total_samples.max(16) as u16;
+            self.info.min_block_size = only.min(self.info.max_block_size);
+        } else if self.info.min_block_size < 16 {
+            self.info.min_block_size = 16;
+        }
+        let end = self.writer.stream_position()?;
+        self.writer.seek(SeekFrom::Start(self.start + 4))?;
+        self.writer.write_all(&stream_info_block(&self.info, true))?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Float samples to integers at `bits` (clamped)
+pub fn quantize(samples: &[f32], bits: u16) -> Vec<i32> {
+    let scale = (1i64 << (bits - 1)) as f32;
+    let (lo, hi) = (-(scale as i64), scale as i64 - 1);
+    samples
+        .iter()
+        .map(|s| ((s * scale).round() as i64).clamp(lo, hi) as i32)
+        .collect()
+}
+
+/// Encode interleaved float samples into an in-memory FLAC stream
+pub fn encode_f32(samples: &[f32], sample_rate: u32, channels: u16, bits_per_sample: u16) -> Result<Vec<u8>, AudioError> {
+    let mut encoder = FlacEncoder::new(std::io::Cursor::new(Vec::new()), sample_rate, channels, bits_per_sample)?;
+    encoder.write_f32(samples)?;
+    Ok(encoder.finish()?.into_inner())
+}
+
+fn parse_stream_info(data: &[u8]) -> Result<FlacStreamInfo, AudioError> {
+    let mut r = BitReader::new(data);
+    Ok(FlacStreamInfo {
+        min_block_size: r.read(16)? as u16,
+        max_block_size: r.read(16)? as u16,
+        min_frame_size: r.read(24)? as u32,
+        max_frame_size: r.read(24)? as u32,
+        sample_rate: r.read(20)? as u32,
+        channels: r.read(3)? as u16 + 1,
+        bits_per_sample: r.read(5)? as u16 + 1,
+        total_samples: r.read(36)?,
+    })
+}
+
+/// Read STREAMINFO and return it with the offset of the first frame
+pub fn read_stream_info(data: &[u8]) -> Result<(FlacStreamInfo, usize), AudioError> {
+    if data.len() < 4 || &data[..4] != b"fLaC" {
+        return Err(codec_error("Not a FLAC stream"));
+    }
+    let mut pos = 4;
+    let mut info = None;
+    loop {
+        let header = data.get(pos..pos + 4).ok_or_else(|| codec_error("Truncated FLAC metadata"))?;
+        let last = header[0] & 0x80 != 0;
+        let block_type = header[0] & 0x7F;
+        let len = ((header[1] as usize) << 16) | ((header[2] as usize) << 8) | header[3] as usize;
+        let body = data.get(pos + 4..pos + 4 + len).ok_or_else(|| codec_error("Truncated FLAC metadata"))?;
+        if block_type == 0 {
+            info = Some(parse_stream_info(body)?);
+        }
+        pos += 4 + len;
+        if last {
+            break;
+        }
+    }
+    info.map(|i| (i, pos)).ok_or_else(|| codec_error("FLAC stream has no STREAMINFO"))
+}
+
+fn read_utf8_number(r: &mut BitReader) -> Result<u64, AudioError> {
+    let first = r.read(8)?;
+    let continuation = match first {
+        0x00..=0x7F => return Ok(first),
+        0xC0..=0xDF => 1,
+        0xE0..=0xEF => 2,
+        0xF0..=0xF7 => 3,
+        0xF8..=0xFB => 4,
+        0xFC..=0xFD => 5,
+        0xFE => 6,
+        _ => return Err(codec_error("Invalid FLAC frame number")),
+    };
+    let mut value = first & (0x3F >> continuation);
+    for _ in 0..continuation {
+        let byte = r.read(8)?;
+        if byte & 0xC0 != 0x80 {
+            return Err(codec_error("Invalid FLAC frame number"));
+        }
+        value = (value << 6) | (byte & 0x3F);
+    }
+    Ok(value)
+}
+
+fn decode_residual(r: &mut BitReader, block_size: usize, order: usize, out: &mut Vec<i64>) -> Result<(), AudioError> {
+    let param_bits = match r.read(2)? {
+        0 => 4,
+        1 => 5,
+        _ => return Err(codec_error("Reserved FLAC residual coding method")),
+    };
+    let escape = (1u64 << param_bits) - 1;
+    let partition_order = r.read(4)? as u32;
+    let partitions = 1usize << partition_order;
+    if !block_size.is_multiple_of(partitions) || block_size / partitions < order {
+        return Err(codec_error("Invalid FLAC residual partitioning"));
+    }
+    for p in 0..partitions {
+        let len = if p == 0 { block_size / partitions - order } else { block_size / partitions };
+        let param = r.read(param_bits)?;
+        if param == escape {
+            let raw_bits = r.read(5)? as u32;
+            for _ in 0..len {
+                out.push(r.read_signed(raw_bits)?);
+            }
+        } else {
+            for _ in 0..len {
+                out.push(r.read_rice(param as u32)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn decode_subframe(r: &mut BitReader, block_size: usize, bits: u32) -> Result<Vec<i64>, AudioError> {
+    if r.bit()? != 0 {
+        return Err(codec_error("Invalid FLAC subframe padding"));
+    }
+    let kind = r.read(6)?;
+    let wasted = if r.bit()? == 1 { r.read_unary()? as u32 + 1 } else { 0 };
+    let bits = bits - wasted;
+
+    let mut samples: Vec<i64> = Vec::with_capacity(block_size);
+    match kind {
+        0 => {
+            let value = r.read_signed(bits)?;
+            samples.resize(block_size, value);
+        }
+        1 => {
+            for _ in 0..block_size {
+                samples.push(r.read_signed(bits)?);
+            }
+        }
+        8..=12 => {
+            let order = (kind - 8) as usize;
+            for _ in 0..order {
+                samples.push(r.read_signed(bits)?);
+            }
+            decode_residual(r, block_size, order, &mut samples)?;
+            for i in order..block_size {
+                let s = |k: usize| samples[i - k];
+                let prediction = match order {
+                    0 => 0,
+                    1 => s(1),
+                    2 => 2 * s(1) - s(2),
+                    3 => 3 * s(1) - 3 * s(2) + s(3),
+                    _ => 4 * s(1) - 6 * s(2) + 4 * s(3) - s(4),
+                };
+                samples[i] += prediction;
+            }
+        }
+        32..=63 => {
+            let order = (kind - 31) as usize;
+            for _ in 0..order {
+                samples.push(r.read_signed(bits)?);
+            }
+            let precision = r.read(4)? as u32 + 1;
+            if precision == 16 {
+                return Err(codec_error("Invalid FLAC LPC precision"));
+            }
+            let shift = r.read_signed(5)?;
+            if shift < 0 {
+                return Err(codec_error("Negative FLAC LPC shift"));
+            }
+            let coefs: Vec<i64> = (0..order).map(|_| r.read_signed(precision)).collect::<Result<_, _>>()?;
+            decode_residual(r, block_size, order, &mut samples)?;
+            for i in order..block_size {
+                let prediction: i64 = coefs.iter().enumerate().map(|(j, c)| c * samples[i - j - 1]).sum();
+                samples[i] += prediction >> shift;
+            }
+        }
+        _ => return Err(codec_error(format!("Reserved FLAC subframe type {}", kind))),
+    }
+    if wasted > 0 {
+        samples.iter_mut().for_each(|s| *s <<= wasted);
+    }
+    Ok(samples)
+}
+
+/// Decode one frame starting at `data[0]`; returns channel blocks and bytes used
+fn decode_frame(data: &[u8], info: &FlacStreamInfo) -> Result<(Vec<Vec<i64>>, usize), AudioError> {
+    let mut r = BitReader::new(data);
+    if r.read(14)? != FRAME_SYNC {
+        return Err(codec_error("Lost FLAC frame sync"));
+    }
+    r.read(2)?; // reserved, blocking strategy
+    let block_code = r.read(4)?;
+    let rate_code = r.read(4)?;
+    let assignment = r.read(4)?;
+    let size_code = r.read(3)?;
+    r.read(1)?;
+    read_utf8_number(&mut r)?;
+    let block_size = match block_code {
+        1 => 192,
+        2..=5 => 576 << (block_code - 2),
+        6 => r.read(8)? as usize + 1,
+        7 => r.read(16)? as usize + 1,
+        8..=15 => 256 << (block_code - 8),
+        _ => return Err(codec_error("Reserved FLAC block size")),
+    };
+    match rate_code {
+        12 => { r.read(8)?; }
+        13 | 14 => { r.read(16)?; }
+        15 => return Err(codec_error("Invalid FLAC sample rate code")),
+        _ => {}
+    }
+    let bps = match size_code {
+        0 => info.bits_per_sample as u32,
+        1 => 8,
+        2 => 12,
+        4 => 16,
+        5 => 20,
+        6 => 24,
+        7 => 32,
+        _ => return Err(codec_error("Reserved FLAC sample size")),
+    };
+    let header_len = r.byte_pos();
+    if r.read(8)? as u8 != crc8(&data[..header_len]) {
+        return Err(codec_error("FLAC frame header CRC mismatch"));
+    }
+
+    let (channel_count, side_channel) = match assignment {
+        0..=7 => (assignment as usize + 1, None),
+        8 => (2, Some(1)),
+        9 => (2, Some(0)),
+        10 => (2, Some(1)),
+        _ => return Err(codec_error("Reserved FLAC channel assignment")),
+    };
+    let mut channels = Vec::with_capacity(channel_count);
+    for c in 0..channel_count {
+        let bits = if side_channel == Some(c) { bps + 1 } else { bps };
+        channels.push(decode_subframe(&mut r, block_size, bits)?);
+    }
+    r.align();
+    let end = r.byte_pos();
+    let expected = r.read(16)? as u16;
+    if expected != crc16(&data[..end]) {
+        return Err(codec_error("FLAC frame CRC mismatch"));
+    }
+
+    if let [first, second] = channels.as_mut_slice() {
+        for (a, b) in first.iter_mut().zip(second.iter_mut()) {
+            match assignment {
+                // left/side
+                8 => *b = *a - *b,
+                // side/right
+                9 => *a += *b,
+                // mid/side
+                10 => {
+                    let side = *b;
+                    let mid = (*a << 1) | (side & 1);
+                    *a = (mid + side) >> 1;
+                    *b = (mid - side) >> 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok((channels, end + 2))
+}
+
+/// Decode a complete FLAC stream
+pub fn decode(data: &[u8]) -> Result<FlacAudio, AudioError> {
+    let (info, mut pos) = read_stream_info(data)?;
+    let mut samples = Vec::with_capacity(info.total_samples as usize * info.channels as usize);
+    while pos + 2 <= data.len() {
+        let (channels, used) = decode_frame(&data[pos..], &info)?;
+        if channels.len() != info.channels as usize {
+            return Err(codec_error("FLAC frame channel count differs from STREAMINFO"));
+        }
+        for i in 0..channels[0].len() {
+            for channel in &channels {
+                samples.push(channel[i] as i32);
+            }
+        }
+        pos += used;
+    }
+    let decoded = samples.len() as u64 / info.channels as u64;
+    if info.total_samples != 0 && decoded != info.total_samples {
+        return Err(codec_error(format!("FLAC stream truncated: {} of {} samples", decoded, info.total_samples)));
+    }
+    Ok(FlacAudio { info, samples })
+}
+
+/// Decode a FLAC file
+pub fn read_file(path: &Path) -> Result<FlacAudio, AudioError> {
+    decode(&std::fs::read(path)?)
+}
+
+/// Decode `written` and check it reproduces `expected` exactly
+pub fn verify(written: &[u8], expected: &[i32]) -> Result<(), AudioError> {
+    let decoded = decode(written)?;
+    if decoded.samples.len() != expected.len() {
+        return Err(codec_error(format!(
+            "FLAC verify failed: {} samples written, {} decoded",
+            expected.len(),
+            decoded.samples.len()
+        )));
+    }
+    if let Some(i) = decoded.samples.iter().zip(expected).position(|(a, b)| a != b) {
+        return Err(codec_error(format!("FLAC verify failed: sample {} differs", i)));
+    }
+    Ok(())
+}
+
+/// Convert a WAV file to FLAC, verifying the output before returning
+pub fn convert_wav_file(wav_path: &Path, flac_path: &Path) -> Result<FlacStreamInfo, AudioError> {
+    let mut reader = hound::WavReader::open(wav_path)
+        .map_err(|e| codec_error(format!("Failed to open WAV {}: {}", wav_path.display(), e)))?;
+    let spec = reader.spec();
+    let samples: Vec<i32> = match spec.sample_format {
+        hound::SampleFormat::Int if spec.bits_per_sample <= 24 => reader
+            .samples::<i32>()
+            .collect::<Result<_, _>>()
+            .map_err(|e| codec_error(format!("Failed to read WAV samples: {}", e)))?,
+        // Float and 32-bit WAVs are stored at 24 bits
+        hound::SampleFormat::Float => {
+            let floats: Vec<f32> = reader
+                .samples::<f32>()
+                .collect::<Result<_, _>>()
+                .map_err(|e| codec_error(format!("Failed to read WAV samples: {}", e)))?;
+            quantize(&floats, 24)
+        }
+        hound::SampleFormat::Int => reader
+            .samples::<i32>()
+            .map(|s| s.map(|v| v >> 8))
+            .collect::<Result<_, _>>()
+            .map_err(|e| codec_error(format!("Failed to read WAV samples: {}", e)))?,
+    };
+    let bits = match spec.sample_format {
+        hound::SampleFormat::Int if spec.bits_per_sample <= 24 => spec.bits_per_sample,
+        _ => 24,
+    };
+
+    let file = BufWriter::new(File::create(flac_path)?);
+    let mut encoder = FlacEncoder::new(file, spec.sample_rate, spec.channels, bits)?;
+    for chunk in samples.chunks(DEFAULT_BLOCK_SIZE * spec.channels as usize * 16) {
+        encoder.write_samples(chunk)?;
+    }
+    let info = *encoder.info();
+    let mut file = encoder.finish()?;
+    file.flush()?;
+    drop(file);
+
+    if let Err(e) = verify(&std::fs::read(flac_path)?, &samples) {
+        let _ = std::fs::remove_file(flac_path);
+        return Err(e);
+    }
+    Ok(FlacStreamInfo { total_samples: (samples.len() / spec.channels.max(1) as usize) as u64, ..info })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal(len: usize, channels: usize) -> Vec<i32> {
+        let mut seed = 7u32;
+        (0..len * channels)
+            .map(|i| {
+                let t = (i / channels) as f32 / 16000.0;
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                let noise = ((seed >> 16) % 64) as f32 - 32.0;
+                let tone = if i % channels == 0 { 440.0 } else { 660.0 };
+                (8000.0 * (2.0 * std::f32::consts::PI * tone * t).sin() + noise) as i32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip_mono_and_stereo() {
+        for channels in [1u16, 2] {
+            // Not a multiple of the block size, so the last frame is partial
+            let samples = signal(DEFAULT_BLOCK_SIZE * 3 + 1234, channels as usize);
+            let mut encoder = FlacEncoder::new(std::io::Cursor::new(Vec::new()), 16000, channels, 16).unwrap();
+            for chunk in samples.chunks(1000) {
+                encoder.write_samples(chunk).unwrap();
+            }
+            let bytes = encoder.finish().unwrap().into_inner();
+
+            verify(&bytes, &samples).unwrap();
+            let decoded = decode(&bytes).unwrap();
+            assert_eq!(decoded.info.total_samples, (DEFAULT_BLOCK_SIZE * 3 + 1234) as u64);
+            assert_eq!(decoded.info.channels, channels);
+            assert!(bytes.len() < samples.len() * 2 * 3 / 4, "{} bytes for {} samples", bytes.len(), samples.len());
+        }
+    }
+
+    #[test]
+    fn test_corruption_is_detected() {
+        let samples = signal(DEFAULT_BLOCK_SIZE, 1);
+        let mut bytes = encode_f32(&samples.iter().map(|s| *s as f32 / 32768.0).collect::<Vec<_>>(), 16000, 1, 16).unwrap();
+        let silence = encode_f32(&[0.0; 100], 44100, 1, 16).unwrap();
+        assert_eq!(decode(&silence).unwrap().samples, vec![0; 100]);
+
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0x10;
+        assert!(decode(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_wav_archive_conversion() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let wav = dir.path().join("session.wav");
+        let spec = hound::WavSpec { channels: 1, sample_rate: 16000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut writer = hound::WavWriter::create(&wav, spec).unwrap();
+        let samples = signal(20000, 1);
+        for s in &samples {
+            writer.write_sample(*s as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let flac_path = dir.path().join("session.flac");
+        let info = convert_wav_file(&wav, &flac_path).unwrap();
+        assert_eq!(info.total_samples, 20000);
+        assert_eq!(read_file(&flac_path).unwrap().samples, samples);
+        assert!(std::fs::metadata(&flac_path).unwrap().len() < std::fs::metadata(&wav).unwrap().len());
+    }
+}
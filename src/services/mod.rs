@@ -2,23 +2,48 @@
 
 pub mod audio;
 pub mod audio_playback;
+pub mod time_stretch;
 pub mod clipboard;
 pub mod hotkey;
 pub mod paste;
 pub mod stt;
 pub mod tts;
 pub mod vad;
+pub mod agc;
+pub mod capture_config;
+pub mod device_monitor;
+pub mod source_mixer;
+pub mod signal_analysis;
+pub mod echo_cancel;
+pub mod wake_word;
+pub mod diarization;
+pub mod prosody;
 pub mod voice_commands;
 pub mod audio_archive;
 pub mod audio_storage;
+pub mod path_template;
+pub mod flac;
+pub mod ogg_opus;
+pub mod encryption;
+pub mod retention;
 pub mod audio_menu;
 pub mod audio_session_manager;
+pub mod session_journal;
+pub mod session_edit;
+pub mod session_markers;
+pub mod session_supervisor;
+pub mod session_import;
+pub mod session_bundle;
+pub mod dataset_export;
+pub mod archive_check;
+pub mod waveform;
 pub mod session_transcript_tracker;
 pub mod transcription_log;
 pub mod transcription_deduplication;
 pub mod transcription_search;
 pub mod transcription_analytics;
 pub mod transcript_storage;
+pub mod transcript_revisions;
 pub mod transcription_manager;
 
 pub use audio::AudioService;
@@ -29,13 +54,36 @@ pub use paste::PasteService;
 pub use stt::STTService;
 pub use tts::TTSService;
 pub use vad::{VADService, VADMode};
+pub use agc::{AutomaticGainControl, AGCConfig, GainStats};
+pub use capture_config::{CaptureConfig, CaptureSampleFormat, NegotiatedConfig, PreRollBuffer};
+pub use device_monitor::{DeviceEvent, DeviceWatcher};
+pub use source_mixer::{FileSource, MixMode, MixedFrame, MixerStats, SecondarySourceConfig, SourceMixer, SourceMixerConfig};
+pub use signal_analysis::{SignalAnalysisConfig, SignalAnalyzer};
+pub use echo_cancel::{EchoCancellerConfig, EchoReference, EchoStats};
+pub use wake_word::{WakeDecision, WakeWordConfig, WakeWordGate};
+pub use diarization::{DiarizationConfig, SpeakerAssignment, SpeakerDiarizer};
+pub use prosody::{ProsodyAnalyzer, ProsodyFeatures, ProsodyProfile};
 pub use voice_commands::{VoiceCommandEngine, VoiceCommand, CommandCategory, CommandResult, VoiceCommandError};
 pub use audio_archive::{AudioArchiveService, AudioError, RecordingSession};
 pub use audio_storage::FileAudioStorage;
+pub use path_template::PathTemplate;
+pub use flac::{FlacEncoder, FlacStreamInfo};
+pub use encryption::{EncryptionError, KeySource, StorageCipher};
+pub use retention::{RetentionEngine, RetentionPeriod, RetentionRules};
 pub use audio_menu::AudioRecordingMenu;
+pub use session_journal::SessionJournal;
+pub use session_edit::SplitPoint;
+pub use session_markers::{MarkerKind, SessionMarker};
+pub use session_supervisor::{SessionSupervisor, SupervisorConfig, SupervisorEvent};
+pub use session_import::{ImportOutcome, ImportedAudio};
+pub use session_bundle::{BundleAudio, BundleManifest, BundleOptions, BundleSigningKey, IdPolicy, SessionBundle};
+pub use dataset_export::{DatasetFormat, DatasetOptions, DatasetSummary, DatasetWriter, SplitBy};
+pub use archive_check::{ArchiveCheckReport, ArchiveChecker, ArchiveIssue, IssueKind};
+pub use waveform::{LoudnessSummary, SessionOverview, WaveformLevel};
 pub use transcription_log::{TranscriptionLogService, TranscriptEntry, TranscriptError};
 pub use transcription_deduplication::TranscriptDeduplicator;
 pub use transcription_search::TranscriptIndexer;
 pub use transcription_analytics::TranscriptAnalytics;
 pub use transcript_storage::{FileTranscriptStorage, FileStorageConfig};
+pub use transcript_revisions::{DecodingConfig, RetranscriptionJob, RetranscriptionSelection, TranscriptRevision};
 pub use transcription_manager::{TranscriptionManager, TranscriptionManagerConfig, TranscriptionResult};
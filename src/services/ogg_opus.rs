@@ -0,0 +1,436 @@
+//! Ogg/Opus archive encoding (RFC 7845).
+//!
+//! Long recordings are archived as speech-tuned Opus in an Ogg container.
+//! The container, header packets, granule positions and resampling live
+//! here; the Opus codec itself comes from libopus through the `opus` crate
+//! and is only available with the `opus-archive` feature.
+//!
+//! Opus runs at 8, 12, 16, 24 or 48 kHz. Sessions at one of those rates are
+//! encoded directly; anything else is resampled to 48 kHz and converted
+//! back to the original rate (stored in OpusHead) on decode.
+
+use std::io::Write;
+
+use super::audio_archive::{AudioError, CompressionLevel};
+
+/// Granule positions are always counted at 48 kHz
+pub const GRANULE_RATE: u32 = 48_000;
+
+/// Frame duration used by the encoder
+pub const FRAME_MS: u32 = 20;
+
+/// Target page body size before a page is flushed
+const PAGE_TARGET_BYTES: usize = 4096;
+
+const VENDOR: &str = concat!("stt-clippy ", env!("CARGO_PKG_VERSION"));
+
+/// Decoded Opus audio at the original input rate
+#[derive(Debug, Clone)]
+pub struct OpusAudio {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Interleaved samples
+    pub samples: Vec<f32>,
+}
+
+fn codec_error(message: impl Into<String>) -> AudioError {
+    AudioError::CompressionError(message.into())
+}
+
+/// Rate the Opus codec runs at for a given input rate
+pub fn codec_rate(sample_rate: u32) -> u32 {
+    match sample_rate {
+        8_000 | 12_000 | 16_000 | 24_000 | 48_000 => sample_rate,
+        _ => GRANULE_RATE,
+    }
+}
+
+/// Target bitrate in bits per second for an archive compression level
+pub fn bitrate_for_level(level: &CompressionLevel, channels: u16) -> i32 {
+    let per_channel = match level {
+        CompressionLevel::None => 48_000,
+        CompressionLevel::Low => 32_000,
+        CompressionLevel::Medium => 24_000,
+        CompressionLevel::High => 16_000,
+        CompressionLevel::Maximum => 12_000,
+    };
+    per_channel * channels.max(1) as i32
+}
+
+/// Linear-interpolation resampler for interleaved audio
+pub fn resample(samples: &[f32], channels: u16, from_rate: u32, to_rate: u32) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let frames_in = samples.len() / channels;
+    let frames_out = (frames_in as u64 * to_rate as u64 / from_rate as u64) as usize;
+    let step = from_rate as f64 / to_rate as f64;
+    let mut out = Vec::with_capacity(frames_out * channels);
+    for i in 0..frames_out {
+        let pos = i as f64 * step;
+        let index = pos as usize;
+        let frac = (pos - index as f64) as f32;
+        let next = (index + 1).min(frames_in - 1);
+        for c in 0..channels {
+            let a = samples[index * channels + c];
+            let b = samples[next * channels + c];
+            out.push(a + (b - a) * frac);
+        }
+    }
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for &byte in data {
+        crc ^= (byte as u32) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 { (crc << 1) ^ 0x04C1_1DB7 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Writes packets into Ogg pages of one logical stream
+pub struct OggWriter<W: Write> {
+    writer: W,
+    serial: u32,
+    sequence: u32,
+    lacing: Vec<u8>,
+    body: Vec<u8>,
+    granule: u64,
+    /// A packet ends on the current page
+    completed: bool,
+    begun: bool,
+    continued: bool,
+}
+
+impl<W: Write> OggWriter<W> {
+    pub fn new(writer: W, serial: u32) -> Self {
+        Self {
+            writer,
+            serial,
+            sequence: 0,
+            lacing: Vec::new(),
+            body: Vec::new(),
+            granule: 0,
+            completed: false,
+            begun: false,
+            continued: false,
+        }
+    }
+
+    /// Queue a packet; `granule` is the stream position after it
+    pub fn write_packet(&mut self, packet: &[u8], granule: u64) -> Result<(), AudioError> {
+        let mut rest = packet;
+        let mut started = false;
+        loop {
+            if self.lacing.len() == 255 {
+                self.flush_page(false)?;
+                // A packet split across pages marks the next page as continued
+                self.continued = started;
+            }
+            started = true;
+            let take = rest.len().min(255);
+            self.lacing.push(take as u8);
+            self.body.extend_from_slice(&rest[..take]);
+            rest = &rest[take..];
+            if take < 255 {
+                break;
+            }
+        }
+        self.granule = granule;
+        self.completed = true;
+        if self.body.len() >= PAGE_TARGET_BYTES {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Close the current page so the next packet starts a new one
+    pub fn flush(&mut self) -> Result<(), AudioError> {
+        if !self.lacing.is_empty() {
+            self.flush_page(false)?;
+        }
+        Ok(())
+    }
+
+    /// Write the final page, marked end-of-stream, and return the writer
+    pub fn finish(mut self) -> Result<W, AudioError> {
+        self.flush_page(true)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn flush_page(&mut self, last: bool) -> Result<(), AudioError> {
+        // Pages where no packet finishes carry a granule position of -1
+        let granule = if std::mem::take(&mut self.completed) || last { self.granule } else { u64::MAX };
+        let mut flags = 0u8;
+        if std::mem::take(&mut self.continued) {
+            flags |= 0x01;
+        }
+        if !self.begun {
+            flags |= 0x02;
+            self.begun = true;
+        }
+        if last {
+            flags |= 0x04;
+        }
+        let mut page = Vec::with_capacity(27 + self.lacing.len() + self.body.len());
+        page.extend_from_slice(b"OggS");
+        page.push(0);
+        page.push(flags);
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&self.serial.to_le_bytes());
+        page.extend_from_slice(&self.sequence.to_le_bytes());
+        page.extend_from_slice(&[0; 4]);
+        page.push(self.lacing.len() as u8);
+        page.append(&mut self.lacing);
+        page.append(&mut self.body);
+        let crc = crc32(&page);
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+        self.writer.write_all(&page)?;
+        self.sequence += 1;
+        Ok(())
+    }
+}
+
+/// Packets of the first logical stream, with the last page's granule position
+pub fn read_packets(data: &[u8]) -> Result<(Vec<Vec<u8>>, u64), AudioError> {
+    let mut packets = Vec::new();
+    let mut partial: Vec<u8> = Vec::new();
+    let mut pos = 0;
+    let mut serial = None;
+    let mut granule = 0u64;
+    while pos < data.len() {
+        let header = data.get(pos..pos + 27).ok_or_else(|| codec_error("Truncated Ogg page"))?;
+        if &header[..4] != b"OggS" {
+            return Err(codec_error("Lost Ogg page sync"));
+        }
+        let segments = header[26] as usize;
+        let lacing = data.get(pos + 27..pos + 27 + segments).ok_or_else(|| codec_error("Truncated Ogg page"))?;
+        let body_len: usize = lacing.iter().map(|l| *l as usize).sum();
+        let page_len = 27 + segments + body_len;
+        let page = data.get(pos..pos + page_len).ok_or_else(|| codec_error("Truncated Ogg page"))?;
+
+        let mut check = page.to_vec();
+        check[22..26].fill(0);
+        let expected = u32::from_le_bytes([page[22], page[23], page[24], page[25]]);
+        if crc32(&check) != expected {
+            return Err(codec_error("Ogg page CRC mismatch"));
+        }
+
+        let page_serial = u32::from_le_bytes([header[14], header[15], header[16], header[17]]);
+        if *serial.get_or_insert(page_serial) == page_serial {
+            let page_granule = u64::from_le_bytes(header[6..14].try_into().unwrap_or_default());
+            if page_granule != u64::MAX {
+                granule = page_granule;
+            }
+            let mut body = &page[27 + segments..];
+            for &len in lacing {
+                partial.extend_from_slice(&body[..len as usize]);
+                body = &body[len as usize..];
+                if len < 255 {
+                    packets.push(std::mem::take(&mut partial));
+                }
+            }
+        }
+        pos += page_len;
+    }
+    Ok((packets, granule))
+}
+
+/// OpusHead identification packet
+pub fn opus_head(channels: u16, pre_skip: u16, input_sample_rate: u32) -> Vec<u8> {
+    let mut head = Vec::with_capacity(19);
+    head.extend_from_slice(b"OpusHead");
+    head.push(1);
+    head.push(channels as u8);
+    head.extend_from_slice(&pre_skip.to_le_bytes());
+    head.extend_from_slice(&input_sample_rate.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+    head.push(0); // channel mapping family
+    head
+}
+
+/// OpusTags comment packet with no user comments
+pub fn opus_tags() -> Vec<u8> {
+    let mut tags = Vec::new();
+    tags.extend_from_slice(b"OpusTags");
+    tags.extend_from_slice(&(VENDOR.len() as u32).to_le_bytes());
+    tags.extend_from_slice(VENDOR.as_bytes());
+    tags.extend_from_slice(&0u32.to_le_bytes());
+    tags
+}
+
+/// Channels, pre-skip and original input rate from an OpusHead packet
+fn parse_opus_head(packet: &[u8]) -> Result<(u16, u16, u32), AudioError> {
+    if packet.len() < 19 || &packet[..8] != b"OpusHead" {
+        return Err(codec_error("Missing OpusHead packet"));
+    }
+    if packet[8] >> 4 != 0 {
+        return Err(codec_error(format!("Unsupported Opus header version {}", packet[8])));
+    }
+    if packet[18] != 0 {
+        return Err(codec_error("Only mono/stereo Opus streams are supported"));
+    }
+    let channels = packet[9] as u16;
+    let pre_skip = u16::from_le_bytes([packet[10], packet[11]]);
+    let input_rate = u32::from_le_bytes([packet[12], packet[13], packet[14], packet[15]]);
+    Ok((channels, pre_skip, input_rate))
+}
+
+#[cfg(feature = "opus-archive")]
+fn opus_channels(channels: u16) -> Result<opus::Channels, AudioError> {
+    match channels {
+        1 => Ok(opus::Channels::Mono),
+        2 => Ok(opus::Channels::Stereo),
+        _ => Err(codec_error(format!("Opus archive supports 1 or 2 channels, got {}", channels))),
+    }
+}
+
+/// Encode interleaved samples as an Ogg/Opus stream
+#[cfg(feature = "opus-archive")]
+pub fn encode(samples: &[f32], sample_rate: u32, channels: u16, level: &CompressionLevel) -> Result<Vec<u8>, AudioError> {
+    let opus_error = |e: opus::Error| codec_error(format!("Opus encoder error: {}", e));
+    let rate = codec_rate(sample_rate);
+    let input = resample(samples, channels, sample_rate, rate);
+    let ch = channels as usize;
+    let frame = (rate * FRAME_MS / 1000) as usize;
+    let scale = (GRANULE_RATE / rate) as u64;
+
+    let mut encoder = opus::Encoder::new(rate, opus_channels(channels)?, opus::Application::Voip).map_err(opus_error)?;
+    encoder
+        .set_bitrate(opus::Bitrate::Bits(bitrate_for_level(level, channels)))
+        .map_err(opus_error)?;
+    let pre_skip = (encoder.get_lookahead().map_err(opus_error)? as u64 * scale) as u16;
+
+    let mut ogg = OggWriter::new(Vec::new(), rand::random());
+    ogg.write_packet(&opus_head(channels, pre_skip, sample_rate), 0)?;
+    ogg.flush()?;
+    ogg.write_packet(&opus_tags(), 0)?;
+    ogg.flush()?;
+
+    let total_frames = (input.len() / ch) as u64;
+    let mut packet = vec![0u8; 4000];
+    let mut block = vec![0f32; frame * ch];
+    let mut encoded = 0u64;
+    for chunk in input.chunks(frame * ch) {
+        block[..chunk.len()].copy_from_slice(chunk);
+        block[chunk.len()..].fill(0.0);
+        let len = encoder.encode_float(&block, &mut packet).map_err(opus_error)?;
+        encoded += (chunk.len() / ch) as u64;
+        // The final granule trims the zero padding of the last frame
+        ogg.write_packet(&packet[..len], pre_skip as u64 + encoded.min(total_frames) * scale)?;
+    }
+    ogg.finish()
+}
+
+/// Decode an Ogg/Opus stream back to the original sample rate
+#[cfg(feature = "opus-archive")]
+pub fn decode(data: &[u8]) -> Result<OpusAudio, AudioError> {
+    let opus_error = |e: opus::Error| codec_error(format!("Opus decoder error: {}", e));
+    let (packets, final_granule) = read_packets(data)?;
+    let head = packets.first().ok_or_else(|| codec_error("Empty Ogg stream"))?;
+    let (channels, pre_skip, input_rate) = parse_opus_head(head)?;
+    let input_rate = if input_rate == 0 { GRANULE_RATE } else { input_rate };
+    let rate = codec_rate(input_rate);
+    let scale = (GRANULE_RATE / rate) as usize;
+    let ch = channels as usize;
+
+    let mut decoder = opus::Decoder::new(rate, opus_channels(channels)?).map_err(opus_error)?;
+    // 120 ms is the longest Opus packet
+    let mut pcm = vec![0f32; (rate as usize * 120 / 1000) * ch];
+    let mut decoded = Vec::new();
+    for packet in packets.iter().skip(2) {
+        let frames = decoder.decode_float(packet, &mut pcm, false).map_err(opus_error)?;
+        decoded.extend_from_slice(&pcm[..frames * ch]);
+    }
+
+    let skip = (pre_skip as usize / scale) * ch;
+    let keep = (final_granule.saturating_sub(pre_skip as u64) as usize / scale) * ch;
+    let decoded: Vec<f32> = decoded.into_iter().skip(skip).take(keep).collect();
+    Ok(OpusAudio {
+        sample_rate: input_rate,
+        channels,
+        samples: resample(&decoded, channels, rate, input_rate),
+    })
+}
+
+#[cfg(not(feature = "opus-archive"))]
+pub fn encode(_samples: &[f32], _sample_rate: u32, _channels: u16, _level: &CompressionLevel) -> Result<Vec<u8>, AudioError> {
+    Err(codec_error("Opus archiving requires the `opus-archive` feature"))
+}
+
+#[cfg(not(feature = "opus-archive"))]
+pub fn decode(data: &[u8]) -> Result<OpusAudio, AudioError> {
+    // Headers can still be validated without the codec
+    let (packets, _) = read_packets(data)?;
+    parse_opus_head(packets.first().ok_or_else(|| codec_error("Empty Ogg stream"))?)?;
+    Err(codec_error("Opus decoding requires the `opus-archive` feature"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ogg_packets_round_trip_across_pages() {
+        let packets: Vec<Vec<u8>> = (0..40u32)
+            .map(|i| (0..(i * 37 % 700) as usize).map(|b| (b as u32 ^ i) as u8).collect())
+            .collect();
+        let mut ogg = OggWriter::new(Vec::new(), 42);
+        ogg.write_packet(&opus_head(1, 312, 16000), 0).unwrap();
+        ogg.flush().unwrap();
+        for (i, packet) in packets.iter().enumerate() {
+            ogg.write_packet(packet, (i as u64 + 1) * 960).unwrap();
+        }
+        let bytes = ogg.finish().unwrap();
+
+        let (read, granule) = read_packets(&bytes).unwrap();
+        assert_eq!(granule, 40 * 960);
+        assert_eq!(parse_opus_head(&read[0]).unwrap(), (1, 312, 16000));
+        assert_eq!(&read[1..], &packets[..]);
+
+        let mut corrupted = bytes.clone();
+        let middle = corrupted.len() / 2;
+        corrupted[middle] ^= 0x01;
+        assert!(read_packets(&corrupted).is_err());
+    }
+
+    #[test]
+    fn test_sample_rate_handling() {
+        assert_eq!(codec_rate(16000), 16000);
+        assert_eq!(codec_rate(44100), 48000);
+
+        let tone: Vec<f32> = (0..44100).map(|i| (i as f32 * 0.01).sin()).collect();
+        let up = resample(&tone, 1, 44100, 48000);
+        assert_eq!(up.len(), 48000);
+        let back = resample(&up, 1, 48000, 44100);
+        assert_eq!(back.len(), 44100);
+        let error: f32 = tone.iter().zip(&back).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max);
+        assert!(error < 0.01, "max resampling error {}", error);
+
+        assert!(bitrate_for_level(&CompressionLevel::Maximum, 1) < bitrate_for_level(&CompressionLevel::Low, 1));
+    }
+
+    #[cfg(feature = "opus-archive")]
+    #[test]
+    fn test_opus_round_trip() {
+        for rate in [16000u32, 44100] {
+            let speech: Vec<f32> = (0..rate * 2)
+                .map(|i| 0.3 * (2.0 * std::f32::consts::PI * 220.0 * i as f32 / rate as f32).sin())
+                .collect();
+            let bytes = encode(&speech, rate, 1, &CompressionLevel::Medium).unwrap();
+            assert!(bytes.len() < speech.len() * 4 / 10);
+
+            let audio = decode(&bytes).unwrap();
+            assert_eq!(audio.sample_rate, rate);
+            assert_eq!(audio.channels, 1);
+            assert!((audio.samples.len() as i64 - speech.len() as i64).abs() <= 2);
+            let energy: f32 = audio.samples.iter().map(|s| s * s).sum::<f32>() / audio.samples.len() as f32;
+            assert!((energy - 0.045).abs() < 0.015, "decoded energy {}", energy);
+        }
+    }
+}
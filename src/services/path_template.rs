@@ -0,0 +1,213 @@
+//! Path templates for archived sessions
+//!
+//! A template such as `{date:%Y/%m}/{tag}/{name}-{id8}` expands to a relative
+//! path. Placeholders:
+//!
+//! - `{name}`: session name
+//! - `{id}` / `{id8}`: full session UUID / its first 8 hex digits
+//! - `{date}` / `{date:FORMAT}`: start time, `%Y-%m-%d` or a chrono format
+//!   (which may contain `/` to create directories)
+//! - `{tag}`: first tag; the directory is left out when there are none
+//!
+//! Substituted values are sanitized so they can never escape the archive.
+
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::audio_archive::{AudioError, RecordingSession};
+
+/// Longest sanitized path component taken from a name or tag
+const MAX_COMPONENT_LEN: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Name,
+    Id,
+    ShortId,
+    Date(String),
+    Tag,
+}
+
+/// Session fields a template can refer to
+#[derive(Debug, Clone)]
+pub struct PathFields<'a> {
+    pub id: Uuid,
+    pub name: &'a str,
+    pub start_time: DateTime<Utc>,
+    pub tags: &'a [String],
+}
+
+impl<'a> From<&'a RecordingSession> for PathFields<'a> {
+    fn from(session: &'a RecordingSession) -> Self {
+        Self {
+            id: session.id,
+            name: &session.name,
+            start_time: session.start_time,
+            tags: &session.tags,
+        }
+    }
+}
+
+/// A parsed path template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl PathTemplate {
+    /// Parse a template, rejecting unknown placeholders and paths that leave the archive
+    pub fn parse(template: &str) -> Result<Self, AudioError> {
+        let invalid = |reason: &str| AudioError::InvalidConfiguration(format!("Invalid path template {:?}: {}", template, reason));
+        let path = Path::new(template);
+        if path.is_absolute() || path.components().any(|c| matches!(c, Component::ParentDir | Component::RootDir | Component::Prefix(_))) {
+            return Err(invalid("must be a relative path inside the archive"));
+        }
+
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(open) = rest.find(['{', '}']) {
+            if rest[open..].starts_with('}') {
+                return Err(invalid("unmatched '}'"));
+            }
+            if open > 0 {
+                parts.push(Part::Literal(rest[..open].to_string()));
+            }
+            let close = rest[open..].find('}').ok_or_else(|| invalid("unclosed '{'"))? + open;
+            let placeholder = &rest[open + 1..close];
+            parts.push(match placeholder.split_once(':') {
+                Some(("date", format)) if !format.is_empty() => {
+                    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                        return Err(invalid(&format!("bad date format {:?}", format)));
+                    }
+                    Part::Date(format.to_string())
+                }
+                None if placeholder == "date" => Part::Date("%Y-%m-%d".to_string()),
+                None if placeholder == "name" => Part::Name,
+                None if placeholder == "id" => Part::Id,
+                None if placeholder == "id8" => Part::ShortId,
+                None if placeholder == "tag" => Part::Tag,
+                _ => return Err(invalid(&format!("unknown placeholder {{{}}}", placeholder))),
+            });
+            rest = &rest[close + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+
+        Ok(Self {
+            source: template.to_string(),
+            parts,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Expand the template to a relative path; empty directories are dropped
+    pub fn render(&self, fields: &PathFields) -> PathBuf {
+        let mut rendered = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => rendered.push_str(text),
+                Part::Name => rendered.push_str(&sanitize_component(fields.name)),
+                Part::Id => rendered.push_str(&fields.id.to_string()),
+                Part::ShortId => rendered.push_str(&fields.id.simple().to_string()[..8]),
+                Part::Date(format) => {
+                    let date = fields.start_time.format(format).to_string();
+                    let sanitized: Vec<String> = date.split('/').map(sanitize_component).collect();
+                    rendered.push_str(&sanitized.join("/"));
+                }
+                Part::Tag => {
+                    if let Some(tag) = fields.tags.first() {
+                        rendered.push_str(&sanitize_component(tag));
+                    }
+                }
+            }
+        }
+        rendered.split('/').filter(|component| !component.is_empty()).collect()
+    }
+}
+
+impl fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Make a name safe to use as a single path component
+pub fn sanitize_component(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .take(MAX_COMPONENT_LEN)
+        .collect()
+}
+
+/// First of `path`, `path-2`, `path-3`, ... (suffix before the extension) that is not taken
+pub fn unique_path(path: PathBuf, taken: impl Fn(&Path) -> bool) -> PathBuf {
+    if !taken(&path) {
+        return path;
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().into_owned());
+    (2..)
+        .map(|n| {
+            let file_name = match &extension {
+                Some(extension) => format!("{}-{}.{}", stem, n, extension),
+                None => format!("{}-{}", stem, n),
+            };
+            path.with_file_name(file_name)
+        })
+        .find(|candidate| !taken(candidate))
+        .expect("unbounded suffixes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields<'a>(name: &'a str, tags: &'a [String]) -> PathFields<'a> {
+        PathFields {
+            id: Uuid::parse_str("0123abcd-0000-4000-8000-000000000000").unwrap(),
+            name,
+            start_time: "2024-05-07T09:30:00Z".parse().unwrap(),
+            tags,
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        let tags = vec!["Team Sync".to_string()];
+        let template = PathTemplate::parse("{date:%Y/%m}/{tag}/{name}-{id8}").unwrap();
+        assert_eq!(template.render(&fields("Q2 plan/../x", &tags)), PathBuf::from("2024/05/Team_Sync/Q2_plan____x-0123abcd"));
+        // No tags: the tag directory is skipped
+        assert_eq!(template.render(&fields("standup", &[])), PathBuf::from("2024/05/standup-0123abcd"));
+        assert_eq!(
+            PathTemplate::parse("{date}_{id}").unwrap().render(&fields("x", &[])),
+            PathBuf::from("2024-05-07_0123abcd-0000-4000-8000-000000000000")
+        );
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(PathTemplate::parse("{name").is_err());
+        assert!(PathTemplate::parse("name}").is_err());
+        assert!(PathTemplate::parse("{speaker}").is_err());
+        assert!(PathTemplate::parse("{date:%Q}").is_err());
+        assert!(PathTemplate::parse("../{name}").is_err());
+        assert!(PathTemplate::parse("/abs/{name}").is_err());
+    }
+
+    #[test]
+    fn test_unique_path() {
+        let taken = [PathBuf::from("a/x.flac"), PathBuf::from("a/x-2.flac")];
+        assert_eq!(unique_path(PathBuf::from("a/x.flac"), |p| taken.iter().any(|t| t == p)), PathBuf::from("a/x-3.flac"));
+        assert_eq!(unique_path(PathBuf::from("a/y"), |p| taken.iter().any(|t| t == p)), PathBuf::from("a/y"));
+    }
+}
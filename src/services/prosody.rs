@@ -0,0 +1,443 @@
+//! Pitch and prosody analysis.
+//!
+//! Per segment: F0 from the YIN estimator, pitch range and variability,
+//! loudness and its variance, pauses, rhythm, and speaking rate (from word
+//! timings when the recognizer provides them, otherwise from the word count
+//! over the span of speech). `ProsodyProfile` accumulates segments into the
+//! per-speaker picture used by `SpeakerProfile` and the session commands.
+
+use serde::{Deserialize, Serialize};
+
+/// Prosody analysis configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProsodyConfig {
+    /// Lowest pitch searched for, in Hz
+    pub min_f0_hz: f32,
+    /// Highest pitch searched for, in Hz
+    pub max_f0_hz: f32,
+    /// YIN aperiodicity threshold; lower is stricter about what counts as voiced
+    pub yin_threshold: f32,
+    /// Analysis frame length in milliseconds (must hold two periods of `min_f0_hz`)
+    pub frame_ms: u32,
+    /// Hop between frames in milliseconds
+    pub hop_ms: u32,
+    /// Frames quieter than this fraction of the segment's loudest frame are silence
+    pub silence_ratio: f32,
+    /// Shortest silence counted as a pause, in milliseconds
+    pub min_pause_ms: u32,
+}
+
+impl Default for ProsodyConfig {
+    fn default() -> Self {
+        Self {
+            min_f0_hz: 60.0,
+            max_f0_hz: 500.0,
+            yin_threshold: 0.15,
+            frame_ms: 40,
+            hop_ms: 10,
+            silence_ratio: 0.1,
+            min_pause_ms: 200,
+        }
+    }
+}
+
+/// Pauses inside a segment (leading and trailing silence excluded)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PauseStats {
+    pub count: u32,
+    pub total_ms: u64,
+    pub mean_ms: f32,
+    pub max_ms: u64,
+    pub per_minute: f32,
+}
+
+/// A recognized word with its position in the segment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WordTiming {
+    pub word: String,
+    /// Seconds from the start of the segment
+    pub start_s: f32,
+    pub end_s: f32,
+}
+
+/// Prosodic features of one segment
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProsodyFeatures {
+    /// Mean F0 over voiced frames (0 if nothing was voiced)
+    pub mean_f0_hz: f32,
+    /// 5th percentile of F0
+    pub f0_min_hz: f32,
+    /// 95th percentile of F0
+    pub f0_max_hz: f32,
+    /// F0 standard deviation relative to the mean
+    pub pitch_variance: f32,
+    /// Share of speech frames that are voiced
+    pub voiced_ratio: f32,
+    /// Mean RMS of speech frames
+    pub energy_mean: f32,
+    /// Variance of the frame level over speech, in dB²
+    pub energy_variance_db: f32,
+    /// 10th and 90th percentile RMS of speech frames
+    pub volume_range: (f32, f32),
+    /// Words per minute over the span of speech, pauses included
+    pub speaking_rate_wpm: f32,
+    /// Words per minute with pauses removed
+    pub articulation_rate_wpm: f32,
+    pub pauses: PauseStats,
+    /// 1.0 for evenly sized speech runs between pauses, lower when uneven
+    pub rhythm_regularity: f32,
+    /// Span of speech in seconds
+    pub speech_duration_s: f32,
+    pub word_count: usize,
+}
+
+/// F0 of one frame with the YIN estimator, or `None` if it is unvoiced
+pub fn yin_pitch(frame: &[f32], sample_rate: u32, min_f0: f32, max_f0: f32, threshold: f32) -> Option<f32> {
+    if sample_rate == 0 || min_f0 <= 0.0 || max_f0 <= min_f0 {
+        return None;
+    }
+    let max_lag = (sample_rate as f32 / min_f0).ceil() as usize;
+    let min_lag = ((sample_rate as f32 / max_f0).floor() as usize).max(2);
+    if frame.len() < 2 * max_lag + 2 {
+        return None;
+    }
+    let window = frame.len() - max_lag;
+
+    // Difference function and its cumulative mean normalised form
+    let mut cmnd = vec![1.0f32; max_lag + 1];
+    let mut running = 0.0f32;
+    for lag in 1..=max_lag {
+        let d: f32 = (0..window).map(|j| {
+            let diff = frame[j] - frame[j + lag];
+            diff * diff
+        }).sum();
+        running += d;
+        cmnd[lag] = if running > 0.0 { d * lag as f32 / running } else { 1.0 };
+    }
+
+    // First dip below the threshold, followed down to its local minimum
+    let mut lag = (min_lag..max_lag).find(|&l| cmnd[l] < threshold)?;
+    while lag + 1 < max_lag && cmnd[lag + 1] < cmnd[lag] {
+        lag += 1;
+    }
+
+    // Parabolic interpolation for sub-sample precision
+    let (a, b, c) = (cmnd[lag - 1], cmnd[lag], cmnd[lag + 1]);
+    let denom = a - 2.0 * b + c;
+    let offset = if denom.abs() > f32::EPSILON { (0.5 * (a - c) / denom).clamp(-0.5, 0.5) } else { 0.0 };
+    let f0 = sample_rate as f32 / (lag as f32 + offset);
+    (min_f0..=max_f0).contains(&f0).then_some(f0)
+}
+
+fn percentile(sorted: &[f32], p: f32) -> f32 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let idx = ((sorted.len() - 1) as f32 * p).round() as usize;
+    sorted[idx.min(sorted.len() - 1)]
+}
+
+fn mean_and_variance(values: &[f32]) -> (f32, f32) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    let var = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / values.len() as f32;
+    (mean, var)
+}
+
+/// Segment prosody analyzer
+#[derive(Debug, Clone, Default)]
+pub struct ProsodyAnalyzer {
+    config: ProsodyConfig,
+}
+
+impl ProsodyAnalyzer {
+    /// Create an analyzer
+    pub fn new(config: ProsodyConfig) -> Self {
+        Self { config }
+    }
+
+    /// Current configuration
+    pub fn config(&self) -> &ProsodyConfig {
+        &self.config
+    }
+
+    fn framing(&self, sample_rate: u32) -> (usize, usize) {
+        let frame = (sample_rate as usize * self.config.frame_ms as usize / 1000).max(1);
+        let hop = (sample_rate as usize * self.config.hop_ms as usize / 1000).max(1);
+        (frame, hop)
+    }
+
+    /// F0 per hop (`None` for unvoiced frames)
+    pub fn pitch_track(&self, samples: &[f32], sample_rate: u32) -> Vec<Option<f32>> {
+        let (frame, hop) = self.framing(sample_rate);
+        let mut track = Vec::new();
+        let mut start = 0;
+        while start + frame <= samples.len() {
+            track.push(yin_pitch(
+                &samples[start..start + frame],
+                sample_rate,
+                self.config.min_f0_hz,
+                self.config.max_f0_hz,
+                self.config.yin_threshold,
+            ));
+            start += hop;
+        }
+        track
+    }
+
+    /// Analyze a segment, estimating speaking rate from its word count
+    pub fn analyze(&self, samples: &[f32], sample_rate: u32, word_count: usize) -> ProsodyFeatures {
+        self.analyze_inner(samples, sample_rate, word_count, None)
+    }
+
+    /// Analyze a segment using word timings for speaking rate and pauses
+    pub fn analyze_with_timings(&self, samples: &[f32], sample_rate: u32, words: &[WordTiming]) -> ProsodyFeatures {
+        self.analyze_inner(samples, sample_rate, words.len(), Some(words))
+    }
+
+    fn analyze_inner(&self, samples: &[f32], sample_rate: u32, word_count: usize, words: Option<&[WordTiming]>) -> ProsodyFeatures {
+        let mut features = ProsodyFeatures { word_count, ..ProsodyFeatures::default() };
+        if sample_rate == 0 {
+            return features;
+        }
+        let (_, hop) = self.framing(sample_rate);
+        let hop_ms = hop as f32 * 1000.0 / sample_rate as f32;
+        let rms: Vec<f32> = samples
+            .chunks(hop)
+            .map(|c| (c.iter().map(|s| s * s).sum::<f32>() / c.len() as f32).sqrt())
+            .collect();
+        let peak = rms.iter().cloned().fold(0.0f32, f32::max);
+        if peak <= 1e-6 {
+            return features;
+        }
+        let speech: Vec<bool> = rms.iter().map(|r| *r >= peak * self.config.silence_ratio).collect();
+        let (Some(first), Some(last)) = (speech.iter().position(|s| *s), speech.iter().rposition(|s| *s)) else {
+            return features;
+        };
+        features.speech_duration_s = (last + 1 - first) as f32 * hop_ms / 1000.0;
+
+        // Pauses and the speech runs between them
+        let min_pause_frames = (self.config.min_pause_ms as f32 / hop_ms).ceil() as usize;
+        let mut pauses_ms: Vec<u64> = Vec::new();
+        let mut runs: Vec<f32> = Vec::new();
+        let (mut silent, mut run) = (0usize, 0usize);
+        for &is_speech in &speech[first..=last] {
+            if is_speech {
+                if silent >= min_pause_frames {
+                    pauses_ms.push((silent as f32 * hop_ms) as u64);
+                    runs.push(run as f32);
+                    run = 0;
+                } else {
+                    run += silent;
+                }
+                silent = 0;
+                run += 1;
+            } else {
+                silent += 1;
+            }
+        }
+        runs.push(run as f32);
+
+        // Word timings, when present, are more reliable than energy for both
+        if let Some(words) = words.filter(|w| !w.is_empty()) {
+            let span = words.last().map(|w| w.end_s).unwrap_or(0.0) - words[0].start_s;
+            if span > 0.0 {
+                features.speech_duration_s = span;
+            }
+            pauses_ms = words
+                .windows(2)
+                .map(|w| ((w[1].start_s - w[0].end_s).max(0.0) * 1000.0) as u64)
+                .filter(|gap| *gap >= self.config.min_pause_ms as u64)
+                .collect();
+        }
+
+        let total_pause_ms: u64 = pauses_ms.iter().sum();
+        let minutes = features.speech_duration_s / 60.0;
+        features.pauses = PauseStats {
+            count: pauses_ms.len() as u32,
+            total_ms: total_pause_ms,
+            mean_ms: if pauses_ms.is_empty() { 0.0 } else { total_pause_ms as f32 / pauses_ms.len() as f32 },
+            max_ms: pauses_ms.iter().cloned().max().unwrap_or(0),
+            per_minute: if minutes > 0.0 { pauses_ms.len() as f32 / minutes } else { 0.0 },
+        };
+        if minutes > 0.0 {
+            features.speaking_rate_wpm = word_count as f32 / minutes;
+            let articulation_minutes = (features.speech_duration_s - total_pause_ms as f32 / 1000.0) / 60.0;
+            if articulation_minutes > 0.0 {
+                features.articulation_rate_wpm = word_count as f32 / articulation_minutes;
+            }
+        }
+        let (run_mean, run_var) = mean_and_variance(&runs);
+        features.rhythm_regularity = if run_mean > 0.0 { 1.0 / (1.0 + run_var.sqrt() / run_mean) } else { 1.0 };
+
+        // Loudness over speech frames
+        let mut speech_rms: Vec<f32> = rms[first..=last]
+            .iter()
+            .zip(&speech[first..=last])
+            .filter(|(_, s)| **s)
+            .map(|(r, _)| *r)
+            .collect();
+        let levels_db: Vec<f32> = speech_rms.iter().map(|r| 20.0 * r.max(1e-6).log10()).collect();
+        features.energy_mean = speech_rms.iter().sum::<f32>() / speech_rms.len() as f32;
+        features.energy_variance_db = mean_and_variance(&levels_db).1;
+        speech_rms.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        features.volume_range = (percentile(&speech_rms, 0.1), percentile(&speech_rms, 0.9));
+
+        // Pitch over speech frames
+        let track = self.pitch_track(samples, sample_rate);
+        let speech_frames = (first..=last).filter(|i| speech[*i] && *i < track.len()).count();
+        let mut f0s: Vec<f32> = (first..=last)
+            .filter(|i| speech[*i])
+            .filter_map(|i| track.get(i).copied().flatten())
+            .collect();
+        if !f0s.is_empty() {
+            let (mean, var) = mean_and_variance(&f0s);
+            f0s.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            features.mean_f0_hz = mean;
+            features.f0_min_hz = percentile(&f0s, 0.05);
+            features.f0_max_hz = percentile(&f0s, 0.95);
+            features.pitch_variance = var.sqrt() / mean;
+            features.voiced_ratio = f0s.len() as f32 / speech_frames.max(1) as f32;
+        }
+        features
+    }
+}
+
+/// Running prosody of one speaker across a session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProsodyProfile {
+    /// Segments folded in
+    pub segments: usize,
+    /// Seconds of speech folded in
+    pub speech_s: f32,
+    /// Mean F0, weighted by voiced speech time
+    pub mean_f0_hz: f32,
+    /// Lowest and highest typical F0 seen
+    pub f0_range_hz: (f32, f32),
+    pub pitch_variance: f32,
+    pub energy_variance_db: f32,
+    pub volume_range: (f32, f32),
+    /// Words per minute over all speech
+    pub speaking_rate_wpm: f32,
+    pub pause_count: u32,
+    pub pause_total_ms: u64,
+    pub rhythm_regularity: f32,
+    words: usize,
+    voiced_s: f32,
+}
+
+impl ProsodyProfile {
+    /// Fold in one segment
+    pub fn add(&mut self, features: &ProsodyFeatures) {
+        if features.speech_duration_s <= 0.0 {
+            return;
+        }
+        let w_old = self.speech_s;
+        let w_new = features.speech_duration_s;
+        let blend = |old: f32, new: f32| (old * w_old + new * w_new) / (w_old + w_new);
+
+        let voiced = features.voiced_ratio * w_new;
+        if features.mean_f0_hz > 0.0 && voiced > 0.0 {
+            self.mean_f0_hz = (self.mean_f0_hz * self.voiced_s + features.mean_f0_hz * voiced) / (self.voiced_s + voiced);
+            self.f0_range_hz = if self.voiced_s > 0.0 {
+                (self.f0_range_hz.0.min(features.f0_min_hz), self.f0_range_hz.1.max(features.f0_max_hz))
+            } else {
+                (features.f0_min_hz, features.f0_max_hz)
+            };
+            self.pitch_variance = (self.pitch_variance * self.voiced_s + features.pitch_variance * voiced) / (self.voiced_s + voiced);
+            self.voiced_s += voiced;
+        }
+        self.energy_variance_db = blend(self.energy_variance_db, features.energy_variance_db);
+        self.rhythm_regularity = blend(self.rhythm_regularity, features.rhythm_regularity);
+        self.volume_range = if self.segments > 0 {
+            (self.volume_range.0.min(features.volume_range.0), self.volume_range.1.max(features.volume_range.1))
+        } else {
+            features.volume_range
+        };
+        self.pause_count += features.pauses.count;
+        self.pause_total_ms += features.pauses.total_ms;
+        self.words += features.word_count;
+        self.speech_s += w_new;
+        self.segments += 1;
+        self.speaking_rate_wpm = self.words as f32 * 60.0 / self.speech_s;
+    }
+
+    /// Pauses per minute of speech
+    pub fn pauses_per_minute(&self) -> f32 {
+        if self.speech_s > 0.0 { self.pause_count as f32 * 60.0 / self.speech_s } else { 0.0 }
+    }
+
+    /// Average pause length in milliseconds
+    pub fn mean_pause_ms(&self) -> f32 {
+        if self.pause_count > 0 { self.pause_total_ms as f32 / self.pause_count as f32 } else { 0.0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(f0: f32, seconds: f32, sr: u32) -> Vec<f32> {
+        (0..(seconds * sr as f32) as usize)
+            .map(|i| {
+                let t = i as f32 / sr as f32;
+                0.3 * (2.0 * std::f32::consts::PI * f0 * t).sin() + 0.15 * (4.0 * std::f32::consts::PI * f0 * t).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_yin_tracks_pitch() {
+        let sr = 16000;
+        let f0 = yin_pitch(&tone(140.0, 0.04, sr), sr, 60.0, 500.0, 0.15).unwrap();
+        assert!((f0 - 140.0).abs() < 2.0, "f0 = {}", f0);
+        let f0 = yin_pitch(&tone(310.0, 0.04, sr), sr, 60.0, 500.0, 0.15).unwrap();
+        assert!((f0 - 310.0).abs() < 4.0, "f0 = {}", f0);
+
+        // Noise is not voiced
+        let mut seed = 12345u32;
+        let noise: Vec<f32> = (0..640)
+            .map(|_| {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                (seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+            })
+            .collect();
+        assert!(yin_pitch(&noise, sr, 60.0, 500.0, 0.15).is_none());
+    }
+
+    #[test]
+    fn test_pauses_rate_and_profile() {
+        let sr = 16000;
+        // 1 s speech, 0.5 s pause, 1 s speech at a higher pitch
+        let mut samples = tone(120.0, 1.0, sr);
+        samples.extend(vec![0.0; sr as usize / 2]);
+        samples.extend(tone(180.0, 1.0, sr));
+
+        let features = ProsodyAnalyzer::default().analyze(&samples, sr, 5);
+        assert_eq!(features.pauses.count, 1);
+        assert!((features.pauses.mean_ms - 500.0).abs() < 30.0);
+        assert!((features.speech_duration_s - 2.5).abs() < 0.05);
+        assert!((features.speaking_rate_wpm - 120.0).abs() < 5.0);
+        assert!((features.mean_f0_hz - 150.0).abs() < 5.0);
+        assert!(features.f0_min_hz < 125.0 && features.f0_max_hz > 175.0);
+        assert!(features.voiced_ratio > 0.9);
+
+        let words = vec![
+            WordTiming { word: "one".into(), start_s: 0.0, end_s: 0.4 },
+            WordTiming { word: "two".into(), start_s: 0.5, end_s: 1.0 },
+            WordTiming { word: "three".into(), start_s: 1.5, end_s: 2.0 },
+        ];
+        let timed = ProsodyAnalyzer::default().analyze_with_timings(&samples, sr, &words);
+        assert_eq!(timed.pauses.count, 1);
+        assert!((timed.speaking_rate_wpm - 90.0).abs() < 1.0);
+
+        let mut profile = ProsodyProfile::default();
+        profile.add(&features);
+        profile.add(&features);
+        assert_eq!(profile.segments, 2);
+        assert!((profile.mean_f0_hz - features.mean_f0_hz).abs() < 0.01);
+        assert!((profile.pauses_per_minute() - 24.0).abs() < 1.0);
+    }
+}
@@ -0,0 +1,516 @@
+//! Retention policy enforcement for recorded sessions and transcripts
+//!
+//! Session output under `sessions/` (in whatever layout the session template
+//! gives) is split into raw audio, cleaned audio, segment clips and metadata
+//! (which carries the transcript), and each category expires on its own
+//! schedule. Transcript
+//! logs under `transcripts/` follow the transcript policy.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use chrono::{DateTime, Utc};
+use tracing::{info, warn};
+
+use crate::core::config::PrivacyConfig;
+
+use super::audio_archive::{AudioError, CleanupResult};
+use super::audio_storage::{FileAudioStorage, StorageConfig};
+use super::encryption::{self, StorageCipher};
+use super::session_journal::JOURNAL_DIR;
+use super::transcript_storage::{FileStorageConfig, FileTranscriptStorage};
+use super::waveform::{OVERVIEW_FILE, WAVEFORM_DIR};
+
+/// Parse a duration spec such as "12h", "30d", "2w", "6m" (months) or "1y"
+pub fn parse_duration(spec: &str) -> Result<chrono::Duration, AudioError> {
+    let spec = spec.trim().to_lowercase();
+    let split = spec.find(|c: char| !c.is_ascii_digit()).unwrap_or(spec.len());
+    let (number, unit) = spec.split_at(split);
+    let invalid = || AudioError::InvalidConfiguration(format!("Invalid retention duration: {:?}", spec));
+    let count: i64 = number.parse().map_err(|_| invalid())?;
+
+    let days_per_unit = match unit.trim() {
+        "h" | "hour" | "hours" => return Ok(chrono::Duration::hours(count)),
+        "" | "d" | "day" | "days" => 1,
+        "w" | "week" | "weeks" => 7,
+        "m" | "mo" | "month" | "months" => 30,
+        "y" | "year" | "years" => 365,
+        _ => return Err(invalid()),
+    };
+    count.checked_mul(days_per_unit)
+        .filter(|days| *days <= 100 * 365)
+        .map(chrono::Duration::days)
+        .ok_or_else(invalid)
+}
+
+/// How long one category of data is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionPeriod {
+    /// Never expire
+    Forever,
+    /// Expire once older than this
+    For(chrono::Duration),
+}
+
+impl RetentionPeriod {
+    /// Parse a duration spec, or "never"/"forever" to keep data indefinitely
+    pub fn parse(spec: &str) -> Result<Self, AudioError> {
+        match spec.trim().to_lowercase().as_str() {
+            "never" | "forever" | "keep" => Ok(Self::Forever),
+            other => parse_duration(other).map(Self::For),
+        }
+    }
+
+    /// Anything created before the returned time has expired
+    pub fn cutoff(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Forever => None,
+            Self::For(age) => Some(now - *age),
+        }
+    }
+
+    fn expired(&self, created: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        self.cutoff(now).is_some_and(|cutoff| created < cutoff)
+    }
+}
+
+/// Retention periods per kind of stored data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionRules {
+    pub raw_audio: RetentionPeriod,
+    pub cleaned_audio: RetentionPeriod,
+    pub segments: RetentionPeriod,
+    pub transcripts: RetentionPeriod,
+}
+
+impl RetentionRules {
+    /// Apply the same period to everything
+    pub fn uniform(period: RetentionPeriod) -> Self {
+        Self {
+            raw_audio: period,
+            cleaned_audio: period,
+            segments: period,
+            transcripts: period,
+        }
+    }
+
+    /// Build rules from the privacy settings; per-category specs override `data_retention`
+    pub fn from_privacy(privacy: &PrivacyConfig) -> Result<Self, AudioError> {
+        let default = RetentionPeriod::parse(&privacy.data_retention)?;
+        let category = |spec: &Option<String>| match spec {
+            Some(spec) => RetentionPeriod::parse(spec),
+            None => Ok(default),
+        };
+        Ok(Self {
+            raw_audio: category(&privacy.raw_audio_retention)?,
+            cleaned_audio: category(&privacy.cleaned_audio_retention)?,
+            segments: category(&privacy.segment_retention)?,
+            transcripts: category(&privacy.transcript_retention)?,
+        })
+    }
+}
+
+/// What a session directory entry holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryKind {
+    RawAudio,
+    CleanedAudio,
+    Segments,
+    Transcript,
+}
+
+impl EntryKind {
+    fn classify(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy();
+        let stem = path.file_stem()?.to_string_lossy();
+        match (name.as_ref(), stem.as_ref()) {
+            ("segments", _) if path.is_dir() => Some(Self::Segments),
+            // Revisions hold full transcript text
+            ("revisions", _) if path.is_dir() => Some(Self::Transcript),
+            ("session_metadata.json", _) => Some(Self::Transcript),
+            (_, "chapters") => Some(Self::Transcript),
+            (_, "raw_audio") => Some(Self::RawAudio),
+            // Waveform envelopes and the overview are derived from the raw audio
+            (WAVEFORM_DIR, _) if path.is_dir() => Some(Self::RawAudio),
+            (OVERVIEW_FILE, _) => Some(Self::RawAudio),
+            (_, "cleaned_audio") => Some(Self::CleanedAudio),
+            _ => None,
+        }
+    }
+}
+
+/// Applies [`RetentionRules`] to a data directory
+pub struct RetentionEngine {
+    data_dir: PathBuf,
+    rules: RetentionRules,
+    dry_run: bool,
+    cipher: Option<Arc<StorageCipher>>,
+}
+
+impl RetentionEngine {
+    /// Create an engine for a plaintext data directory
+    pub fn new(data_dir: PathBuf, rules: RetentionRules) -> Self {
+        Self::new_with_cipher(data_dir, rules, None)
+    }
+
+    /// Create an engine that can read encrypted session metadata and transcripts
+    pub fn new_with_cipher(data_dir: PathBuf, rules: RetentionRules, cipher: Option<Arc<StorageCipher>>) -> Self {
+        Self {
+            data_dir,
+            rules,
+            dry_run: false,
+            cipher,
+        }
+    }
+
+    /// Build an engine from the privacy settings, or `None` when `auto_expiry` is off
+    pub fn from_privacy(data_dir: PathBuf, privacy: &PrivacyConfig, cipher: Option<Arc<StorageCipher>>) -> Result<Option<Self>, AudioError> {
+        if !privacy.auto_expiry {
+            return Ok(None);
+        }
+        let rules = RetentionRules::from_privacy(privacy)?;
+        Ok(Some(Self::new_with_cipher(data_dir, rules, cipher)))
+    }
+
+    /// Only report what would be deleted
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    pub fn rules(&self) -> &RetentionRules {
+        &self.rules
+    }
+
+    /// Delete everything that has outlived its retention period
+    pub fn run(&self) -> Result<CleanupResult, AudioError> {
+        self.run_at(Utc::now())
+    }
+
+    fn run_at(&self, now: DateTime<Utc>) -> Result<CleanupResult, AudioError> {
+        let mut result = CleanupResult {
+            dry_run: self.dry_run,
+            ..Default::default()
+        };
+        self.sweep_sessions(now, &mut result)?;
+        self.unindex_deleted_audio(&result)?;
+        self.sweep_transcripts(now, &mut result)?;
+        Ok(result)
+    }
+
+    /// Run now and then every `interval` on a background thread
+    pub fn spawn(self, interval: std::time::Duration) -> RetentionScheduler {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = thread::spawn(move || loop {
+            match self.run() {
+                Ok(result) if result.files_deleted > 0 || result.transcripts_removed > 0 => info!(
+                    dry_run = result.dry_run,
+                    files = result.files_deleted,
+                    sessions = result.sessions_removed,
+                    transcripts = result.transcripts_removed,
+                    bytes = result.space_freed,
+                    "🧹 Retention sweep expired old data"
+                ),
+                Ok(_) => {}
+                Err(e) => warn!(error = %e, "Retention sweep failed"),
+            }
+            match stopped.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => continue,
+                _ => break,
+            }
+        });
+        RetentionScheduler { stop: Some(stop), handle: Some(handle) }
+    }
+
+    fn sweep_sessions(&self, now: DateTime<Utc>, result: &mut CleanupResult) -> Result<(), AudioError> {
+        let sessions_dir = self.data_dir.join("sessions");
+        if sessions_dir.is_dir() {
+            self.sweep_directory(&sessions_dir, now, result)?;
+        }
+        Ok(())
+    }
+
+    /// Walk the session layout, whatever template produced it, looking for session directories
+    fn sweep_directory(&self, dir: &Path, now: DateTime<Utc>, result: &mut CleanupResult) -> Result<(), AudioError> {
+        for entry in sorted_entries(dir)? {
+            if entry.is_dir() {
+                // A session still recording, or left for crash recovery, keeps its audio in the journal
+                if entry.join(JOURNAL_DIR).is_dir() || entry.file_name().is_some_and(|name| name == JOURNAL_DIR) {
+                    continue;
+                }
+                if is_session_directory(&entry)? {
+                    self.sweep_session(&entry, now, result)?;
+                } else {
+                    self.sweep_directory(&entry, now, result)?;
+                    if !self.dry_run {
+                        // Only succeeds once the directory is empty
+                        let _ = fs::remove_dir(&entry);
+                    }
+                }
+            } else if entry.extension().is_some_and(|ext| ext == "json") {
+                // Legacy per-session metadata next to the session directories
+                let recorded = self.metadata_time(&entry, None);
+                if recorded.is_some_and(|recorded| self.rules.transcripts.expired(recorded, now)) {
+                    self.delete(&entry, result)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn sweep_session(&self, session_dir: &Path, now: DateTime<Utc>, result: &mut CleanupResult) -> Result<(), AudioError> {
+        let recorded = match self.metadata_time(&session_dir.join("session_metadata.json"), Some("session")) {
+            Some(recorded) => recorded,
+            // Without readable metadata, go by the newest file so nothing goes early
+            None => newest_modification(session_dir)?,
+        };
+        let mut remaining = 0;
+        for entry in sorted_entries(session_dir)? {
+            let period = match EntryKind::classify(&entry) {
+                Some(EntryKind::RawAudio) => self.rules.raw_audio,
+                Some(EntryKind::CleanedAudio) => self.rules.cleaned_audio,
+                Some(EntryKind::Segments) => self.rules.segments,
+                Some(EntryKind::Transcript) => self.rules.transcripts,
+                None => RetentionPeriod::Forever,
+            };
+            if period.expired(recorded, now) {
+                self.delete(&entry, result)?;
+            } else {
+                remaining += 1;
+            }
+        }
+
+        if remaining == 0 {
+            result.sessions_removed += 1;
+            if !self.dry_run {
+                fs::remove_dir(session_dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// When a session finished recording, from its metadata (optionally nested under `key`)
+    fn metadata_time(&self, path: &Path, key: Option<&str>) -> Option<DateTime<Utc>> {
+        let data = encryption::read_file(path, self.cipher.as_deref()).ok()?;
+        let metadata: serde_json::Value = serde_json::from_slice(&data).ok()?;
+        let session = match key {
+            Some(key) => &metadata[key],
+            None => &metadata,
+        };
+        session["end_time"].as_str()
+            .or_else(|| session["start_time"].as_str())
+            .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+            .map(|time| time.with_timezone(&Utc))
+    }
+
+    /// Keep the audio index from pointing at raw audio the sweep deleted
+    fn unindex_deleted_audio(&self, result: &CleanupResult) -> Result<(), AudioError> {
+        if self.dry_run || result.deleted_paths.is_empty() || !self.data_dir.join("session_index.json").exists() {
+            return Ok(());
+        }
+        let mut storage = FileAudioStorage::new_with_cipher(self.data_dir.clone(), StorageConfig::default(), self.cipher.clone())?;
+        storage.unindex_files(&result.deleted_paths)?;
+        Ok(())
+    }
+
+    fn sweep_transcripts(&self, now: DateTime<Utc>, result: &mut CleanupResult) -> Result<(), AudioError> {
+        let transcripts_dir = self.data_dir.join("transcripts");
+        let Some(cutoff) = self.rules.transcripts.cutoff(now) else {
+            return Ok(());
+        };
+        if !transcripts_dir.join("index.json").exists() {
+            return Ok(());
+        }
+
+        let mut storage = FileTranscriptStorage::new_with_cipher(transcripts_dir, FileStorageConfig::default(), self.cipher.clone())
+            .map_err(|e| AudioError::StorageError(e.to_string()))?;
+        let expired = storage.expire_transcripts(cutoff, self.dry_run)
+            .map_err(|e| AudioError::StorageError(e.to_string()))?;
+        result.transcripts_removed += expired.transcripts;
+        result.files_deleted += expired.files.len();
+        result.space_freed += expired.bytes_freed;
+        result.deleted_paths.extend(expired.files);
+        Ok(())
+    }
+
+    fn delete(&self, path: &Path, result: &mut CleanupResult) -> Result<(), AudioError> {
+        let (files, bytes) = disk_usage(path)?;
+        if !self.dry_run {
+            if path.is_dir() {
+                fs::remove_dir_all(path)?;
+            } else {
+                fs::remove_file(path)?;
+            }
+        }
+        result.files_deleted += files;
+        result.space_freed += bytes;
+        result.deleted_paths.push(path.to_path_buf());
+        Ok(())
+    }
+}
+
+/// Handle to a background retention sweep; stops when dropped
+pub struct RetentionScheduler {
+    stop: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RetentionScheduler {
+    /// Stop sweeping and wait for a sweep in progress to finish
+    pub fn stop(mut self) {
+        self.stop.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for RetentionScheduler {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>, AudioError> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+/// A directory holding session output rather than more layout levels
+fn is_session_directory(dir: &Path) -> Result<bool, AudioError> {
+    Ok(sorted_entries(dir)?.iter().any(|entry| EntryKind::classify(entry).is_some()))
+}
+
+fn newest_modification(dir: &Path) -> Result<DateTime<Utc>, AudioError> {
+    let mut newest = fs::metadata(dir)?.modified()?;
+    for entry in sorted_entries(dir)? {
+        let modified = if entry.is_dir() {
+            newest_modification(&entry)?.into()
+        } else {
+            fs::metadata(&entry)?.modified()?
+        };
+        newest = newest.max(modified);
+    }
+    Ok(newest.into())
+}
+
+fn disk_usage(path: &Path) -> Result<(usize, u64), AudioError> {
+    if !path.is_dir() {
+        return Ok((1, fs::metadata(path)?.len()));
+    }
+    let mut total = (0, 0);
+    for entry in sorted_entries(path)? {
+        let (files, bytes) = disk_usage(&entry)?;
+        total.0 += files;
+        total.1 += bytes;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::audio_session_manager::AudioRecordingSession;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, bytes: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![0u8; bytes]).unwrap();
+    }
+
+    fn session(root: &Path, layout_path: &str, start_time: &str) -> PathBuf {
+        let dir = root.join("sessions").join(layout_path);
+        write(&dir.join("raw_audio.flac"), 100);
+        write(&dir.join("cleaned_audio.wav"), 50);
+        write(&dir.join("segments/segment_001.wav"), 10);
+        write(&dir.join("segments/segment_002.wav"), 10);
+        write(&dir.join("revisions/revision.json"), 20);
+        write(&dir.join("waveform/256.json"), 5);
+        write(&dir.join("overview.json"), 5);
+        let metadata = serde_json::json!({ "session": { "start_time": start_time, "end_time": null } });
+        fs::write(dir.join("session_metadata.json"), metadata.to_string()).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_retention_specs() {
+        assert_eq!(parse_duration("30d").unwrap(), chrono::Duration::days(30));
+        assert_eq!(parse_duration("12h").unwrap(), chrono::Duration::hours(12));
+        assert_eq!(parse_duration("2w").unwrap(), chrono::Duration::days(14));
+        assert_eq!(parse_duration("6m").unwrap(), chrono::Duration::days(180));
+        assert_eq!(parse_duration(" 1 year ").unwrap(), chrono::Duration::days(365));
+        assert_eq!(RetentionPeriod::parse("never").unwrap(), RetentionPeriod::Forever);
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("5x").is_err());
+
+        let mut privacy = PrivacyConfig::new();
+        privacy.data_retention = "30d".into();
+        privacy.transcript_retention = Some("1y".into());
+        let rules = RetentionRules::from_privacy(&privacy).unwrap();
+        assert_eq!(rules.raw_audio, RetentionPeriod::For(chrono::Duration::days(30)));
+        assert_eq!(rules.transcripts, RetentionPeriod::For(chrono::Duration::days(365)));
+    }
+
+    #[test]
+    fn test_per_category_expiry_and_dry_run() {
+        let temp = TempDir::new().unwrap();
+        let old = session(temp.path(), "2024/01/10/old_1", "2024-01-10T12:00:00Z");
+        // Sessions are found whatever layout template wrote them
+        let recent = session(temp.path(), "recent_2", "2024-03-01T12:00:00Z");
+        let mut storage = FileAudioStorage::new(temp.path().to_path_buf(), StorageConfig::default()).unwrap();
+        let indexed: Vec<_> = [&old, &recent].iter()
+            .map(|dir| AudioRecordingSession::for_test("standup").with_file_path(dir.join("raw_audio.flac")))
+            .collect();
+        indexed.iter().for_each(|session| storage.index_saved_session(session).unwrap());
+
+        let rules = RetentionRules {
+            raw_audio: RetentionPeriod::For(chrono::Duration::days(30)),
+            cleaned_audio: RetentionPeriod::For(chrono::Duration::days(30)),
+            segments: RetentionPeriod::For(chrono::Duration::days(7)),
+            transcripts: RetentionPeriod::Forever,
+        };
+        let now = "2024-03-05T00:00:00Z".parse().unwrap();
+
+        let mut engine = RetentionEngine::new(temp.path().to_path_buf(), rules);
+        engine.set_dry_run(true);
+        let preview = engine.run_at(now).unwrap();
+        assert!(preview.dry_run);
+        assert_eq!(preview.files_deleted, 6);
+        assert_eq!(preview.space_freed, 180);
+        assert_eq!(preview.sessions_removed, 0);
+        assert!(old.join("raw_audio.flac").exists());
+
+        engine.set_dry_run(false);
+        let result = engine.run_at(now).unwrap();
+        assert_eq!(result.deleted_paths, preview.deleted_paths);
+        assert!(!old.join("raw_audio.flac").exists());
+        assert!(!old.join("segments").exists());
+        assert!(!old.join("waveform").exists());
+        assert!(old.join("session_metadata.json").exists());
+        assert!(recent.join("raw_audio.flac").exists());
+        assert_eq!(result.sessions_removed, 0);
+        let storage = FileAudioStorage::new(temp.path().to_path_buf(), StorageConfig::default()).unwrap();
+        assert!(!storage.is_indexed(indexed[0].id));
+        assert!(storage.is_indexed(indexed[1].id));
+
+        // A crashed session's journal waits for recovery, however old
+        let crashed = temp.path().join("sessions/2024/01/11/crashed_3");
+        fs::create_dir_all(crashed.join(JOURNAL_DIR)).unwrap();
+        fs::write(crashed.join(JOURNAL_DIR).join("journal.json"), r#"{"start_time":"2024-01-11T12:00:00Z"}"#).unwrap();
+
+        // Once transcripts expire too the whole session and its date directories go
+        engine.rules.transcripts = RetentionPeriod::For(chrono::Duration::days(30));
+        let result = engine.run_at(now).unwrap();
+        assert_eq!(result.sessions_removed, 1);
+        assert!(!old.exists());
+        assert!(crashed.join(JOURNAL_DIR).join("journal.json").exists());
+        assert!(recent.exists());
+    }
+}
@@ -0,0 +1,467 @@
+//! Portable session bundles.
+//!
+//! A bundle packs one saved session into a single tar file that can be handed
+//! to someone else: its audio (the original WAV, or FLAC or Opus to save
+//! space), `session_metadata.json`, the transcript segments, markers and any
+//! transcript revisions. `manifest.json` lists every other entry with its
+//! SHA-256 checksum and may be signed with an Ed25519 key, in which case the
+//! signature is stored in `manifest.sig`. Bundles are always written in the
+//! clear, whether or not the archive they came from is encrypted.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::services::audio_archive::{AudioError, CompressionLevel};
+use crate::services::audio_session_manager::{AudioRecordingSession, TranscriptSegment};
+use crate::services::flac::{self, FlacEncoder};
+use crate::services::ogg_opus;
+use crate::services::session_edit::SessionAudio;
+use crate::services::session_markers::{self, SessionMarker};
+use crate::services::transcript_revisions::{TranscriptRevision, REVISIONS_DIR};
+
+/// Version written to new manifests; newer bundles are refused
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST: &str = "manifest.json";
+const SIGNATURE: &str = "manifest.sig";
+const METADATA: &str = "session_metadata.json";
+const TRANSCRIPT: &str = "transcript.json";
+const MARKERS: &str = "markers.json";
+const BLOCK: usize = 512;
+
+/// How the session's audio is stored in a bundle
+#[derive(Debug, Clone)]
+pub enum BundleAudio {
+    /// The session's raw WAV as recorded
+    Original,
+    /// Lossless FLAC
+    Flac,
+    /// Lossy Ogg Opus at the given level (needs the `opus-archive` feature)
+    Opus(CompressionLevel),
+}
+
+impl BundleAudio {
+    fn file_name(&self) -> &'static str {
+        match self {
+            Self::Original => "audio.wav",
+            Self::Flac => "audio.flac",
+            Self::Opus(_) => "audio.opus",
+        }
+    }
+}
+
+/// Options for [`write_bundle`]
+#[derive(Default)]
+pub struct BundleOptions<'a> {
+    pub audio: Option<BundleAudio>,
+    /// Sign the manifest with this key
+    pub signer: Option<&'a BundleSigningKey>,
+}
+
+/// Whether an imported bundle keeps its session, segment and marker IDs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdPolicy {
+    /// Keep the IDs; the import fails if the session already exists
+    Preserve,
+    /// Give the session and everything in it fresh IDs
+    Remap,
+}
+
+/// One entry of a bundle, as listed in its manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleFile {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Contents list of a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub session_id: Uuid,
+    pub session_name: String,
+    pub exported_at: DateTime<Utc>,
+    /// Name of the audio entry
+    pub audio: String,
+    pub files: Vec<BundleFile>,
+}
+
+/// A bundle read back and checked against its manifest
+#[derive(Debug, Clone)]
+pub struct SessionBundle {
+    pub manifest: BundleManifest,
+    pub session: AudioRecordingSession,
+    pub audio: SessionAudio,
+    pub revisions: Vec<TranscriptRevision>,
+    /// Whether the manifest signature was checked against a trusted key
+    pub verified: bool,
+}
+
+/// Ed25519 key for signing bundle manifests
+pub struct BundleSigningKey {
+    key_pair: Ed25519KeyPair,
+}
+
+impl BundleSigningKey {
+    /// A new key, as PKCS#8 bytes to keep for later use
+    pub fn generate_pkcs8() -> Result<Vec<u8>, AudioError> {
+        Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new())
+            .map(|document| document.as_ref().to_vec())
+            .map_err(|_| AudioError::InvalidConfiguration("Failed to generate a signing key".to_string()))
+    }
+
+    pub fn from_pkcs8(pkcs8: &[u8]) -> Result<Self, AudioError> {
+        Ed25519KeyPair::from_pkcs8(pkcs8)
+            .map(|key_pair| Self { key_pair })
+            .map_err(|e| AudioError::InvalidConfiguration(format!("Invalid signing key: {}", e)))
+    }
+
+    /// Public key to give to whoever verifies the bundles
+    pub fn public_key(&self) -> Vec<u8> {
+        self.key_pair.public_key().as_ref().to_vec()
+    }
+}
+
+/// Write a session to a bundle file. `raw_wav` is the session's raw audio file.
+pub fn write_bundle(
+    out: &Path,
+    session: &AudioRecordingSession,
+    raw_wav: &[u8],
+    revisions: &[TranscriptRevision],
+    options: BundleOptions,
+) -> Result<BundleManifest, AudioError> {
+    #[derive(Serialize)]
+    struct SavedMetadata<'a> {
+        session: &'a AudioRecordingSession,
+    }
+
+    let audio_format = options.audio.unwrap_or(BundleAudio::Original);
+    let audio = match &audio_format {
+        BundleAudio::Original => raw_wav.to_vec(),
+        BundleAudio::Flac => {
+            let (samples, channels, sample_rate) = wav_samples(raw_wav)?;
+            let mut encoder = FlacEncoder::new(Cursor::new(Vec::new()), sample_rate, channels, 16)?;
+            encoder.write_samples(&samples)?;
+            encoder.finish()?.into_inner()
+        }
+        BundleAudio::Opus(level) => {
+            let (samples, channels, sample_rate) = wav_samples(raw_wav)?;
+            let samples: Vec<f32> = samples.iter().map(|s| *s as f32 / i16::MAX as f32).collect();
+            ogg_opus::encode(&samples, sample_rate, channels, level)?
+        }
+    };
+
+    let mut entries = vec![
+        (audio_format.file_name().to_string(), audio),
+        (METADATA.to_string(), serde_json::to_vec_pretty(&SavedMetadata { session })?),
+        (TRANSCRIPT.to_string(), serde_json::to_vec_pretty(&session.transcript_segments)?),
+        (MARKERS.to_string(), serde_json::to_vec_pretty(&session_markers::markers(&session.metadata))?),
+    ];
+    for revision in revisions {
+        entries.push((format!("{}/{}.json", REVISIONS_DIR, revision.id), serde_json::to_vec_pretty(revision)?));
+    }
+
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        session_id: session.id,
+        session_name: session.name.clone(),
+        exported_at: Utc::now(),
+        audio: audio_format.file_name().to_string(),
+        files: entries.iter()
+            .map(|(path, data)| BundleFile { path: path.clone(), size: data.len() as u64, sha256: sha256_hex(data) })
+            .collect(),
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+    let mut tar = Vec::new();
+    if let Some(signer) = options.signer {
+        let signature = hex::encode(signer.key_pair.sign(&manifest_json));
+        append_entry(&mut tar, SIGNATURE, signature.as_bytes())?;
+    }
+    append_entry(&mut tar, MANIFEST, &manifest_json)?;
+    for (path, data) in &entries {
+        append_entry(&mut tar, path, data)?;
+    }
+    tar.resize(tar.len() + 2 * BLOCK, 0);
+
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(out, tar)?;
+    Ok(manifest)
+}
+
+/// Read a bundle, checking every entry against the manifest. With a trusted
+/// public key the manifest must carry a valid signature from it.
+pub fn read_bundle(path: &Path, trusted_key: Option<&[u8]>) -> Result<SessionBundle, AudioError> {
+    #[derive(Deserialize)]
+    struct SavedMetadata {
+        session: AudioRecordingSession,
+    }
+
+    let mut entries = read_entries(&fs::read(path)?)?;
+    let manifest_json = entries.remove(MANIFEST).ok_or_else(|| bundle_error("Bundle has no manifest"))?;
+    let manifest: BundleManifest = serde_json::from_slice(&manifest_json)?;
+    if manifest.format_version > BUNDLE_FORMAT_VERSION {
+        return Err(bundle_error(format!("Bundle format {} is newer than this version supports", manifest.format_version)));
+    }
+
+    let signature = entries.remove(SIGNATURE);
+    if let Some(key) = trusted_key {
+        let signature = signature
+            .and_then(|signature| hex::decode(signature.trim_ascii()).ok())
+            .ok_or_else(|| bundle_error("Bundle is not signed"))?;
+        UnparsedPublicKey::new(&ED25519, key)
+            .verify(&manifest_json, &signature)
+            .map_err(|_| bundle_error("Bundle signature does not match the trusted key"))?;
+    }
+
+    for file in &manifest.files {
+        let data = entries.get(&file.path).ok_or_else(|| bundle_error(format!("Bundle is missing {}", file.path)))?;
+        if data.len() as u64 != file.size || sha256_hex(data) != file.sha256 {
+            return Err(bundle_error(format!("Checksum mismatch for {}", file.path)));
+        }
+    }
+    if let Some(extra) = entries.keys().find(|path| !manifest.files.iter().any(|file| &file.path == *path)) {
+        return Err(bundle_error(format!("{} is not listed in the manifest", extra)));
+    }
+
+    let metadata = entries.get(METADATA).ok_or_else(|| bundle_error("Bundle has no session metadata"))?;
+    let session = serde_json::from_slice::<SavedMetadata>(metadata)?.session;
+    let audio = entries.get(&manifest.audio).ok_or_else(|| bundle_error("Bundle has no audio"))?;
+    let audio = decode_audio(&manifest.audio, audio)?;
+    let revisions = manifest.files.iter()
+        .filter(|file| file.path.starts_with(&format!("{}/", REVISIONS_DIR)))
+        .map(|file| serde_json::from_slice::<TranscriptRevision>(&entries[&file.path]))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(SessionBundle {
+        manifest,
+        session,
+        audio,
+        revisions,
+        verified: trusted_key.is_some(),
+    })
+}
+
+impl SessionBundle {
+    /// Give the session, its segments, markers and revisions fresh IDs,
+    /// keeping revision segments matched to the session's segments
+    pub fn remap_ids(&mut self) {
+        let session_id = Uuid::new_v4();
+        let mut segment_ids: HashMap<Uuid, Uuid> = HashMap::new();
+        let mut remap = |segment: &mut TranscriptSegment| {
+            segment.id = *segment_ids.entry(segment.id).or_insert_with(Uuid::new_v4);
+        };
+
+        self.session.id = session_id;
+        self.session.transcript_segments.iter_mut().for_each(&mut remap);
+        for revision in &mut self.revisions {
+            revision.id = Uuid::new_v4();
+            revision.session_id = session_id;
+            revision.segments.iter_mut().for_each(&mut remap);
+        }
+        let markers: Vec<SessionMarker> = session_markers::markers(&self.session.metadata)
+            .into_iter()
+            .map(|marker| SessionMarker { id: Uuid::new_v4(), ..marker })
+            .collect();
+        if !markers.is_empty() {
+            session_markers::set_markers(&mut self.session.metadata, &markers);
+        }
+    }
+}
+
+fn bundle_error(message: impl Into<String>) -> AudioError {
+    AudioError::StorageError(message.into())
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(ring::digest::digest(&ring::digest::SHA256, data))
+}
+
+/// Interleaved 16-bit samples of a WAV file
+fn wav_samples(wav: &[u8]) -> Result<(Vec<i32>, u16, u32), AudioError> {
+    let wav_error = |e: hound::Error| bundle_error(format!("Invalid session audio: {}", e));
+    let mut reader = hound::WavReader::new(Cursor::new(wav)).map_err(wav_error)?;
+    let spec = reader.spec();
+    let samples = reader.samples::<i16>()
+        .map(|s| s.map(i32::from))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(wav_error)?;
+    Ok((samples, spec.channels, spec.sample_rate))
+}
+
+fn decode_audio(name: &str, data: &[u8]) -> Result<SessionAudio, AudioError> {
+    // Scaled like the session's own WAV so re-saving doesn't shift the samples
+    let scale = i16::MAX as f32;
+    let (samples, channels, sample_rate) = if name.ends_with(".flac") {
+        let decoded = flac::decode(data)?;
+        let samples = decoded.samples.iter().map(|s| *s as f32 / scale).collect();
+        (samples, decoded.info.channels, decoded.info.sample_rate)
+    } else if name.ends_with(".opus") {
+        let decoded = ogg_opus::decode(data)?;
+        (decoded.samples, decoded.channels, decoded.sample_rate)
+    } else {
+        let (samples, channels, sample_rate) = wav_samples(data)?;
+        (samples.iter().map(|s| *s as f32 / scale).collect(), channels, sample_rate)
+    };
+    Ok(SessionAudio::from_interleaved(samples, channels, sample_rate))
+}
+
+fn append_entry(tar: &mut Vec<u8>, path: &str, data: &[u8]) -> Result<(), AudioError> {
+    if path.len() > 100 {
+        return Err(bundle_error(format!("Bundle entry name too long: {}", path)));
+    }
+    let mut header = [0u8; BLOCK];
+    header[..path.len()].copy_from_slice(path.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", data.len()).as_bytes());
+    header[136..148].copy_from_slice(format!("{:011o}\0", Utc::now().timestamp().max(0)).as_bytes());
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[148..156].fill(b' ');
+    let checksum: u32 = header.iter().map(|b| *b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+    tar.extend_from_slice(&header);
+    tar.extend_from_slice(data);
+    tar.resize(tar.len().div_ceil(BLOCK) * BLOCK, 0);
+    Ok(())
+}
+
+fn read_entries(tar: &[u8]) -> Result<HashMap<String, Vec<u8>>, AudioError> {
+    let octal = |field: &[u8]| {
+        let text = String::from_utf8_lossy(field);
+        u64::from_str_radix(text.trim_matches(|c: char| c == '\0' || c == ' '), 8)
+            .map_err(|_| bundle_error("Corrupt bundle header"))
+    };
+
+    let mut entries = HashMap::new();
+    let mut pos = 0;
+    while let Some(header) = tar.get(pos..pos + BLOCK) {
+        if header.iter().all(|b| *b == 0) {
+            break;
+        }
+        let mut unsigned = header.to_vec();
+        unsigned[148..156].fill(b' ');
+        if unsigned.iter().map(|b| *b as u64).sum::<u64>() != octal(&header[148..156])? {
+            return Err(bundle_error("Corrupt bundle header"));
+        }
+        let name_len = header[..100].iter().position(|b| *b == 0).unwrap_or(100);
+        let name = String::from_utf8_lossy(&header[..name_len]).into_owned();
+        let size = octal(&header[124..136])? as usize;
+        let data = tar.get(pos + BLOCK..pos + BLOCK + size).ok_or_else(|| bundle_error("Truncated bundle"))?;
+        if matches!(header[156], b'0' | 0) {
+            entries.insert(name, data.to_vec());
+        }
+        pos += BLOCK + size.div_ceil(BLOCK) * BLOCK;
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::session_markers::MarkerKind;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn segment(start: f64, text: &str) -> TranscriptSegment {
+        TranscriptSegment::for_test(start, start + 1.0, text).with_confidence(0.8)
+    }
+
+    fn session() -> AudioRecordingSession {
+        let mut session = AudioRecordingSession::for_test("Standup")
+            .with_duration(Duration::from_secs(2))
+            .with_segments(vec![segment(0.0, "good morning"), segment(1.0, "let's start")]);
+        session_markers::set_markers(&mut session.metadata, &[SessionMarker::new(Duration::from_secs(1), MarkerKind::Chapter, None)]);
+        session
+    }
+
+    fn wav(samples: &[i16]) -> Vec<u8> {
+        let spec = hound::WavSpec { channels: 1, sample_rate: 8000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut out = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut out, spec).unwrap();
+        samples.iter().for_each(|s| writer.write_sample(*s).unwrap());
+        writer.finalize().unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn test_bundle_round_trip_with_flac_audio_and_remapped_ids() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("standup.tar");
+        let samples: Vec<i16> = (0..16000).map(|i| ((i % 80) * 300 - 12000) as i16).collect();
+        let session = session();
+        let revision = TranscriptRevision {
+            id: Uuid::new_v4(),
+            session_id: session.id,
+            created_at: Utc::now(),
+            model: "large".to_string(),
+            decoding: Default::default(),
+            segments: session.transcript_segments.clone(),
+        };
+
+        let options = BundleOptions { audio: Some(BundleAudio::Flac), signer: None };
+        let manifest = write_bundle(&path, &session, &wav(&samples), std::slice::from_ref(&revision), options).unwrap();
+        assert_eq!(manifest.audio, "audio.flac");
+        assert_eq!(manifest.files.len(), 5);
+
+        let mut bundle = read_bundle(&path, None).unwrap();
+        assert_eq!(bundle.session.id, session.id);
+        assert_eq!(bundle.revisions.len(), 1);
+        assert!(!bundle.verified);
+        let restored: Vec<i16> = bundle.audio.mono.iter().map(|s| (s * i16::MAX as f32).round() as i16).collect();
+        assert_eq!(restored, samples);
+
+        bundle.remap_ids();
+        assert_ne!(bundle.session.id, session.id);
+        assert_ne!(bundle.session.transcript_segments[0].id, session.transcript_segments[0].id);
+        assert_eq!(bundle.revisions[0].session_id, bundle.session.id);
+        assert_eq!(bundle.revisions[0].segments[1].id, bundle.session.transcript_segments[1].id);
+        assert_ne!(session_markers::markers(&bundle.session.metadata)[0].id, session_markers::markers(&session.metadata)[0].id);
+    }
+
+    #[test]
+    fn test_tampered_bundle_is_rejected() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("standup.tar");
+        write_bundle(&path, &session(), &wav(&[100; 800]), &[], BundleOptions::default()).unwrap();
+
+        let mut data = fs::read(&path).unwrap();
+        let at = data.windows(12).position(|w| w == b"good morning").unwrap();
+        data[at] = b'G';
+        fs::write(&path, data).unwrap();
+
+        let error = read_bundle(&path, None).unwrap_err();
+        assert!(error.to_string().contains("Checksum mismatch for"), "{}", error);
+    }
+
+    #[test]
+    fn test_signed_bundle_verifies_only_against_its_key() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("standup.tar");
+        let key = BundleSigningKey::from_pkcs8(&BundleSigningKey::generate_pkcs8().unwrap()).unwrap();
+        let other = BundleSigningKey::from_pkcs8(&BundleSigningKey::generate_pkcs8().unwrap()).unwrap();
+
+        let options = BundleOptions { audio: None, signer: Some(&key) };
+        write_bundle(&path, &session(), &wav(&[100; 800]), &[], options).unwrap();
+
+        assert!(read_bundle(&path, Some(&key.public_key())).unwrap().verified);
+        assert!(read_bundle(&path, Some(&other.public_key())).is_err());
+
+        let unsigned = dir.path().join("unsigned.tar");
+        write_bundle(&unsigned, &session(), &wav(&[100; 800]), &[], BundleOptions::default()).unwrap();
+        assert!(read_bundle(&unsigned, Some(&key.public_key())).is_err());
+    }
+}
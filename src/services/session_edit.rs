@@ -0,0 +1,227 @@
+//! Building blocks for editing saved sessions.
+//!
+//! The session manager loads a saved session into [`SessionAudio`], edits the
+//! audio and retimes the transcript with the helpers here, then regenerates
+//! the session outputs so the WAV files, segment files and
+//! `session_metadata.json` stay consistent.
+
+use std::ops::Range;
+use std::time::Duration;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::audio_archive::AudioError;
+use super::audio_session_manager::{AudioRecordingSession, TranscriptSegment};
+
+/// Text left in transcript segments that overlap a redacted range
+pub const REDACTED_TEXT: &str = "[redacted]";
+
+/// Where to split a session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitPoint {
+    /// A time from the start of the session
+    At(Duration),
+    /// The start of a transcript segment
+    BeforeSegment(Uuid),
+}
+
+impl SplitPoint {
+    /// The split time for a session with these transcript segments
+    pub fn resolve(&self, segments: &[TranscriptSegment]) -> Result<Duration, AudioError> {
+        match self {
+            SplitPoint::At(at) => Ok(*at),
+            SplitPoint::BeforeSegment(id) => segments.iter()
+                .find(|segment| segment.id == *id)
+                .map(|segment| segment.start_time)
+                .ok_or_else(|| AudioError::InvalidConfiguration(format!("No transcript segment {}", id))),
+        }
+    }
+}
+
+/// Audio of a saved session: the mono mix plus interleaved stereo for two-source recordings
+#[derive(Debug, Clone, Default)]
+pub struct SessionAudio {
+    pub mono: Vec<f32>,
+    /// Interleaved left/right, empty for mono sessions
+    pub stereo: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl SessionAudio {
+    /// Wrap interleaved samples, downmixing stereo for the mono mix
+    pub fn from_interleaved(samples: Vec<f32>, channels: u16, sample_rate: u32) -> Self {
+        match channels {
+            2 => Self {
+                mono: samples.chunks(2).map(|frame| frame.iter().sum::<f32>() / frame.len() as f32).collect(),
+                stereo: samples,
+                sample_rate,
+            },
+            _ => Self { mono: samples, stereo: Vec::new(), sample_rate },
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.mono.len() as f64 / self.sample_rate.max(1) as f64)
+    }
+
+    /// Frame index of a time, clamped to the audio
+    fn frame_at(&self, t: Duration) -> usize {
+        ((t.as_secs_f64() * self.sample_rate as f64).round() as usize).min(self.mono.len())
+    }
+
+    fn frames(&self, range: &Range<Duration>) -> Range<usize> {
+        let start = self.frame_at(range.start);
+        start..self.frame_at(range.end).max(start)
+    }
+
+    /// The audio in `range`
+    pub fn slice(&self, range: Range<Duration>) -> Self {
+        let frames = self.frames(&range);
+        Self {
+            stereo: if self.stereo.is_empty() { Vec::new() } else { self.stereo[frames.start * 2..frames.end * 2].to_vec() },
+            mono: self.mono[frames].to_vec(),
+            sample_rate: self.sample_rate,
+        }
+    }
+
+    /// Replace `range` with silence
+    pub fn silence(&mut self, range: Range<Duration>) {
+        let frames = self.frames(&range);
+        self.mono[frames.clone()].fill(0.0);
+        if !self.stereo.is_empty() {
+            self.stereo[frames.start * 2..frames.end * 2].fill(0.0);
+        }
+    }
+
+    /// Append another session's audio; mono audio is duplicated onto both channels if either side is stereo
+    pub fn append(&mut self, other: &Self) {
+        if !self.stereo.is_empty() || !other.stereo.is_empty() {
+            let mut stereo = self.interleaved_stereo();
+            stereo.extend(other.interleaved_stereo());
+            self.stereo = stereo;
+        }
+        self.mono.extend_from_slice(&other.mono);
+    }
+
+    fn interleaved_stereo(&self) -> Vec<f32> {
+        if self.stereo.is_empty() {
+            self.mono.iter().flat_map(|&s| [s, s]).collect()
+        } else {
+            self.stereo.clone()
+        }
+    }
+}
+
+/// Check an edit range against the session length, clamping its end
+pub fn edit_range(start: Duration, end: Duration, total: Duration) -> Result<Range<Duration>, AudioError> {
+    let end = end.min(total);
+    if start >= end {
+        return Err(AudioError::InvalidConfiguration(format!(
+            "Empty edit range {:.2}s-{:.2}s for a {:.2}s session",
+            start.as_secs_f64(),
+            end.as_secs_f64(),
+            total.as_secs_f64()
+        )));
+    }
+    Ok(start..end)
+}
+
+/// Segments whose midpoint lies in `range`, clipped to it and retimed from its start
+pub fn retime_segments(segments: &[TranscriptSegment], range: Range<Duration>) -> Vec<TranscriptSegment> {
+    segments.iter()
+        .filter(|segment| range.contains(&((segment.start_time + segment.end_time) / 2)))
+        .map(|segment| TranscriptSegment {
+            start_time: segment.start_time.clamp(range.start, range.end) - range.start,
+            end_time: segment.end_time.clamp(range.start, range.end) - range.start,
+            ..segment.clone()
+        })
+        .collect()
+}
+
+/// Segments moved later by `offset`
+pub fn offset_segments(segments: &[TranscriptSegment], offset: Duration) -> Vec<TranscriptSegment> {
+    segments.iter()
+        .map(|segment| TranscriptSegment {
+            start_time: segment.start_time + offset,
+            end_time: segment.end_time + offset,
+            ..segment.clone()
+        })
+        .collect()
+}
+
+/// Blank the text of segments overlapping `range`; returns how many were redacted
+pub fn redact_segments(segments: &mut [TranscriptSegment], range: Range<Duration>) -> usize {
+    let mut redacted = 0;
+    for segment in segments.iter_mut().filter(|s| s.start_time < range.end && s.end_time > range.start) {
+        segment.text = REDACTED_TEXT.to_string();
+        segment.word_count = 0;
+        segment.prosody = None;
+        redacted += 1;
+    }
+    redacted
+}
+
+/// Note an edit in the session metadata
+pub fn record_edit(session: &mut AudioRecordingSession, description: &str) {
+    let entry = format!("{} {}", Utc::now().to_rfc3339(), description);
+    session.metadata.entry("edit_history".to_string())
+        .and_modify(|history| {
+            history.push_str("; ");
+            history.push_str(&entry);
+        })
+        .or_insert(entry);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retime_and_redact_segments() {
+        let secs = Duration::from_secs_f64;
+        let segment = TranscriptSegment::for_test;
+        let segments = vec![segment(0.5, 2.0, "hello there"), segment(2.5, 4.5, "my pin is"), segment(5.0, 6.0, "bye")];
+
+        // The middle segment's midpoint (3.5s) is before the cut at 4s, so it stays with the first part
+        let head = retime_segments(&segments, secs(0.0)..secs(4.0));
+        assert_eq!(head.iter().map(|s| s.text.as_str()).collect::<Vec<_>>(), ["hello there", "my pin is"]);
+        assert_eq!(head[1].end_time, secs(4.0));
+        let tail = retime_segments(&segments, secs(4.0)..secs(6.0));
+        assert_eq!(tail.len(), 1);
+        assert_eq!((tail[0].start_time, tail[0].end_time), (secs(1.0), secs(2.0)));
+
+        let merged = offset_segments(&tail, secs(10.0));
+        assert_eq!(merged[0].start_time, secs(11.0));
+
+        let mut redacted = segments.clone();
+        assert_eq!(redact_segments(&mut redacted, secs(3.0)..secs(3.5)), 1);
+        assert_eq!(redacted[1].text, REDACTED_TEXT);
+        assert_eq!(redacted[1].word_count, 0);
+        assert_eq!(redacted[0].text, "hello there");
+    }
+
+    #[test]
+    fn test_session_audio_edits() {
+        let secs = Duration::from_secs_f64;
+        let stereo: Vec<f32> = (0..20).map(|i| i as f32).collect();
+        let mut audio = SessionAudio::from_interleaved(stereo, 2, 10);
+        assert_eq!(audio.mono.len(), 10);
+        assert_eq!(audio.mono[1], 2.5);
+
+        let middle = audio.slice(secs(0.2)..secs(0.5));
+        assert_eq!(middle.mono, [4.5, 6.5, 8.5]);
+        assert_eq!(middle.stereo, [4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
+
+        audio.silence(secs(0.0)..secs(0.2));
+        assert_eq!(&audio.stereo[..5], [0.0, 0.0, 0.0, 0.0, 4.0]);
+
+        let mono = SessionAudio::from_interleaved(vec![0.5, 0.25], 1, 10);
+        audio.append(&mono);
+        assert_eq!(audio.duration(), secs(1.2));
+        assert_eq!(&audio.stereo[20..], [0.5, 0.5, 0.25, 0.25]);
+
+        assert!(edit_range(secs(2.0), secs(3.0), secs(1.2)).is_err());
+        assert_eq!(edit_range(secs(1.0), secs(3.0), secs(1.2)).unwrap(), secs(1.0)..secs(1.2));
+    }
+}
@@ -0,0 +1,399 @@
+//! Import of external audio files into the session archive.
+//!
+//! WAV, FLAC and Ogg Opus files are decoded into [`SessionAudio`] and dated
+//! from their own metadata where they carry it: the BWF `bext` origination
+//! time or RIFF `ICRD` tag for WAV, and the Vorbis comment `DATE` for FLAC
+//! and Opus. Files without one fall back to their modification time, less
+//! the recording's duration. Each import records a hash of its decoded audio
+//! so the same recording is recognised when imported again, whatever
+//! container it arrives in.
+//!
+//! Ogg Opus needs the `opus-archive` feature; without it, and for Ogg Vorbis,
+//! an Ogg file is turned away before anything is decoded.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::services::audio_archive::AudioError;
+use crate::services::audio_session_manager::AudioRecordingSession;
+use crate::services::flac;
+use crate::services::ogg_opus;
+use crate::services::session_edit::SessionAudio;
+
+/// Session metadata key holding the hash of an imported file's audio
+pub const CONTENT_HASH_KEY: &str = "content_hash";
+/// Session metadata key holding the path the audio was imported from
+pub const IMPORTED_FROM_KEY: &str = "imported_from";
+/// Session metadata key naming where the session's start time came from
+pub const TIMESTAMP_SOURCE_KEY: &str = "timestamp_source";
+
+/// Where an imported recording's start time came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampSource {
+    /// BWF `bext` origination date and time
+    Broadcast,
+    /// RIFF `LIST/INFO` creation date
+    RiffInfo,
+    /// Vorbis comment `DATE` in FLAC or Opus
+    VorbisComment,
+    /// File modification time, less the duration
+    Modified,
+}
+
+impl TimestampSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Broadcast => "bext",
+            Self::RiffInfo => "riff_info",
+            Self::VorbisComment => "vorbis_comment",
+            Self::Modified => "mtime",
+        }
+    }
+}
+
+/// A decoded audio file ready to become a session
+#[derive(Debug, Clone)]
+pub struct ImportedAudio {
+    pub path: PathBuf,
+    pub audio: SessionAudio,
+    pub recorded_at: DateTime<Utc>,
+    pub timestamp_source: TimestampSource,
+    pub content_hash: String,
+}
+
+impl ImportedAudio {
+    /// Session name, from the file name
+    pub fn name(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "Imported audio".to_string())
+    }
+}
+
+/// Result of importing one file
+#[derive(Debug, Clone)]
+pub enum ImportOutcome {
+    Imported(Box<AudioRecordingSession>),
+    /// The file's audio is already in the archive
+    Duplicate { existing: Uuid },
+}
+
+/// Decode a WAV, FLAC or Ogg Opus file and work out when it was recorded
+pub fn decode(path: &Path) -> Result<ImportedAudio, AudioError> {
+    let data = fs::read(path)?;
+    let (samples, channels, sample_rate, tagged) = if data.starts_with(b"RIFF") {
+        let (samples, channels, sample_rate) = decode_wav(&data)?;
+        (samples, channels, sample_rate, wav_timestamp(&data))
+    } else if data.starts_with(b"fLaC") {
+        let decoded = flac::decode(&data)?;
+        let tagged = flac_comments(&data)
+            .and_then(|comments| comment_timestamp(&comments))
+            .map(|t| (t, TimestampSource::VorbisComment));
+        (decoded.to_f32(), decoded.info.channels, decoded.info.sample_rate, tagged)
+    } else if data.starts_with(b"OggS") {
+        check_ogg_supported(path, &data)?;
+        let decoded = ogg_opus::decode(&data)?;
+        let tagged = opus_comments(&data)
+            .and_then(|comments| comment_timestamp(&comments))
+            .map(|t| (t, TimestampSource::VorbisComment));
+        (decoded.samples, decoded.channels, decoded.sample_rate, tagged)
+    } else {
+        return Err(AudioError::InvalidConfiguration(format!(
+            "{} is not a WAV, FLAC or Ogg Opus file",
+            path.display()
+        )));
+    };
+    if sample_rate == 0 || channels == 0 {
+        return Err(AudioError::InvalidConfiguration(format!("{} has no audio format", path.display())));
+    }
+
+    let content_hash = content_hash(&samples, channels, sample_rate);
+    let audio = match channels {
+        1 | 2 => SessionAudio::from_interleaved(samples, channels, sample_rate),
+        // Surround files are archived as a mono mix
+        _ => SessionAudio::from_interleaved(
+            samples.chunks(channels as usize).map(|frame| frame.iter().sum::<f32>() / frame.len() as f32).collect(),
+            1,
+            sample_rate,
+        ),
+    };
+
+    let (recorded_at, timestamp_source) = match tagged {
+        Some(tagged) => tagged,
+        None => {
+            let modified: DateTime<Utc> = fs::metadata(path)?.modified()?.into();
+            let duration = chrono::Duration::from_std(audio.duration()).unwrap_or_default();
+            (modified - duration, TimestampSource::Modified)
+        }
+    };
+
+    Ok(ImportedAudio {
+        path: path.to_path_buf(),
+        audio,
+        recorded_at,
+        timestamp_source,
+        content_hash,
+    })
+}
+
+/// Turn away Ogg streams this build can't decode, saying why
+fn check_ogg_supported(path: &Path, data: &[u8]) -> Result<(), AudioError> {
+    let (packets, _) = ogg_opus::read_packets(data)?;
+    let head = packets.first().map(Vec::as_slice).unwrap_or_default();
+    if head.starts_with(b"\x01vorbis") {
+        return Err(AudioError::InvalidConfiguration(format!(
+            "{} is Ogg Vorbis, which can't be imported; convert it to FLAC or Opus",
+            path.display()
+        )));
+    }
+    if !head.starts_with(b"OpusHead") {
+        return Err(AudioError::InvalidConfiguration(format!("{} is not an Ogg Opus file", path.display())));
+    }
+    if !cfg!(feature = "opus-archive") {
+        return Err(AudioError::InvalidConfiguration(format!(
+            "importing Ogg Opus ({}) needs a build with the `opus-archive` feature",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+/// SHA-256 of the audio quantised to 16 bits, so container and bit depth don't matter
+pub fn content_hash(samples: &[f32], channels: u16, sample_rate: u32) -> String {
+    let mut context = ring::digest::Context::new(&ring::digest::SHA256);
+    context.update(&channels.to_le_bytes());
+    context.update(&sample_rate.to_le_bytes());
+    for &sample in samples {
+        let quantised = (sample * 32768.0).round().clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        context.update(&quantised.to_le_bytes());
+    }
+    context.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_wav(data: &[u8]) -> Result<(Vec<f32>, u16, u32), AudioError> {
+    let wav_error = |e: hound::Error| AudioError::InvalidConfiguration(format!("Invalid WAV file: {}", e));
+    let mut reader = hound::WavReader::new(std::io::Cursor::new(data)).map_err(wav_error)?;
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader.samples::<i32>().map(|s| s.map(|sample| sample as f32 / scale)).collect()
+        }
+    }
+    .map_err(wav_error)?;
+    Ok((samples, spec.channels, spec.sample_rate))
+}
+
+/// RIFF chunks of a WAV file as (id, body) pairs
+fn riff_chunks(data: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut chunks = Vec::new();
+    let mut pos = 12;
+    while let Some(header) = data.get(pos..pos + 8) {
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let body = &data[pos + 8..(pos + 8 + len).min(data.len())];
+        chunks.push((&header[..4], body));
+        // Chunks are padded to an even length
+        pos += 8 + len + (len & 1);
+    }
+    chunks
+}
+
+fn wav_timestamp(data: &[u8]) -> Option<(DateTime<Utc>, TimestampSource)> {
+    let chunks = riff_chunks(data);
+    let broadcast = chunks.iter().find(|(id, _)| *id == b"bext").and_then(|(_, body)| {
+        // OriginationDate and OriginationTime follow the description,
+        // originator and reference fields
+        let date = String::from_utf8_lossy(body.get(320..330)?).into_owned();
+        let time = String::from_utf8_lossy(body.get(330..338)?).replace(['-', '.'], ":");
+        parse_timestamp(&format!("{} {}", date, time))
+    });
+    if let Some(t) = broadcast {
+        return Some((t, TimestampSource::Broadcast));
+    }
+    chunks
+        .iter()
+        .filter(|(id, body)| *id == b"LIST" && body.starts_with(b"INFO"))
+        .find_map(|(_, body)| {
+            let mut pos = 4;
+            while let Some(header) = body.get(pos..pos + 8) {
+                let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+                let value = body.get(pos + 8..pos + 8 + len)?;
+                if &header[..4] == b"ICRD" {
+                    return parse_timestamp(String::from_utf8_lossy(value).trim_end_matches('\0'));
+                }
+                pos += 8 + len + (len & 1);
+            }
+            None
+        })
+        .map(|t| (t, TimestampSource::RiffInfo))
+}
+
+/// Vorbis comments from a FLAC `VORBIS_COMMENT` block
+fn flac_comments(data: &[u8]) -> Option<Vec<String>> {
+    let mut pos = 4;
+    loop {
+        let header = data.get(pos..pos + 4)?;
+        let len = ((header[1] as usize) << 16) | ((header[2] as usize) << 8) | header[3] as usize;
+        if header[0] & 0x7F == 4 {
+            return vorbis_comments(data.get(pos + 4..pos + 4 + len)?);
+        }
+        if header[0] & 0x80 != 0 {
+            return None;
+        }
+        pos += 4 + len;
+    }
+}
+
+/// Vorbis comments from the `OpusTags` packet
+fn opus_comments(data: &[u8]) -> Option<Vec<String>> {
+    let (packets, _) = ogg_opus::read_packets(data).ok()?;
+    let tags = packets.get(1)?.strip_prefix(b"OpusTags")?;
+    vorbis_comments(tags)
+}
+
+/// `KEY=value` strings from a Vorbis comment block
+fn vorbis_comments(block: &[u8]) -> Option<Vec<String>> {
+    let read_u32 = |pos: usize| block.get(pos..pos + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+    let mut pos = 4 + read_u32(0)?;
+    let count = read_u32(pos)?;
+    pos += 4;
+    let mut comments = Vec::with_capacity(count.min(64));
+    for _ in 0..count {
+        let len = read_u32(pos)?;
+        comments.push(String::from_utf8_lossy(block.get(pos + 4..pos + 4 + len)?).into_owned());
+        pos += 4 + len;
+    }
+    Some(comments)
+}
+
+fn comment_timestamp(comments: &[String]) -> Option<DateTime<Utc>> {
+    comments.iter().find_map(|comment| {
+        let (key, value) = comment.split_once('=')?;
+        matches!(key.to_ascii_uppercase().as_str(), "DATE" | "CREATION_TIME")
+            .then(|| parse_timestamp(value))
+            .flatten()
+    })
+}
+
+/// Parse a tag date; times without a zone are taken as local time
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(value) {
+        return Some(t.with_timezone(&Utc));
+    }
+    let naive = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y:%m:%d %H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)))?;
+    Local.from_local_datetime(&naive).earliest().map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_wav(path: &Path, samples: &[i16], info_date: Option<&str>) {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for &sample in samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        if let Some(date) = info_date {
+            let mut value = date.as_bytes().to_vec();
+            value.push(0);
+            let mut list = b"INFO".to_vec();
+            list.extend_from_slice(b"ICRD");
+            list.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            list.extend_from_slice(&value);
+            if value.len() % 2 == 1 {
+                list.push(0);
+            }
+            let mut data = fs::read(path).unwrap();
+            data.extend_from_slice(b"LIST");
+            data.extend_from_slice(&(list.len() as u32).to_le_bytes());
+            data.extend_from_slice(&list);
+            let riff_len = (data.len() - 8) as u32;
+            data[4..8].copy_from_slice(&riff_len.to_le_bytes());
+            fs::write(path, data).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_wav_import_reads_riff_creation_date() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("memo.wav");
+        let samples: Vec<i16> = (0..8000).map(|i| ((i % 50) * 200) as i16).collect();
+        write_wav(&path, &samples, Some("2024-03-05T09:30:00Z"));
+
+        let imported = decode(&path).unwrap();
+        assert_eq!(imported.name(), "memo");
+        assert_eq!(imported.timestamp_source, TimestampSource::RiffInfo);
+        assert_eq!(imported.recorded_at, "2024-03-05T09:30:00Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(imported.audio.sample_rate, 8000);
+        assert_eq!(imported.audio.mono.len(), 8000);
+        assert!(imported.audio.stereo.is_empty());
+    }
+
+    #[test]
+    fn test_untagged_wav_falls_back_to_modification_time() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("untagged.wav");
+        write_wav(&path, &[0; 16000], None);
+
+        let imported = decode(&path).unwrap();
+        let modified: DateTime<Utc> = fs::metadata(&path).unwrap().modified().unwrap().into();
+        assert_eq!(imported.timestamp_source, TimestampSource::Modified);
+        assert_eq!(imported.recorded_at, modified - chrono::Duration::seconds(2));
+    }
+
+    #[test]
+    fn test_content_hash_ignores_container_but_not_audio() {
+        let dir = TempDir::new().unwrap();
+        let samples: Vec<i16> = (0..4000).map(|i| (i * 7) as i16).collect();
+        let tagged = dir.path().join("tagged.wav");
+        let plain = dir.path().join("plain.wav");
+        write_wav(&tagged, &samples, Some("2024-03-05"));
+        write_wav(&plain, &samples, None);
+
+        let hash = decode(&tagged).unwrap().content_hash;
+        assert_eq!(hash, decode(&plain).unwrap().content_hash);
+        assert_eq!(hash.len(), 64);
+
+        let mut changed = samples.clone();
+        changed[100] += 1;
+        write_wav(&plain, &changed, None);
+        assert_ne!(hash, decode(&plain).unwrap().content_hash);
+    }
+
+    #[test]
+    fn test_unsupported_ogg_is_rejected_up_front() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("song.ogg");
+        let mut ogg = ogg_opus::OggWriter::new(Vec::new(), 7);
+        ogg.write_packet(b"\x01vorbis\x00\x00\x00\x00\x01\x44\xac\x00\x00", 0).unwrap();
+        fs::write(&path, ogg.finish().unwrap()).unwrap();
+        let err = decode(&path).unwrap_err().to_string();
+        assert!(err.contains("Ogg Vorbis"), "{}", err);
+
+        #[cfg(not(feature = "opus-archive"))]
+        {
+            let mut ogg = ogg_opus::OggWriter::new(Vec::new(), 7);
+            ogg.write_packet(&ogg_opus::opus_head(1, 312, 16000), 0).unwrap();
+            fs::write(&path, ogg.finish().unwrap()).unwrap();
+            assert!(decode(&path).unwrap_err().to_string().contains("opus-archive"));
+        }
+    }
+}
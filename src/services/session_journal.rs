@@ -0,0 +1,387 @@
+//! Crash-safe on-disk journal for sessions being recorded
+//!
+//! While a session records, audio is streamed to numbered chunk files in
+//! `<session dir>/journal/` by a background writer, next to a `journal.json`
+//! snapshot of the session. A clean stop turns the journal into the usual
+//! session outputs and deletes it; a journal still present at startup
+//! belongs to a session that never stopped and can be recovered.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use super::audio_archive::AudioError;
+use super::encryption::{self, StorageCipher};
+
+/// Journal directory inside a session directory
+pub const JOURNAL_DIR: &str = "journal";
+const HEADER_FILE: &str = "journal.json";
+const CHUNK_EXTENSION: &str = "chunk";
+/// Sample rate (u32) and channel count (u16) ahead of the samples
+const CHUNK_HEADER_LEN: usize = 6;
+
+/// Audio streams kept in a journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalStream {
+    /// The mono session mix
+    Mono,
+    /// Interleaved two-source stereo
+    Stereo,
+}
+
+impl JournalStream {
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Mono => "mono",
+            Self::Stereo => "stereo",
+        }
+    }
+
+    fn channels(self) -> u16 {
+        match self {
+            Self::Mono => 1,
+            Self::Stereo => 2,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Audio read back from a journal
+#[derive(Debug, Clone, Default)]
+pub struct JournalAudio {
+    pub samples: Vec<f32>,
+    /// Rate of the recorded chunks; `None` when nothing was journaled
+    pub sample_rate: Option<u32>,
+}
+
+/// A session's journal directory
+#[derive(Debug, Clone)]
+pub struct SessionJournal {
+    dir: PathBuf,
+    cipher: Option<Arc<StorageCipher>>,
+}
+
+impl SessionJournal {
+    /// Create the journal directory for a session
+    pub fn create(session_dir: &Path, cipher: Option<Arc<StorageCipher>>) -> Result<Self, AudioError> {
+        let dir = session_dir.join(JOURNAL_DIR);
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, cipher })
+    }
+
+    /// Open an existing journal directory
+    pub fn open(dir: PathBuf, cipher: Option<Arc<StorageCipher>>) -> Self {
+        Self { dir, cipher }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Journals left under `sessions_dir` by sessions that never stopped
+    pub fn find_unfinished(sessions_dir: &Path) -> Result<Vec<PathBuf>, AudioError> {
+        let mut found = Vec::new();
+        if sessions_dir.is_dir() {
+            Self::scan(sessions_dir, &mut found)?;
+        }
+        found.sort();
+        Ok(found)
+    }
+
+    fn scan(dir: &Path, found: &mut Vec<PathBuf>) -> Result<(), AudioError> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if !path.is_dir() {
+                continue;
+            }
+            if path.file_name().is_some_and(|name| name == JOURNAL_DIR) && path.join(HEADER_FILE).exists() {
+                found.push(path);
+            } else {
+                Self::scan(&path, found)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Save a snapshot of the session being recorded
+    pub fn write_header<T: Serialize>(&self, session: &T) -> Result<(), AudioError> {
+        let data = serde_json::to_vec_pretty(session)?;
+        encryption::write_file(&self.dir.join(HEADER_FILE), &data, self.cipher.as_deref())?;
+        Ok(())
+    }
+
+    pub fn read_header<T: DeserializeOwned>(&self) -> Result<T, AudioError> {
+        let data = encryption::read_file(&self.dir.join(HEADER_FILE), self.cipher.as_deref())?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Chunk files of a stream, in recording order
+    fn chunks(&self, stream: JournalStream) -> Result<Vec<PathBuf>, AudioError> {
+        let prefix = format!("{}_", stream.prefix());
+        let mut chunks: Vec<PathBuf> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.extension().is_some_and(|ext| ext == CHUNK_EXTENSION)
+                    && path.file_name().is_some_and(|name| name.to_string_lossy().starts_with(&prefix))
+            })
+            .collect();
+        // Zero-padded indices sort in order
+        chunks.sort();
+        Ok(chunks)
+    }
+
+    fn read_chunk(&self, path: &Path) -> Result<(u32, Vec<f32>), AudioError> {
+        let data = encryption::read_file(path, self.cipher.as_deref())?;
+        if data.len() < CHUNK_HEADER_LEN {
+            return Err(AudioError::StorageError(format!("Truncated journal chunk {}", path.display())));
+        }
+        let sample_rate = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let samples = data[CHUNK_HEADER_LEN..]
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect();
+        Ok((sample_rate, samples))
+    }
+
+    /// Read a stream back one chunk at a time, as (sample rate, samples)
+    pub fn read_chunks(
+        &self,
+        stream: JournalStream,
+    ) -> Result<impl Iterator<Item = Result<(u32, Vec<f32>), AudioError>> + '_, AudioError> {
+        Ok(self.chunks(stream)?.into_iter().map(move |chunk| self.read_chunk(&chunk)))
+    }
+
+    /// Whether anything was journaled for a stream
+    pub fn is_empty(&self, stream: JournalStream) -> Result<bool, AudioError> {
+        Ok(self.chunks(stream)?.is_empty())
+    }
+
+    /// Sample rate and length of a stream, without keeping its samples
+    pub fn measure(&self, stream: JournalStream) -> Result<(Option<u32>, usize), AudioError> {
+        let mut sample_rate = None;
+        let mut len = 0;
+        for chunk in self.read_chunks(stream)? {
+            let (rate, samples) = chunk?;
+            sample_rate.get_or_insert(rate);
+            len += samples.len();
+        }
+        Ok((sample_rate, len))
+    }
+
+    /// Read a whole stream back
+    pub fn read_stream(&self, stream: JournalStream) -> Result<JournalAudio, AudioError> {
+        let mut audio = JournalAudio::default();
+        for chunk in self.read_chunks(stream)? {
+            let (sample_rate, samples) = chunk?;
+            audio.sample_rate.get_or_insert(sample_rate);
+            audio.samples.extend(samples);
+        }
+        Ok(audio)
+    }
+
+    /// Read samples `start..end` of a stream from the chunks written so far
+    pub fn read_range(&self, stream: JournalStream, start: usize, end: usize) -> Result<Vec<f32>, AudioError> {
+        let mut range = Vec::new();
+        let mut position = 0;
+        for chunk in self.chunks(stream)? {
+            if position >= end {
+                break;
+            }
+            let (_, samples) = self.read_chunk(&chunk)?;
+            let chunk_end = position + samples.len();
+            if chunk_end > start {
+                range.extend_from_slice(&samples[start.saturating_sub(position)..end.min(chunk_end) - position]);
+            }
+            position = chunk_end;
+        }
+        Ok(range)
+    }
+
+    /// Start the background writer for this journal
+    pub fn start_writer(&self, chunk_duration: Duration) -> JournalWriter {
+        let (sender, receiver) = mpsc::channel();
+        let journal = self.clone();
+        let handle = thread::spawn(move || journal.write_chunks(receiver, chunk_duration));
+        JournalWriter {
+            sender: JournalSender(sender),
+            handle: Some(handle),
+        }
+    }
+
+    fn write_chunks(&self, receiver: Receiver<JournalMessage>, chunk_duration: Duration) -> Result<usize, AudioError> {
+        let mut pending: [PendingChunk; 2] = Default::default();
+        let mut chunks_written = 0;
+        let mut first_error = None;
+        let mut write = |stream: JournalStream, chunk: &mut PendingChunk| {
+            if chunk.samples.is_empty() {
+                return;
+            }
+            match self.write_chunk(stream, chunk) {
+                Ok(()) => chunks_written += 1,
+                Err(e) => {
+                    warn!(journal = %self.dir.display(), error = %e, "Failed to write journal chunk");
+                    first_error.get_or_insert(e);
+                }
+            }
+            chunk.index += 1;
+            chunk.samples.clear();
+        };
+
+        // A closed channel means the writer was dropped without finishing; flush anyway
+        while let Ok(JournalMessage::Samples { stream, samples, sample_rate }) = receiver.recv() {
+            let chunk = &mut pending[stream.index()];
+            if chunk.sample_rate != sample_rate && !chunk.samples.is_empty() {
+                write(stream, chunk);
+            }
+            chunk.sample_rate = sample_rate;
+            chunk.samples.extend_from_slice(&samples);
+            let chunk_len = (chunk_duration.as_secs_f64() * sample_rate as f64) as usize * stream.channels() as usize;
+            if chunk.samples.len() >= chunk_len.max(1) {
+                write(stream, chunk);
+            }
+        }
+        for stream in [JournalStream::Mono, JournalStream::Stereo] {
+            write(stream, &mut pending[stream.index()]);
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(chunks_written),
+        }
+    }
+
+    fn write_chunk(&self, stream: JournalStream, chunk: &PendingChunk) -> Result<(), AudioError> {
+        let mut data = Vec::with_capacity(CHUNK_HEADER_LEN + chunk.samples.len() * 2);
+        data.extend_from_slice(&chunk.sample_rate.to_le_bytes());
+        data.extend_from_slice(&stream.channels().to_le_bytes());
+        for &sample in &chunk.samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        let path = self.dir.join(format!("{}_{:06}.{}", stream.prefix(), chunk.index, CHUNK_EXTENSION));
+        encryption::write_file(&path, &data, self.cipher.as_deref())?;
+        debug!(chunk = %path.display(), samples = chunk.samples.len(), "💾 Wrote journal chunk");
+        Ok(())
+    }
+
+    /// Delete the journal once its audio is safely in the session outputs
+    pub fn remove(&self) -> Result<(), AudioError> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct PendingChunk {
+    index: usize,
+    sample_rate: u32,
+    samples: Vec<f32>,
+}
+
+#[derive(Debug)]
+enum JournalMessage {
+    Samples { stream: JournalStream, samples: Vec<f32>, sample_rate: u32 },
+    Finish,
+}
+
+/// Cheap handle for audio callbacks to feed a journal
+#[derive(Debug, Clone)]
+pub struct JournalSender(Sender<JournalMessage>);
+
+impl JournalSender {
+    /// Queue samples for the journal; ignored once the journal has finished
+    pub fn append(&self, stream: JournalStream, samples: &[f32], sample_rate: u32) {
+        let _ = self.0.send(JournalMessage::Samples {
+            stream,
+            samples: samples.to_vec(),
+            sample_rate,
+        });
+    }
+}
+
+/// Background writer streaming a session's audio into its journal
+#[derive(Debug)]
+pub struct JournalWriter {
+    sender: JournalSender,
+    handle: Option<JoinHandle<Result<usize, AudioError>>>,
+}
+
+impl JournalWriter {
+    pub fn sender(&self) -> JournalSender {
+        self.sender.clone()
+    }
+
+    /// Flush buffered audio and stop; returns the number of chunks written
+    pub fn finish(mut self) -> Result<usize, AudioError> {
+        let _ = self.sender.0.send(JournalMessage::Finish);
+        match self.handle.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(_)) => Err(AudioError::StorageError("Journal writer panicked".to_string())),
+            None => Ok(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_journal_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let journal = SessionJournal::create(temp.path(), None).unwrap();
+        journal.write_header(&serde_json::json!({ "name": "standup" })).unwrap();
+
+        let writer = journal.start_writer(Duration::from_millis(100));
+        let sender = writer.sender();
+        let tone: Vec<f32> = (0..4000).map(|i| (i as f32 * 0.01).sin() * 0.5).collect();
+        for frame in tone.chunks(320) {
+            sender.append(JournalStream::Mono, frame, 16000);
+        }
+        sender.append(JournalStream::Stereo, &[0.25, -0.25], 16000);
+        // 4000 samples in 1600-sample chunks, plus the stereo tail
+        assert_eq!(writer.finish().unwrap(), 4);
+        sender.append(JournalStream::Mono, &[1.0], 16000);
+
+        let audio = journal.read_stream(JournalStream::Mono).unwrap();
+        assert_eq!(audio.sample_rate, Some(16000));
+        assert_eq!(audio.samples.len(), tone.len());
+        assert!(audio.samples.iter().zip(&tone).all(|(a, b)| (a - b).abs() < 1e-3));
+        let range = journal.read_range(JournalStream::Mono, 1500, 1700).unwrap();
+        assert_eq!(range.len(), 200);
+        assert!((range[0] - tone[1500]).abs() < 1e-3);
+        assert_eq!(journal.read_stream(JournalStream::Stereo).unwrap().samples.len(), 2);
+        assert_eq!(journal.measure(JournalStream::Mono).unwrap(), (Some(16000), tone.len()));
+        assert_eq!(journal.read_chunks(JournalStream::Mono).unwrap().count(), 3);
+
+        let header: serde_json::Value = journal.read_header().unwrap();
+        assert_eq!(header["name"], "standup");
+    }
+
+    #[test]
+    fn test_find_unfinished_journals() {
+        let temp = TempDir::new().unwrap();
+        let sessions = temp.path().join("sessions");
+        let crashed = SessionJournal::create(&sessions.join("2024/05/07/a_1"), None).unwrap();
+        crashed.write_header(&serde_json::json!({})).unwrap();
+        // A journal directory without a header was never started
+        SessionJournal::create(&sessions.join("2024/05/07/b_2"), None).unwrap();
+
+        assert_eq!(SessionJournal::find_unfinished(&sessions).unwrap(), vec![crashed.dir().to_path_buf()]);
+        crashed.remove().unwrap();
+        assert!(SessionJournal::find_unfinished(&sessions).unwrap().is_empty());
+    }
+}
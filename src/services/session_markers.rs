@@ -0,0 +1,240 @@
+//! Markers and chapters within a recording session.
+//!
+//! Markers are dropped by voice while recording ("mark that", "new chapter
+//! budget discussion") and kept as JSON under the `markers` metadata key, so
+//! recording sessions and archived sessions carry them the same way. Every
+//! marker starts a chapter; chapters export as WebVTT chapters or as an
+//! ffmetadata file that ffmpeg can mux into the audio.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::ops::Range;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Metadata key holding the markers of a session
+pub const MARKERS_KEY: &str = "markers";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkerKind {
+    /// "mark that": a point of interest
+    Mark,
+    /// "new chapter ...": the start of a new topic
+    Chapter,
+}
+
+/// A timestamped marker in a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionMarker {
+    pub id: Uuid,
+    /// Position in the recorded audio
+    pub at: Duration,
+    pub kind: MarkerKind,
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SessionMarker {
+    pub fn new(at: Duration, kind: MarkerKind, label: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            at,
+            kind,
+            label,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// A span of the session between two markers
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    pub start: Duration,
+    pub end: Duration,
+    pub title: String,
+}
+
+/// Markers stored in session metadata, in time order
+pub fn markers(metadata: &HashMap<String, String>) -> Vec<SessionMarker> {
+    let mut markers: Vec<SessionMarker> = metadata.get(MARKERS_KEY)
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default();
+    markers.sort_by_key(|marker| marker.at);
+    markers
+}
+
+/// Store markers in session metadata, dropping the key when there are none
+pub fn set_markers(metadata: &mut HashMap<String, String>, markers: &[SessionMarker]) {
+    let mut markers = markers.to_vec();
+    markers.sort_by_key(|marker| marker.at);
+    match serde_json::to_string(&markers) {
+        Ok(json) if !markers.is_empty() => {
+            metadata.insert(MARKERS_KEY.to_string(), json);
+        }
+        _ => {
+            metadata.remove(MARKERS_KEY);
+        }
+    }
+}
+
+/// Markers inside `range`, retimed from its start
+pub fn retime_markers(markers: &[SessionMarker], range: Range<Duration>) -> Vec<SessionMarker> {
+    markers.iter()
+        .filter(|marker| range.contains(&marker.at))
+        .map(|marker| SessionMarker { at: marker.at - range.start, ..marker.clone() })
+        .collect()
+}
+
+/// Markers moved later by `offset`
+pub fn offset_markers(markers: &[SessionMarker], offset: Duration) -> Vec<SessionMarker> {
+    markers.iter()
+        .map(|marker| SessionMarker { at: marker.at + offset, ..marker.clone() })
+        .collect()
+}
+
+/// Chapters of a session of length `total`; audio before the first marker is an untitled opening chapter
+pub fn chapters(markers: &[SessionMarker], total: Duration) -> Vec<Chapter> {
+    let mut markers: Vec<&SessionMarker> = markers.iter().filter(|marker| marker.at < total).collect();
+    markers.sort_by_key(|marker| marker.at);
+
+    let mut starts: Vec<(Duration, String)> = Vec::new();
+    if markers.first().is_some_and(|marker| !marker.at.is_zero()) {
+        starts.push((Duration::ZERO, "Start".to_string()));
+    }
+    let (mut marks, mut chapters) = (0, 0);
+    for marker in markers {
+        let default_title = match marker.kind {
+            MarkerKind::Mark => {
+                marks += 1;
+                format!("Mark {}", marks)
+            }
+            MarkerKind::Chapter => {
+                chapters += 1;
+                format!("Chapter {}", chapters)
+            }
+        };
+        let title = marker.label.clone().unwrap_or(default_title);
+        // Markers at the same instant: the later one names the chapter
+        match starts.last_mut() {
+            Some(last) if last.0 == marker.at => last.1 = title,
+            _ => starts.push((marker.at, title)),
+        }
+    }
+
+    let ends: Vec<Duration> = starts.iter().skip(1).map(|(start, _)| *start).chain([total]).collect();
+    starts.into_iter()
+        .zip(ends)
+        .map(|((start, title), end)| Chapter { start, end, title })
+        .collect()
+}
+
+/// WebVTT chapters track
+pub fn to_webvtt(chapters: &[Chapter]) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+    for (i, chapter) in chapters.iter().enumerate() {
+        let _ = write!(
+            vtt,
+            "\n{}\n{} --> {}\n{}\n",
+            i + 1,
+            vtt_timestamp(chapter.start),
+            vtt_timestamp(chapter.end),
+            chapter.title.replace("-->", "->").replace('\n', " ")
+        );
+    }
+    vtt
+}
+
+/// ffmpeg metadata file (`ffmpeg -i audio.wav -i chapters.ffmetadata -map_metadata 1 ...`)
+pub fn to_ffmetadata(chapters: &[Chapter], title: &str) -> String {
+    let mut metadata = format!(";FFMETADATA1\ntitle={}\n", ffmetadata_escape(title));
+    for chapter in chapters {
+        let _ = write!(
+            metadata,
+            "\n[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+            chapter.start.as_millis(),
+            chapter.end.as_millis(),
+            ffmetadata_escape(&chapter.title)
+        );
+    }
+    metadata
+}
+
+fn vtt_timestamp(t: Duration) -> String {
+    let ms = t.as_millis();
+    format!("{:02}:{:02}:{:02}.{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+}
+
+fn ffmetadata_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The words spoken after `keyword`, e.g. "budget discussion" from "New chapter: budget discussion."
+pub fn spoken_label(text: &str, keyword: &str) -> Option<String> {
+    let start = text.to_ascii_lowercase().find(&keyword.to_ascii_lowercase())? + keyword.len();
+    let label = text[start..].trim_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation());
+    (!label.is_empty()).then(|| label.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(secs: u64, kind: MarkerKind, label: Option<&str>) -> SessionMarker {
+        SessionMarker::new(Duration::from_secs(secs), kind, label.map(str::to_string))
+    }
+
+    #[test]
+    fn test_chapters_and_exports() {
+        let markers = vec![
+            marker(90, MarkerKind::Chapter, Some("Budget discussion")),
+            marker(30, MarkerKind::Mark, None),
+        ];
+        let chapters = chapters(&markers, Duration::from_secs(120));
+        let titles: Vec<&str> = chapters.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, ["Start", "Mark 1", "Budget discussion"]);
+        assert_eq!(chapters[1].end, Duration::from_secs(90));
+        assert_eq!(chapters[2].end, Duration::from_secs(120));
+
+        let vtt = to_webvtt(&chapters);
+        assert!(vtt.starts_with("WEBVTT\n"));
+        assert!(vtt.contains("\n3\n00:01:30.000 --> 00:02:00.000\nBudget discussion\n"));
+
+        let ffmetadata = to_ffmetadata(&chapters, "Q3 = plan");
+        assert!(ffmetadata.starts_with(";FFMETADATA1\ntitle=Q3 \\= plan\n"));
+        assert!(ffmetadata.contains("[CHAPTER]\nTIMEBASE=1/1000\nSTART=30000\nEND=90000\ntitle=Mark 1\n"));
+    }
+
+    #[test]
+    fn test_markers_in_metadata() {
+        let mut metadata = HashMap::new();
+        set_markers(&mut metadata, &[marker(20, MarkerKind::Mark, None), marker(5, MarkerKind::Chapter, Some("Intro"))]);
+        let stored = markers(&metadata);
+        assert_eq!(stored.iter().map(|m| m.at.as_secs()).collect::<Vec<_>>(), [5, 20]);
+
+        let retimed = retime_markers(&stored, Duration::from_secs(10)..Duration::from_secs(30));
+        assert_eq!(retimed.len(), 1);
+        assert_eq!(retimed[0].at, Duration::from_secs(10));
+        assert_eq!(offset_markers(&retimed, Duration::from_secs(60))[0].at, Duration::from_secs(70));
+
+        set_markers(&mut metadata, &[]);
+        assert!(!metadata.contains_key(MARKERS_KEY));
+    }
+
+    #[test]
+    fn test_spoken_label() {
+        assert_eq!(spoken_label("New chapter: budget discussion.", "new chapter").as_deref(), Some("budget discussion"));
+        assert_eq!(spoken_label("new chapter", "new chapter"), None);
+        assert_eq!(spoken_label("mark that", "add marker"), None);
+    }
+}
@@ -0,0 +1,216 @@
+//! Enforces recording session limits.
+//!
+//! The [`SessionSupervisor`] is a small state machine fed with voice activity
+//! and the state of the current session. It never touches audio itself: the
+//! session manager applies the [`SupervisorAction`]s it returns and reports
+//! what happened as [`SupervisorEvent`]s the main loop can announce.
+
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use super::audio_session_manager::{AudioRecordingSession, SessionConfig, SessionState};
+
+/// How the supervisor reacts to silence and the duration limit
+#[derive(Debug, Clone, PartialEq)]
+pub struct SupervisorConfig {
+    /// Pause after `silence_timeout` without speech and resume when speech returns
+    pub auto_pause: bool,
+    /// How long before `max_session_duration` to warn
+    pub warning_lead: Duration,
+    /// At the limit, continue in a new session instead of stopping
+    pub rollover: bool,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            auto_pause: true,
+            warning_lead: Duration::from_secs(60),
+            rollover: false,
+        }
+    }
+}
+
+/// What the session manager should do next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SupervisorAction {
+    Pause,
+    Resume,
+    Warn { remaining: Duration },
+    Stop,
+    Rollover,
+}
+
+/// What the supervisor did to the current session
+#[derive(Debug, Clone)]
+pub enum SupervisorEvent {
+    AutoPaused { silence: Duration },
+    AutoResumed,
+    LimitApproaching { remaining: Duration, rollover: bool },
+    Stopped { session: AudioRecordingSession },
+    RolledOver { previous: AudioRecordingSession, next: Uuid },
+}
+
+impl SupervisorEvent {
+    /// Short sentence suitable for speaking to the user
+    pub fn announcement(&self) -> String {
+        match self {
+            SupervisorEvent::AutoPaused { .. } => "Recording paused, no speech detected".to_string(),
+            SupervisorEvent::AutoResumed => "Recording resumed".to_string(),
+            SupervisorEvent::LimitApproaching { remaining, rollover } => format!(
+                "Recording limit in {}, {}",
+                spoken_duration(*remaining),
+                if *rollover { "continuing in a new session" } else { "the session will stop" }
+            ),
+            SupervisorEvent::Stopped { .. } => "Recording limit reached, session saved".to_string(),
+            SupervisorEvent::RolledOver { .. } => "Recording limit reached, continuing in a new session".to_string(),
+        }
+    }
+}
+
+fn spoken_duration(duration: Duration) -> String {
+    let secs = duration.as_secs().max(1);
+    match secs {
+        1 => "1 second".to_string(),
+        s if s < 60 => format!("{} seconds", s),
+        s if s < 120 => "1 minute".to_string(),
+        s => format!("{} minutes", s / 60),
+    }
+}
+
+/// Tracks silence and elapsed time of the current session
+#[derive(Debug, Default)]
+pub struct SessionSupervisor {
+    session: Option<Uuid>,
+    last_state: Option<SessionState>,
+    last_voice: Option<Instant>,
+    /// When the supervisor paused the session; manual pauses are left alone
+    auto_paused_at: Option<Instant>,
+    warned: bool,
+}
+
+impl SessionSupervisor {
+    /// Record that speech was heard
+    pub fn observe_voice(&mut self, now: Instant) {
+        self.last_voice = Some(now);
+    }
+
+    /// Decide what to do with the current session, given how long it has been running
+    pub fn tick(
+        &mut self,
+        config: &SessionConfig,
+        now: Instant,
+        session: Option<(Uuid, &SessionState, Duration)>,
+    ) -> Option<SupervisorAction> {
+        let Some((id, state, elapsed)) = session else {
+            *self = Self { last_voice: self.last_voice, ..Self::default() };
+            return None;
+        };
+        if self.session != Some(id) {
+            self.session = Some(id);
+            self.auto_paused_at = None;
+            self.warned = false;
+            self.last_voice = Some(now);
+        }
+        // A manual resume restarts the silence clock
+        if *state == SessionState::Recording && self.last_state.as_ref() != Some(state) {
+            self.auto_paused_at = None;
+            self.last_voice = self.last_voice.max(Some(now));
+        }
+        self.last_state = Some(state.clone());
+
+        let limit = config.max_session_duration;
+        if !limit.is_zero() {
+            if elapsed >= limit {
+                return Some(if config.supervisor.rollover { SupervisorAction::Rollover } else { SupervisorAction::Stop });
+            }
+            let remaining = limit - elapsed;
+            if !self.warned && remaining <= config.supervisor.warning_lead {
+                self.warned = true;
+                return Some(SupervisorAction::Warn { remaining });
+            }
+        }
+
+        if !config.supervisor.auto_pause || config.silence_timeout.is_zero() {
+            return None;
+        }
+        let last_voice = self.last_voice.unwrap_or(now);
+        match (state, self.auto_paused_at) {
+            (SessionState::Recording, _) if now.saturating_duration_since(last_voice) >= config.silence_timeout => {
+                self.auto_paused_at = Some(now);
+                Some(SupervisorAction::Pause)
+            }
+            (SessionState::Paused, Some(paused_at)) if last_voice > paused_at => {
+                self.auto_paused_at = None;
+                Some(SupervisorAction::Resume)
+            }
+            _ => None,
+        }
+    }
+
+    /// Time since speech was last heard
+    pub fn silence(&self, now: Instant) -> Duration {
+        self.last_voice.map(|t| now.saturating_duration_since(t)).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SessionConfig {
+        SessionConfig {
+            max_session_duration: Duration::from_secs(600),
+            silence_timeout: Duration::from_secs(30),
+            ..SessionConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_pause_on_silence_and_resume_on_speech() {
+        let config = config();
+        let mut supervisor = SessionSupervisor::default();
+        let id = Uuid::new_v4();
+        let t0 = Instant::now();
+        let at = |secs| t0 + Duration::from_secs(secs);
+
+        assert_eq!(supervisor.tick(&config, at(0), Some((id, &SessionState::Recording, Duration::ZERO))), None);
+        supervisor.observe_voice(at(10));
+        assert_eq!(supervisor.tick(&config, at(39), Some((id, &SessionState::Recording, Duration::from_secs(39)))), None);
+        assert_eq!(
+            supervisor.tick(&config, at(40), Some((id, &SessionState::Recording, Duration::from_secs(40)))),
+            Some(SupervisorAction::Pause)
+        );
+        assert_eq!(supervisor.tick(&config, at(50), Some((id, &SessionState::Paused, Duration::from_secs(50)))), None);
+        supervisor.observe_voice(at(55));
+        assert_eq!(
+            supervisor.tick(&config, at(55), Some((id, &SessionState::Paused, Duration::from_secs(55)))),
+            Some(SupervisorAction::Resume)
+        );
+        // Manual pauses are never resumed automatically
+        assert_eq!(supervisor.tick(&config, at(56), Some((id, &SessionState::Recording, Duration::from_secs(56)))), None);
+        assert_eq!(supervisor.tick(&config, at(57), Some((id, &SessionState::Paused, Duration::from_secs(57)))), None);
+        supervisor.observe_voice(at(58));
+        assert_eq!(supervisor.tick(&config, at(58), Some((id, &SessionState::Paused, Duration::from_secs(58)))), None);
+    }
+
+    #[test]
+    fn test_warn_then_stop_or_roll_over_at_limit() {
+        let mut config = config();
+        config.supervisor.auto_pause = false;
+        let mut supervisor = SessionSupervisor::default();
+        let id = Uuid::new_v4();
+        let now = Instant::now();
+        let tick = |supervisor: &mut SessionSupervisor, config: &SessionConfig, secs| {
+            supervisor.tick(config, now, Some((id, &SessionState::Recording, Duration::from_secs(secs))))
+        };
+
+        assert_eq!(tick(&mut supervisor, &config, 500), None);
+        assert_eq!(tick(&mut supervisor, &config, 545), Some(SupervisorAction::Warn { remaining: Duration::from_secs(55) }));
+        assert_eq!(tick(&mut supervisor, &config, 550), None);
+        assert_eq!(tick(&mut supervisor, &config, 600), Some(SupervisorAction::Stop));
+        config.supervisor.rollover = true;
+        assert_eq!(tick(&mut supervisor, &config, 600), Some(SupervisorAction::Rollover));
+    }
+}
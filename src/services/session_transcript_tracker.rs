@@ -10,6 +10,9 @@ use serde::{Deserialize, Serialize};
 use tracing::{info, warn, debug, error};
 use uuid::Uuid;
 
+use crate::services::diarization::{DiarizationConfig, SpeakerAssignment, SpeakerDiarizer};
+use crate::services::prosody::{ProsodyAnalyzer, ProsodyFeatures, ProsodyProfile};
+
 /// Transcript phrase with detailed timing and metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptPhrase {
@@ -31,6 +34,9 @@ pub struct TranscriptPhrase {
     pub phrase_type: PhraseType,
     pub keywords: Vec<String>,
     pub sentiment: Option<SentimentScore>,
+    /// Pitch, loudness, pauses and rate, when the phrase audio was provided
+    #[serde(default)]
+    pub prosody: Option<ProsodyFeatures>,
 }
 
 /// Type of phrase detected
@@ -73,6 +79,9 @@ pub struct SpeakerProfile {
     pub average_confidence: f32,
     pub first_appearance: DateTime<Utc>,
     pub last_appearance: DateTime<Utc>,
+    /// Prosody accumulated over the speaker's phrases with audio
+    #[serde(default)]
+    pub prosody: ProsodyProfile,
 }
 
 /// Voice characteristics for speaker identification
@@ -152,12 +161,14 @@ pub struct SessionTranscriptTracker {
     phrase_buffer: VecDeque<String>,
     /// Timing tracker
     timing_tracker: TimingTracker,
-    /// Speaker detection (preparation for diarization)
+    /// Speaker diarization
     speaker_detector: SpeakerDetector,
     /// Keyword extractor
     keyword_extractor: KeywordExtractor,
     /// Phrase classifier
     phrase_classifier: PhraseClassifier,
+    /// Pitch and prosody analysis of phrase audio
+    prosody_analyzer: ProsodyAnalyzer,
     /// Configuration
     config: TrackerConfig,
     /// Statistics
@@ -173,13 +184,10 @@ pub struct TimingTracker {
     current_phrase_start: Option<Instant>,
 }
 
-/// Speaker detection system (preparation for full diarization)
+/// Speaker detection from voice embeddings
 #[derive(Debug)]
 pub struct SpeakerDetector {
-    known_speakers: HashMap<String, SpeakerProfile>,
-    current_speaker: Option<String>,
-    speaker_change_threshold: f32,
-    voice_feature_buffer: VecDeque<VoiceFeatures>,
+    diarizer: SpeakerDiarizer,
 }
 
 /// Voice features for speaker detection
@@ -246,9 +254,10 @@ impl SessionTranscriptTracker {
             current_session: None,
             phrase_buffer: VecDeque::new(),
             timing_tracker: TimingTracker::new(),
-            speaker_detector: SpeakerDetector::new(),
+            speaker_detector: SpeakerDetector::new(config.speaker_change_sensitivity),
             keyword_extractor: KeywordExtractor::new(),
             phrase_classifier: PhraseClassifier::new(),
+            prosody_analyzer: ProsodyAnalyzer::default(),
             config,
             session_stats: HashMap::new(),
         }
@@ -278,6 +287,7 @@ impl SessionTranscriptTracker {
         };
 
         self.timing_tracker.start_session();
+        self.speaker_detector.reset();
         self.current_session = Some(session);
 
         info!(
@@ -297,6 +307,20 @@ impl SessionTranscriptTracker {
         audio_start: Duration,
         audio_end: Duration,
         volume_level: f32,
+    ) -> Result<Uuid, Box<dyn std::error::Error>> {
+        self.add_phrase_with_audio(text, confidence, audio_start, audio_end, volume_level, None)
+    }
+
+    /// Add a new phrase with the audio it was transcribed from, so the speaker
+    /// can be identified from the voice
+    pub fn add_phrase_with_audio(
+        &mut self,
+        text: String,
+        confidence: f32,
+        audio_start: Duration,
+        audio_end: Duration,
+        volume_level: f32,
+        audio: Option<(&[f32], u32)>,
     ) -> Result<Uuid, Box<dyn std::error::Error>> {
         let session = self.current_session.as_mut()
             .ok_or("No active session")?;
@@ -320,8 +344,11 @@ impl SessionTranscriptTracker {
             *session.keywords.entry(keyword.clone()).or_insert(0) += 1;
         }
 
-        // Detect speaker (preparation for diarization)
-        let speaker_id = self.speaker_detector.detect_speaker(&text, volume_level, confidence);
+        // Identify the speaker from the voice
+        let speaker = self.speaker_detector.detect_speaker(audio);
+        let speaker_id = speaker.as_ref().map(|s| s.speaker_id.clone());
+        let word_count = text.split_whitespace().count();
+        let prosody = audio.map(|(samples, sample_rate)| self.prosody_analyzer.analyze(samples, sample_rate, word_count));
 
         // Calculate sentiment if enabled
         let sentiment = if self.config.sentiment_analysis_enabled {
@@ -340,7 +367,7 @@ impl SessionTranscriptTracker {
             confidence,
             speaker_id: speaker_id.clone(),
             language: Some(session.language.clone()),
-            word_count: text.split_whitespace().count(),
+            word_count,
             is_final: true,
             audio_segment_start: audio_start,
             audio_segment_end: audio_end,
@@ -349,13 +376,14 @@ impl SessionTranscriptTracker {
             phrase_type,
             keywords,
             sentiment,
+            prosody,
         };
 
         session.phrases.push(phrase.clone());
 
         // Update speaker profile after adding phrase
-        if let Some(speaker_id) = &speaker_id {
-            self.update_speaker_profile(speaker_id, &phrase);
+        if let Some(speaker) = &speaker {
+            self.update_speaker_profile(speaker, &phrase);
         }
 
         // Update session statistics
@@ -416,14 +444,24 @@ impl SessionTranscriptTracker {
         self.session_stats.get(session_id)
     }
 
+    /// Enroll a named speaker from a sample of their voice
+    pub fn enroll_speaker(&mut self, name: &str, samples: &[f32], sample_rate: u32) -> bool {
+        self.speaker_detector.diarizer.enroll(name, samples, sample_rate)
+    }
+
+    /// Speaker diarizer, for loading and saving enrolled profiles
+    pub fn diarizer_mut(&mut self) -> &mut SpeakerDiarizer {
+        &mut self.speaker_detector.diarizer
+    }
+
     /// Update speaker profile with new phrase data
-    fn update_speaker_profile(&mut self, speaker_id: &str, phrase: &TranscriptPhrase) {
+    fn update_speaker_profile(&mut self, speaker: &SpeakerAssignment, phrase: &TranscriptPhrase) {
         let session = self.current_session.as_mut().unwrap();
         
-        let profile = session.speakers.entry(speaker_id.to_string()).or_insert_with(|| {
+        let profile = session.speakers.entry(speaker.speaker_id.clone()).or_insert_with(|| {
             SpeakerProfile {
-                id: speaker_id.to_string(),
-                name: None,
+                id: speaker.speaker_id.clone(),
+                name: speaker.name.clone(),
                 voice_characteristics: VoiceCharacteristics::default(),
                 speaking_patterns: SpeakingPatterns::default(),
                 total_speaking_time: Duration::from_secs(0),
@@ -431,6 +469,7 @@ impl SessionTranscriptTracker {
                 average_confidence: 0.0,
                 first_appearance: phrase.start_time,
                 last_appearance: phrase.start_time,
+                prosody: ProsodyProfile::default(),
             }
         });
 
@@ -453,6 +492,22 @@ impl SessionTranscriptTracker {
         if phrase.phrase_type == PhraseType::Question {
             profile.speaking_patterns.question_frequency += 1.0;
         }
+
+        // Voice characteristics and rhythm come from the phrase audio
+        if let Some(features) = &phrase.prosody {
+            profile.prosody.add(features);
+            let prosody = &profile.prosody;
+            let voice = &mut profile.voice_characteristics;
+            if prosody.mean_f0_hz > 0.0 {
+                voice.fundamental_frequency = prosody.mean_f0_hz;
+                voice.frequency_range = prosody.f0_range_hz;
+                voice.pitch_variance = prosody.pitch_variance;
+            }
+            voice.speaking_rate = prosody.speaking_rate_wpm;
+            voice.volume_range = prosody.volume_range;
+            profile.speaking_patterns.pause_frequency = prosody.pauses_per_minute();
+            profile.speaking_patterns.speaking_rhythm = prosody.rhythm_regularity;
+        }
     }
 
     /// Update current session statistics
@@ -524,6 +579,21 @@ impl SessionTranscriptTracker {
             .collect::<Vec<_>>()
             .join(", ");
 
+        let mut speakers: Vec<&SpeakerProfile> = session.speakers.values().collect();
+        speakers.sort_by(|a, b| a.id.cmp(&b.id));
+        let delivery = speakers.iter()
+            .filter(|s| s.prosody.segments > 0)
+            .map(|s| format!(
+                "\n  {}: {:.0} Hz ({:.0}-{:.0}), {:.0} WPM, {:.1} pauses/min",
+                s.name.as_ref().unwrap_or(&s.id),
+                s.prosody.mean_f0_hz,
+                s.prosody.f0_range_hz.0,
+                s.prosody.f0_range_hz.1,
+                s.prosody.speaking_rate_wpm,
+                s.prosody.pauses_per_minute()
+            ))
+            .collect::<String>();
+
         format!(
             "Session Summary:\n\
             Duration: {}:{:02}\n\
@@ -531,14 +601,16 @@ impl SessionTranscriptTracker {
             Words: {}\n\
             Speakers: {}\n\
             Questions: {}\n\
-            Top Keywords: {}",
+            Top Keywords: {}\n\
+            Delivery:{}",
             session.total_duration.as_secs() / 60,
             session.total_duration.as_secs() % 60,
             session.session_statistics.total_phrases,
             session.session_statistics.total_words,
             session.session_statistics.speaker_count,
             session.session_statistics.question_count,
-            keyword_list
+            keyword_list,
+            if delivery.is_empty() { " n/a".to_string() } else { delivery }
         )
     }
 
@@ -594,22 +666,34 @@ impl TimingTracker {
 }
 
 impl SpeakerDetector {
-    fn new() -> Self {
+    /// Higher sensitivity starts a new speaker on smaller voice differences
+    fn new(sensitivity: f32) -> Self {
+        let config = DiarizationConfig {
+            change_threshold: 0.88 + 0.1 * sensitivity.clamp(0.0, 1.0),
+            ..DiarizationConfig::default()
+        };
         Self {
-            known_speakers: HashMap::new(),
-            current_speaker: None,
-            speaker_change_threshold: 0.7,
-            voice_feature_buffer: VecDeque::new(),
+            diarizer: SpeakerDiarizer::new(config),
         }
     }
 
-    fn detect_speaker(&mut self, _text: &str, _volume: f32, _confidence: f32) -> Option<String> {
-        // Mock speaker detection - in real implementation, use voice characteristics
-        // For now, assign speakers based on simple heuristics
-        if self.current_speaker.is_none() {
-            self.current_speaker = Some("Speaker_1".to_string());
-        }
-        self.current_speaker.clone()
+    fn reset(&mut self) {
+        self.diarizer.reset();
+    }
+
+    /// Without audio the phrase is attributed to whoever spoke last
+    fn detect_speaker(&mut self, audio: Option<(&[f32], u32)>) -> Option<SpeakerAssignment> {
+        let identified = audio.and_then(|(samples, sample_rate)| self.diarizer.identify(samples, sample_rate));
+        identified.or_else(|| {
+            let current = self.diarizer.current_speaker().unwrap_or("Speaker_1").to_string();
+            let name = self.diarizer.speakers().iter().find(|c| c.id == current).and_then(|c| c.name.clone());
+            Some(SpeakerAssignment {
+                speaker_id: current,
+                name,
+                similarity: 0.0,
+                new_speaker: false,
+            })
+        })
     }
 }
 
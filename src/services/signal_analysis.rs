@@ -0,0 +1,462 @@
+//! Signal quality analysis for transcripts and recording sessions.
+//!
+//! Audio is split into short frames that are labelled speech or noise, either
+//! by the caller (labels from a VAD) or by a built-in energy detector that
+//! adapts to the noise floor of the clip. From those frames we derive:
+//!
+//! - levels: peak, RMS and dynamic range (loud vs. quiet frame spread)
+//! - SNR: mean speech-frame power against mean noise-frame power
+//! - spectrum: dominant frequency, spectral centroid and spread of the
+//!   averaged Hann-windowed FFT magnitude of the speech frames
+//! - clipping events (runs of samples at full scale) and silence percentage
+//!
+//! The result is a [`SignalMetrics`] that is stored on every transcript entry
+//! and summarised into the session's quality metrics.
+
+use crate::services::transcription_log::{AudioLevels, FrequencyAnalysis, QualityIssue, SignalMetrics};
+use serde::{Deserialize, Serialize};
+
+/// Analysis parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalAnalysisConfig {
+    /// Frame length in milliseconds
+    pub frame_ms: u32,
+    /// FFT length in samples (rounded up to a power of two)
+    pub fft_size: usize,
+    /// Absolute sample value treated as clipped
+    pub clip_threshold: f32,
+    /// Frames this many dB above the noise floor are treated as speech
+    pub speech_margin_db: f32,
+    /// Frames quieter than this (dBFS) are never treated as speech
+    pub silence_floor_db: f32,
+}
+
+impl Default for SignalAnalysisConfig {
+    fn default() -> Self {
+        Self {
+            frame_ms: 30,
+            fft_size: 1024,
+            clip_threshold: 0.99,
+            speech_margin_db: 9.0,
+            silence_floor_db: -55.0,
+        }
+    }
+}
+
+/// Computes [`SignalMetrics`] from mono audio
+#[derive(Debug, Clone, Default)]
+pub struct SignalAnalyzer {
+    config: SignalAnalysisConfig,
+}
+
+impl SignalAnalyzer {
+    /// Create an analyzer
+    pub fn new(config: SignalAnalysisConfig) -> Self {
+        Self { config }
+    }
+
+    /// Frame length in samples for a sample rate
+    pub fn frame_len(&self, sample_rate: u32) -> usize {
+        ((sample_rate as u64 * self.config.frame_ms as u64 / 1000) as usize).max(1)
+    }
+
+    /// Analyze audio, labelling speech frames with the built-in energy detector
+    pub fn analyze(&self, samples: &[f32], sample_rate: u32) -> SignalMetrics {
+        let frame_len = self.frame_len(sample_rate);
+        let frame_db: Vec<f32> = samples.chunks(frame_len).map(|f| power_to_db(mean_power(f))).collect();
+        let labels = self.label_frames(&frame_db);
+        self.analyze_with_labels(samples, sample_rate, &labels)
+    }
+
+    /// Analyze audio using per-frame speech labels from a VAD.
+    /// `labels[i]` covers samples `i * frame_len(sample_rate)..` of the clip.
+    pub fn analyze_with_labels(&self, samples: &[f32], sample_rate: u32, labels: &[bool]) -> SignalMetrics {
+        let mut accumulator = self.accumulator(sample_rate, labels.to_vec());
+        accumulator.push(samples);
+        accumulator.finish()
+    }
+
+    /// Analyze audio fed a block at a time, labelled as in [`Self::analyze_with_labels`]
+    pub fn accumulator(&self, sample_rate: u32, labels: Vec<bool>) -> SignalAccumulator {
+        SignalAccumulator {
+            config: self.config.clone(),
+            sample_rate,
+            frame_len: self.frame_len(sample_rate),
+            labels,
+            frame: Vec::new(),
+            frame_db: Vec::new(),
+            peak: 0.0,
+            sum_squares: 0.0,
+            samples: 0,
+            speech_power: (0.0, 0),
+            noise_power: (0.0, 0),
+            speech_spectrum: Spectrum::new(self.config.fft_size),
+            noise_spectrum: Spectrum::new(self.config.fft_size),
+            clipping_events: 0,
+            in_clip: false,
+        }
+    }
+
+    /// Energy-based speech labels relative to the clip's noise floor
+    pub fn label_frames(&self, frame_db: &[f32]) -> Vec<bool> {
+        let noise_floor = percentile(frame_db, 0.1);
+        let threshold = (noise_floor + self.config.speech_margin_db).max(self.config.silence_floor_db);
+        frame_db.iter().map(|db| *db >= threshold).collect()
+    }
+}
+
+/// Running [`SignalMetrics`] over audio too long to hold in memory; only the
+/// per-frame levels are kept
+#[derive(Debug, Clone)]
+pub struct SignalAccumulator {
+    config: SignalAnalysisConfig,
+    sample_rate: u32,
+    frame_len: usize,
+    labels: Vec<bool>,
+    /// Samples of the frame being filled
+    frame: Vec<f32>,
+    frame_db: Vec<f32>,
+    peak: f32,
+    sum_squares: f64,
+    samples: usize,
+    speech_power: (f64, usize),
+    noise_power: (f64, usize),
+    speech_spectrum: Spectrum,
+    noise_spectrum: Spectrum,
+    clipping_events: u32,
+    in_clip: bool,
+}
+
+impl SignalAccumulator {
+    /// Add the next block of audio
+    pub fn push(&mut self, samples: &[f32]) {
+        for &s in samples {
+            self.peak = self.peak.max(s.abs());
+            self.sum_squares += (s * s) as f64;
+            let clipped = s.abs() >= self.config.clip_threshold;
+            if clipped && !self.in_clip {
+                self.clipping_events += 1;
+            }
+            self.in_clip = clipped;
+        }
+        self.samples += samples.len();
+
+        let mut rest = samples;
+        while !rest.is_empty() {
+            let take = (self.frame_len - self.frame.len()).min(rest.len());
+            self.frame.extend_from_slice(&rest[..take]);
+            rest = &rest[take..];
+            if self.frame.len() == self.frame_len {
+                self.end_frame();
+            }
+        }
+    }
+
+    fn end_frame(&mut self) {
+        let power = mean_power(&self.frame);
+        let is_speech = self.labels.get(self.frame_db.len()).copied().unwrap_or(false);
+        self.frame_db.push(power_to_db(power));
+        let (acc, spectrum) = if is_speech {
+            (&mut self.speech_power, &mut self.speech_spectrum)
+        } else {
+            (&mut self.noise_power, &mut self.noise_spectrum)
+        };
+        acc.0 += power as f64;
+        acc.1 += 1;
+        spectrum.add(&self.frame);
+        self.frame.clear();
+    }
+
+    /// Metrics for everything pushed so far
+    pub fn finish(mut self) -> SignalMetrics {
+        if !self.frame.is_empty() {
+            self.end_frame();
+        }
+        if self.samples == 0 || self.sample_rate == 0 {
+            return SignalMetrics::default();
+        }
+        let frame_db = &self.frame_db;
+
+        let rms = (self.sum_squares / self.samples as f64).sqrt() as f32;
+        let dynamic_range = (percentile(frame_db, 0.95) - percentile(frame_db, 0.1)).max(0.0);
+
+        let snr_db = match (self.speech_power.1, self.noise_power.1) {
+            (0, _) => 0.0,
+            (_, 0) => {
+                // No pause to measure noise in: use the quietest frames instead
+                let noise = db_to_power(percentile(frame_db, 0.1)) as f64;
+                ratio_db(self.speech_power.0 / self.speech_power.1 as f64, noise)
+            }
+            (s, n) => ratio_db(self.speech_power.0 / s as f64, self.noise_power.0 / n as f64),
+        };
+
+        // Without speech frames every frame is noise, so the noise spectrum covers the whole clip
+        let frequency_analysis = if self.speech_power.1 == 0 {
+            self.noise_spectrum.summary(self.sample_rate)
+        } else {
+            self.speech_spectrum.summary(self.sample_rate)
+        };
+
+        SignalMetrics {
+            snr_db,
+            audio_levels: AudioLevels { peak: self.peak, rms, dynamic_range },
+            frequency_analysis,
+            clipping_events: self.clipping_events,
+            silence_percentage: self.noise_power.1 as f32 / frame_db.len().max(1) as f32 * 100.0,
+        }
+    }
+}
+
+/// Average Hann-windowed FFT magnitude of a set of frames
+#[derive(Debug, Clone)]
+struct Spectrum {
+    window: Vec<f32>,
+    magnitude: Vec<f32>,
+    count: usize,
+    re: Vec<f32>,
+    im: Vec<f32>,
+}
+
+impl Spectrum {
+    fn new(fft_size: usize) -> Self {
+        let n = fft_size.max(2).next_power_of_two();
+        Self {
+            window: Vec::new(),
+            magnitude: vec![0.0; n / 2],
+            count: 0,
+            re: vec![0.0; n],
+            im: vec![0.0; n],
+        }
+    }
+
+    fn add(&mut self, frame: &[f32]) {
+        // The window spans the frame; the rest of the FFT input is zero padding
+        let len = frame.len().min(self.re.len());
+        if self.window.len() != len {
+            self.window = (0..len)
+                .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (len.max(2) - 1) as f32).cos())
+                .collect();
+        }
+        self.re.iter_mut().for_each(|v| *v = 0.0);
+        self.im.iter_mut().for_each(|v| *v = 0.0);
+        for (i, s) in frame.iter().take(len).enumerate() {
+            self.re[i] = s * self.window[i];
+        }
+        fft(&mut self.re, &mut self.im);
+        for (k, m) in self.magnitude.iter_mut().enumerate() {
+            *m += (self.re[k] * self.re[k] + self.im[k] * self.im[k]).sqrt();
+        }
+        self.count += 1;
+    }
+
+    /// Reduce the averaged spectrum to summary features
+    fn summary(&self, sample_rate: u32) -> FrequencyAnalysis {
+        if self.count == 0 {
+            return FrequencyAnalysis::default();
+        }
+        let magnitude = &self.magnitude;
+
+        let bin_hz = sample_rate as f32 / self.re.len() as f32;
+        // Skip the DC bin: offsets say nothing about the voice
+        let total: f32 = magnitude[1..].iter().sum();
+        if total <= f32::EPSILON {
+            return FrequencyAnalysis::default();
+        }
+        let (dominant_bin, _) = magnitude
+            .iter()
+            .enumerate()
+            .skip(1)
+            .fold((1, 0.0f32), |best, (k, m)| if *m > best.1 { (k, *m) } else { best });
+        let centroid: f32 = magnitude.iter().enumerate().skip(1).map(|(k, m)| k as f32 * bin_hz * m).sum::<f32>() / total;
+        let spread = (magnitude
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(k, m)| (k as f32 * bin_hz - centroid).powi(2) * m)
+            .sum::<f32>()
+            / total)
+            .sqrt();
+
+        FrequencyAnalysis {
+            dominant_frequency: dominant_bin as f32 * bin_hz,
+            frequency_spread: spread,
+            spectral_centroid: centroid,
+        }
+    }
+}
+
+/// Per-frame speech labels from speech segments given as sample ranges.
+/// A frame counts as speech when any part of it overlaps a segment.
+pub fn labels_from_segments(segments: &[(usize, usize)], frame_len: usize, total_samples: usize) -> Vec<bool> {
+    let frame_len = frame_len.max(1);
+    let mut labels = vec![false; total_samples.div_ceil(frame_len)];
+    for &(start, end) in segments {
+        let end = end.min(total_samples);
+        if start >= end {
+            continue;
+        }
+        for label in &mut labels[start / frame_len..end.div_ceil(frame_len)] {
+            *label = true;
+        }
+    }
+    labels
+}
+
+/// Quality issues worth surfacing for a set of signal metrics
+pub fn quality_issues(metrics: &SignalMetrics) -> Vec<QualityIssue> {
+    let mut issues = Vec::new();
+    if metrics.clipping_events > 0 {
+        issues.push(QualityIssue::AudioQuality {
+            issue: format!("{} clipping event(s)", metrics.clipping_events),
+            severity: (metrics.clipping_events as f32 / 10.0).min(1.0),
+        });
+    }
+    if metrics.audio_levels.rms > 0.0 && metrics.snr_db < 10.0 {
+        issues.push(QualityIssue::AudioQuality {
+            issue: format!("Low signal-to-noise ratio ({:.1} dB)", metrics.snr_db),
+            severity: ((10.0 - metrics.snr_db) / 10.0).clamp(0.0, 1.0),
+        });
+    }
+    if metrics.audio_levels.peak > 0.0 && metrics.audio_levels.peak < 0.05 {
+        issues.push(QualityIssue::AudioQuality {
+            issue: "Input level very low".to_string(),
+            severity: 0.5,
+        });
+    }
+    issues
+}
+
+/// Count runs of consecutive samples at or above the clip threshold
+pub fn count_clipping_events(samples: &[f32], threshold: f32) -> u32 {
+    let mut events = 0;
+    let mut in_run = false;
+    for s in samples {
+        let clipped = s.abs() >= threshold;
+        if clipped && !in_run {
+            events += 1;
+        }
+        in_run = clipped;
+    }
+    events
+}
+
+/// In-place iterative radix-2 FFT; `re.len()` must be a power of two
+pub(crate) fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * std::f32::consts::PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_re, w_im) = ((angle * k as f32).cos(), (angle * k as f32).sin());
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+fn mean_power(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32
+}
+
+fn power_to_db(power: f32) -> f32 {
+    10.0 * power.max(1e-10).log10()
+}
+
+fn db_to_power(db: f32) -> f32 {
+    10f32.powf(db / 10.0)
+}
+
+fn ratio_db(signal: f64, noise: f64) -> f32 {
+    (10.0 * (signal / noise.max(1e-10)).log10()) as f32
+}
+
+fn percentile(values: &[f32], p: f32) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let idx = ((sorted.len() - 1) as f32 * p).round() as usize;
+    sorted[idx]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1 s of low noise, 1 s of a 440 Hz tone over the same noise
+    fn noise_then_tone(sample_rate: u32) -> Vec<f32> {
+        let mut seed = 12345u32;
+        let mut noise = || {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            (seed >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0
+        };
+        let n = sample_rate as usize;
+        let mut out: Vec<f32> = (0..n).map(|_| 0.001 * noise()).collect();
+        out.extend((0..n).map(|i| {
+            0.3 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / sample_rate as f32).sin() + 0.001 * noise()
+        }));
+        out
+    }
+
+    #[test]
+    fn test_snr_and_spectrum_from_labelled_frames() {
+        let samples = noise_then_tone(16000);
+        let metrics = SignalAnalyzer::default().analyze(&samples, 16000);
+        assert!(metrics.snr_db > 40.0, "snr {}", metrics.snr_db);
+        assert!((metrics.frequency_analysis.dominant_frequency - 440.0).abs() < 20.0);
+        assert!((metrics.silence_percentage - 50.0).abs() < 5.0);
+        assert_eq!(metrics.clipping_events, 0);
+    }
+
+    #[test]
+    fn test_accumulator_matches_whole_clip_analysis() {
+        let samples = noise_then_tone(16000);
+        let analyzer = SignalAnalyzer::default();
+        let labels = labels_from_segments(&[(16000, 32000)], analyzer.frame_len(16000), samples.len());
+        let whole = analyzer.analyze_with_labels(&samples, 16000, &labels);
+
+        // Blocks that do not line up with analysis frames
+        let mut accumulator = analyzer.accumulator(16000, labels);
+        for block in samples.chunks(1234) {
+            accumulator.push(block);
+        }
+        let streamed = accumulator.finish();
+        assert!((streamed.snr_db - whole.snr_db).abs() < 1e-3);
+        assert!((streamed.audio_levels.rms - whole.audio_levels.rms).abs() < 1e-6);
+        assert_eq!(streamed.frequency_analysis.dominant_frequency, whole.frequency_analysis.dominant_frequency);
+        assert_eq!(streamed.silence_percentage, whole.silence_percentage);
+    }
+
+    #[test]
+    fn test_clipping_runs_are_counted_once() {
+        let samples = [0.0, 1.0, 1.0, 1.0, 0.2, -1.0, 0.1, 0.995];
+        assert_eq!(count_clipping_events(&samples, 0.99), 3);
+        let metrics = SignalAnalyzer::default().analyze(&samples, 16000);
+        assert!(quality_issues(&metrics).iter().any(|i| matches!(i, QualityIssue::AudioQuality { issue, .. } if issue.contains("clipping"))));
+    }
+}
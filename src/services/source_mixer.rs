@@ -0,0 +1,382 @@
+//! Two-source capture mixing.
+//!
+//! Recording a call needs the microphone plus a second input (a PulseAudio /
+//! PipeWire monitor device, a loopback driver, or any other named input).
+//! The two devices run on independent clocks, so even at the same nominal
+//! sample rate one slowly drifts against the other. The mixer treats the
+//! primary source as the master clock and feeds the secondary source through
+//! an adaptive resampler whose ratio is nudged to keep the secondary queue at
+//! a fixed latency. Output is either a mono mix or interleaved stereo with the
+//! primary on the left and the secondary on the right; a mono mix is always
+//! produced as well for VAD and STT.
+
+use crate::services::agc::db_to_linear;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// How the two sources are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MixMode {
+    /// Sum both sources into a single mono channel
+    Mix,
+    /// Keep sources apart: primary on the left, secondary on the right
+    Stereo,
+}
+
+/// Mixer configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceMixerConfig {
+    /// Output layout
+    pub mode: MixMode,
+    /// Gain applied to the primary source in dB
+    pub primary_gain_db: f32,
+    /// Gain applied to the secondary source in dB
+    pub secondary_gain_db: f32,
+    /// Latency kept in the secondary queue to absorb callback jitter (ms)
+    pub target_latency_ms: u32,
+    /// Largest resampling correction applied for clock drift (fraction, 0.005 = 0.5%)
+    pub max_drift_correction: f64,
+}
+
+impl Default for SourceMixerConfig {
+    fn default() -> Self {
+        Self {
+            mode: MixMode::Mix,
+            primary_gain_db: 0.0,
+            secondary_gain_db: 0.0,
+            target_latency_ms: 100,
+            max_drift_correction: 0.005,
+        }
+    }
+}
+
+/// Second capture input recorded alongside the primary device
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecondarySourceConfig {
+    /// Device stable ID or name; `None` picks a system audio monitor/loopback input
+    pub device: Option<String>,
+    /// Mixing, gain and drift settings
+    pub mixer: SourceMixerConfig,
+}
+
+/// Mixer health counters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MixerStats {
+    /// Times the secondary source ran dry and silence was inserted
+    pub underruns: u64,
+    /// Secondary samples discarded because the queue overflowed
+    pub dropped_samples: u64,
+    /// Current drift correction in parts per million
+    pub drift_correction_ppm: f64,
+}
+
+/// One block of mixer output
+#[derive(Debug, Clone, Default)]
+pub struct MixedFrame {
+    /// Mono mix of both sources (always present)
+    pub mono: Vec<f32>,
+    /// Interleaved stereo (primary left, secondary right) in [`MixMode::Stereo`]
+    pub stereo: Option<Vec<f32>>,
+}
+
+/// Linear resampler that keeps its phase between calls, so the ratio can be
+/// adjusted block by block without clicks.
+#[derive(Debug, Clone)]
+struct DriftResampler {
+    /// Read position relative to `last` (index 0) followed by the new block
+    position: f64,
+    last: f32,
+}
+
+impl DriftResampler {
+    fn new() -> Self {
+        Self { position: 1.0, last: 0.0 }
+    }
+
+    /// Resample `input`, consuming `step` input samples per output sample
+    fn process(&mut self, input: &[f32], step: f64, output: &mut VecDeque<f32>) {
+        if input.is_empty() {
+            return;
+        }
+        let last = self.last;
+        let at = |i: usize| if i == 0 { last } else { input[i - 1] };
+        let len = input.len() + 1;
+        while self.position + 1.0 < len as f64 {
+            let idx = self.position.floor() as usize;
+            let frac = (self.position - idx as f64) as f32;
+            let (a, b) = (at(idx), at(idx + 1));
+            output.push_back(a + (b - a) * frac);
+            self.position += step;
+        }
+        self.position -= input.len() as f64;
+        self.last = input[input.len() - 1];
+    }
+}
+
+/// Mixes a primary (clock master) and a secondary capture source
+#[derive(Debug)]
+pub struct SourceMixer {
+    config: SourceMixerConfig,
+    primary_rate: Option<u32>,
+    primary: VecDeque<f32>,
+    secondary: VecDeque<f32>,
+    resampler: DriftResampler,
+    correction: f64,
+    primed: bool,
+    stats: MixerStats,
+}
+
+impl SourceMixer {
+    /// Create a mixer
+    pub fn new(config: SourceMixerConfig) -> Self {
+        Self {
+            config,
+            primary_rate: None,
+            primary: VecDeque::new(),
+            secondary: VecDeque::new(),
+            resampler: DriftResampler::new(),
+            correction: 0.0,
+            primed: false,
+            stats: MixerStats::default(),
+        }
+    }
+
+    /// Get current configuration
+    pub fn config(&self) -> &SourceMixerConfig {
+        &self.config
+    }
+
+    /// Change gains or output mode
+    pub fn set_config(&mut self, config: SourceMixerConfig) {
+        self.config = config;
+    }
+
+    /// Set per-source gains in dB
+    pub fn set_gains_db(&mut self, primary_gain_db: f32, secondary_gain_db: f32) {
+        self.config.primary_gain_db = primary_gain_db;
+        self.config.secondary_gain_db = secondary_gain_db;
+    }
+
+    /// Mixer health counters
+    pub fn stats(&self) -> MixerStats {
+        MixerStats { drift_correction_ppm: self.correction * 1_000_000.0, ..self.stats.clone() }
+    }
+
+    /// Sample rate of the mixed output (the primary source's rate)
+    pub fn output_sample_rate(&self) -> Option<u32> {
+        self.primary_rate
+    }
+
+    /// Number of secondary samples currently queued (at the output rate)
+    pub fn secondary_queue_len(&self) -> usize {
+        self.secondary.len()
+    }
+
+    /// Queue mono samples from the primary source
+    pub fn push_primary(&mut self, samples: &[f32], sample_rate: u32) {
+        if self.primary_rate != Some(sample_rate) {
+            // Output rate changed: queued secondary audio no longer lines up
+            self.primary_rate = Some(sample_rate);
+            self.secondary.clear();
+            self.primed = false;
+        }
+        self.primary.extend(samples.iter().copied());
+    }
+
+    /// Queue mono samples from the secondary source, resampling to the output rate
+    pub fn push_secondary(&mut self, samples: &[f32], sample_rate: u32) {
+        let Some(target) = self.primary_rate else {
+            return;
+        };
+        if sample_rate == 0 {
+            return;
+        }
+        let step = (sample_rate as f64 / target as f64) * (1.0 + self.correction);
+        self.resampler.process(samples, step, &mut self.secondary);
+
+        let limit = self.target_latency_samples() * 4;
+        if self.secondary.len() > limit {
+            let excess = self.secondary.len() - limit;
+            self.secondary.drain(..excess);
+            self.stats.dropped_samples += excess as u64;
+        }
+    }
+
+    /// Take all primary audio queued so far and combine it with the secondary source
+    pub fn pull(&mut self) -> MixedFrame {
+        let n = self.primary.len();
+        if n == 0 {
+            return MixedFrame::default();
+        }
+        let target = self.target_latency_samples();
+
+        if !self.primed && self.secondary.len() >= target + n {
+            self.primed = true;
+        }
+
+        let mut secondary: Vec<f32> = Vec::with_capacity(n);
+        if self.primed {
+            // Steer the resampler so the queue stays at the target latency
+            let leftover = self.secondary.len() as f64 - n as f64;
+            let error = ((leftover - target as f64) / target.max(1) as f64).clamp(-1.0, 1.0);
+            let wanted = error * self.config.max_drift_correction;
+            self.correction += 0.05 * (wanted - self.correction);
+
+            let available = self.secondary.len().min(n);
+            secondary.extend(self.secondary.drain(..available));
+            if available < n {
+                self.stats.underruns += 1;
+                self.primed = false;
+            }
+        }
+        secondary.resize(n, 0.0);
+
+        let primary_gain = db_to_linear(self.config.primary_gain_db);
+        let secondary_gain = db_to_linear(self.config.secondary_gain_db);
+        let primary: Vec<f32> = self.primary.drain(..).map(|s| s * primary_gain).collect();
+        let secondary: Vec<f32> = secondary.into_iter().map(|s| s * secondary_gain).collect();
+
+        let mono = primary
+            .iter()
+            .zip(&secondary)
+            .map(|(p, s)| (p + s).clamp(-1.0, 1.0))
+            .collect();
+        let stereo = match self.config.mode {
+            MixMode::Mix => None,
+            MixMode::Stereo => Some(
+                primary
+                    .iter()
+                    .zip(&secondary)
+                    .flat_map(|(p, s)| [p.clamp(-1.0, 1.0), s.clamp(-1.0, 1.0)])
+                    .collect(),
+            ),
+        };
+        MixedFrame { mono, stereo }
+    }
+
+    fn target_latency_samples(&self) -> usize {
+        let rate = self.primary_rate.unwrap_or(16000) as usize;
+        (rate * self.config.target_latency_ms as usize / 1000).max(1)
+    }
+}
+
+/// Virtual capture source backed by a WAV file, delivering mono blocks.
+/// Used to exercise the mixer without audio hardware.
+#[derive(Debug, Clone)]
+pub struct FileSource {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    position: usize,
+}
+
+impl FileSource {
+    /// Load a WAV file, downmixing to mono
+    pub fn open(path: &Path) -> crate::Result<Self> {
+        let mut reader = hound::WavReader::open(path)
+            .map_err(|e| crate::core::error::AudioError::StorageError(format!("Failed to open {}: {e}", path.display())))?;
+        let spec = reader.spec();
+        let interleaved: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().filter_map(|s| s.ok()).collect(),
+            hound::SampleFormat::Int => {
+                let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader.samples::<i32>().filter_map(|s| s.ok()).map(|s| s as f32 / scale).collect()
+            }
+        };
+        let channels = spec.channels.max(1) as usize;
+        let samples = interleaved
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+        Ok(Self::from_samples(samples, spec.sample_rate))
+    }
+
+    /// Wrap in-memory mono samples
+    pub fn from_samples(samples: Vec<f32>, sample_rate: u32) -> Self {
+        Self { samples, sample_rate, position: 0 }
+    }
+
+    /// Nominal sample rate of the source
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Read up to `frames` samples; `None` once the file is exhausted
+    pub fn read(&mut self, frames: usize) -> Option<&[f32]> {
+        if self.position >= self.samples.len() {
+            return None;
+        }
+        let end = (self.position + frames).min(self.samples.len());
+        let block = &self.samples[self.position..end];
+        self.position = end;
+        Some(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_tone(path: &Path, sample_rate: u32, samples: usize, freq: f32, amplitude: f32) {
+        let spec = hound::WavSpec { channels: 1, sample_rate, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for i in 0..samples {
+            let s = amplitude * (2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate as f32).sin();
+            writer.write_sample((s * i16::MAX as f32) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_drift_is_compensated_between_file_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let mic = dir.path().join("mic.wav");
+        let monitor = dir.path().join("monitor.wav");
+        // The "monitor" clock runs 0.1% fast: 16016 samples per real second, labelled 16000 Hz
+        write_tone(&mic, 16000, 16000 * 40, 440.0, 0.3);
+        write_tone(&monitor, 16000, 16016 * 40, 220.0, 0.3);
+
+        let mut primary = FileSource::open(&mic).unwrap();
+        let mut secondary = FileSource::open(&monitor).unwrap();
+        let mut mixer = SourceMixer::new(SourceMixerConfig::default());
+
+        let mut produced = 0usize;
+        let mut owed = 0.0f64;
+        for _ in 0..2000 {
+            // 20 ms of wall-clock time per iteration
+            let Some(block) = primary.read(320) else { break };
+            mixer.push_primary(block, 16000);
+            owed += 320.32;
+            let frames = owed.floor() as usize;
+            owed -= frames as f64;
+            if let Some(block) = secondary.read(frames) {
+                mixer.push_secondary(block, 16000);
+            }
+            produced += mixer.pull().mono.len();
+        }
+
+        let stats = mixer.stats();
+        assert_eq!(produced, 16000 * 40);
+        assert_eq!(stats.dropped_samples, 0);
+        assert!(mixer.secondary_queue_len() < 1600 * 2, "queue grew to {}", mixer.secondary_queue_len());
+        assert!(stats.drift_correction_ppm > 500.0 && stats.drift_correction_ppm < 2000.0, "correction {}", stats.drift_correction_ppm);
+    }
+
+    #[test]
+    fn test_stereo_mode_keeps_sources_apart_with_gain() {
+        let mut mixer = SourceMixer::new(SourceMixerConfig {
+            mode: MixMode::Stereo,
+            secondary_gain_db: -6.0,
+            target_latency_ms: 10,
+            ..SourceMixerConfig::default()
+        });
+        mixer.push_primary(&[0.5; 160], 16000);
+        mixer.push_secondary(&[0.4; 800], 16000);
+        let frame = mixer.pull();
+        let stereo = frame.stereo.unwrap();
+        assert_eq!(stereo.len(), 320);
+        assert!((stereo[100] - 0.5).abs() < 1e-4);
+        assert!((stereo[101] - 0.4 * db_to_linear(-6.0)).abs() < 1e-3);
+        assert!((frame.mono[50] - (0.5 + 0.4 * db_to_linear(-6.0))).abs() < 1e-3);
+    }
+}
@@ -33,8 +33,11 @@ impl LocalWhisperBackend {
         const C_VAL: &str = "\x1b[32m"; // green
         const C_RESET: &str = "\x1b[0m";
 
-        let model_path = std::env::var("WHISPER_MODEL_PATH")
-            .unwrap_or_else(|_| "ggml-large-v3-turbo-q8_0.bin".to_string());
+        let model_path = if cfg.model_path.trim().is_empty() {
+            std::env::var("WHISPER_MODEL_PATH").unwrap_or_else(|_| "ggml-large-v3-turbo-q8_0.bin".to_string())
+        } else {
+            cfg.model_path.clone()
+        };
         let model_path = model_path.trim().to_string();
         if model_path.is_empty() {
             return Err(crate::core::error::STTError::ModelNotFound(
@@ -87,7 +90,11 @@ impl STTBackend for LocalWhisperBackend {
         let start_time = Instant::now();
         debug!(target: "stt", "Starting transcription: {} samples", audio.len());
 
-        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        let strategy = match cfg.beam_size {
+            0 | 1 => SamplingStrategy::Greedy { best_of: 1 },
+            beam_size => SamplingStrategy::BeamSearch { beam_size: beam_size as i32, patience: -1.0 },
+        };
+        let mut params = FullParams::new(strategy);
         let threads = std::env::var("WHISPER_THREADS")
             .ok()
             .and_then(|v| v.parse::<i32>().ok())
@@ -278,6 +285,11 @@ impl STTService {
         }
 
         info!(backend = %cfg.backend, model = %cfg.model_size, "STTService apply_config called");
+        // A different model file needs a fresh whisper context
+        #[cfg(feature = "local-stt")]
+        if cfg.model_path != self.config.model_path {
+            self.local_backend = None;
+        }
         self.backend = cfg.backend.clone();
         self.selected_model = cfg.model_size.clone();
         self.config = cfg;
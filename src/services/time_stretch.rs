@@ -0,0 +1,137 @@
+//! Pitch-preserving playback speed change.
+//!
+//! Waveform-similarity overlap-add (WSOLA): output is built from Hann-windowed
+//! frames at a fixed hop, while the read position in the input advances by
+//! `hop * speed`. Each frame is shifted by up to a few milliseconds so it lines
+//! up with the waveform already written, which avoids the phasing artefacts of
+//! plain overlap-add. Speech stays at its original pitch from 0.5× to 2×.
+
+use std::f32::consts::PI;
+
+/// Slowest supported playback speed
+pub const MIN_SPEED: f32 = 0.5;
+/// Fastest supported playback speed
+pub const MAX_SPEED: f32 = 2.0;
+
+/// Streaming WSOLA time stretcher for mono audio
+#[derive(Debug, Clone)]
+pub struct TimeStretcher {
+    frame_len: usize,
+    hop: usize,
+    tolerance: usize,
+    window: Vec<f32>,
+    /// Second half of the last windowed frame, waiting to be overlapped
+    tail: Vec<f32>,
+    /// Input position of the last frame written
+    last_frame: Option<usize>,
+}
+
+impl TimeStretcher {
+    pub fn new(sample_rate: u32) -> Self {
+        // 30 ms frames, 50% overlap, ±8 ms alignment search
+        let hop = (sample_rate as usize * 15 / 1000).max(16);
+        let frame_len = hop * 2;
+        let window = (0..frame_len)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / frame_len as f32).cos())
+            .collect();
+        Self {
+            frame_len,
+            hop,
+            tolerance: (sample_rate as usize * 8 / 1000).max(4),
+            window,
+            tail: vec![0.0; hop],
+            last_frame: None,
+        }
+    }
+
+    /// Forget the previous frame; call after seeking
+    pub fn reset(&mut self) {
+        self.tail.fill(0.0);
+        self.last_frame = None;
+    }
+
+    /// Output samples produced per call to [`render`](Self::render)
+    pub fn hop(&self) -> usize {
+        self.hop
+    }
+
+    /// Append one hop of output read around `position` and advance it by `hop * speed`
+    pub fn render(&mut self, input: &[f32], position: &mut f64, speed: f32, output: &mut Vec<f32>) {
+        let nominal = *position as usize;
+        let start = match self.last_frame {
+            // Already continuous (always the case at 1×)
+            Some(last) if last + self.hop == nominal => nominal,
+            Some(last) => self.best_alignment(input, last + self.hop, nominal),
+            None => nominal,
+        };
+
+        let frame: Vec<f32> = (0..self.frame_len)
+            .map(|i| input.get(start + i).copied().unwrap_or(0.0) * self.window[i])
+            .collect();
+        output.extend(frame[..self.hop].iter().zip(&self.tail).map(|(a, b)| a + b));
+        self.tail.copy_from_slice(&frame[self.hop..]);
+
+        self.last_frame = Some(start);
+        *position += self.hop as f64 * speed.clamp(MIN_SPEED, MAX_SPEED) as f64;
+    }
+
+    /// Frame start near `nominal` whose opening best matches the natural continuation at `continuation`
+    fn best_alignment(&self, input: &[f32], continuation: usize, nominal: usize) -> usize {
+        let sample = |i: usize| input.get(i).copied().unwrap_or(0.0);
+        // Correlating every 4th sample is plenty for alignment and keeps the audio callback cheap
+        let score = |start: usize| -> f32 {
+            (0..self.hop).step_by(4).map(|i| sample(continuation + i) * sample(start + i)).sum()
+        };
+        (nominal.saturating_sub(self.tolerance)..=nominal + self.tolerance)
+            .map(|start| (start, score(start)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(start, _)| start)
+            .unwrap_or(nominal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stretch(input: &[f32], speed: f32) -> Vec<f32> {
+        let mut stretcher = TimeStretcher::new(16000);
+        let mut position = 0.0;
+        let mut output = Vec::new();
+        while (position as usize) < input.len() {
+            stretcher.render(input, &mut position, speed, &mut output);
+        }
+        output
+    }
+
+    /// Frequency estimated from upward zero crossings
+    fn frequency(samples: &[f32], sample_rate: f32) -> f32 {
+        let crossings = samples.windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count();
+        crossings as f32 * sample_rate / samples.len() as f32
+    }
+
+    #[test]
+    fn test_speed_changes_length_but_not_pitch() {
+        let input: Vec<f32> = (0..32000).map(|i| (2.0 * PI * 220.0 * i as f32 / 16000.0).sin() * 0.5).collect();
+
+        for speed in [MIN_SPEED, 1.5, MAX_SPEED] {
+            let output = stretch(&input, speed);
+            let expected = input.len() as f32 / speed;
+            assert!((output.len() as f32 - expected).abs() < expected * 0.02, "speed {}: {} samples", speed, output.len());
+            // Skip the fade-in of the first frame
+            let pitch = frequency(&output[1000..output.len() - 1000], 16000.0);
+            assert!((pitch - 220.0).abs() < 5.0, "speed {}: {} Hz", speed, pitch);
+        }
+    }
+
+    #[test]
+    fn test_unit_speed_preserves_signal() {
+        let input: Vec<f32> = (0..8000).map(|i| (i as f32 * 0.05).sin() * 0.3).collect();
+        let output = stretch(&input, 1.0);
+        // Hann windows at 50% overlap sum to one, so everything after the fade-in is reproduced
+        let hop = TimeStretcher::new(16000).hop();
+        for i in hop..input.len() {
+            assert!((output[i] - input[i]).abs() < 1e-3, "sample {}", i);
+        }
+    }
+}
@@ -0,0 +1,618 @@
+//! Re-transcription of saved sessions into transcript revisions.
+//!
+//! Live transcription favours a fast model. A [`RetranscriptionJob`] later
+//! runs each live transcript segment's audio through a larger model and stores
+//! the result as a [`TranscriptRevision`] in the session's `revisions/`
+//! directory, leaving the live transcript untouched. Revisions keep the live
+//! segment IDs and timings, so they diff word by word against the live
+//! transcript, and the revision with the best confidence becomes the one
+//! search and analytics use.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::core::config::STTConfig;
+use crate::core::types::STTResult;
+use crate::services::audio_archive::AudioError;
+use crate::services::audio_session_manager::{self, AudioRecordingSession, TranscriptSegment};
+use crate::services::capture_config::resample_linear;
+use crate::services::encryption::{self, StorageCipher};
+use crate::services::session_edit::{self, SessionAudio};
+use crate::services::stt::STTService;
+
+/// Directory inside a session directory holding its revisions
+pub const REVISIONS_DIR: &str = "revisions";
+
+/// Model name of the revision holding a person's corrections
+pub const MANUAL_MODEL: &str = "manual";
+
+/// Sample rate the STT models expect
+const STT_SAMPLE_RATE: u32 = 16000;
+/// Audio is transcribed in windows of this length when a session has no live segments
+const WINDOW: Duration = Duration::from_secs(30);
+
+/// Which saved sessions to re-transcribe
+#[derive(Debug, Clone, PartialEq)]
+pub enum RetranscriptionSelection {
+    Sessions(Vec<Uuid>),
+    /// Every session started at or after this time
+    Since(DateTime<Utc>),
+}
+
+/// Model and decoding settings for a re-transcription
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecodingConfig {
+    /// Model size, e.g. "large"
+    pub model: String,
+    /// Model file, e.g. `ggml-large-v3.bin`; `None` uses WHISPER_MODEL_PATH
+    pub model_path: Option<PathBuf>,
+    /// Language code; empty keeps each segment's live language
+    pub language: String,
+    /// Beam search width; 0 or 1 decodes greedily
+    pub beam_size: u32,
+}
+
+impl Default for DecodingConfig {
+    fn default() -> Self {
+        Self {
+            model: "large".to_string(),
+            model_path: None,
+            language: String::new(),
+            beam_size: 5,
+        }
+    }
+}
+
+impl DecodingConfig {
+    /// Name recorded with the revision: the model file name if one is given
+    pub fn model_label(&self) -> String {
+        self.model_path.as_ref()
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.model.clone())
+    }
+
+    /// STT configuration for transcribing `language`
+    pub fn stt_config(&self, language: &str) -> STTConfig {
+        STTConfig {
+            model_size: self.model.clone(),
+            model_path: self.model_path.as_ref().map(|path| path.display().to_string()).unwrap_or_default(),
+            language: language.to_string(),
+            beam_size: self.beam_size,
+            ..STTConfig::new()
+        }
+    }
+}
+
+/// A transcript of a saved session produced after recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptRevision {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub model: String,
+    pub decoding: DecodingConfig,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+impl TranscriptRevision {
+    /// Word-weighted mean confidence
+    pub fn confidence(&self) -> f32 {
+        mean_confidence(&self.segments)
+    }
+
+    pub fn text(&self) -> String {
+        transcript_text(&self.segments)
+    }
+
+    /// Whether this revision holds manual corrections
+    pub fn is_manual(&self) -> bool {
+        self.model == MANUAL_MODEL
+    }
+}
+
+/// Correct one segment's text in the session's manual revision, starting one
+/// from the live transcript if there is none. Corrected segments get full confidence.
+pub fn correct_segment(
+    session: &AudioRecordingSession,
+    revisions: &[TranscriptRevision],
+    segment_id: Uuid,
+    text: &str,
+) -> Result<TranscriptRevision, AudioError> {
+    let mut revision = revisions.iter().rev().find(|revision| revision.is_manual()).cloned().unwrap_or_else(|| {
+        TranscriptRevision {
+            id: Uuid::new_v4(),
+            session_id: session.id,
+            created_at: Utc::now(),
+            model: MANUAL_MODEL.to_string(),
+            decoding: DecodingConfig {
+                model: MANUAL_MODEL.to_string(),
+                model_path: None,
+                language: String::new(),
+                beam_size: 0,
+            },
+            segments: session.transcript_segments.clone(),
+        }
+    });
+    let segment = revision.segments.iter_mut()
+        .find(|segment| segment.id == segment_id)
+        .ok_or_else(|| AudioError::InvalidConfiguration(format!("Session {} has no segment {}", session.id, segment_id)))?;
+    segment.text = text.trim().to_string();
+    segment.word_count = segment.text.split_whitespace().count();
+    segment.confidence = 1.0;
+    revision.created_at = Utc::now();
+    Ok(revision)
+}
+
+/// Revisions for the `range` part of an edited session, retimed the way its live
+/// transcript is. Copies for a different session get fresh IDs; revisions left
+/// with no segments are dropped.
+pub fn retime_revisions(revisions: &[TranscriptRevision], session_id: Uuid, range: Range<Duration>) -> Vec<TranscriptRevision> {
+    revisions.iter()
+        .map(|revision| TranscriptRevision {
+            id: if revision.session_id == session_id { revision.id } else { Uuid::new_v4() },
+            session_id,
+            segments: session_edit::retime_segments(&revision.segments, range.clone()),
+            ..revision.clone()
+        })
+        .filter(|revision| !revision.segments.is_empty())
+        .collect()
+}
+
+/// Revisions of `second` appended to `first` at `offset`. Model revisions of
+/// both are kept; the manual corrections are combined into one revision, filled
+/// in from the live transcript of a side that had none, so every segment stays correctable.
+pub fn merge_revisions(
+    first: &AudioRecordingSession,
+    first_revisions: &[TranscriptRevision],
+    second: &AudioRecordingSession,
+    second_revisions: &[TranscriptRevision],
+    offset: Duration,
+) -> Vec<TranscriptRevision> {
+    let latest_manual = |revisions: &[TranscriptRevision]| revisions.iter().rev().find(|revision| revision.is_manual()).cloned();
+    let first_manual = latest_manual(first_revisions);
+    let second_manual = latest_manual(second_revisions);
+
+    let mut merged: Vec<TranscriptRevision> = first_revisions.iter().filter(|revision| !revision.is_manual()).cloned().collect();
+    merged.extend(second_revisions.iter().filter(|revision| !revision.is_manual()).map(|revision| TranscriptRevision {
+        id: Uuid::new_v4(),
+        session_id: first.id,
+        segments: session_edit::offset_segments(&revision.segments, offset),
+        ..revision.clone()
+    }));
+
+    if let Some(template) = first_manual.clone().or_else(|| second_manual.clone()) {
+        let mut segments = first_manual.as_ref().map_or(&first.transcript_segments, |revision| &revision.segments).clone();
+        let second_segments = second_manual.as_ref().map_or(&second.transcript_segments, |revision| &revision.segments);
+        segments.extend(session_edit::offset_segments(second_segments, offset));
+        merged.push(TranscriptRevision {
+            id: first_manual.map_or_else(Uuid::new_v4, |revision| revision.id),
+            session_id: first.id,
+            created_at: Utc::now(),
+            segments,
+            ..template
+        });
+    }
+    merged
+}
+
+/// Resample mono audio to the STT rate, padded with silence to at least a second
+pub(crate) fn stt_input(samples: &[f32], sample_rate: u32) -> Vec<f32> {
+    let mut samples = resample_linear(samples, sample_rate, STT_SAMPLE_RATE);
+    // Whisper rejects less than a second of audio
+    if samples.len() < STT_SAMPLE_RATE as usize {
+        samples.resize(STT_SAMPLE_RATE as usize, 0.0);
+    }
+    samples
+}
+
+/// Word-weighted mean confidence of transcript segments
+pub fn mean_confidence(segments: &[TranscriptSegment]) -> f32 {
+    let weight = |segment: &TranscriptSegment| segment.word_count.max(1) as f32;
+    let total: f32 = segments.iter().map(weight).sum();
+    if total == 0.0 {
+        return 0.0;
+    }
+    segments.iter().map(|segment| segment.confidence * weight(segment)).sum::<f32>() / total
+}
+
+pub fn transcript_text(segments: &[TranscriptSegment]) -> String {
+    segments.iter().map(|segment| segment.text.trim()).filter(|text| !text.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+/// The revision search and analytics should use, or `None` to keep the live transcript.
+/// A revision must beat the live confidence; among equals the newest wins.
+pub fn preferred_revision<'a>(session: &AudioRecordingSession, revisions: &'a [TranscriptRevision]) -> Option<&'a TranscriptRevision> {
+    let live = mean_confidence(&session.transcript_segments);
+    revisions.iter()
+        .filter(|revision| revision.confidence() > live)
+        .max_by(|a, b| a.confidence().total_cmp(&b.confidence()).then(a.created_at.cmp(&b.created_at)))
+}
+
+/// Store a revision in a session directory
+pub fn save_revision(session_dir: &Path, revision: &TranscriptRevision, cipher: Option<&StorageCipher>) -> Result<PathBuf, AudioError> {
+    let dir = session_dir.join(REVISIONS_DIR);
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.json", revision.id));
+    encryption::write_file(&path, &serde_json::to_vec_pretty(revision)?, cipher)?;
+    Ok(path)
+}
+
+/// Revisions stored in a session directory, oldest first
+pub fn load_revisions(session_dir: &Path, cipher: Option<&StorageCipher>) -> Result<Vec<TranscriptRevision>, AudioError> {
+    let dir = session_dir.join(REVISIONS_DIR);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut revisions = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let data = encryption::read_file(&path, cipher)?;
+            revisions.push(serde_json::from_slice::<TranscriptRevision>(&data)?);
+        }
+    }
+    revisions.sort_by_key(|revision| revision.created_at);
+    Ok(revisions)
+}
+
+/// Transcribe a session's audio again, one live segment at a time.
+/// Sessions without live segments are transcribed in 30 second windows.
+pub fn retranscribe<F>(
+    session: &AudioRecordingSession,
+    audio: &SessionAudio,
+    decoding: &DecodingConfig,
+    mut transcribe: F,
+) -> crate::Result<TranscriptRevision>
+where
+    F: FnMut(&[f32], &str) -> crate::Result<STTResult>,
+{
+    let mut spans: Vec<TranscriptSegment> = session.transcript_segments.clone();
+    if spans.is_empty() {
+        let total = audio.duration();
+        let mut start = Duration::ZERO;
+        while start < total {
+            let end = (start + WINDOW).min(total);
+            spans.push(TranscriptSegment {
+                id: Uuid::new_v4(),
+                start_time: start,
+                end_time: end,
+                text: String::new(),
+                confidence: 0.0,
+                speaker_id: None,
+                language: None,
+                word_count: 0,
+                is_final: true,
+                prosody: None,
+            });
+            start = end;
+        }
+    }
+
+    let mut segments = Vec::with_capacity(spans.len());
+    for span in spans {
+        let samples = stt_input(&audio.slice(span.start_time..span.end_time).mono, audio.sample_rate);
+        let language = match (decoding.language.as_str(), span.language.as_deref()) {
+            ("", Some(live)) => live.to_string(),
+            (language, _) => language.to_string(),
+        };
+        let result = transcribe(&samples, &language)?;
+        let text = result.text.trim().to_string();
+        segments.push(TranscriptSegment {
+            word_count: text.split_whitespace().count(),
+            // Token probabilities are a better guide than the backend's fixed confidence
+            confidence: result.log_probability.map(f32::exp).unwrap_or(result.confidence),
+            language: result.language.or((!language.is_empty()).then_some(language)),
+            text,
+            ..span
+        });
+    }
+
+    Ok(TranscriptRevision {
+        id: Uuid::new_v4(),
+        session_id: session.id,
+        created_at: Utc::now(),
+        model: decoding.model_label(),
+        decoding: decoding.clone(),
+        segments,
+    })
+}
+
+/// One word in a word-level diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordChange {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+impl WordChange {
+    pub fn word(&self) -> &str {
+        match self {
+            WordChange::Same(word) | WordChange::Removed(word) | WordChange::Added(word) => word,
+        }
+    }
+}
+
+/// Word-level changes from one transcript segment to its revision
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentDiff {
+    pub segment_id: Uuid,
+    pub start_time: Duration,
+    pub changes: Vec<WordChange>,
+}
+
+impl SegmentDiff {
+    pub fn is_changed(&self) -> bool {
+        self.changes.iter().any(|change| !matches!(change, WordChange::Same(_)))
+    }
+}
+
+/// Diff two texts word by word, ignoring case and surrounding punctuation
+pub fn word_diff(before: &str, after: &str) -> Vec<WordChange> {
+    let key = |word: &str| word.trim_matches(|c: char| c.is_ascii_punctuation()).to_lowercase();
+    let old: Vec<&str> = before.split_whitespace().collect();
+    let new: Vec<&str> = after.split_whitespace().collect();
+    let old_keys: Vec<String> = old.iter().map(|word| key(word)).collect();
+    let new_keys: Vec<String> = new.iter().map(|word| key(word)).collect();
+
+    // lcs[i][j]: longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old_keys[i] == new_keys[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changes = Vec::with_capacity(old.len().max(new.len()));
+    while i < old.len() && j < new.len() {
+        if old_keys[i] == new_keys[j] {
+            changes.push(WordChange::Same(new[j].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            changes.push(WordChange::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            changes.push(WordChange::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    changes.extend(old[i..].iter().map(|word| WordChange::Removed(word.to_string())));
+    changes.extend(new[j..].iter().map(|word| WordChange::Added(word.to_string())));
+    changes
+}
+
+/// Diff a revision against the live segments it was made from, pairing segments by ID
+pub fn diff_segments(live: &[TranscriptSegment], revision: &[TranscriptSegment]) -> Vec<SegmentDiff> {
+    let mut diffs: Vec<SegmentDiff> = revision.iter()
+        .map(|segment| {
+            let before = live.iter().find(|live| live.id == segment.id).map(|live| live.text.as_str()).unwrap_or("");
+            SegmentDiff { segment_id: segment.id, start_time: segment.start_time, changes: word_diff(before, &segment.text) }
+        })
+        .collect();
+    // Live segments the revision dropped
+    diffs.extend(live.iter()
+        .filter(|segment| !revision.iter().any(|revised| revised.id == segment.id))
+        .map(|segment| SegmentDiff { segment_id: segment.id, start_time: segment.start_time, changes: word_diff(&segment.text, "") }));
+    diffs.sort_by_key(|diff| diff.start_time);
+    diffs
+}
+
+/// wdiff-style text: `the [-quick-] {+quack+} fox`
+pub fn render_diff(changes: &[WordChange]) -> String {
+    changes.chunk_by(|a, b| std::mem::discriminant(a) == std::mem::discriminant(b))
+        .map(|run| {
+            let words = run.iter().map(WordChange::word).collect::<Vec<_>>().join(" ");
+            match run[0] {
+                WordChange::Same(_) => words,
+                WordChange::Removed(_) => format!("[-{}-]", words),
+                WordChange::Added(_) => format!("{{+{}+}}", words),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Progress of a [`RetranscriptionJob`]
+#[derive(Debug, Clone)]
+pub enum RetranscriptionEvent {
+    Started { session_id: Uuid, name: String },
+    /// `preferred` is set when the new revision is now the one search and analytics should use
+    Finished { revision: TranscriptRevision, preferred: bool },
+    Failed { session_id: Uuid, error: String },
+    Done { completed: usize, failed: usize },
+}
+
+/// Re-transcribes saved sessions on a background thread
+pub struct RetranscriptionJob {
+    events: Receiver<RetranscriptionEvent>,
+    cancel: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RetranscriptionJob {
+    /// Re-transcribe the sessions whose `session_metadata.json` files are listed
+    pub fn spawn(metadata_paths: Vec<PathBuf>, decoding: DecodingConfig, cipher: Option<Arc<StorageCipher>>) -> Self {
+        let (sender, events) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancelled = cancel.clone();
+        let handle = thread::spawn(move || {
+            let mut stt = match STTService::new() {
+                Ok(stt) => stt,
+                Err(e) => {
+                    let _ = sender.send(RetranscriptionEvent::Done { completed: 0, failed: metadata_paths.len() });
+                    warn!("Re-transcription could not start: {}", e);
+                    return;
+                }
+            };
+            let (mut completed, mut failed) = (0, 0);
+            for path in metadata_paths {
+                if cancelled.load(Ordering::SeqCst) {
+                    break;
+                }
+                match retranscribe_saved(&path, &decoding, cipher.as_deref(), &mut stt, &sender) {
+                    Ok(()) => completed += 1,
+                    Err((session_id, error)) => {
+                        warn!(path = %path.display(), "Re-transcription failed: {}", error);
+                        let _ = sender.send(RetranscriptionEvent::Failed { session_id, error });
+                        failed += 1;
+                    }
+                }
+            }
+            info!(completed, failed, "📝 Re-transcription finished");
+            let _ = sender.send(RetranscriptionEvent::Done { completed, failed });
+        });
+        Self { events, cancel, handle: Some(handle) }
+    }
+
+    /// Progress events; the last one is always `Done`
+    pub fn events(&self) -> &Receiver<RetranscriptionEvent> {
+        &self.events
+    }
+
+    /// Stop after the session being transcribed
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.as_ref().is_none_or(|handle| handle.is_finished())
+    }
+
+    /// Block until the job is done
+    pub fn wait(mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn retranscribe_saved(
+    metadata_path: &Path,
+    decoding: &DecodingConfig,
+    cipher: Option<&StorageCipher>,
+    stt: &mut STTService,
+    sender: &Sender<RetranscriptionEvent>,
+) -> Result<(), (Uuid, String)> {
+    let (session, audio) = audio_session_manager::read_saved_session(metadata_path, cipher)
+        .map_err(|e| (Uuid::nil(), e.to_string()))?;
+    let failed = |e: &dyn std::fmt::Display| (session.id, e.to_string());
+    let _ = sender.send(RetranscriptionEvent::Started { session_id: session.id, name: session.name.clone() });
+
+    let mut language = None;
+    let revision = retranscribe(&session, &audio, decoding, |samples, segment_language| {
+        if language.as_deref() != Some(segment_language) {
+            stt.apply_config(decoding.stt_config(segment_language))?;
+            language = Some(segment_language.to_string());
+        }
+        stt.transcribe(samples)
+    }).map_err(|e| failed(&e))?;
+
+    let session_dir = metadata_path.parent().unwrap_or(Path::new("."));
+    save_revision(session_dir, &revision, cipher).map_err(|e| failed(&e))?;
+    let revisions = load_revisions(session_dir, cipher).map_err(|e| failed(&e))?;
+    let preferred = preferred_revision(&session, &revisions).is_some_and(|best| best.id == revision.id);
+    info!(session_id = %session.id, revision_id = %revision.id, model = %revision.model, preferred, "📝 Stored transcript revision");
+    let _ = sender.send(RetranscriptionEvent::Finished { revision, preferred });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: u64, end: u64, text: &str, confidence: f32) -> TranscriptSegment {
+        TranscriptSegment::for_test(start as f64, end as f64, text).with_confidence(confidence).with_language("en")
+    }
+
+    #[test]
+    fn test_word_diff() {
+        let changes = word_diff("The quick brown fox", "the quack brown fox jumps.");
+        assert_eq!(render_diff(&changes), "the [-quick-] {+quack+} brown fox {+jumps.+}");
+        assert!(word_diff("Hello, world", "hello world").iter().all(|c| matches!(c, WordChange::Same(_))));
+
+        let live = vec![segment(0, 2, "recognise speech", 0.6), segment(2, 4, "dropped", 0.5)];
+        let mut revised = live[..1].to_vec();
+        revised[0].text = "wreck a nice beach".to_string();
+        let diffs = diff_segments(&live, &revised);
+        assert_eq!(diffs.len(), 2);
+        assert!(diffs.iter().all(SegmentDiff::is_changed));
+        assert_eq!(render_diff(&diffs[1].changes), "[-dropped-]");
+    }
+
+    #[test]
+    fn test_retranscribe_keeps_segment_timing_and_prefers_better_revision() {
+        let mut session = AudioRecordingSession::for_test("standup")
+            .with_duration(Duration::from_secs(4))
+            .with_segments(vec![segment(0, 1, "helo", 0.5), segment(1, 4, "world", 0.5)]);
+        let audio = SessionAudio { mono: vec![0.1; 32000], stereo: Vec::new(), sample_rate: 8000 };
+
+        let mut lengths = Vec::new();
+        let decoding = DecodingConfig { model_path: Some(PathBuf::from("/models/ggml-large-v3.bin")), ..DecodingConfig::default() };
+        let revision = retranscribe(&session, &audio, &decoding, |samples, language| {
+            assert_eq!(language, "en");
+            lengths.push(samples.len());
+            Ok(STTResult::new(" hello ".to_string(), 1.0, "large".to_string(), "local".to_string()).with_log_probability(-0.1))
+        }).unwrap();
+
+        // Resampled to 16 kHz, with the one second segment left as is and the longer one intact
+        assert_eq!(lengths, [16000, 48000]);
+        assert_eq!(revision.model, "ggml-large-v3");
+        assert_eq!(revision.segments[1].id, session.transcript_segments[1].id);
+        assert_eq!(revision.segments[1].start_time, Duration::from_secs(1));
+        assert_eq!(revision.text(), "hello hello");
+        assert!((revision.confidence() - (-0.1f32).exp()).abs() < 1e-6);
+
+        let worse = TranscriptRevision { id: Uuid::new_v4(), segments: vec![segment(0, 4, "hullo", 0.2)], ..revision.clone() };
+        let revisions = vec![revision.clone(), worse];
+        assert_eq!(preferred_revision(&session, &revisions).map(|r| r.id), Some(revision.id));
+        session.transcript_segments[0].confidence = 0.99;
+        session.transcript_segments[1].confidence = 0.99;
+        assert!(preferred_revision(&session, &revisions).is_none());
+    }
+
+    #[test]
+    fn test_revisions_follow_trim_and_merge() {
+        let first = AudioRecordingSession::for_test("standup").with_segments(vec![segment(0, 1, "helo", 0.5), segment(1, 2, "world", 0.5)]);
+        let manual = correct_segment(&first, &[], first.transcript_segments[0].id, "hello").unwrap();
+
+        // Trimming to the second half keeps the correction's ID and shifts its timing
+        let trimmed = retime_revisions(std::slice::from_ref(&manual), first.id, Duration::from_secs(1)..Duration::from_secs(2));
+        assert_eq!(trimmed.len(), 1);
+        assert_eq!(trimmed[0].id, manual.id);
+        assert_eq!(trimmed[0].text(), "world");
+        assert_eq!(trimmed[0].segments[0].start_time, Duration::ZERO);
+        let split_off = retime_revisions(std::slice::from_ref(&manual), Uuid::new_v4(), Duration::ZERO..Duration::from_secs(1));
+        assert_ne!(split_off[0].id, manual.id);
+        assert!(retime_revisions(std::slice::from_ref(&manual), first.id, Duration::from_secs(5)..Duration::from_secs(6)).is_empty());
+
+        let second = AudioRecordingSession::for_test("standup 2").with_segments(vec![segment(0, 1, "again", 0.5)]);
+        let model = TranscriptRevision {
+            id: Uuid::new_v4(),
+            session_id: second.id,
+            created_at: Utc::now(),
+            model: "large".to_string(),
+            decoding: DecodingConfig::default(),
+            segments: vec![segment(0, 1, "again", 0.9)],
+        };
+        let merged = merge_revisions(&first, std::slice::from_ref(&manual), &second, &[model], Duration::from_secs(2));
+        assert_eq!(merged.len(), 2);
+        assert!(merged.iter().all(|revision| revision.session_id == first.id));
+        assert_eq!(merged[0].segments[0].start_time, Duration::from_secs(2));
+        // The second session had no corrections, so its live transcript fills in the manual revision
+        assert_eq!(merged[1].id, manual.id);
+        assert_eq!(merged[1].text(), "hello world again");
+    }
+}
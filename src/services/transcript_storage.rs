@@ -7,11 +7,14 @@ use std::collections::HashMap;
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufReader, BufWriter, Write, BufRead};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json;
 
+use super::archive_check::{ArchiveIssue, IssueKind};
+use super::encryption::{self, StorageCipher};
 use super::transcription_log::{
     TranscriptStorage, TranscriptEntry, TranscriptId, SearchCriteria, TranscriptError,
     TranscriptStorageStats, BackupResult, RestoreResult, DeduplicationSavings
@@ -29,6 +32,8 @@ pub struct FileTranscriptStorage {
     stats: TranscriptStorageStats,
     /// Configuration
     config: FileStorageConfig,
+    /// Encrypts the index and transcript files when set
+    cipher: Option<Arc<StorageCipher>>,
 }
 
 /// Storage configuration
@@ -93,6 +98,11 @@ struct StorageFile {
 impl FileTranscriptStorage {
     /// Create a new file-based transcript storage
     pub fn new(storage_path: PathBuf, config: FileStorageConfig) -> Result<Self, TranscriptError> {
+        Self::new_with_cipher(storage_path, config, None)
+    }
+
+    /// Create file-based transcript storage that encrypts everything it writes
+    pub fn new_with_cipher(storage_path: PathBuf, config: FileStorageConfig, cipher: Option<Arc<StorageCipher>>) -> Result<Self, TranscriptError> {
         // Ensure storage directory exists
         if !storage_path.exists() {
             fs::create_dir_all(&storage_path)
@@ -103,7 +113,7 @@ impl FileTranscriptStorage {
         
         // Load or create index
         let index = if index_path.exists() {
-            Self::load_index(&index_path)?
+            Self::load_index(&index_path, cipher.as_deref())?
         } else {
             TranscriptIndex {
                 transcript_locations: HashMap::new(),
@@ -121,6 +131,7 @@ impl FileTranscriptStorage {
             index,
             stats,
             config,
+            cipher,
         };
 
         // Verify index integrity
@@ -133,12 +144,11 @@ impl FileTranscriptStorage {
     }
 
     /// Load index from file
-    fn load_index(index_path: &Path) -> Result<TranscriptIndex, TranscriptError> {
-        let file = File::open(index_path)
+    fn load_index(index_path: &Path, cipher: Option<&StorageCipher>) -> Result<TranscriptIndex, TranscriptError> {
+        let data = encryption::read_file(index_path, cipher)
             .map_err(|e| TranscriptError::StorageError(format!("Failed to open index file: {}", e)))?;
         
-        let reader = BufReader::new(file);
-        let index: TranscriptIndex = serde_json::from_reader(reader)
+        let index: TranscriptIndex = serde_json::from_slice(&data)
             .map_err(|e| TranscriptError::StorageError(format!("Failed to parse index file: {}", e)))?;
         
         Ok(index)
@@ -146,11 +156,9 @@ impl FileTranscriptStorage {
 
     /// Save index to file
     fn save_index(&self) -> Result<(), TranscriptError> {
-        let file = File::create(&self.index_path)
-            .map_err(|e| TranscriptError::StorageError(format!("Failed to create index file: {}", e)))?;
-        
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, &self.index)
+        let data = serde_json::to_vec_pretty(&self.index)
+            .map_err(|e| TranscriptError::StorageError(format!("Failed to serialize index: {}", e)))?;
+        encryption::write_file(&self.index_path, &data, self.cipher.as_deref())
             .map_err(|e| TranscriptError::StorageError(format!("Failed to write index file: {}", e)))?;
         
         Ok(())
@@ -225,6 +233,126 @@ impl FileTranscriptStorage {
         Ok(())
     }
 
+    /// Check an on-disk transcript store against its index without opening it,
+    /// since opening silently drops index entries whose files are gone.
+    /// With `repair`, unreadable files are renamed to `*.corrupt` and the index
+    /// is rebuilt from the transcript files that remain.
+    /// Returns the number of transcripts found and the problems.
+    pub fn check(storage_path: &Path, cipher: Option<&StorageCipher>, repair: bool) -> Result<(usize, Vec<ArchiveIssue>), TranscriptError> {
+        let mut issues = Vec::new();
+        if !storage_path.is_dir() {
+            return Ok((0, issues));
+        }
+
+        let index_path = storage_path.join("index.json");
+        let index = if index_path.exists() {
+            match Self::load_index(&index_path, cipher) {
+                Ok(index) => Some(index),
+                Err(e) => {
+                    issues.push(ArchiveIssue { repaired: repair, ..ArchiveIssue::new(IssueKind::CorruptIndex, &index_path, e.to_string()) });
+                    None
+                }
+            }
+        } else {
+            Some(TranscriptIndex {
+                transcript_locations: HashMap::new(),
+                file_metadata: HashMap::new(),
+                last_updated: Utc::now(),
+            })
+        };
+
+        // Rebuild an index from whatever transcript files parse
+        let mut rebuilt = TranscriptIndex {
+            transcript_locations: HashMap::new(),
+            file_metadata: HashMap::new(),
+            last_updated: Utc::now(),
+        };
+        let mut corrupt_files = Vec::new();
+        let mut file_names: Vec<String> = fs::read_dir(storage_path)
+            .map_err(|e| TranscriptError::StorageError(format!("Failed to read storage directory: {}", e)))?
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|name| name.starts_with("transcripts_") && name.ends_with(".json"))
+            .collect();
+        file_names.sort();
+        for file_name in file_names {
+            let file_path = storage_path.join(&file_name);
+            let parsed = encryption::read_file(&file_path, cipher)
+                .map_err(|e| e.to_string())
+                .and_then(|data| serde_json::from_slice::<StorageFile>(&data).map_err(|e| e.to_string()));
+            let storage_file = match parsed {
+                Ok(storage_file) => storage_file,
+                Err(detail) => {
+                    let mut issue = ArchiveIssue::new(IssueKind::CorruptFile, &file_path, detail);
+                    if repair {
+                        fs::rename(&file_path, storage_path.join(format!("{}.corrupt", file_name)))
+                            .map_err(|e| TranscriptError::StorageError(format!("Failed to set aside {}: {}", file_name, e)))?;
+                        issue.repaired = true;
+                    }
+                    issues.push(issue);
+                    corrupt_files.push(file_name);
+                    continue;
+                }
+            };
+            let modified_at = fs::metadata(&file_path)
+                .and_then(|metadata| metadata.modified())
+                .map(DateTime::<Utc>::from)
+                .unwrap_or_else(|_| Utc::now());
+            rebuilt.file_metadata.insert(file_name.clone(), FileMetadata {
+                transcript_count: storage_file.transcripts.len(),
+                file_size: fs::metadata(&file_path).map(|metadata| metadata.len()).unwrap_or(0),
+                created_at: storage_file.created_at,
+                modified_at,
+            });
+            for transcript in &storage_file.transcripts {
+                rebuilt.transcript_locations.insert(transcript.id, FileLocation {
+                    file_name: file_name.clone(),
+                    offset: None,
+                    size: None,
+                });
+            }
+        }
+        let checked = rebuilt.transcript_locations.len();
+
+        // An unreadable index was already reported; otherwise compare entry by entry
+        if let Some(index) = &index {
+            for file_name in index.file_metadata.keys() {
+                if !rebuilt.file_metadata.contains_key(file_name) && !corrupt_files.contains(file_name) {
+                    let detail = "indexed transcript file is gone";
+                    issues.push(ArchiveIssue { repaired: repair, ..ArchiveIssue::new(IssueKind::MissingFile, storage_path.join(file_name), detail) });
+                }
+            }
+            for (id, location) in &rebuilt.transcript_locations {
+                let file_path = storage_path.join(&location.file_name);
+                match index.transcript_locations.get(id) {
+                    None => {
+                        let detail = format!("transcript {} is missing from the index", id);
+                        issues.push(ArchiveIssue { repaired: repair, ..ArchiveIssue::new(IssueKind::Unindexed, file_path, detail) });
+                    }
+                    Some(indexed) if indexed.file_name != location.file_name => {
+                        let detail = format!("index places transcript {} in {}", id, indexed.file_name);
+                        issues.push(ArchiveIssue { repaired: repair, ..ArchiveIssue::new(IssueKind::StaleIndexEntry, file_path, detail) });
+                    }
+                    Some(_) => {}
+                }
+            }
+            for (id, location) in &index.transcript_locations {
+                if !rebuilt.transcript_locations.contains_key(id) && rebuilt.file_metadata.contains_key(&location.file_name) {
+                    let detail = format!("transcript {} is not in the file", id);
+                    let file_path = storage_path.join(&location.file_name);
+                    issues.push(ArchiveIssue { repaired: repair, ..ArchiveIssue::new(IssueKind::StaleIndexEntry, file_path, detail) });
+                }
+            }
+        }
+
+        if repair && !issues.is_empty() {
+            let data = serde_json::to_vec_pretty(&rebuilt)
+                .map_err(|e| TranscriptError::StorageError(format!("Failed to serialize index: {}", e)))?;
+            encryption::write_file(&index_path, &data, cipher)
+                .map_err(|e| TranscriptError::StorageError(format!("Failed to write index file: {}", e)))?;
+        }
+        Ok((checked, issues))
+    }
+
     /// Get next available file name
     fn get_next_file_name(&self) -> String {
         let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
@@ -248,11 +376,10 @@ impl FileTranscriptStorage {
     /// Load storage file
     fn load_storage_file(&self, file_name: &str) -> Result<StorageFile, TranscriptError> {
         let file_path = self.storage_path.join(file_name);
-        let file = File::open(&file_path)
+        let data = encryption::read_file(&file_path, self.cipher.as_deref())
             .map_err(|e| TranscriptError::StorageError(format!("Failed to open storage file {}: {}", file_name, e)))?;
         
-        let reader = BufReader::new(file);
-        let storage_file: StorageFile = serde_json::from_reader(reader)
+        let storage_file: StorageFile = serde_json::from_slice(&data)
             .map_err(|e| TranscriptError::StorageError(format!("Failed to parse storage file {}: {}", file_name, e)))?;
         
         Ok(storage_file)
@@ -261,11 +388,9 @@ impl FileTranscriptStorage {
     /// Save storage file
     fn save_storage_file(&mut self, file_name: &str, storage_file: &StorageFile) -> Result<(), TranscriptError> {
         let file_path = self.storage_path.join(file_name);
-        let file = File::create(&file_path)
-            .map_err(|e| TranscriptError::StorageError(format!("Failed to create storage file {}: {}", file_name, e)))?;
-        
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, storage_file)
+        let data = serde_json::to_vec_pretty(storage_file)
+            .map_err(|e| TranscriptError::StorageError(format!("Failed to serialize storage file {}: {}", file_name, e)))?;
+        encryption::write_file(&file_path, &data, self.cipher.as_deref())
             .map_err(|e| TranscriptError::StorageError(format!("Failed to write storage file {}: {}", file_name, e)))?;
 
         // Update file metadata
@@ -296,6 +421,68 @@ impl FileTranscriptStorage {
         }
         None
     }
+
+    /// Remove transcripts created before `cutoff`; with `dry_run` only report what would go
+    pub fn expire_transcripts(&mut self, cutoff: DateTime<Utc>, dry_run: bool) -> Result<ExpiredTranscripts, TranscriptError> {
+        let mut expired = ExpiredTranscripts::default();
+        let mut file_names: Vec<String> = self.index.file_metadata.keys().cloned().collect();
+        file_names.sort();
+
+        for file_name in file_names {
+            let mut storage_file = self.load_storage_file(&file_name)?;
+            let before = storage_file.transcripts.len();
+            let expired_ids: Vec<TranscriptId> = storage_file.transcripts.iter()
+                .filter(|t| t.timestamp < cutoff)
+                .map(|t| t.id)
+                .collect();
+            if expired_ids.is_empty() {
+                continue;
+            }
+            expired.transcripts += expired_ids.len();
+
+            let file_path = self.storage_path.join(&file_name);
+            if expired_ids.len() == before {
+                expired.bytes_freed += self.index.file_metadata.get(&file_name).map(|m| m.file_size).unwrap_or(0);
+                expired.files.push(file_path.clone());
+            }
+            if dry_run {
+                continue;
+            }
+
+            for id in &expired_ids {
+                self.index.transcript_locations.remove(id);
+            }
+            if expired_ids.len() == before {
+                fs::remove_file(&file_path)
+                    .map_err(|e| TranscriptError::StorageError(format!("Failed to remove storage file {}: {}", file_name, e)))?;
+                self.index.file_metadata.remove(&file_name);
+            } else {
+                let old_size = self.index.file_metadata.get(&file_name).map(|m| m.file_size).unwrap_or(0);
+                storage_file.transcripts.retain(|t| t.timestamp >= cutoff);
+                self.save_storage_file(&file_name, &storage_file)?;
+                let new_size = self.index.file_metadata.get(&file_name).map(|m| m.file_size).unwrap_or(0);
+                expired.bytes_freed += old_size.saturating_sub(new_size);
+            }
+            self.stats.total_transcripts = self.stats.total_transcripts.saturating_sub(expired_ids.len());
+        }
+
+        if !dry_run && expired.transcripts > 0 {
+            self.index.last_updated = Utc::now();
+            self.save_index()?;
+        }
+        Ok(expired)
+    }
+}
+
+/// Transcripts removed by [`FileTranscriptStorage::expire_transcripts`]
+#[derive(Debug, Clone, Default)]
+pub struct ExpiredTranscripts {
+    /// Transcript entries removed
+    pub transcripts: usize,
+    /// Storage files deleted because every entry in them expired
+    pub files: Vec<PathBuf>,
+    /// Bytes freed on disk
+    pub bytes_freed: u64,
 }
 
 impl TranscriptStorage for FileTranscriptStorage {
@@ -616,6 +803,8 @@ mod tests {
                             frequency_spread: 100.0,
                             spectral_centroid: 1000.0,
                         },
+                        clipping_events: 0,
+                        silence_percentage: 0.0,
                     },
                 },
                 annotations: Vec::new(),
@@ -661,6 +850,32 @@ mod tests {
         assert_eq!(retrieved.confidence, 0.95);
     }
 
+    #[test]
+    fn test_expire_transcripts() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = FileTranscriptStorage::new(temp_dir.path().to_path_buf(), FileStorageConfig::default()).unwrap();
+        let mut old = create_test_transcript("Last year", 0.9);
+        old.timestamp = Utc::now() - chrono::Duration::days(400);
+        let recent = create_test_transcript("This week", 0.9);
+        storage.store_transcript(&old).unwrap();
+        storage.store_transcript(&recent).unwrap();
+
+        let cutoff = Utc::now() - chrono::Duration::days(365);
+        let preview = storage.expire_transcripts(cutoff, true).unwrap();
+        assert_eq!(preview.transcripts, 1);
+        assert!(storage.get_transcript(old.id).unwrap().is_some());
+
+        let expired = storage.expire_transcripts(cutoff, false).unwrap();
+        assert_eq!(expired.transcripts, 1);
+        assert!(expired.files.is_empty());
+        assert!(storage.get_transcript(old.id).unwrap().is_none());
+        assert!(storage.get_transcript(recent.id).unwrap().is_some());
+
+        // The index on disk no longer lists the expired entry
+        let reopened = FileTranscriptStorage::new(temp_dir.path().to_path_buf(), FileStorageConfig::default()).unwrap();
+        assert_eq!(reopened.index.transcript_locations.len(), 1);
+    }
+
     #[test]
     fn test_search_transcripts() {
         let temp_dir = TempDir::new().unwrap();
@@ -216,6 +216,71 @@ pub struct QualityInsights {
     pub quality_by_hour: HashMap<u32, f32>,
     /// Quality by session length
     pub quality_by_session_length: HashMap<String, f32>,
+    /// Relationship between audio SNR and transcription confidence
+    #[serde(default)]
+    pub signal_quality: SignalQualityCorrelation,
+}
+
+/// Running correlation between signal-to-noise ratio and confidence,
+/// fed from the signal metrics attached to each transcript
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignalQualityCorrelation {
+    /// Transcripts with signal metrics
+    pub samples: u64,
+    sum_snr: f64,
+    sum_confidence: f64,
+    sum_snr_sq: f64,
+    sum_confidence_sq: f64,
+    sum_product: f64,
+    /// Average confidence per SNR bucket, with sample counts
+    pub confidence_by_snr: HashMap<String, (f32, u32)>,
+    /// Transcripts that contained clipped audio
+    pub clipped_transcripts: u32,
+}
+
+impl SignalQualityCorrelation {
+    /// Add one transcript's SNR and confidence
+    pub fn record(&mut self, snr_db: f32, confidence: f32, clipped: bool) {
+        let (x, y) = (snr_db as f64, confidence as f64);
+        self.samples += 1;
+        self.sum_snr += x;
+        self.sum_confidence += y;
+        self.sum_snr_sq += x * x;
+        self.sum_confidence_sq += y * y;
+        self.sum_product += x * y;
+        if clipped {
+            self.clipped_transcripts += 1;
+        }
+
+        let (average, count) = self.confidence_by_snr.entry(Self::bucket(snr_db).to_string()).or_insert((0.0, 0));
+        *average = (*average * *count as f32 + confidence) / (*count + 1) as f32;
+        *count += 1;
+    }
+
+    /// Pearson correlation between SNR and confidence, once there is enough data
+    pub fn correlation(&self) -> Option<f32> {
+        if self.samples < 3 {
+            return None;
+        }
+        let n = self.samples as f64;
+        let covariance = self.sum_product - self.sum_snr * self.sum_confidence / n;
+        let var_snr = self.sum_snr_sq - self.sum_snr * self.sum_snr / n;
+        let var_confidence = self.sum_confidence_sq - self.sum_confidence * self.sum_confidence / n;
+        if var_snr <= f64::EPSILON || var_confidence <= f64::EPSILON {
+            return None;
+        }
+        Some((covariance / (var_snr * var_confidence).sqrt()) as f32)
+    }
+
+    /// SNR bucket label used in `confidence_by_snr`
+    pub fn bucket(snr_db: f32) -> &'static str {
+        match snr_db {
+            s if s < 10.0 => "<10 dB",
+            s if s < 20.0 => "10-20 dB",
+            s if s < 30.0 => "20-30 dB",
+            _ => ">=30 dB",
+        }
+    }
 }
 
 /// Quality trend point
@@ -413,6 +478,41 @@ impl TranscriptAnalytics {
         Ok(())
     }
     
+    /// Swap a session's transcripts for a better revision without counting its words twice
+    pub fn replace_session_transcripts(
+        &mut self,
+        session_id: SessionId,
+        previous: &[TranscriptEntry],
+        revised: &[TranscriptEntry],
+    ) -> Result<(), TranscriptError> {
+        if self.config.enable_word_frequency {
+            for transcript in previous {
+                for word in self.tokenize_text(&transcript.text) {
+                    if let Some(stats) = self.word_frequency.get_mut(&word) {
+                        stats.frequency = stats.frequency.saturating_sub(1);
+                        if stats.frequency == 0 {
+                            self.word_frequency.remove(&word);
+                        }
+                    }
+                }
+            }
+        }
+        self.session_correlations.remove(&session_id);
+
+        for transcript in revised {
+            if self.config.enable_word_frequency {
+                self.update_word_frequency(transcript)?;
+            }
+            if self.config.enable_accuracy_tracking {
+                self.update_accuracy_trends(transcript)?;
+            }
+            self.update_session_analytics(session_id, transcript)?;
+            self.update_model_performance(transcript)?;
+            self.update_quality_insights(transcript)?;
+        }
+        Ok(())
+    }
+
     /// Get word frequency analysis
     pub fn get_word_frequency(&self, limit: Option<usize>) -> Vec<(&String, &WordStats)> {
         let mut words: Vec<_> = self.word_frequency.iter().collect();
@@ -477,6 +577,7 @@ impl TranscriptAnalytics {
             usage_insights: self.generate_usage_insights(),
             performance_summary: self.generate_performance_summary(),
             recommendations: self.generate_recommendations(),
+            snr_confidence_correlation: self.quality_insights.signal_quality.correlation(),
         }
     }
     
@@ -694,6 +795,12 @@ impl TranscriptAnalytics {
         let hour = transcript.timestamp.hour();
         let hour_quality = self.quality_insights.quality_by_hour.entry(hour).or_insert(0.0);
         *hour_quality = (*hour_quality + transcript.confidence) / 2.0;
+
+        // Correlate audio quality with accuracy when the transcript carries signal metrics
+        let signal = &transcript.metadata.quality_metrics.signal_metrics;
+        if signal.audio_levels.rms > 0.0 {
+            self.quality_insights.signal_quality.record(signal.snr_db, transcript.confidence, signal.clipping_events > 0);
+        }
         
         Ok(())
     }
@@ -788,6 +895,25 @@ impl TranscriptAnalytics {
         if self.usage_patterns.peak_hours.len() > 2 {
             recommendations.push("Consider load balancing during peak usage hours".to_string());
         }
+
+        let signal = &self.quality_insights.signal_quality;
+        if signal.correlation().map(|c| c > 0.3).unwrap_or(false) {
+            if let (Some((low, _)), Some((high, _))) = (
+                signal.confidence_by_snr.get("<10 dB"),
+                signal.confidence_by_snr.get(">=30 dB").or_else(|| signal.confidence_by_snr.get("20-30 dB")),
+            ) {
+                if high - low > 0.1 {
+                    recommendations.push(format!(
+                        "Noisy audio lowers confidence ({:.0}% below 10 dB SNR vs {:.0}% in clean audio); reduce background noise or move closer to the microphone",
+                        low * 100.0,
+                        high * 100.0
+                    ));
+                }
+            }
+        }
+        if signal.samples > 0 && signal.clipped_transcripts as f64 / signal.samples as f64 > 0.1 {
+            recommendations.push("Input is clipping frequently; lower the input gain or enable AGC".to_string());
+        }
         
         recommendations
     }
@@ -805,6 +931,9 @@ pub struct AnalyticsReport {
     pub usage_insights: Vec<String>,
     pub performance_summary: String,
     pub recommendations: Vec<String>,
+    /// Pearson correlation between SNR and confidence (None until enough data)
+    #[serde(default)]
+    pub snr_confidence_correlation: Option<f32>,
 }
 
 // Default implementations
@@ -829,6 +958,7 @@ impl QualityInsights {
             recommendations: Vec::new(),
             quality_by_hour: HashMap::new(),
             quality_by_session_length: HashMap::new(),
+            signal_quality: SignalQualityCorrelation::default(),
         }
     }
 }
@@ -907,6 +1037,8 @@ mod tests {
                             frequency_spread: 100.0,
                             spectral_centroid: 1000.0,
                         },
+                        clipping_events: 0,
+                        silence_percentage: 0.0,
                     },
                 },
                 annotations: Vec::new(),
@@ -727,6 +727,8 @@ mod tests {
                             frequency_spread: 100.0,
                             spectral_centroid: 1000.0,
                         },
+                        clipping_events: 0,
+                        silence_percentage: 0.0,
                     },
                 },
                 annotations: Vec::new(),
@@ -207,7 +207,7 @@ pub enum QualityIssue {
 }
 
 /// Signal quality metrics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SignalMetrics {
     /// Signal-to-noise ratio
     pub snr_db: f32,
@@ -215,10 +215,16 @@ pub struct SignalMetrics {
     pub audio_levels: AudioLevels,
     /// Frequency analysis
     pub frequency_analysis: FrequencyAnalysis,
+    /// Number of clipping events (runs of full-scale samples)
+    #[serde(default)]
+    pub clipping_events: u32,
+    /// Percentage of frames without speech
+    #[serde(default)]
+    pub silence_percentage: f32,
 }
 
 /// Audio level statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AudioLevels {
     pub peak: f32,
     pub rms: f32,
@@ -226,7 +232,7 @@ pub struct AudioLevels {
 }
 
 /// Frequency analysis results
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FrequencyAnalysis {
     pub dominant_frequency: f32,
     pub frequency_spread: f32,
@@ -604,8 +610,16 @@ pub enum TranscriptError {
 impl TranscriptionLogService {
     /// Create a new transcription log service with file storage
     pub fn new(config: TranscriptionLogConfig) -> Result<Self, TranscriptError> {
+        Self::new_with_cipher(config, None)
+    }
+
+    /// Create a transcription log service whose file storage is encrypted
+    pub fn new_with_cipher(
+        config: TranscriptionLogConfig,
+        cipher: Option<std::sync::Arc<super::encryption::StorageCipher>>,
+    ) -> Result<Self, TranscriptError> {
         let storage_config = FileStorageConfig::default();
-        let file_storage = FileTranscriptStorage::new(config.storage_path.clone(), storage_config)?;
+        let file_storage = FileTranscriptStorage::new_with_cipher(config.storage_path.clone(), storage_config, cipher)?;
         let storage: Box<dyn TranscriptStorage> = Box::new(file_storage);
         
         Self::new_with_storage(storage, config)
@@ -658,6 +672,22 @@ impl TranscriptionLogService {
     
     /// Log a new transcription
     pub fn log_transcription(&mut self, text: &str, confidence: f32, model: &str, duration_ms: u64) -> Result<TranscriptEntry, TranscriptError> {
+        self.log_transcription_with_signal(text, confidence, model, duration_ms, None)
+    }
+
+    /// Log a new transcription together with the signal metrics of its audio
+    pub fn log_transcription_with_signal(
+        &mut self,
+        text: &str,
+        confidence: f32,
+        model: &str,
+        duration_ms: u64,
+        signal_metrics: Option<SignalMetrics>,
+    ) -> Result<TranscriptEntry, TranscriptError> {
+        let issues = signal_metrics
+            .as_ref()
+            .map(super::signal_analysis::quality_issues)
+            .unwrap_or_default();
         let entry = TranscriptEntry {
             id: Uuid::new_v4(),
             timestamp: Utc::now(),
@@ -680,30 +710,22 @@ impl TranscriptionLogService {
                 quality_metrics: QualityMetrics {
                     quality_score: confidence,
                     word_confidences: Vec::new(),
-                    issues: Vec::new(),
-                    signal_metrics: SignalMetrics {
-                        snr_db: 0.0,
-                        audio_levels: AudioLevels {
-                            peak: 0.0,
-                            rms: 0.0,
-                            dynamic_range: 0.0,
-                        },
-                        frequency_analysis: FrequencyAnalysis {
-                            dominant_frequency: 0.0,
-                            frequency_spread: 0.0,
-                            spectral_centroid: 0.0,
-                        },
-                    },
+                    issues,
+                    signal_metrics: signal_metrics.unwrap_or_default(),
                 },
                 annotations: Vec::new(),
             },
             language: None,
             speaker: None,
         };
-        
+        self.log_entry(entry)
+    }
+
+    /// Log a transcript entry built by the caller, e.g. one from recorded audio
+    pub fn log_entry(&mut self, entry: TranscriptEntry) -> Result<TranscriptEntry, TranscriptError> {
         // Check for duplicates if enabled
         if self.config.enable_deduplication {
-            match self.deduplicator.is_duplicate(text)? {
+            match self.deduplicator.is_duplicate(&entry.text)? {
                 DuplicationResult::ExactDuplicate(existing_id) => {
                     println!("🔄 Exact duplicate detected, skipping: {}", existing_id);
                     return self.storage.get_transcript(existing_id)?
@@ -736,7 +758,7 @@ impl TranscriptionLogService {
         }
         
         println!("📝 Logged transcription: {} characters, {:.1}% confidence", 
-                text.len(), confidence * 100.0);
+                entry.text.len(), entry.confidence * 100.0);
         
         Ok(entry)
     }
@@ -19,6 +19,12 @@ use super::transcription_log::IndexConfig;
 use super::transcription_analytics::{TranscriptAnalytics, AnalyticsReport};
 use super::transcription_log::AnalyticsConfig;
 use super::audio_archive::SessionId;
+use super::signal_analysis::SignalAnalyzer;
+use super::diarization::SpeakerDiarizer;
+use super::transcription_log::SignalMetrics;
+use super::transcript_revisions::TranscriptRevision;
+use super::transcription_log::TranscriptSource;
+use super::audio_session_manager::AudioRecordingSession;
 
 /// Unified transcription management service
 pub struct TranscriptionManager {
@@ -34,6 +40,19 @@ pub struct TranscriptionManager {
     config: TranscriptionManagerConfig,
     /// Performance metrics
     metrics: ManagerMetrics,
+    /// Audio quality analysis for transcripts with audio attached
+    signal_analyzer: SignalAnalyzer,
+    /// Speaker labelling for transcripts with audio attached
+    diarizer: SpeakerDiarizer,
+}
+
+/// What was derived from a transcript's audio
+struct AudioDetails {
+    signal_metrics: Option<SignalMetrics>,
+    speaker: Option<String>,
+    source: TranscriptSource,
+    /// When the audio was spoken, for recordings transcribed after the fact
+    spoken_at: Option<DateTime<Utc>>,
 }
 
 /// Configuration for the transcription manager
@@ -87,9 +106,26 @@ pub struct TranscriptionResult {
     pub warnings: Vec<String>,
 }
 
+impl std::fmt::Debug for TranscriptionManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TranscriptionManager")
+            .field("config", &self.config)
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
 impl TranscriptionManager {
     /// Create a new transcription manager
     pub fn new(config: TranscriptionManagerConfig) -> Result<Self, TranscriptError> {
+        Self::new_with_cipher(config, None)
+    }
+
+    /// Create a transcription manager whose transcript log is encrypted
+    pub fn new_with_cipher(
+        config: TranscriptionManagerConfig,
+        cipher: Option<std::sync::Arc<super::encryption::StorageCipher>>,
+    ) -> Result<Self, TranscriptError> {
         println!("🚀 Initializing Transcription Manager...");
         let start_time = Instant::now();
 
@@ -110,10 +146,10 @@ impl TranscriptionManager {
         };
 
         let log_service = if config.enable_logging {
-            TranscriptionLogService::new(log_config)?
+            TranscriptionLogService::new_with_cipher(log_config, cipher)?
         } else {
             // Create a minimal service for testing
-            TranscriptionLogService::new(log_config)?
+            TranscriptionLogService::new_with_cipher(log_config, cipher)?
         };
 
         // Initialize deduplication engine
@@ -170,6 +206,8 @@ impl TranscriptionManager {
             analytics,
             config,
             metrics,
+            signal_analyzer: SignalAnalyzer::default(),
+            diarizer: SpeakerDiarizer::default(),
         })
     }
 
@@ -182,6 +220,61 @@ impl TranscriptionManager {
         duration_ms: u64,
         session_id: Option<SessionId>,
     ) -> Result<TranscriptionResult, TranscriptError> {
+        self.process_transcription_with_signal(text, confidence, model, duration_ms, session_id, None)
+    }
+
+    /// Process a transcription, analysing the audio it came from so the entry
+    /// carries SNR, levels, spectrum, clipping and silence metrics
+    pub fn process_transcription_with_audio(
+        &mut self,
+        text: &str,
+        confidence: f32,
+        model: &str,
+        session_id: Option<SessionId>,
+        samples: &[f32],
+        sample_rate: u32,
+    ) -> Result<TranscriptionResult, TranscriptError> {
+        let duration_ms = if sample_rate > 0 { samples.len() as u64 * 1000 / sample_rate as u64 } else { 0 };
+        let signal_metrics = self.signal_analyzer.analyze(samples, sample_rate);
+        let speaker = self.diarizer.identify(samples, sample_rate).map(|s| s.speaker_id);
+        let details = AudioDetails {
+            signal_metrics: Some(signal_metrics),
+            speaker,
+            source: TranscriptSource::LiveAudio,
+            spoken_at: None,
+        };
+        self.process_transcription_inner(text, confidence, model, duration_ms, session_id, details)
+    }
+
+    /// Process a transcription with precomputed signal metrics
+    pub fn process_transcription_with_signal(
+        &mut self,
+        text: &str,
+        confidence: f32,
+        model: &str,
+        duration_ms: u64,
+        session_id: Option<SessionId>,
+        signal_metrics: Option<SignalMetrics>,
+    ) -> Result<TranscriptionResult, TranscriptError> {
+        let details = AudioDetails { signal_metrics, speaker: None, source: TranscriptSource::LiveAudio, spoken_at: None };
+        self.process_transcription_inner(text, confidence, model, duration_ms, session_id, details)
+    }
+
+    /// Speaker diarizer used for transcripts with audio attached
+    pub fn diarizer_mut(&mut self) -> &mut SpeakerDiarizer {
+        &mut self.diarizer
+    }
+
+    fn process_transcription_inner(
+        &mut self,
+        text: &str,
+        confidence: f32,
+        model: &str,
+        duration_ms: u64,
+        session_id: Option<SessionId>,
+        details: AudioDetails,
+    ) -> Result<TranscriptionResult, TranscriptError> {
+        let AudioDetails { signal_metrics, speaker, source, spoken_at } = details;
         let start_time = Instant::now();
         let mut warnings = Vec::new();
 
@@ -222,11 +315,16 @@ impl TranscriptionManager {
         };
 
         // Step 2: Create transcript entry
-        let mut entry = self.create_transcript_entry(text, confidence, model, duration_ms, session_id);
+        let mut entry = self.create_transcript_entry(text, confidence, model, duration_ms, session_id, signal_metrics);
+        entry.speaker = speaker;
+        entry.metadata.source = source;
+        if let Some(spoken_at) = spoken_at {
+            entry.timestamp = spoken_at;
+        }
 
         // Step 3: Store transcript
         let stored = if self.config.enable_logging {
-            match self.log_service.log_transcription(text, confidence, model, duration_ms) {
+            match self.log_service.log_entry(entry.clone()) {
                 Ok(logged_entry) => {
                     entry = logged_entry;
                     true
@@ -363,6 +461,63 @@ impl TranscriptionManager {
         Ok(self.deduplicator.get_stats().total_duplicates)
     }
 
+    /// Make search and analytics use a transcript revision for its session in
+    /// place of the transcripts indexed so far; returns how many were replaced
+    pub fn apply_session_revision(&mut self, revision: &TranscriptRevision) -> Result<usize, TranscriptError> {
+        let previous = self.indexer.session_transcripts(revision.session_id);
+        let revised: Vec<TranscriptEntry> = revision.segments.iter()
+            .filter(|segment| !segment.text.trim().is_empty())
+            .map(|segment| {
+                let duration_ms = segment.end_time.saturating_sub(segment.start_time).as_millis() as u64;
+                let mut entry = self.create_transcript_entry(
+                    &segment.text, segment.confidence, &revision.model, duration_ms, Some(revision.session_id), None,
+                );
+                entry.timestamp = revision.created_at;
+                entry.language = segment.language.clone();
+                entry.speaker = segment.speaker_id.clone();
+                entry.tags.push("revision".to_string());
+                entry.metadata.processing_info.model_params.insert("revision_id".to_string(), revision.id.to_string());
+                entry
+            })
+            .collect();
+
+        if self.config.enable_search {
+            for entry in &previous {
+                self.indexer.remove_transcript(entry.id)?;
+            }
+            for entry in &revised {
+                self.indexer.index_transcript(entry)?;
+            }
+        }
+        if self.config.enable_analytics {
+            self.analytics.replace_session_transcripts(revision.session_id, &previous, &revised)?;
+        }
+
+        println!("📝 Applied revision {} ({}) to session {}: {} transcripts replaced",
+                revision.id, revision.model, revision.session_id, previous.len());
+        Ok(previous.len())
+    }
+
+    /// Log the transcript of a recorded or imported session, one entry per segment,
+    /// dated from when each segment was spoken
+    pub fn process_recorded_session(&mut self, session: &AudioRecordingSession) -> Result<Vec<TranscriptionResult>, TranscriptError> {
+        let model = session.metadata.get("transcription_model").map(String::as_str).unwrap_or("unknown");
+        let mut results = Vec::new();
+        for segment in session.transcript_segments.iter().filter(|segment| !segment.text.trim().is_empty()) {
+            let duration_ms = segment.end_time.saturating_sub(segment.start_time).as_millis() as u64;
+            let details = AudioDetails {
+                signal_metrics: None,
+                speaker: segment.speaker_id.clone(),
+                source: TranscriptSource::RecordedAudio { file_path: session.file_path.clone() },
+                spoken_at: chrono::Duration::from_std(segment.start_time).ok().map(|offset| session.start_time + offset),
+            };
+            results.push(self.process_transcription_inner(
+                &segment.text, segment.confidence, model, duration_ms, Some(session.id), details,
+            )?);
+        }
+        Ok(results)
+    }
+
     /// Get transcript by ID
     pub fn get_transcript(&self, id: super::transcription_log::TranscriptId) -> Result<Option<TranscriptEntry>, TranscriptError> {
         self.log_service.get_transcript(id)
@@ -377,11 +532,17 @@ impl TranscriptionManager {
         model: &str,
         duration_ms: u64,
         session_id: Option<SessionId>,
+        signal_metrics: Option<SignalMetrics>,
     ) -> TranscriptEntry {
         use uuid::Uuid;
         use std::collections::HashMap;
         use super::transcription_log::*;
 
+        let issues = signal_metrics
+            .as_ref()
+            .map(super::signal_analysis::quality_issues)
+            .unwrap_or_default();
+
         TranscriptEntry {
             id: Uuid::new_v4(),
             timestamp: Utc::now(),
@@ -404,20 +565,8 @@ impl TranscriptionManager {
                 quality_metrics: QualityMetrics {
                     quality_score: confidence,
                     word_confidences: Vec::new(),
-                    issues: Vec::new(),
-                    signal_metrics: SignalMetrics {
-                        snr_db: 0.0,
-                        audio_levels: AudioLevels {
-                            peak: 0.0,
-                            rms: 0.0,
-                            dynamic_range: 0.0,
-                        },
-                        frequency_analysis: FrequencyAnalysis {
-                            dominant_frequency: 0.0,
-                            frequency_spread: 0.0,
-                            spectral_centroid: 0.0,
-                        },
-                    },
+                    issues,
+                    signal_metrics: signal_metrics.unwrap_or_default(),
                 },
                 annotations: Vec::new(),
             },
@@ -251,6 +251,13 @@ impl TranscriptIndexer {
         Ok(())
     }
     
+    /// Indexed transcripts of a recording session
+    pub fn session_transcripts(&self, session_id: super::audio_archive::SessionId) -> Vec<TranscriptEntry> {
+        self.session_index.get(&session_id.to_string())
+            .map(|ids| ids.iter().filter_map(|id| self.transcript_storage.get(id).cloned()).collect())
+            .unwrap_or_default()
+    }
+
     /// Get index statistics
     pub fn get_stats(&self) -> &IndexStats {
         &self.stats
@@ -904,6 +911,8 @@ mod tests {
                             frequency_spread: 100.0,
                             spectral_centroid: 1000.0,
                         },
+                        clipping_events: 0,
+                        silence_percentage: 0.0,
                     },
                 },
                 annotations: Vec::new(),
@@ -445,6 +445,66 @@ impl TTSService {
     }
 }
 
+/// Render speech to mono samples instead of playing it, so the caller can
+/// play it itself (e.g. through an echo canceller reference tap).
+/// Uses `say` on macOS and `espeak-ng`/`espeak` elsewhere.
+pub fn synthesize_to_samples(text: &str) -> Result<(Vec<f32>, u32), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join(format!("stt-clippy-tts-{}.wav", std::process::id()));
+    let rendered = if cfg!(target_os = "macos") {
+        Command::new("say")
+            .arg("-o")
+            .arg(&path)
+            .arg("--file-format=WAVE")
+            .arg("--data-format=LEI16@22050")
+            .arg(text)
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    } else {
+        ["espeak-ng", "espeak"].iter().any(|program| {
+            Command::new(program)
+                .arg("-w")
+                .arg(&path)
+                .arg(text)
+                .output()
+                .map(|o| o.status.success())
+                .unwrap_or(false)
+        })
+    };
+    if !rendered {
+        return Err("no speech synthesizer available to render feedback".into());
+    }
+
+    let result = (|| {
+        let mut reader = hound::WavReader::open(&path)?;
+        let spec = reader.spec();
+        let channels = spec.channels.max(1) as usize;
+        let interleaved: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|s| s.map(|v| v as f32 / scale))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        let mono = interleaved
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+        Ok::<_, Box<dyn std::error::Error>>((mono, spec.sample_rate))
+    })();
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Rough duration of spoken text at a typical TTS rate (~170 words per minute)
+pub fn estimate_speech_duration(text: &str) -> std::time::Duration {
+    let words = text.split_whitespace().count() as u64;
+    std::time::Duration::from_millis(words * 350 + 400)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -94,6 +94,8 @@ pub struct SystemContext {
 #[derive(Debug)]
 pub struct ServiceContext {
     pub audio_session_manager: Option<std::sync::Arc<std::sync::Mutex<crate::services::audio_session_manager::AudioSessionManager>>>,
+    pub audio_service: Option<std::sync::Arc<std::sync::Mutex<crate::services::audio::AudioService>>>,
+    pub wake_word: Option<std::sync::Arc<std::sync::Mutex<crate::services::wake_word::WakeWordGate>>>,
 }
 
 /// System operating mode
@@ -117,6 +119,8 @@ pub struct AudioState {
     pub buffer_size: usize,
     pub current_device: Option<String>,
     pub recording_active: bool,
+    pub agc_enabled: bool,
+    pub input_gain_db: f32,
 }
 
 /// STT system state
@@ -728,6 +732,8 @@ impl Default for AudioState {
             buffer_size: 1024,
             current_device: None,
             recording_active: false,
+            agc_enabled: true,
+            input_gain_db: 0.0,
         }
     }
 }
@@ -9,20 +9,38 @@ use regex::Regex;
 
 use super::*;
 
+/// Get the live audio service from the service context, if one is attached
+fn audio_service(services: Option<&ServiceContext>) -> Option<&std::sync::Arc<std::sync::Mutex<crate::services::audio::AudioService>>> {
+    services.and_then(|s| s.audio_service.as_ref())
+}
+
+/// Map a 0-100% volume level to an input gain in dB (50% is unity gain)
+fn volume_to_gain_db(volume: u8) -> f32 {
+    (volume as f32 - 50.0) * 0.4
+}
+
 /// Set sample rate command
 pub struct SetSampleRateCommand;
 
 impl VoiceCommand for SetSampleRateCommand {
-    fn execute(&self, params: CommandParams, context: &mut SystemContext, _services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+    fn execute(&self, params: CommandParams, context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
         let regex = Regex::new(r"(?:sample rate|rate) (?:to )?(\d+)").unwrap();
         if let Some(captures) = regex.captures(&params.text) {
             if let Some(rate_str) = captures.get(1) {
                 if let Ok(rate) = rate_str.as_str().parse::<u32>() {
                     let valid_rates = [8000, 16000, 22050, 44100, 48000, 96000];
                     if valid_rates.contains(&rate) {
+                        let mut message = format!("Sample rate set to {}Hz", rate);
+                        if let Some(service) = audio_service(services) {
+                            let mut service = service.lock().map_err(|_| VoiceCommandError::ServiceUnavailable("Audio service lock poisoned".to_string()))?;
+                            let active = service.set_sample_rate(rate).map_err(|e| VoiceCommandError::ExecutionFailed(format!("Failed to apply sample rate: {e}")))?;
+                            if let Some(active) = active.filter(|a| a.sample_rate != rate) {
+                                message = format!("Sample rate set to {}Hz (device running at {}Hz)", rate, active.sample_rate);
+                            }
+                        }
                         context.audio_state.sample_rate = rate;
                         return Ok(CommandResult::success_with_data(
-                            format!("Sample rate set to {}Hz", rate),
+                            message,
                             CommandData::Number(rate as f64)
                         ).with_execution_time(Duration::from_millis(50)));
                     } else {
@@ -79,11 +97,31 @@ impl VoiceCommand for SetSampleRateCommand {
 pub struct SwitchAudioDeviceCommand;
 
 impl VoiceCommand for SwitchAudioDeviceCommand {
-    fn execute(&self, params: CommandParams, context: &mut SystemContext, _services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+    fn execute(&self, params: CommandParams, context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
         let regex = Regex::new(r"(?:switch to|use) device (.+)").unwrap();
         if let Some(captures) = regex.captures(&params.text) {
             if let Some(device_name) = captures.get(1) {
                 let device = device_name.as_str().trim();
+                if let Some(service) = audio_service(services) {
+                    let mut service = service.lock().map_err(|_| VoiceCommandError::ServiceUnavailable("Audio service lock poisoned".to_string()))?;
+                    let inputs: Vec<_> = service.get_devices()
+                        .map_err(|e| VoiceCommandError::ExecutionFailed(format!("Failed to list audio devices: {e}")))?
+                        .into_iter()
+                        .filter(|d| d.device_type == crate::core::types::AudioDeviceType::Input)
+                        .collect();
+                    let spoken = device.to_lowercase();
+                    let found = inputs.iter()
+                        .find(|d| d.id.to_lowercase() == spoken || d.name.to_lowercase() == spoken)
+                        .or_else(|| inputs.iter().find(|d| d.name.to_lowercase().contains(&spoken)))
+                        .ok_or_else(|| VoiceCommandError::InvalidParameters(format!("No input device matching '{}'", device)))?;
+                    service.switch_input_device(Some(found.id.clone()))
+                        .map_err(|e| VoiceCommandError::ExecutionFailed(format!("Failed to switch device: {e}")))?;
+                    context.audio_state.current_device = Some(found.id.clone());
+                    return Ok(CommandResult::success_with_data(
+                        format!("Switched to audio device: {}", found.name),
+                        CommandData::Text(found.id.clone())
+                    ).with_execution_time(Duration::from_millis(100)));
+                }
                 context.audio_state.current_device = Some(device.to_string());
                 return Ok(CommandResult::success_with_data(
                     format!("Switched to audio device: {}", device),
@@ -137,14 +175,25 @@ impl VoiceCommand for SwitchAudioDeviceCommand {
 pub struct AdjustVolumeCommand;
 
 impl VoiceCommand for AdjustVolumeCommand {
-    fn execute(&self, params: CommandParams, _context: &mut SystemContext, _services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+    fn execute(&self, params: CommandParams, context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
         let regex = Regex::new(r"(?:volume|gain) (?:to )?(\d+)(?:%|percent)?").unwrap();
         if let Some(captures) = regex.captures(&params.text) {
             if let Some(volume_str) = captures.get(1) {
                 if let Ok(volume) = volume_str.as_str().parse::<u8>() {
                     if volume <= 100 {
+                        // A manual level overrides automatic gain control
+                        let gain_db = volume_to_gain_db(volume);
+                        context.audio_state.input_gain_db = gain_db;
+                        context.audio_state.agc_enabled = false;
+                        if let Some(audio) = audio_service(services) {
+                            let mut audio = audio.lock().map_err(|_| {
+                                VoiceCommandError::ExecutionFailed("Audio service lock poisoned".to_string())
+                            })?;
+                            audio.set_manual_gain_db(gain_db);
+                            audio.set_agc_enabled(false);
+                        }
                         return Ok(CommandResult::success_with_data(
-                            format!("Volume set to {}%", volume),
+                            format!("Volume set to {}% ({:+.1} dB), automatic gain control off", volume, gain_db),
                             CommandData::Number(volume as f64)
                         ).with_execution_time(Duration::from_millis(30)));
                     } else {
@@ -173,7 +222,7 @@ impl VoiceCommand for AdjustVolumeCommand {
     }
     
     fn get_help_text(&self) -> &str {
-        "Adjusts the microphone volume/gain from 0 to 100%"
+        "Adjusts the microphone volume/gain from 0 to 100% (50% is unity gain). Setting a level turns off automatic gain control"
     }
     
     fn get_name(&self) -> &str {
@@ -295,16 +344,27 @@ impl VoiceCommand for DisableNoiseReductionCommand {
 pub struct SetBufferSizeCommand;
 
 impl VoiceCommand for SetBufferSizeCommand {
-    fn execute(&self, params: CommandParams, context: &mut SystemContext, _services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+    fn execute(&self, params: CommandParams, context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
         let regex = Regex::new(r"buffer size (?:to )?(\d+)").unwrap();
         if let Some(captures) = regex.captures(&params.text) {
             if let Some(size_str) = captures.get(1) {
                 if let Ok(size) = size_str.as_str().parse::<usize>() {
                     let valid_sizes = [64, 128, 256, 512, 1024, 2048, 4096];
                     if valid_sizes.contains(&size) {
+                        let mut message = format!("Buffer size set to {} samples", size);
+                        if let Some(service) = audio_service(services) {
+                            let mut service = service.lock().map_err(|_| VoiceCommandError::ServiceUnavailable("Audio service lock poisoned".to_string()))?;
+                            let active = service.set_buffer_size(size as u32).map_err(|e| VoiceCommandError::ExecutionFailed(format!("Failed to apply buffer size: {e}")))?;
+                            if let Some(active) = active.filter(|a| a.buffer_size != Some(size as u32)) {
+                                message = match active.buffer_size {
+                                    Some(actual) => format!("Buffer size set to {} samples (device using {})", size, actual),
+                                    None => format!("Buffer size set to {} samples (device using its default buffer)", size),
+                                };
+                            }
+                        }
                         context.audio_state.buffer_size = size;
                         return Ok(CommandResult::success_with_data(
-                            format!("Buffer size set to {} samples", size),
+                            message,
                             CommandData::Number(size as f64)
                         ).with_execution_time(Duration::from_millis(40)));
                     } else {
@@ -505,8 +565,14 @@ impl VoiceCommand for ShowAudioDevicesCommand {
 pub struct EnableAGCCommand;
 
 impl VoiceCommand for EnableAGCCommand {
-    fn execute(&self, _params: CommandParams, _context: &mut SystemContext, _services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
-        Ok(CommandResult::success("Automatic gain control enabled".to_string())
+    fn execute(&self, _params: CommandParams, context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+        context.audio_state.agc_enabled = true;
+        if let Some(audio) = audio_service(services) {
+            audio.lock()
+                .map_err(|_| VoiceCommandError::ExecutionFailed("Audio service lock poisoned".to_string()))?
+                .set_agc_enabled(true);
+        }
+        Ok(CommandResult::success_with_data("Automatic gain control enabled".to_string(), CommandData::Boolean(true))
             .with_execution_time(Duration::from_millis(20)))
     }
     
@@ -544,7 +610,7 @@ impl VoiceCommand for EnableAGCCommand {
     }
     
     fn get_related_commands(&self) -> Vec<String> {
-        vec!["disable_agc".to_string()]
+        vec!["disable_agc".to_string(), "adjust_volume".to_string()]
     }
     
     fn get_difficulty(&self) -> DifficultyLevel {
@@ -556,9 +622,17 @@ impl VoiceCommand for EnableAGCCommand {
 pub struct DisableAGCCommand;
 
 impl VoiceCommand for DisableAGCCommand {
-    fn execute(&self, _params: CommandParams, _context: &mut SystemContext, _services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
-        Ok(CommandResult::success("Automatic gain control disabled".to_string())
-            .with_execution_time(Duration::from_millis(20)))
+    fn execute(&self, _params: CommandParams, context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+        context.audio_state.agc_enabled = false;
+        if let Some(audio) = audio_service(services) {
+            audio.lock()
+                .map_err(|_| VoiceCommandError::ExecutionFailed("Audio service lock poisoned".to_string()))?
+                .set_agc_enabled(false);
+        }
+        Ok(CommandResult::success_with_data(
+            format!("Automatic gain control disabled, using manual gain of {:+.1} dB", context.audio_state.input_gain_db),
+            CommandData::Boolean(false)
+        ).with_execution_time(Duration::from_millis(20)))
     }
     
     fn get_patterns(&self) -> Vec<PatternType> {
@@ -595,7 +669,7 @@ impl VoiceCommand for DisableAGCCommand {
     }
     
     fn get_related_commands(&self) -> Vec<String> {
-        vec!["enable_agc".to_string()]
+        vec!["enable_agc".to_string(), "adjust_volume".to_string()]
     }
     
     fn get_difficulty(&self) -> DifficultyLevel {
@@ -607,7 +681,10 @@ impl VoiceCommand for DisableAGCCommand {
 pub struct ShowAudioSettingsCommand;
 
 impl VoiceCommand for ShowAudioSettingsCommand {
-    fn execute(&self, _params: CommandParams, context: &mut SystemContext, _services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+    fn execute(&self, _params: CommandParams, context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+        let current_gain = audio_service(services)
+            .and_then(|audio| audio.lock().ok().map(|a| a.get_gain_stats().current_gain_db))
+            .unwrap_or(context.audio_state.input_gain_db);
         let settings = format!(
             "Audio Settings:\n\
             • Sample Rate: {}Hz\n\
@@ -615,12 +692,16 @@ impl VoiceCommand for ShowAudioSettingsCommand {
             • Buffer Size: {} samples\n\
             • VAD Enabled: {}\n\
             • Sensitivity: {:.2}\n\
+            • Automatic Gain Control: {}\n\
+            • Input Gain: {:+.1} dB\n\
             • Current Device: {}",
             context.audio_state.sample_rate,
             context.audio_state.channels,
             context.audio_state.buffer_size,
             if context.audio_state.vad_enabled { "Yes" } else { "No" },
             context.audio_state.sensitivity,
+            if context.audio_state.agc_enabled { "On" } else { "Off" },
+            current_gain,
             context.audio_state.current_device.as_ref().unwrap_or(&"Default".to_string())
         );
         
@@ -733,6 +814,22 @@ mod tests {
         }
     }
     
+    #[tokio::test]
+    async fn test_agc_commands_update_state() {
+        let mut engine = VoiceCommandEngine::new();
+        register_audio_commands(&mut engine).unwrap();
+
+        let result = engine.process_voice_input("disable agc", 0.95).await.unwrap();
+        assert!(result.success);
+
+        let result = engine.process_voice_input("set volume to 75", 0.95).await.unwrap();
+        assert!(result.message.contains("+10.0 dB"));
+
+        let result = engine.process_voice_input("show audio settings", 0.95).await.unwrap();
+        assert!(result.message.contains("Automatic Gain Control: Off"));
+        assert!(result.message.contains("Input Gain: +10.0 dB"));
+    }
+
     #[test]
     fn test_invalid_parameters() {
         let mut engine = VoiceCommandEngine::new();
@@ -10,6 +10,7 @@ use chrono::Utc;
 
 use super::*;
 use crate::services::audio_session_manager::AudioSource;
+use crate::services::session_markers::{self, MarkerKind};
 
 /// Session information for display
 #[derive(Debug, Clone)]
@@ -502,6 +503,150 @@ impl VoiceCommand for ResumeRecordingCommand {
     }
 }
 
+/// Add a marker to the active recording session
+fn add_session_marker(kind: MarkerKind, label: Option<String>, services: Option<&super::ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+    let start_time = std::time::Instant::now();
+    let audio_session_manager = services
+        .and_then(|s| s.audio_session_manager.as_ref())
+        .ok_or_else(|| VoiceCommandError::ServiceUnavailable("AudioSessionManager not available".to_string()))?;
+
+    let marker = match audio_session_manager.lock() {
+        Ok(mut manager) => manager.add_marker(kind, label),
+        Err(e) => {
+            return Ok(CommandResult {
+                success: false,
+                message: format!("❌ Failed to access audio session manager: {}", e),
+                data: Some(CommandData::Text("service_lock_failed".to_string())),
+                execution_time: start_time.elapsed(),
+                timestamp: Utc::now(),
+            });
+        }
+    };
+    let marker = match marker {
+        Ok(marker) => marker,
+        Err(e) => {
+            return Ok(CommandResult {
+                success: false,
+                message: format!("⚠️  {}", e),
+                data: Some(CommandData::Text("no_active_session".to_string())),
+                execution_time: start_time.elapsed(),
+                timestamp: Utc::now(),
+            });
+        }
+    };
+
+    let secs = marker.at.as_secs();
+    let position = format!("{}:{:02}", secs / 60, secs % 60);
+    let message = match (&marker.kind, &marker.label) {
+        (MarkerKind::Chapter, Some(label)) => format!("Chapter {} at {}", label, position),
+        (MarkerKind::Chapter, None) => format!("New chapter at {}", position),
+        (MarkerKind::Mark, _) => format!("Marked at {}", position),
+    };
+
+    Ok(CommandResult {
+        success: true,
+        message,
+        data: Some(CommandData::Object({
+            let mut data = std::collections::HashMap::new();
+            data.insert("marker_id".to_string(), serde_json::Value::String(marker.id.to_string()));
+            data.insert("at_seconds".to_string(), serde_json::json!(marker.at.as_secs_f64()));
+            data.insert("kind".to_string(), serde_json::json!(marker.kind));
+            data.insert("label".to_string(), serde_json::json!(marker.label));
+            data
+        })),
+        execution_time: start_time.elapsed(),
+        timestamp: Utc::now(),
+    })
+}
+
+/// Mark the current moment of a recording
+pub struct AddMarkerCommand;
+
+impl VoiceCommand for AddMarkerCommand {
+    fn execute(&self, params: CommandParams, _context: &mut SystemContext, services: Option<&super::ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+        add_session_marker(MarkerKind::Mark, session_markers::spoken_label(&params.text, "add marker"), services)
+    }
+    
+    fn get_patterns(&self) -> Vec<PatternType> {
+        vec![
+            PatternType::Exact("mark that".to_string()),
+            PatternType::Exact("mark this".to_string()),
+            PatternType::Exact("mark it".to_string()),
+            PatternType::Contains("add marker".to_string()),
+        ]
+    }
+    
+    fn get_category(&self) -> CommandCategory {
+        CommandCategory::Audio
+    }
+    
+    fn get_name(&self) -> &str {
+        "add_marker"
+    }
+    
+    fn get_description(&self) -> &str {
+        "Drop a timestamped marker in the current recording"
+    }
+    
+    fn get_help_text(&self) -> &str {
+        "Add marker: 'mark that' or 'add marker [label]'"
+    }
+    
+    fn get_examples(&self) -> Vec<String> {
+        vec![
+            "mark that".to_string(),
+            "add marker action item".to_string(),
+        ]
+    }
+    
+    fn validate_context(&self, _context: &SystemContext) -> Result<(), VoiceCommandError> {
+        Ok(())
+    }
+}
+
+/// Start a new chapter in a recording
+pub struct NewChapterCommand;
+
+impl VoiceCommand for NewChapterCommand {
+    fn execute(&self, params: CommandParams, _context: &mut SystemContext, services: Option<&super::ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+        add_session_marker(MarkerKind::Chapter, session_markers::spoken_label(&params.text, "new chapter"), services)
+    }
+    
+    fn get_patterns(&self) -> Vec<PatternType> {
+        vec![
+            PatternType::Exact("new chapter".to_string()),
+            PatternType::Contains("new chapter".to_string()),
+        ]
+    }
+    
+    fn get_category(&self) -> CommandCategory {
+        CommandCategory::Audio
+    }
+    
+    fn get_name(&self) -> &str {
+        "new_chapter"
+    }
+    
+    fn get_description(&self) -> &str {
+        "Start a new, optionally named chapter in the current recording"
+    }
+    
+    fn get_help_text(&self) -> &str {
+        "New chapter: 'new chapter [title]', e.g. 'new chapter budget discussion'"
+    }
+    
+    fn get_examples(&self) -> Vec<String> {
+        vec![
+            "new chapter".to_string(),
+            "new chapter budget discussion".to_string(),
+        ]
+    }
+    
+    fn validate_context(&self, _context: &SystemContext) -> Result<(), VoiceCommandError> {
+        Ok(())
+    }
+}
+
 /// List sessions command
 pub struct ListSessionsCommand;
 
@@ -620,12 +765,65 @@ impl VoiceCommand for ListSessionsCommand {
 pub struct CompressFilesCommand;
 
 impl VoiceCommand for CompressFilesCommand {
-    fn execute(&self, _params: CommandParams, _context: &mut SystemContext, _services: Option<&super::ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+    fn execute(&self, _params: CommandParams, _context: &mut SystemContext, services: Option<&super::ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+        let start_time = std::time::Instant::now();
+        let audio_session_manager = services
+            .and_then(|s| s.audio_session_manager.as_ref())
+            .ok_or_else(|| VoiceCommandError::ServiceUnavailable("AudioSessionManager not available".to_string()))?;
+
+        let result = match audio_session_manager.lock() {
+            Ok(manager) => manager.compress_archive(),
+            Err(e) => {
+                return Ok(CommandResult {
+                    success: false,
+                    message: format!("❌ Failed to access audio session manager: {}", e),
+                    data: Some(CommandData::Text("service_lock_failed".to_string())),
+                    execution_time: start_time.elapsed(),
+                    timestamp: Utc::now(),
+                });
+            }
+        };
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                return Ok(CommandResult {
+                    success: false,
+                    message: format!("❌ Failed to compress audio files: {}", e),
+                    data: Some(CommandData::Text("compression_failed".to_string())),
+                    execution_time: start_time.elapsed(),
+                    timestamp: Utc::now(),
+                });
+            }
+        };
+
+        let mb = |bytes: u64| bytes as f64 / 1024.0 / 1024.0;
+        let mut message = if result.files_compressed == 0 {
+            "🗜️  No uncompressed audio files to compress".to_string()
+        } else {
+            format!(
+                "🗜️  Compressed {} audio file(s). Saved {:.0}% storage space ({:.1} MB → {:.1} MB)",
+                result.files_compressed,
+                (1.0 - result.compression_ratio) * 100.0,
+                mb(result.original_size),
+                mb(result.compressed_size),
+            )
+        };
+        if !result.skipped.is_empty() {
+            message.push_str(&format!("\n⚠️  {} file(s) could not be compressed", result.skipped.len()));
+        }
+
         Ok(CommandResult {
             success: true,
-            message: "🗜️  Compressing audio files... Saved 45% storage space (3.2 GB → 1.8 GB)".to_string(),
-            data: Some(CommandData::Text("compression_complete".to_string())),
-            execution_time: Duration::from_millis(2500),
+            message,
+            data: Some(CommandData::Object({
+                let mut data = std::collections::HashMap::new();
+                data.insert("files_compressed".to_string(), serde_json::json!(result.files_compressed));
+                data.insert("original_size".to_string(), serde_json::json!(result.original_size));
+                data.insert("compressed_size".to_string(), serde_json::json!(result.compressed_size));
+                data.insert("files_skipped".to_string(), serde_json::json!(result.skipped.len()));
+                data
+            })),
+            execution_time: start_time.elapsed(),
             timestamp: Utc::now(),
         })
     }
@@ -795,6 +993,14 @@ pub fn create_resume_recording_command() -> ResumeRecordingCommand {
     ResumeRecordingCommand
 }
 
+pub fn create_add_marker_command() -> AddMarkerCommand {
+    AddMarkerCommand
+}
+
+pub fn create_new_chapter_command() -> NewChapterCommand {
+    NewChapterCommand
+}
+
 pub fn create_list_sessions_command() -> ListSessionsCommand {
     ListSessionsCommand
 }
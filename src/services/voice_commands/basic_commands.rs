@@ -436,6 +436,267 @@ impl VoiceCommand for SetSensitivityCommand {
     }
 }
 
+fn wake_word_gate(services: Option<&ServiceContext>) -> Result<&std::sync::Arc<std::sync::Mutex<crate::services::wake_word::WakeWordGate>>, VoiceCommandError> {
+    services
+        .and_then(|s| s.wake_word.as_ref())
+        .ok_or_else(|| VoiceCommandError::ServiceUnavailable("Wake word gate not available".to_string()))
+}
+
+/// Require the wake phrase before commands
+pub struct EnableWakeWordCommand;
+
+impl VoiceCommand for EnableWakeWordCommand {
+    fn execute(&self, _params: CommandParams, _context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+        let mut gate = wake_word_gate(services)?
+            .lock()
+            .map_err(|_| VoiceCommandError::ExecutionFailed("Wake word gate lock poisoned".to_string()))?;
+        gate.set_enabled(true);
+        Ok(CommandResult::success_with_data(
+            format!("Wake word enabled. Say '{}' before a command", gate.config().phrase),
+            CommandData::Boolean(true)
+        ).with_execution_time(Duration::from_millis(5)))
+    }
+
+    fn get_patterns(&self) -> Vec<PatternType> {
+        vec![
+            PatternType::Exact("enable wake word".to_string()),
+            PatternType::Exact("wake word on".to_string()),
+            PatternType::Exact("require wake word".to_string()),
+        ]
+    }
+
+    fn get_category(&self) -> CommandCategory {
+        CommandCategory::STT
+    }
+
+    fn get_help_text(&self) -> &str {
+        "Only runs commands that start with the wake phrase; everything else is dictation"
+    }
+
+    fn get_name(&self) -> &str {
+        "enable_wake_word"
+    }
+
+    fn get_description(&self) -> &str {
+        "Enable the wake word gate"
+    }
+
+    fn get_examples(&self) -> Vec<String> {
+        vec!["enable wake word".to_string()]
+    }
+
+    fn get_related_commands(&self) -> Vec<String> {
+        vec!["disable_wake_word".to_string(), "set_wake_word".to_string(), "train_wake_word".to_string()]
+    }
+}
+
+/// Run every recognised command without a wake phrase
+pub struct DisableWakeWordCommand;
+
+impl VoiceCommand for DisableWakeWordCommand {
+    fn execute(&self, _params: CommandParams, _context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+        wake_word_gate(services)?
+            .lock()
+            .map_err(|_| VoiceCommandError::ExecutionFailed("Wake word gate lock poisoned".to_string()))?
+            .set_enabled(false);
+        Ok(CommandResult::success_with_data(
+            "Wake word disabled".to_string(),
+            CommandData::Boolean(false)
+        ).with_execution_time(Duration::from_millis(5)))
+    }
+
+    fn get_patterns(&self) -> Vec<PatternType> {
+        vec![
+            PatternType::Exact("disable wake word".to_string()),
+            PatternType::Exact("wake word off".to_string()),
+        ]
+    }
+
+    fn get_category(&self) -> CommandCategory {
+        CommandCategory::STT
+    }
+
+    fn get_help_text(&self) -> &str {
+        "Recognises commands anywhere, without the wake phrase"
+    }
+
+    fn get_name(&self) -> &str {
+        "disable_wake_word"
+    }
+
+    fn get_description(&self) -> &str {
+        "Disable the wake word gate"
+    }
+
+    fn get_examples(&self) -> Vec<String> {
+        vec!["disable wake word".to_string()]
+    }
+
+    fn get_related_commands(&self) -> Vec<String> {
+        vec!["enable_wake_word".to_string()]
+    }
+}
+
+/// Change the wake phrase
+pub struct SetWakeWordCommand;
+
+impl VoiceCommand for SetWakeWordCommand {
+    fn execute(&self, params: CommandParams, _context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+        let regex = Regex::new(r"(?i)(?:set|change) wake (?:word|phrase) to (.+)").unwrap();
+        let phrase = regex
+            .captures(&params.text)
+            .and_then(|c| c.get(1))
+            .map(|m| m.as_str().trim_matches(|c: char| c.is_ascii_punctuation() || c.is_whitespace()).to_string())
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| VoiceCommandError::InvalidParameters("Could not parse wake phrase".to_string()))?;
+
+        let mut gate = wake_word_gate(services)?
+            .lock()
+            .map_err(|_| VoiceCommandError::ExecutionFailed("Wake word gate lock poisoned".to_string()))?;
+        gate.set_phrase(&phrase);
+        Ok(CommandResult::success_with_data(
+            format!("Wake phrase set to '{}'", gate.config().phrase),
+            CommandData::Text(gate.config().phrase.clone())
+        ).with_execution_time(Duration::from_millis(5)))
+    }
+
+    fn get_patterns(&self) -> Vec<PatternType> {
+        vec![PatternType::Regex(Regex::new(r"(?:set|change) wake (?:word|phrase) to (.+)").unwrap())]
+    }
+
+    fn get_category(&self) -> CommandCategory {
+        CommandCategory::STT
+    }
+
+    fn get_help_text(&self) -> &str {
+        "Changes the phrase that switches the next utterance into command mode"
+    }
+
+    fn get_name(&self) -> &str {
+        "set_wake_word"
+    }
+
+    fn get_description(&self) -> &str {
+        "Set the wake phrase"
+    }
+
+    fn get_examples(&self) -> Vec<String> {
+        vec!["set wake word to computer".to_string()]
+    }
+
+    fn get_related_commands(&self) -> Vec<String> {
+        vec!["train_wake_word".to_string(), "set_wake_word_sensitivity".to_string()]
+    }
+}
+
+/// Set how lenient wake phrase detection is
+pub struct SetWakeWordSensitivityCommand;
+
+impl VoiceCommand for SetWakeWordSensitivityCommand {
+    fn execute(&self, params: CommandParams, _context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+        let regex = Regex::new(r"(?i)wake (?:word|phrase) sensitivity to ([\d.]+)").unwrap();
+        let value = regex
+            .captures(&params.text)
+            .and_then(|c| c.get(1))
+            .and_then(|m| m.as_str().trim_end_matches('.').parse::<f32>().ok())
+            .ok_or_else(|| VoiceCommandError::InvalidParameters("Could not parse wake word sensitivity".to_string()))?;
+
+        let mut gate = wake_word_gate(services)?
+            .lock()
+            .map_err(|_| VoiceCommandError::ExecutionFailed("Wake word gate lock poisoned".to_string()))?;
+        gate.set_sensitivity(value);
+        Ok(CommandResult::success_with_data(
+            format!("Wake word sensitivity set to {:.2}", gate.config().sensitivity),
+            CommandData::Number(gate.config().sensitivity as f64)
+        ).with_execution_time(Duration::from_millis(5)))
+    }
+
+    fn get_patterns(&self) -> Vec<PatternType> {
+        vec![PatternType::Regex(Regex::new(r"wake (?:word|phrase) sensitivity to ([\d.]+)").unwrap())]
+    }
+
+    fn get_category(&self) -> CommandCategory {
+        CommandCategory::STT
+    }
+
+    fn get_help_text(&self) -> &str {
+        "Sets wake phrase detection sensitivity between 0.0 (strict) and 1.0 (lenient)"
+    }
+
+    fn get_name(&self) -> &str {
+        "set_wake_word_sensitivity"
+    }
+
+    fn get_description(&self) -> &str {
+        "Set wake word sensitivity"
+    }
+
+    fn get_examples(&self) -> Vec<String> {
+        vec!["set wake word sensitivity to 0.7".to_string()]
+    }
+
+    fn get_related_commands(&self) -> Vec<String> {
+        vec!["set_wake_word".to_string()]
+    }
+
+    fn get_difficulty(&self) -> DifficultyLevel {
+        DifficultyLevel::Intermediate
+    }
+}
+
+/// Record samples of the wake phrase for acoustic spotting
+pub struct TrainWakeWordCommand;
+
+/// Samples recorded per training run
+const WAKE_WORD_TRAINING_SAMPLES: usize = 3;
+
+impl VoiceCommand for TrainWakeWordCommand {
+    fn execute(&self, _params: CommandParams, _context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+        let mut gate = wake_word_gate(services)?
+            .lock()
+            .map_err(|_| VoiceCommandError::ExecutionFailed("Wake word gate lock poisoned".to_string()))?;
+        gate.clear_templates();
+        gate.request_enrollment(WAKE_WORD_TRAINING_SAMPLES);
+        Ok(CommandResult::success(format!(
+            "Say '{}' {} times, pausing after each",
+            gate.config().phrase,
+            WAKE_WORD_TRAINING_SAMPLES
+        )).with_execution_time(Duration::from_millis(5)))
+    }
+
+    fn get_patterns(&self) -> Vec<PatternType> {
+        vec![
+            PatternType::Exact("train wake word".to_string()),
+            PatternType::Exact("record wake word".to_string()),
+            PatternType::Exact("train wake phrase".to_string()),
+        ]
+    }
+
+    fn get_category(&self) -> CommandCategory {
+        CommandCategory::STT
+    }
+
+    fn get_help_text(&self) -> &str {
+        "Records the wake phrase in your voice so it is recognised from the audio, not just the transcript"
+    }
+
+    fn get_name(&self) -> &str {
+        "train_wake_word"
+    }
+
+    fn get_description(&self) -> &str {
+        "Record wake phrase samples"
+    }
+
+    fn get_examples(&self) -> Vec<String> {
+        vec!["train wake word".to_string()]
+    }
+
+    fn get_related_commands(&self) -> Vec<String> {
+        vec!["set_wake_word".to_string(), "enable_wake_word".to_string()]
+    }
+}
+
 /// Show system status command
 pub struct ShowStatusCommand;
 
@@ -578,6 +839,11 @@ pub fn create_basic_command_registry() -> VoiceCommandEngine {
     engine.register_command(ToggleInstantOutputCommand).unwrap();
     engine.register_command(EnableNarrationCommand).unwrap();
     engine.register_command(DisableNarrationCommand).unwrap();
+    engine.register_command(EnableWakeWordCommand).unwrap();
+    engine.register_command(DisableWakeWordCommand).unwrap();
+    engine.register_command(SetWakeWordCommand).unwrap();
+    engine.register_command(SetWakeWordSensitivityCommand).unwrap();
+    engine.register_command(TrainWakeWordCommand).unwrap();
     engine.register_command(ShowStatusCommand).unwrap();
     engine.register_command(ShowHelpCommand).unwrap();
     
@@ -34,7 +34,7 @@ pub fn create_comprehensive_command_engine_with_config(config: VoiceCommandConfi
 
 /// Register all available voice commands
 pub fn register_all_commands(engine: &mut VoiceCommandEngine) -> Result<(), VoiceCommandError> {
-    // Basic commands (10 commands)
+    // Basic commands (15 commands)
     engine.register_command(EnableVADCommand)?;
     engine.register_command(DisableVADCommand)?;
     engine.register_command(IncreaseSensitivityCommand)?;
@@ -43,17 +43,24 @@ pub fn register_all_commands(engine: &mut VoiceCommandEngine) -> Result<(), Voic
     engine.register_command(ToggleInstantOutputCommand)?;
     engine.register_command(EnableNarrationCommand)?;
     engine.register_command(DisableNarrationCommand)?;
+    engine.register_command(EnableWakeWordCommand)?;
+    engine.register_command(DisableWakeWordCommand)?;
+    engine.register_command(SetWakeWordCommand)?;
+    engine.register_command(SetWakeWordSensitivityCommand)?;
+    engine.register_command(TrainWakeWordCommand)?;
     engine.register_command(ShowStatusCommand)?;
     engine.register_command(ShowHelpCommand)?;
     
     // Audio commands (12 commands)
     register_audio_commands(engine)?;
     
-    // Audio recording commands (8 commands)
+    // Audio recording commands (10 commands)
     engine.register_command(create_start_recording_command())?;
     engine.register_command(create_stop_recording_command())?;
     engine.register_command(create_pause_recording_command())?;
     engine.register_command(create_resume_recording_command())?;
+    engine.register_command(create_add_marker_command())?;
+    engine.register_command(create_new_chapter_command())?;
     engine.register_command(create_list_sessions_command())?;
     engine.register_command(create_compress_files_command())?;
     engine.register_command(create_show_storage_stats_command())?;
@@ -8,6 +8,9 @@ use chrono::Utc;
 use uuid::Uuid;
 
 use super::*;
+use crate::services::audio_session_manager::TranscriptSegment;
+use crate::services::diarization::SpeakerCluster;
+use crate::services::prosody::ProsodyProfile;
 use crate::services::session_transcript_tracker::{
     SessionTranscriptTracker, TrackerConfig, PhraseType, SessionStatistics
 };
@@ -95,37 +98,33 @@ impl VoiceCommand for ShowSessionTranscriptCommand {
 pub struct ShowSpeakerAnalysisCommand;
 
 impl VoiceCommand for ShowSpeakerAnalysisCommand {
-    fn execute(&self, _params: CommandParams, _context: &mut SystemContext, _services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+    fn execute(&self, _params: CommandParams, _context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
         let start_time = std::time::Instant::now();
         
-        // Mock speaker analysis data
-        let speaker_analysis = vec![
-            SpeakerAnalysis {
-                id: "Speaker_1".to_string(),
-                name: Some("Meeting Host".to_string()),
-                speaking_time: Duration::from_secs(45),
-                phrase_count: 12,
-                average_confidence: 0.95,
-                speaking_rate: 145.0,
-                question_count: 5,
-                dominant_topics: vec!["project".to_string(), "timeline".to_string(), "updates".to_string()],
-            },
-            SpeakerAnalysis {
-                id: "Speaker_2".to_string(),
-                name: Some("Team Member".to_string()),
-                speaking_time: Duration::from_secs(30),
-                phrase_count: 8,
-                average_confidence: 0.92,
-                speaking_rate: 160.0,
-                question_count: 1,
-                dominant_topics: vec!["development".to_string(), "progress".to_string(), "deadline".to_string()],
-            },
-        ];
+        // Speakers come from the diarized segments of the current (or last) recording session
+        let speaker_analysis = recorded_session(services)
+            .map(|session| analyze_speakers(&session.segments, &session.speakers))
+            .unwrap_or_default();
+
+        if speaker_analysis.is_empty() {
+            return Ok(CommandResult {
+                success: true,
+                message: "👥 Speaker Analysis:\nNo speakers identified yet. Segments are labelled as a recording session is transcribed.".to_string(),
+                data: Some(CommandData::Object({
+                    let mut data = std::collections::HashMap::new();
+                    data.insert("speaker_count".to_string(), serde_json::Value::Number(serde_json::Number::from(0)));
+                    data
+                })),
+                execution_time: start_time.elapsed(),
+                timestamp: Utc::now(),
+            });
+        }
+        let total_speaking_secs: u64 = speaker_analysis.iter().map(|s| s.speaking_time.as_secs()).sum();
         
         let mut analysis_text = String::new();
         for (i, speaker) in speaker_analysis.iter().enumerate() {
             analysis_text.push_str(&format!(
-                "{}. {} ({})\n   Speaking Time: {}:{:02}\n   Phrases: {} | Questions: {}\n   Confidence: {:.1}% | Rate: {:.0} WPM\n   Topics: {}\n",
+                "{}. {} ({})\n   Speaking Time: {}:{:02}\n   Phrases: {} | Questions: {}\n   Confidence: {:.1}% | Rate: {:.0} WPM\n   {}\n   Topics: {}\n",
                 i + 1,
                 speaker.name.as_ref().unwrap_or(&speaker.id),
                 speaker.id,
@@ -135,6 +134,7 @@ impl VoiceCommand for ShowSpeakerAnalysisCommand {
                 speaker.question_count,
                 speaker.average_confidence * 100.0,
                 speaker.speaking_rate,
+                describe_delivery(&speaker.prosody),
                 speaker.dominant_topics.join(", ")
             ));
         }
@@ -145,8 +145,8 @@ impl VoiceCommand for ShowSpeakerAnalysisCommand {
             analysis_text.trim(),
             speaker_analysis.len(),
             speaker_analysis[0].name.as_ref().unwrap_or(&speaker_analysis[0].id),
-            75 / 60,
-            75 % 60
+            total_speaking_secs / 60,
+            total_speaking_secs % 60
         );
         
         Ok(CommandResult {
@@ -155,7 +155,7 @@ impl VoiceCommand for ShowSpeakerAnalysisCommand {
             data: Some(CommandData::Object({
                 let mut data = std::collections::HashMap::new();
                 data.insert("speaker_count".to_string(), serde_json::Value::Number(serde_json::Number::from(speaker_analysis.len())));
-                data.insert("total_speaking_time".to_string(), serde_json::Value::Number(serde_json::Number::from(75)));
+                data.insert("total_speaking_time".to_string(), serde_json::Value::Number(serde_json::Number::from(total_speaking_secs)));
                 data.insert("most_active_speaker".to_string(), serde_json::Value::String(speaker_analysis[0].id.clone()));
                 data
             })),
@@ -294,45 +294,66 @@ impl VoiceCommand for ShowSessionKeywordsCommand {
 pub struct GenerateSessionSummaryCommand;
 
 impl VoiceCommand for GenerateSessionSummaryCommand {
-    fn execute(&self, _params: CommandParams, _context: &mut SystemContext, _services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
+    fn execute(&self, _params: CommandParams, _context: &mut SystemContext, services: Option<&ServiceContext>) -> Result<CommandResult, VoiceCommandError> {
         let start_time = std::time::Instant::now();
         
-        // Mock session summary generation
-        let summary = "📋 Session Summary:\n\n\
-                      🎯 Meeting Purpose: Project status update and timeline review\n\n\
-                      👥 Participants: 2 speakers identified\n\
-                      • Meeting Host (60% speaking time)\n\
-                      • Team Member (40% speaking time)\n\n\
-                      🔑 Key Points:\n\
-                      • Development progress is on track\n\
-                      • March deadline is achievable\n\
-                      • No major blockers identified\n\
-                      • Team coordination is effective\n\n\
-                      📊 Session Metrics:\n\
-                      • Duration: 1:15\n\
-                      • Total Words: 156\n\
-                      • Questions Asked: 6\n\
-                      • Average Confidence: 93.5%\n\n\
-                      🎭 Sentiment Analysis:\n\
-                      • Overall Tone: Positive (78%)\n\
-                      • Confidence Level: High\n\
-                      • Engagement: Active participation\n\n\
-                      📝 Action Items:\n\
-                      • Continue current development pace\n\
-                      • Monitor for potential blockers\n\
-                      • Schedule next update meeting";
+        let Some(session) = recorded_session(services).filter(|s| !s.segments.is_empty()) else {
+            return Ok(CommandResult {
+                success: true,
+                message: "📋 Session Summary:\nNo transcribed recording session to summarize yet.".to_string(),
+                data: Some(CommandData::Object({
+                    let mut data = std::collections::HashMap::new();
+                    data.insert("summary_generated".to_string(), serde_json::Value::Bool(false));
+                    data
+                })),
+                execution_time: start_time.elapsed(),
+                timestamp: Utc::now(),
+            });
+        };
+
+        let speakers = analyze_speakers(&session.segments, &session.speakers);
+        let total_words: usize = session.segments.iter().map(|s| s.word_count).sum();
+        let questions = session.segments.iter().filter(|s| s.text.contains('?')).count();
+        let average_confidence = session.segments.iter().map(|s| s.confidence).sum::<f32>() / session.segments.len() as f32;
+        let duration = session.segments.iter().map(|s| s.end_time).max().unwrap_or_default().max(session.duration);
+        let total_speaking: f32 = speakers.iter().map(|s| s.speaking_time.as_secs_f32()).sum();
+
+        let mut summary = format!("📋 Session Summary: {}\n\n👥 Participants: {} speakers identified\n", session.name, speakers.len());
+        for speaker in &speakers {
+            let share = if total_speaking > 0.0 { speaker.speaking_time.as_secs_f32() / total_speaking * 100.0 } else { 0.0 };
+            summary.push_str(&format!("• {} ({:.0}% speaking time)\n", speaker.name.as_ref().unwrap_or(&speaker.id), share));
+        }
+        summary.push_str(&format!(
+            "\n📊 Session Metrics:\n• Duration: {}:{:02}\n• Total Words: {}\n• Questions Asked: {}\n• Average Confidence: {:.1}%\n",
+            duration.as_secs() / 60,
+            duration.as_secs() % 60,
+            total_words,
+            questions,
+            average_confidence * 100.0
+        ));
+        let delivery: Vec<String> = speakers
+            .iter()
+            .filter(|s| s.prosody.segments > 0)
+            .map(|s| format!("• {}: {}", s.name.as_ref().unwrap_or(&s.id), describe_delivery(&s.prosody)))
+            .collect();
+        if !delivery.is_empty() {
+            summary.push_str(&format!("\n🎙️ Delivery:\n{}\n", delivery.join("\n")));
+        }
         
         let execution_time = start_time.elapsed();
         
         Ok(CommandResult {
             success: true,
-            message: summary.to_string(),
+            message: summary.trim_end().to_string(),
             data: Some(CommandData::Object({
                 let mut data = std::collections::HashMap::new();
                 data.insert("summary_generated".to_string(), serde_json::Value::Bool(true));
-                data.insert("word_count".to_string(), serde_json::Value::Number(serde_json::Number::from(156)));
-                data.insert("sentiment_score".to_string(), serde_json::Value::Number(serde_json::Number::from_f64(0.78).unwrap()));
-                data.insert("confidence_score".to_string(), serde_json::Value::Number(serde_json::Number::from_f64(0.935).unwrap()));
+                data.insert("word_count".to_string(), serde_json::Value::Number(serde_json::Number::from(total_words)));
+                data.insert("speaker_count".to_string(), serde_json::Value::Number(serde_json::Number::from(speakers.len())));
+                data.insert("question_count".to_string(), serde_json::Value::Number(serde_json::Number::from(questions)));
+                if let Some(confidence) = serde_json::Number::from_f64(average_confidence as f64) {
+                    data.insert("confidence_score".to_string(), serde_json::Value::Number(confidence));
+                }
                 data
             })),
             execution_time,
@@ -482,6 +503,97 @@ struct SpeakerAnalysis {
     speaking_rate: f32,
     question_count: usize,
     dominant_topics: Vec<String>,
+    prosody: ProsodyProfile,
+}
+
+/// Transcript data of the current (or most recent) recording session
+struct RecordedSession {
+    name: String,
+    duration: Duration,
+    segments: Vec<TranscriptSegment>,
+    speakers: Vec<SpeakerCluster>,
+}
+
+fn recorded_session(services: Option<&ServiceContext>) -> Option<RecordedSession> {
+    let manager = services?.audio_session_manager.as_ref()?.lock().ok()?;
+    let session = manager
+        .get_current_session()
+        .or_else(|| manager.get_session_history().last())?;
+    Some(RecordedSession {
+        name: session.name.clone(),
+        duration: session.duration,
+        segments: session.transcript_segments.clone(),
+        speakers: manager.speakers().to_vec(),
+    })
+}
+
+/// One-line description of a speaker's pitch and pausing
+fn describe_delivery(prosody: &ProsodyProfile) -> String {
+    if prosody.segments == 0 {
+        return "Delivery: no audio analyzed".to_string();
+    }
+    let pitch = if prosody.mean_f0_hz > 0.0 {
+        format!("{:.0} Hz ({:.0}-{:.0})", prosody.mean_f0_hz, prosody.f0_range_hz.0, prosody.f0_range_hz.1)
+    } else {
+        "unvoiced".to_string()
+    };
+    format!(
+        "Pitch: {} | Pauses: {:.1}/min, avg {:.0} ms",
+        pitch,
+        prosody.pauses_per_minute(),
+        prosody.mean_pause_ms()
+    )
+}
+
+/// Per-speaker statistics from diarized transcript segments, most active first
+fn analyze_speakers(segments: &[TranscriptSegment], speakers: &[SpeakerCluster]) -> Vec<SpeakerAnalysis> {
+    let mut by_speaker: Vec<(String, Vec<&TranscriptSegment>)> = Vec::new();
+    for segment in segments {
+        let Some(id) = &segment.speaker_id else { continue };
+        match by_speaker.iter_mut().find(|(speaker, _)| speaker == id) {
+            Some((_, list)) => list.push(segment),
+            None => by_speaker.push((id.clone(), vec![segment])),
+        }
+    }
+
+    let mut analysis: Vec<SpeakerAnalysis> = by_speaker
+        .into_iter()
+        .map(|(id, list)| {
+            let speaking_time: Duration = list.iter().map(|s| s.end_time.saturating_sub(s.start_time)).sum();
+            let words: usize = list.iter().map(|s| s.word_count).sum();
+            let minutes = speaking_time.as_secs_f32() / 60.0;
+            let mut topic_counts: HashMap<String, usize> = HashMap::new();
+            for segment in &list {
+                for word in segment.text.split_whitespace() {
+                    let word: String = word.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+                    if word.len() >= 5 {
+                        *topic_counts.entry(word).or_insert(0) += 1;
+                    }
+                }
+            }
+            let mut topics: Vec<(String, usize)> = topic_counts.into_iter().collect();
+            topics.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            let mut prosody = ProsodyProfile::default();
+            for features in list.iter().filter_map(|s| s.prosody.as_ref()) {
+                prosody.add(features);
+            }
+            let word_rate = if minutes > 0.0 { words as f32 / minutes } else { 0.0 };
+
+            SpeakerAnalysis {
+                name: speakers.iter().find(|c| c.id == id).and_then(|c| c.name.clone()),
+                id,
+                speaking_time,
+                phrase_count: list.len(),
+                average_confidence: list.iter().map(|s| s.confidence).sum::<f32>() / list.len() as f32,
+                speaking_rate: if prosody.segments > 0 { prosody.speaking_rate_wpm } else { word_rate },
+                question_count: list.iter().filter(|s| s.text.contains('?')).count(),
+                dominant_topics: topics.into_iter().take(3).map(|(word, _)| word).collect(),
+                prosody,
+            }
+        })
+        .collect();
+    analysis.sort_by_key(|s| std::cmp::Reverse(s.speaking_time));
+    analysis
 }
 
 // Factory functions
@@ -551,6 +663,34 @@ mod tests {
         assert!(cmd_result.message.contains("Speaker Analysis"));
     }
     
+    #[test]
+    fn test_analyze_speakers_groups_diarized_segments() {
+        let segment = |speaker: &str, start: u64, end: u64, text: &str| TranscriptSegment {
+            id: Uuid::new_v4(),
+            start_time: Duration::from_secs(start),
+            end_time: Duration::from_secs(end),
+            text: text.to_string(),
+            confidence: 0.9,
+            speaker_id: Some(speaker.to_string()),
+            language: None,
+            word_count: text.split_whitespace().count(),
+            is_final: true,
+            prosody: None,
+        };
+        let segments = vec![
+            segment("Speaker_1", 0, 10, "Welcome to the planning meeting"),
+            segment("Speaker_2", 10, 40, "Thanks, the planning is going well"),
+            segment("Speaker_1", 40, 45, "Any blockers?"),
+        ];
+
+        let analysis = analyze_speakers(&segments, &[]);
+        assert_eq!(analysis.len(), 2);
+        assert_eq!(analysis[0].id, "Speaker_2");
+        assert_eq!(analysis[1].phrase_count, 2);
+        assert_eq!(analysis[1].question_count, 1);
+        assert_eq!(analysis[1].speaking_time, Duration::from_secs(15));
+    }
+    
     #[test]
     fn test_export_format_detection() {
         let command = ExportSessionTranscriptCommand;
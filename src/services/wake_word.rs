@@ -0,0 +1,549 @@
+//! Wake-word gate for command mode.
+//!
+//! With the gate enabled, an utterance is only handed to the voice command
+//! engine when it starts with the wake phrase ("clippy, stop recording"), or
+//! when the previous utterance was the wake phrase on its own. Everything else
+//! is dictation, so "stop recording the meeting notes" is pasted rather than
+//! executed.
+//!
+//! The phrase is spotted two ways:
+//!
+//! - acoustically, by matching MFCC features of the start of the utterance
+//!   against enrolled recordings of the phrase with subsequence DTW
+//! - textually, by fuzzy-matching the leading words of the transcript, which
+//!   is what is used until the user has enrolled recordings
+//!
+//! One sensitivity setting (0.0 strict, 1.0 lenient) controls both.
+
+use crate::services::signal_analysis::fft;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Wake-word configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WakeWordConfig {
+    /// Require the wake phrase before commands
+    pub enabled: bool,
+    /// Wake phrase, e.g. "clippy"
+    pub phrase: String,
+    /// Detection sensitivity (0.0 strict to 1.0 lenient)
+    pub sensitivity: f32,
+    /// How long a bare wake phrase keeps command mode armed, in milliseconds
+    pub command_window_ms: u64,
+}
+
+impl Default for WakeWordConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            phrase: "clippy".to_string(),
+            sensitivity: 0.5,
+            command_window_ms: 8000,
+        }
+    }
+}
+
+impl From<&crate::core::config::STTConfig> for WakeWordConfig {
+    fn from(config: &crate::core::config::STTConfig) -> Self {
+        Self {
+            enabled: config.wake_word_enabled,
+            phrase: config.wake_word_phrase.clone(),
+            sensitivity: config.wake_word_sensitivity,
+            ..Self::default()
+        }
+    }
+}
+
+/// Where an utterance should go
+#[derive(Debug, Clone, PartialEq)]
+pub enum WakeDecision {
+    /// Gate disabled: try commands first, as without a wake word
+    Ungated(String),
+    /// Wake phrase heard; the text (with the phrase removed) is a command
+    Command(String),
+    /// Only the wake phrase was said; the next utterance is a command
+    WakeOnly,
+    /// No wake phrase: treat as dictation
+    Dictation(String),
+    /// The utterance was recorded as a wake phrase sample
+    Enrolled { templates: usize },
+}
+
+/// Number of cepstral coefficients kept per frame (c1..c12)
+pub(crate) const MFCC_COEFFS: usize = 12;
+/// Mel filters in the filterbank
+const MEL_FILTERS: usize = 26;
+/// Typical DTW distance (per frame) between two samples of the same phrase,
+/// used until a second sample is enrolled to calibrate against
+const DEFAULT_MATCH_DISTANCE: f32 = 9.0;
+
+/// MFCC feature extractor (25 ms frames, 10 ms hop)
+#[derive(Debug, Clone)]
+pub struct MfccExtractor {
+    sample_rate: u32,
+    frame_len: usize,
+    hop: usize,
+    fft_size: usize,
+    window: Vec<f32>,
+    filterbank: Vec<Vec<(usize, f32)>>,
+}
+
+impl MfccExtractor {
+    /// Create an extractor for a sample rate
+    pub fn new(sample_rate: u32) -> Self {
+        let frame_len = (sample_rate as usize * 25 / 1000).max(16);
+        let hop = (sample_rate as usize / 100).max(1);
+        let fft_size = frame_len.next_power_of_two();
+        let window = (0..frame_len)
+            .map(|i| 0.54 - 0.46 * (2.0 * std::f32::consts::PI * i as f32 / (frame_len - 1) as f32).cos())
+            .collect();
+
+        // Triangular filters evenly spaced on the mel scale up to Nyquist (or 8 kHz)
+        let mel = |hz: f32| 2595.0 * (1.0 + hz / 700.0).log10();
+        let hz = |mel: f32| 700.0 * (10f32.powf(mel / 2595.0) - 1.0);
+        let top = mel((sample_rate as f32 / 2.0).min(8000.0));
+        let bin_of = |f: f32| ((f / sample_rate as f32) * fft_size as f32).floor() as usize;
+        let edges: Vec<usize> = (0..MEL_FILTERS + 2)
+            .map(|i| bin_of(hz(top * i as f32 / (MEL_FILTERS + 1) as f32)))
+            .collect();
+        let filterbank = (0..MEL_FILTERS)
+            .map(|m| {
+                let (lo, mid, hi) = (edges[m], edges[m + 1].max(edges[m] + 1), edges[m + 2].max(edges[m] + 2));
+                (lo..=hi)
+                    .filter(|k| *k <= fft_size / 2)
+                    .map(|k| {
+                        let w = if k <= mid {
+                            (k - lo) as f32 / (mid - lo) as f32
+                        } else {
+                            (hi - k) as f32 / (hi - mid) as f32
+                        };
+                        (k, w)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self { sample_rate, frame_len, hop, fft_size, window, filterbank }
+    }
+
+    /// Sample rate the extractor was built for
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Frames per second of the feature sequence
+    pub fn frames_per_second(&self) -> usize {
+        self.sample_rate as usize / self.hop
+    }
+
+    /// MFCC frames (c1..c12) with the utterance mean removed
+    pub fn extract(&self, samples: &[f32]) -> Vec<Vec<f32>> {
+        let mut frames = self.extract_raw(samples);
+
+        // Cepstral mean normalisation removes the microphone/channel colouring
+        let mut mean = vec![0.0f32; MFCC_COEFFS];
+        for f in &frames {
+            for (m, v) in mean.iter_mut().zip(f) {
+                *m += v / frames.len() as f32;
+            }
+        }
+        for f in &mut frames {
+            for (v, m) in f.iter_mut().zip(&mean) {
+                *v -= m;
+            }
+        }
+        frames
+    }
+
+    /// MFCC frames (c1..c12) without normalisation
+    pub fn extract_raw(&self, samples: &[f32]) -> Vec<Vec<f32>> {
+        if samples.len() < self.frame_len {
+            return Vec::new();
+        }
+        let (mut re, mut im) = (vec![0.0f32; self.fft_size], vec![0.0f32; self.fft_size]);
+        let mut frames = Vec::new();
+        let mut start = 0;
+        while start + self.frame_len <= samples.len() {
+            re.iter_mut().for_each(|v| *v = 0.0);
+            im.iter_mut().for_each(|v| *v = 0.0);
+            let frame = &samples[start..start + self.frame_len];
+            for i in 0..self.frame_len {
+                // Pre-emphasis lifts the high frequencies that carry consonants
+                let prev = if i > 0 { frame[i - 1] } else if start > 0 { samples[start - 1] } else { 0.0 };
+                re[i] = (frame[i] - 0.97 * prev) * self.window[i];
+            }
+            fft(&mut re, &mut im);
+            let log_mel: Vec<f32> = self
+                .filterbank
+                .iter()
+                .map(|filter| {
+                    let energy: f32 = filter.iter().map(|(k, w)| w * (re[*k] * re[*k] + im[*k] * im[*k])).sum();
+                    (energy + 1e-10).ln()
+                })
+                .collect();
+            let coeffs: Vec<f32> = (1..=MFCC_COEFFS)
+                .map(|c| {
+                    log_mel
+                        .iter()
+                        .enumerate()
+                        .map(|(m, e)| e * (std::f32::consts::PI * c as f32 * (m as f32 + 0.5) / MEL_FILTERS as f32).cos())
+                        .sum()
+                })
+                .collect();
+            frames.push(coeffs);
+            start += self.hop;
+        }
+        frames
+    }
+
+    /// Samples between successive frames
+    pub fn hop(&self) -> usize {
+        self.hop
+    }
+
+    /// Samples per analysis frame
+    pub fn frame_len(&self) -> usize {
+        self.frame_len
+    }
+}
+
+/// Average per-frame distance of the best alignment of `template` anywhere in
+/// `sequence` (subsequence DTW: free start and end in `sequence`)
+pub fn subsequence_dtw(template: &[Vec<f32>], sequence: &[Vec<f32>]) -> f32 {
+    if template.is_empty() || sequence.is_empty() {
+        return f32::INFINITY;
+    }
+    let dist = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum::<f32>().sqrt();
+    // Each cell stores (accumulated cost, path length)
+    let mut prev: Vec<(f32, u32)> = vec![(0.0, 0); sequence.len()];
+    for t in template {
+        let mut row: Vec<(f32, u32)> = Vec::with_capacity(sequence.len());
+        for (j, s) in sequence.iter().enumerate() {
+            let d = dist(t, s);
+            let mut best = prev[j];
+            if j > 0 {
+                for cand in [prev[j - 1], row[j - 1]] {
+                    if cand.0 / cand.1.max(1) as f32 <= best.0 / best.1.max(1) as f32 {
+                        best = cand;
+                    }
+                }
+            }
+            row.push((best.0 + d, best.1 + 1));
+        }
+        prev = row;
+    }
+    prev.iter().map(|(c, n)| c / *n as f32).fold(f32::INFINITY, f32::min)
+}
+
+/// Wake-word gate and keyword spotter
+#[derive(Debug)]
+pub struct WakeWordGate {
+    config: WakeWordConfig,
+    extractor: Option<MfccExtractor>,
+    templates: Vec<Vec<Vec<f32>>>,
+    /// Typical distance between enrolled samples, used to scale the threshold
+    template_spread: Option<f32>,
+    armed_until: Option<Instant>,
+    pending_enrollments: usize,
+}
+
+/// Enrolled wake phrase samples as stored on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredTemplates {
+    phrase: String,
+    sample_rate: u32,
+    templates: Vec<Vec<Vec<f32>>>,
+}
+
+impl WakeWordGate {
+    /// Create a gate
+    pub fn new(config: WakeWordConfig) -> Self {
+        Self {
+            config,
+            extractor: None,
+            templates: Vec::new(),
+            template_spread: None,
+            armed_until: None,
+            pending_enrollments: 0,
+        }
+    }
+
+    /// Current configuration
+    pub fn config(&self) -> &WakeWordConfig {
+        &self.config
+    }
+
+    /// Enable or disable the gate
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.config.enabled = enabled;
+        self.armed_until = None;
+    }
+
+    /// Whether the gate is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Change the wake phrase; recordings of the old phrase are discarded
+    pub fn set_phrase(&mut self, phrase: &str) {
+        let phrase = normalize(phrase);
+        if phrase != normalize(&self.config.phrase) {
+            self.templates.clear();
+            self.template_spread = None;
+        }
+        self.config.phrase = phrase;
+    }
+
+    /// Set detection sensitivity (clamped to 0.0..=1.0)
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.config.sensitivity = sensitivity.clamp(0.0, 1.0);
+    }
+
+    /// Record the next `count` utterances as samples of the wake phrase
+    pub fn request_enrollment(&mut self, count: usize) {
+        self.pending_enrollments = count;
+    }
+
+    /// Number of enrolled samples
+    pub fn template_count(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Forget all enrolled samples
+    pub fn clear_templates(&mut self) {
+        self.templates.clear();
+        self.template_spread = None;
+    }
+
+    /// Add a recording of the wake phrase on its own
+    pub fn enroll(&mut self, samples: &[f32], sample_rate: u32) -> usize {
+        let features = self.extractor_for(sample_rate).extract(&trim_silence(samples));
+        if !features.is_empty() {
+            self.templates.push(features);
+            self.recalibrate();
+        }
+        self.templates.len()
+    }
+
+    /// Whether the start of the audio matches an enrolled sample
+    pub fn detect_audio(&mut self, samples: &[f32], sample_rate: u32) -> bool {
+        if self.templates.is_empty() {
+            return false;
+        }
+        let extractor = self.extractor_for(sample_rate).clone();
+        let longest = self.templates.iter().map(|t| t.len()).max().unwrap_or(0);
+        // The phrase leads the utterance: compare against a slightly longer
+        // head so the cepstral means cover roughly the same sounds
+        let speech = trim_silence(samples);
+        let head_len = (longest * sample_rate as usize * 6 / 5 / extractor.frames_per_second()).min(speech.len());
+        let features = extractor.extract(&speech[..head_len]);
+        let best = self
+            .templates
+            .iter()
+            .map(|t| subsequence_dtw(t, &features))
+            .fold(f32::INFINITY, f32::min);
+        best <= self.match_threshold()
+    }
+
+    /// Route a transcribed utterance (with its audio) to commands or dictation
+    pub fn route(&mut self, text: &str, samples: &[f32], sample_rate: u32) -> WakeDecision {
+        if self.pending_enrollments > 0 {
+            self.pending_enrollments -= 1;
+            let templates = self.enroll(samples, sample_rate);
+            return WakeDecision::Enrolled { templates };
+        }
+        if !self.config.enabled {
+            return WakeDecision::Ungated(text.to_string());
+        }
+
+        let now = Instant::now();
+        let stripped = strip_wake_phrase(text, &self.config.phrase, self.config.sensitivity);
+        let heard = stripped.is_some() || self.detect_audio(samples, sample_rate);
+        if heard {
+            let remainder = stripped.unwrap_or_else(|| text.trim().to_string());
+            if remainder.is_empty() {
+                self.armed_until = Some(now + Duration::from_millis(self.config.command_window_ms));
+                return WakeDecision::WakeOnly;
+            }
+            self.armed_until = None;
+            return WakeDecision::Command(remainder);
+        }
+        if self.armed_until.take().map(|until| now <= until).unwrap_or(false) {
+            return WakeDecision::Command(text.trim().to_string());
+        }
+        WakeDecision::Dictation(text.to_string())
+    }
+
+    /// Save enrolled samples
+    pub fn save_templates(&self, path: &Path) -> std::io::Result<()> {
+        let stored = StoredTemplates {
+            phrase: self.config.phrase.clone(),
+            sample_rate: self.extractor.as_ref().map(|e| e.sample_rate()).unwrap_or(16000),
+            templates: self.templates.clone(),
+        };
+        let json = serde_json::to_string(&stored).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// Load enrolled samples saved for the current phrase; returns how many were loaded
+    pub fn load_templates(&mut self, path: &Path) -> std::io::Result<usize> {
+        let json = std::fs::read_to_string(path)?;
+        let stored: StoredTemplates = serde_json::from_str(&json).map_err(std::io::Error::other)?;
+        if normalize(&stored.phrase) != normalize(&self.config.phrase) {
+            return Ok(0);
+        }
+        self.extractor = Some(MfccExtractor::new(stored.sample_rate));
+        self.templates = stored.templates;
+        self.recalibrate();
+        Ok(self.templates.len())
+    }
+
+    fn extractor_for(&mut self, sample_rate: u32) -> &MfccExtractor {
+        if self.extractor.as_ref().map(|e| e.sample_rate() != sample_rate).unwrap_or(true) {
+            if self.extractor.is_some() && !self.templates.is_empty() {
+                tracing::warn!(sample_rate, "Wake word samples were recorded at another sample rate; re-enroll for best results");
+            }
+            self.extractor = Some(MfccExtractor::new(sample_rate));
+        }
+        self.extractor.as_ref().expect("extractor initialised above")
+    }
+
+    fn recalibrate(&mut self) {
+        let mut distances = Vec::new();
+        for (i, a) in self.templates.iter().enumerate() {
+            for b in &self.templates[i + 1..] {
+                distances.push(subsequence_dtw(a, b).min(subsequence_dtw(b, a)));
+            }
+        }
+        self.template_spread = (!distances.is_empty()).then(|| distances.iter().sum::<f32>() / distances.len() as f32);
+    }
+
+    /// Largest DTW distance accepted as the wake phrase
+    fn match_threshold(&self) -> f32 {
+        let base = self.template_spread.unwrap_or(DEFAULT_MATCH_DISTANCE);
+        // sensitivity 0 accepts matches as close as the samples are to each other, 1 up to twice that
+        base * (1.0 + self.config.sensitivity)
+    }
+}
+
+/// If `text` starts with (something close to) the wake phrase, return the rest
+pub fn strip_wake_phrase(text: &str, phrase: &str, sensitivity: f32) -> Option<String> {
+    let phrase_words: Vec<String> = normalize(phrase).split_whitespace().map(str::to_string).collect();
+    if phrase_words.is_empty() {
+        return None;
+    }
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.len() < phrase_words.len() {
+        return None;
+    }
+    // Allow "hey clippy" style lead-ins of one word
+    let max_edit_ratio = 0.2 + 0.4 * sensitivity.clamp(0.0, 1.0);
+    for offset in 0..=1.min(words.len() - phrase_words.len()) {
+        let candidate = normalize(&words[offset..offset + phrase_words.len()].join(" "));
+        let target = phrase_words.join(" ");
+        let distance = levenshtein(&candidate, &target) as f32;
+        if distance <= max_edit_ratio * target.chars().count() as f32 {
+            let rest = words[offset + phrase_words.len()..].join(" ");
+            let rest = rest.trim_start_matches(|c: char| c.is_ascii_punctuation() || c.is_whitespace());
+            return Some(rest.to_string());
+        }
+    }
+    None
+}
+
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c.is_whitespace() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            row.push((prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+/// Drop leading and trailing audio well below the utterance's peak level
+fn trim_silence(samples: &[f32]) -> Vec<f32> {
+    let block = 160;
+    let energies: Vec<f32> = samples
+        .chunks(block)
+        .map(|c| c.iter().map(|s| s * s).sum::<f32>() / c.len() as f32)
+        .collect();
+    let peak = energies.iter().cloned().fold(0.0f32, f32::max);
+    let threshold = peak * 0.01;
+    let first = energies.iter().position(|e| *e > threshold).unwrap_or(0);
+    let last = energies.iter().rposition(|e| *e > threshold).map(|i| i + 1).unwrap_or(energies.len());
+    samples[(first * block).min(samples.len())..(last * block).min(samples.len())].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A synthetic "word": a gliding tone followed by a second formant-like tone
+    fn word(sr: u32, f0: f32, f1: f32, jitter: f32) -> Vec<f32> {
+        let n = (sr as f32 * 0.5) as usize;
+        let mut phase = 0.0f32;
+        (0..n)
+            .map(|i| {
+                let t = i as f32 / n as f32;
+                let f = if t < 0.6 { f0 + (f1 - f0) * t / 0.6 } else { f1 * 1.5 };
+                phase += 2.0 * std::f32::consts::PI * f * (1.0 + jitter) / sr as f32;
+                0.4 * phase.sin() + 0.1 * (3.0 * phase).sin()
+            })
+            .collect()
+    }
+
+    fn silence(sr: u32, secs: f32) -> Vec<f32> {
+        vec![0.0; (sr as f32 * secs) as usize]
+    }
+
+    #[test]
+    fn test_text_gate_routes_commands_and_dictation() {
+        let mut gate = WakeWordGate::new(WakeWordConfig { enabled: true, ..Default::default() });
+        assert_eq!(gate.route("Clippy, stop recording.", &[], 16000), WakeDecision::Command("stop recording.".into()));
+        assert_eq!(
+            gate.route("stop recording the meeting notes", &[], 16000),
+            WakeDecision::Dictation("stop recording the meeting notes".into())
+        );
+        // Misrecognised wake word still counts, a bare one arms the next utterance
+        assert_eq!(gate.route("Hey Clippie.", &[], 16000), WakeDecision::WakeOnly);
+        assert_eq!(gate.route("start recording", &[], 16000), WakeDecision::Command("start recording".into()));
+        assert!(matches!(gate.route("start recording", &[], 16000), WakeDecision::Dictation(_)));
+
+        gate.set_enabled(false);
+        assert_eq!(gate.route("stop recording", &[], 16000), WakeDecision::Ungated("stop recording".into()));
+    }
+
+    #[test]
+    fn test_enrolled_samples_are_spotted_acoustically() {
+        let sr = 16000;
+        let mut gate = WakeWordGate::new(WakeWordConfig { enabled: true, phrase: "computer".into(), ..Default::default() });
+        gate.request_enrollment(2);
+        let sample_a = [silence(sr, 0.2), word(sr, 300.0, 900.0, 0.0), silence(sr, 0.2)].concat();
+        let sample_b = [silence(sr, 0.1), word(sr, 300.0, 900.0, 0.03), silence(sr, 0.3)].concat();
+        assert_eq!(gate.route("computer", &sample_a, sr), WakeDecision::Enrolled { templates: 1 });
+        assert_eq!(gate.route("commuter", &sample_b, sr), WakeDecision::Enrolled { templates: 2 });
+
+        // Transcript mangled the wake word, but the audio gives it away
+        let utterance = [word(sr, 300.0, 900.0, 0.015), silence(sr, 0.1), word(sr, 1200.0, 500.0, 0.0)].concat();
+        assert_eq!(gate.route("come pewter open settings", &utterance, sr), WakeDecision::Command("come pewter open settings".into()));
+
+        let other = [word(sr, 1200.0, 500.0, 0.0), silence(sr, 0.1), word(sr, 700.0, 200.0, 0.0)].concat();
+        assert!(matches!(gate.route("open settings", &other, sr), WakeDecision::Dictation(_)));
+    }
+}
@@ -0,0 +1,493 @@
+//! Waveform overviews and loudness summaries for saved sessions.
+//!
+//! When a session is saved we write, next to `session_metadata.json`:
+//!
+//! - `waveform/<samples_per_bin>.json`: a min/max/RMS envelope per zoom level,
+//!   each level four times coarser than the one before, quantized to 8 bits so
+//!   hours of audio stay a few hundred kilobytes at the finest level
+//! - `overview.json`: duration, the available zoom levels, the speech/silence
+//!   map from the VAD, and an EBU R128 loudness summary
+//!
+//! A UI can draw an hour-long recording from the coarse levels without
+//! decoding any audio, and load finer levels only when zooming in.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::audio_archive::AudioError;
+use super::encryption::{self, StorageCipher};
+
+pub const OVERVIEW_FILE: &str = "overview.json";
+pub const WAVEFORM_DIR: &str = "waveform";
+
+/// Samples per bin of each stored zoom level, finest first
+pub const ZOOM_LEVELS: &[u32] = &[256, 1024, 4096, 16384, 65536];
+
+/// Loudness of blocks below this is ignored entirely (BS.1770 absolute gate)
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Blocks this far below the ungated mean are ignored (BS.1770 relative gate)
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// Envelope of a session at one zoom level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaveformLevel {
+    pub sample_rate: u32,
+    /// Frames summarised by each bin
+    pub samples_per_bin: u32,
+    /// Lowest sample per bin, scaled to -127..=127
+    pub min: Vec<i8>,
+    /// Highest sample per bin, scaled to -127..=127
+    pub max: Vec<i8>,
+    /// RMS per bin, scaled to 0..=255
+    pub rms: Vec<u8>,
+}
+
+impl WaveformLevel {
+    pub fn bins(&self) -> usize {
+        self.max.len()
+    }
+
+    /// Time covered by one bin
+    pub fn bin_duration(&self) -> Duration {
+        Duration::from_secs_f64(self.samples_per_bin as f64 / self.sample_rate.max(1) as f64)
+    }
+}
+
+/// A stretch of audio the VAD classified as speech
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpeechSpan {
+    pub start: Duration,
+    pub end: Duration,
+}
+
+/// EBU R128 loudness of a whole session
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessSummary {
+    /// Gated integrated loudness; `None` for silence or audio shorter than one 400 ms block
+    pub integrated_lufs: Option<f64>,
+    /// Loudest 400 ms block
+    pub max_momentary_lufs: Option<f64>,
+    /// Highest absolute sample value in dBFS
+    pub sample_peak_dbfs: f64,
+}
+
+/// Everything a UI needs to draw a session before loading any envelope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionOverview {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub duration: Duration,
+    /// Samples per bin of the envelope files written for this session
+    pub zoom_levels: Vec<u32>,
+    pub speech: Vec<SpeechSpan>,
+    pub speech_percentage: f32,
+    pub loudness: LoudnessSummary,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Overview plus the envelope at every zoom level
+#[derive(Debug, Clone)]
+pub struct Waveform {
+    pub overview: SessionOverview,
+    pub levels: Vec<WaveformLevel>,
+}
+
+/// Analyze interleaved audio. `speech_segments` are VAD frame ranges.
+pub fn analyze(samples: &[f32], channels: u16, sample_rate: u32, speech_segments: &[(usize, usize)]) -> Waveform {
+    let mut builder = WaveformBuilder::new(channels, sample_rate);
+    builder.push(samples);
+    builder.finish(speech_segments)
+}
+
+/// Builds a [`Waveform`] from interleaved audio fed a block at a time, keeping
+/// only the finest envelope bins and the loudness steps
+#[derive(Clone)]
+pub struct WaveformBuilder {
+    channels: u16,
+    sample_rate: u32,
+    samples: usize,
+    bins: Vec<Bin>,
+    bin: Bin,
+    bin_samples: usize,
+    loudness: LoudnessMeter,
+}
+
+impl WaveformBuilder {
+    pub fn new(channels: u16, sample_rate: u32) -> Self {
+        let channels = channels.max(1);
+        let sample_rate = sample_rate.max(1);
+        Self {
+            channels,
+            sample_rate,
+            samples: 0,
+            bins: Vec::new(),
+            bin: Bin::EMPTY,
+            bin_samples: 0,
+            loudness: LoudnessMeter::new(channels, sample_rate),
+        }
+    }
+
+    /// Add the next block of interleaved audio
+    pub fn push(&mut self, samples: &[f32]) {
+        let bin_len = ZOOM_LEVELS.first().copied().unwrap_or(1) as usize * self.channels as usize;
+        for &s in samples {
+            self.bin = self.bin.merge(Bin { min: s, max: s, sum_squares: (s as f64).powi(2), count: 1 });
+            self.bin_samples += 1;
+            if self.bin_samples == bin_len {
+                self.bins.push(std::mem::replace(&mut self.bin, Bin::EMPTY));
+                self.bin_samples = 0;
+            }
+        }
+        self.samples += samples.len();
+        self.loudness.push(samples);
+    }
+
+    pub fn finish(mut self, speech_segments: &[(usize, usize)]) -> Waveform {
+        if self.bin_samples > 0 {
+            self.bins.push(self.bin);
+        }
+        let sample_rate = self.sample_rate;
+        let frames = self.samples / self.channels as usize;
+        let seconds = |frame: usize| Duration::from_secs_f64(frame as f64 / sample_rate as f64);
+
+        let speech: Vec<SpeechSpan> = speech_segments.iter()
+            .map(|&(start, end)| SpeechSpan { start: seconds(start.min(frames)), end: seconds(end.min(frames)) })
+            .filter(|span| span.end > span.start)
+            .collect();
+        let speech_frames: usize = speech_segments.iter().map(|&(start, end)| end.min(frames).saturating_sub(start)).sum();
+        let levels = envelopes(self.bins, sample_rate);
+
+        Waveform {
+            overview: SessionOverview {
+                sample_rate,
+                channels: self.channels,
+                duration: seconds(frames),
+                zoom_levels: levels.iter().map(|level| level.samples_per_bin).collect(),
+                speech,
+                speech_percentage: if frames == 0 { 0.0 } else { speech_frames as f32 / frames as f32 * 100.0 },
+                loudness: self.loudness.finish(),
+                generated_at: Utc::now(),
+            },
+            levels,
+        }
+    }
+}
+
+/// Per-bin accumulator, merged four at a time for each coarser level
+#[derive(Clone, Copy)]
+struct Bin {
+    min: f32,
+    max: f32,
+    sum_squares: f64,
+    count: u64,
+}
+
+impl Bin {
+    const EMPTY: Bin = Bin { min: 0.0, max: 0.0, sum_squares: 0.0, count: 0 };
+
+    fn merge(self, other: Bin) -> Bin {
+        if self.count == 0 {
+            return other;
+        }
+        Bin {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+            sum_squares: self.sum_squares + other.sum_squares,
+            count: self.count + other.count,
+        }
+    }
+}
+
+/// Every zoom level, folded from the bins of the finest one
+fn envelopes(mut bins: Vec<Bin>, sample_rate: u32) -> Vec<WaveformLevel> {
+    let Some((&finest, coarser)) = ZOOM_LEVELS.split_first() else {
+        return Vec::new();
+    };
+    let mut levels = vec![quantize(&bins, finest, sample_rate)];
+    let mut samples_per_bin = finest;
+    for &next in coarser {
+        let factor = (next / samples_per_bin).max(1) as usize;
+        bins = bins.chunks(factor).map(|group| group.iter().fold(Bin::EMPTY, |acc, &bin| acc.merge(bin))).collect();
+        samples_per_bin = next;
+        levels.push(quantize(&bins, next, sample_rate));
+    }
+    levels
+}
+
+fn quantize(bins: &[Bin], samples_per_bin: u32, sample_rate: u32) -> WaveformLevel {
+    let signed = |v: f32| (v.clamp(-1.0, 1.0) * 127.0).round() as i8;
+    WaveformLevel {
+        sample_rate,
+        samples_per_bin,
+        min: bins.iter().map(|bin| signed(bin.min)).collect(),
+        max: bins.iter().map(|bin| signed(bin.max)).collect(),
+        rms: bins.iter()
+            .map(|bin| {
+                let rms = if bin.count == 0 { 0.0 } else { (bin.sum_squares / bin.count as f64).sqrt() };
+                (rms.min(1.0) * 255.0).round() as u8
+            })
+            .collect(),
+    }
+}
+
+/// Second-order IIR section in direct form I
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self { b, a, x: [0.0; 2], y: [0.0; 2] }
+    }
+
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0] - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+/// The BS.1770 K-weighting pre-filter (high shelf, then high-pass) designed for any sample rate
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate as f64;
+
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad::new(
+        [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad::new([1.0, -2.0, 1.0], [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0]);
+
+    [shelf, high_pass]
+}
+
+fn lufs(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.log10()
+}
+
+/// Integrated loudness per EBU R128: K-weighted 400 ms blocks overlapping by 75%,
+/// gated at -70 LUFS and then 10 LU below the mean of the remaining blocks.
+/// Every channel is weighted 1.0, which is right for the mono and stereo audio we record.
+pub fn loudness(samples: &[f32], channels: u16, sample_rate: u32) -> LoudnessSummary {
+    let mut meter = LoudnessMeter::new(channels, sample_rate);
+    meter.push(samples);
+    meter.finish()
+}
+
+/// Running state of [`loudness`] for audio fed a block at a time
+#[derive(Clone)]
+struct LoudnessMeter {
+    channels: usize,
+    step: usize,
+    filters: Vec<[Biquad; 2]>,
+    /// Sum of K-weighted squares per 100 ms step, summed over channels; blocks are four steps
+    steps: Vec<f64>,
+    step_sum: f64,
+    frames: usize,
+    peak: f32,
+    /// Samples of a frame split across blocks
+    partial: Vec<f32>,
+}
+
+impl LoudnessMeter {
+    fn new(channels: u16, sample_rate: u32) -> Self {
+        let channels = channels.max(1) as usize;
+        Self {
+            channels,
+            step: (sample_rate as usize / 10).max(1),
+            filters: vec![k_weighting(sample_rate); channels],
+            steps: Vec::new(),
+            step_sum: 0.0,
+            frames: 0,
+            peak: 0.0,
+            partial: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, samples: &[f32]) {
+        self.peak = samples.iter().fold(self.peak, |peak, s| peak.max(s.abs()));
+        let mut rest = samples;
+        if !self.partial.is_empty() {
+            let take = (self.channels - self.partial.len()).min(rest.len());
+            self.partial.extend_from_slice(&rest[..take]);
+            rest = &rest[take..];
+            if self.partial.len() < self.channels {
+                return;
+            }
+            let frame = std::mem::take(&mut self.partial);
+            self.add_frame(&frame);
+        }
+        let mut frames = rest.chunks_exact(self.channels);
+        for frame in &mut frames {
+            self.add_frame(frame);
+        }
+        self.partial.extend_from_slice(frames.remainder());
+    }
+
+    fn add_frame(&mut self, frame: &[f32]) {
+        for ([shelf, high_pass], &sample) in self.filters.iter_mut().zip(frame) {
+            let weighted = high_pass.process(shelf.process(sample as f64));
+            self.step_sum += weighted * weighted;
+        }
+        self.frames += 1;
+        if self.frames.is_multiple_of(self.step) {
+            self.steps.push(self.step_sum);
+            self.step_sum = 0.0;
+        }
+    }
+
+    fn finish(self) -> LoudnessSummary {
+        let step = self.step;
+        let sample_peak_dbfs = if self.peak > 0.0 { 20.0 * (self.peak as f64).log10() } else { f64::NEG_INFINITY };
+        let blocks: Vec<f64> = self.steps.windows(4).map(|window| window.iter().sum::<f64>() / (4 * step) as f64).collect();
+
+        let absolute: Vec<f64> = blocks.iter().copied().filter(|&z| z > 0.0 && lufs(z) > ABSOLUTE_GATE_LUFS).collect();
+        let integrated_lufs = if absolute.is_empty() {
+            None
+        } else {
+            let threshold = lufs(absolute.iter().sum::<f64>() / absolute.len() as f64) + RELATIVE_GATE_LU;
+            let gated: Vec<f64> = absolute.iter().copied().filter(|&z| lufs(z) > threshold).collect();
+            (!gated.is_empty()).then(|| lufs(gated.iter().sum::<f64>() / gated.len() as f64))
+        };
+        let max_momentary_lufs = blocks.iter().copied().filter(|&z| z > 0.0).reduce(f64::max).map(lufs);
+
+        LoudnessSummary { integrated_lufs, max_momentary_lufs, sample_peak_dbfs }
+    }
+}
+
+fn level_path(session_dir: &Path, samples_per_bin: u32) -> PathBuf {
+    session_dir.join(WAVEFORM_DIR).join(format!("{}.json", samples_per_bin))
+}
+
+/// Write the overview and every envelope level into a session directory
+pub fn save(session_dir: &Path, waveform: &Waveform, cipher: Option<&StorageCipher>) -> Result<(), AudioError> {
+    std::fs::create_dir_all(session_dir.join(WAVEFORM_DIR))?;
+    for level in &waveform.levels {
+        encryption::write_file(&level_path(session_dir, level.samples_per_bin), &serde_json::to_vec(level)?, cipher)?;
+    }
+    encryption::write_file(&session_dir.join(OVERVIEW_FILE), &serde_json::to_vec_pretty(&waveform.overview)?, cipher)?;
+    Ok(())
+}
+
+/// Read a session's overview, if one has been generated
+pub fn load_overview(session_dir: &Path, cipher: Option<&StorageCipher>) -> Result<Option<SessionOverview>, AudioError> {
+    let path = session_dir.join(OVERVIEW_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_slice(&encryption::read_file(&path, cipher)?)?))
+}
+
+/// Read the coarsest stored level that is at least as detailed as `samples_per_bin`,
+/// or the finest level when the request is finer than anything stored
+pub fn load_level(
+    session_dir: &Path,
+    overview: &SessionOverview,
+    samples_per_bin: u32,
+    cipher: Option<&StorageCipher>,
+) -> Result<WaveformLevel, AudioError> {
+    let level = overview.zoom_levels.iter().copied().filter(|&level| level <= samples_per_bin).max()
+        .or_else(|| overview.zoom_levels.iter().copied().min())
+        .ok_or_else(|| AudioError::StorageError("Session overview lists no waveform levels".to_string()))?;
+    Ok(serde_json::from_slice(&encryption::read_file(&level_path(session_dir, level), cipher)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sine(frequency: f32, amplitude: f32, sample_rate: u32, seconds: f32) -> Vec<f32> {
+        (0..(sample_rate as f32 * seconds) as usize)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_loudness_of_reference_sine() {
+        // A 1 kHz sine with a peak of -20 dBFS on one channel measures about -23 LUFS
+        let tone = sine(1000.0, 0.1, 48000, 5.0);
+        let mono = loudness(&tone, 1, 48000);
+        assert!((mono.integrated_lufs.unwrap() + 23.0).abs() < 0.2, "{:?}", mono);
+        assert!((mono.sample_peak_dbfs + 20.0).abs() < 0.01);
+
+        // The same tone on both channels is 3 dB louder
+        let stereo: Vec<f32> = tone.iter().flat_map(|&s| [s, s]).collect();
+        let both = loudness(&stereo, 2, 48000).integrated_lufs.unwrap();
+        assert!((both - mono.integrated_lufs.unwrap() - 3.01).abs() < 0.05);
+
+        // Silence and clips shorter than one block have no integrated loudness
+        assert_eq!(loudness(&vec![0.0; 48000], 1, 48000).integrated_lufs, None);
+        assert_eq!(loudness(&tone[..4800], 1, 48000).integrated_lufs, None);
+    }
+
+    #[test]
+    fn test_envelope_levels_fold_finer_bins() {
+        let mut samples = vec![0.0; 70000];
+        samples[300] = 0.5;
+        samples[5000] = -1.0;
+        let waveform = analyze(&samples, 1, 16000, &[(256, 1024)]);
+
+        assert_eq!(waveform.overview.zoom_levels, ZOOM_LEVELS);
+        let finest = &waveform.levels[0];
+        assert_eq!(finest.bins(), 70000_usize.div_ceil(256));
+        assert_eq!(finest.max[1], 64);
+        assert_eq!(finest.min[5000 / 256], -127);
+        assert_eq!(finest.rms[0], 0);
+
+        let coarse = &waveform.levels[2];
+        assert_eq!(coarse.samples_per_bin, 4096);
+        assert_eq!((coarse.min[1], coarse.max[0]), (-127, 64));
+        assert_eq!(waveform.levels.last().unwrap().bins(), 2);
+
+        assert_eq!(waveform.overview.speech, vec![SpeechSpan { start: Duration::from_millis(16), end: Duration::from_millis(64) }]);
+        assert!((waveform.overview.speech_percentage - 768.0 / 70000.0 * 100.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_builder_fed_in_blocks_matches_whole_analysis() {
+        let stereo: Vec<f32> = sine(997.0, 0.3, 16000, 3.0).iter().flat_map(|&s| [s, -0.5 * s]).collect();
+        let whole = analyze(&stereo, 2, 16000, &[(0, 8000)]);
+
+        // Odd block sizes split both envelope bins and stereo frames
+        let mut builder = WaveformBuilder::new(2, 16000);
+        for block in stereo.chunks(1001) {
+            builder.push(block);
+        }
+        let streamed = builder.finish(&[(0, 8000)]);
+        assert_eq!(streamed.levels, whole.levels);
+        assert_eq!(streamed.overview.duration, whole.overview.duration);
+        assert_eq!(streamed.overview.loudness.integrated_lufs, whole.overview.loudness.integrated_lufs);
+        assert_eq!(streamed.overview.speech_percentage, whole.overview.speech_percentage);
+    }
+
+    #[test]
+    fn test_save_and_load_nearest_level() {
+        let dir = TempDir::new().unwrap();
+        let waveform = analyze(&sine(440.0, 0.5, 16000, 10.0), 1, 16000, &[]);
+        save(dir.path(), &waveform, None).unwrap();
+
+        let overview = load_overview(dir.path(), None).unwrap().unwrap();
+        assert_eq!(overview.duration, Duration::from_secs(10));
+        assert_eq!(load_level(dir.path(), &overview, 5000, None).unwrap(), waveform.levels[2]);
+        assert_eq!(load_level(dir.path(), &overview, 10, None).unwrap().samples_per_bin, 256);
+        assert!(load_overview(&dir.path().join("missing"), None).unwrap().is_none());
+    }
+}